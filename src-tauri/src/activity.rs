@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+use crate::{build_hidden_cmd, repo_key};
+
+/// Entries kept per repository; older ones are dropped first.
+const MAX_ENTRIES: usize = 200;
+/// Error text is cut to this many characters so git's output can't bloat the log.
+const MAX_ERROR_LEN: usize = 300;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ActivityStep {
+    summary: String,
+    ok: bool,
+    error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ActivityEntry {
+    /// Unix time in milliseconds, also used to order entries
+    timestamp: u64,
+    summary: String,
+    ok: bool,
+    error: Option<String>,
+    /// Short hash of HEAD after the operation, null on an unborn branch
+    head: Option<String>,
+    /// Individual git invocations of a composite operation such as a commit
+    steps: Vec<ActivityStep>,
+}
+
+/// A mutating operation being assembled before it's written to the log.
+pub struct Operation {
+    summary: String,
+    steps: Vec<ActivityStep>,
+}
+
+/// First line of `text`, capped at `max` characters. Messages and errors only ever go into
+/// the log in this form, so commit bodies and diff output never do.
+pub fn first_line(text: &str, max: usize) -> String {
+    let line = text.trim().lines().next().unwrap_or_default();
+    match line.char_indices().nth(max) {
+        Some((cut, _)) => format!("{}…", &line[..cut]),
+        None => line.to_string(),
+    }
+}
+
+fn error_text<T, E: Display>(result: &Result<T, E>) -> Option<String> {
+    result.as_ref().err().map(|e| first_line(&e.to_string(), MAX_ERROR_LEN))
+}
+
+impl Operation {
+    pub fn new(summary: impl Into<String>) -> Self {
+        Operation { summary: summary.into(), steps: Vec::new() }
+    }
+
+    pub fn step<T, E: Display>(&mut self, summary: impl Into<String>, result: &Result<T, E>) {
+        self.steps.push(ActivityStep {
+            summary: summary.into(),
+            ok: result.is_ok(),
+            error: error_text(result),
+        });
+    }
+}
+
+type Log = BTreeMap<String, VecDeque<ActivityEntry>>;
+
+/// GitPop's own record of the git commands it ran that change a repository, including
+/// failed attempts. Stored in `activity.json` next to the settings, keyed by repo root.
+pub struct ActivityLog {
+    file: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl ActivityLog {
+    pub fn new(file: PathBuf) -> Self {
+        ActivityLog { file, lock: Mutex::new(()) }
+    }
+
+    fn load(&self) -> Log {
+        std::fs::read_to_string(&self.file)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, log: &Log) -> Result<(), String> {
+        let text = serde_json::to_string(log).map_err(|e| e.to_string())?;
+        if let Some(dir) = self.file.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let mut tmp = self.file.as_os_str().to_owned();
+        tmp.push(".tmp");
+        std::fs::write(&tmp, text).map_err(|e| e.to_string())?;
+        std::fs::rename(&tmp, &self.file).map_err(|e| e.to_string())
+    }
+
+    /// Appends `op` with the outcome of the whole operation. Logging never fails the
+    /// operation itself, so write errors are ignored.
+    pub fn record<T, E: Display>(&self, path: &str, op: Operation, result: &Result<T, E>) {
+        let head = build_hidden_cmd("git")
+            .current_dir(path)
+            .args(["rev-parse", "--verify", "--quiet", "--short", "HEAD"])
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string());
+        let entry = ActivityEntry {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            summary: op.summary,
+            ok: result.is_ok(),
+            error: error_text(result),
+            head,
+            steps: op.steps,
+        };
+
+        let key = repo_key(path);
+        let _guard = self.lock.lock().unwrap();
+        // Re-read so entries written by another GitPop window aren't lost
+        let mut log = self.load();
+        let entries = log.entry(key).or_default();
+        entries.push_back(entry);
+        while entries.len() > MAX_ENTRIES {
+            entries.pop_front();
+        }
+        let _ = self.save(&log);
+    }
+}
+
+/// Newest first.
+#[tauri::command]
+pub fn get_activity_log(path: &str, log: State<'_, ActivityLog>) -> Vec<ActivityEntry> {
+    let key = repo_key(path);
+    let _guard = log.lock.lock().unwrap();
+    log.load()
+        .remove(&key)
+        .map(|entries| entries.into_iter().rev().collect())
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn clear_activity_log(path: &str, log: State<'_, ActivityLog>) -> Result<(), String> {
+    let key = repo_key(path);
+    let _guard = log.lock.lock().unwrap();
+    let mut entries = log.load();
+    if entries.remove(&key).is_some() {
+        log.save(&entries)?;
+    }
+    Ok(())
+}
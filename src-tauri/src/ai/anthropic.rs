@@ -0,0 +1,162 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use super::{send, status_error, AiError};
+
+const PROVIDER: &str = "Anthropic";
+pub const API_URL: &str = "https://api.anthropic.com/v1/messages";
+
+#[derive(Serialize)]
+struct MessagesRequest<'a> {
+    model: &'a str,
+    max_tokens: u32,
+    messages: Vec<Message<'a>>,
+}
+
+#[derive(Serialize)]
+struct Message<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Deserialize)]
+struct MessagesResponse {
+    #[serde(default)]
+    content: Vec<ContentBlock>,
+    stop_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ContentBlock {
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: ErrorDetail,
+}
+
+#[derive(Deserialize)]
+struct ErrorDetail {
+    #[serde(rename = "type")]
+    kind: String,
+    message: String,
+}
+
+fn parse_response(status: u16, body: &str) -> Result<String, AiError> {
+    if !(200..300).contains(&status) {
+        return Err(match serde_json::from_str::<ErrorResponse>(body) {
+            // Anthropic signals overload with 529 and its own error type
+            Ok(e) if e.error.kind == "overloaded_error" || e.error.kind == "rate_limit_error" => AiError::RateLimited {
+                provider: PROVIDER,
+                message: e.error.message,
+            },
+            Ok(e) => status_error(PROVIDER, status, e.error.message),
+            Err(_) => status_error(PROVIDER, status, body.to_string()),
+        });
+    }
+
+    let parsed: MessagesResponse = serde_json::from_str(body).map_err(|e| AiError::InvalidResponse {
+        provider: PROVIDER,
+        message: e.to_string(),
+    })?;
+
+    match parsed.content.into_iter().find_map(|block| block.text) {
+        Some(text) if !text.trim().is_empty() => Ok(text),
+        _ if parsed.stop_reason.as_deref() == Some("refusal") => Err(AiError::ContentFiltered { provider: PROVIDER }),
+        _ => Err(AiError::EmptyResponse { provider: PROVIDER }),
+    }
+}
+
+pub async fn generate(client: &Client, url: &str, api_key: &str, model: &str, prompt: &str) -> Result<String, AiError> {
+    let request = client
+        .post(url)
+        .header("x-api-key", api_key)
+        .header("anthropic-version", "2023-06-01")
+        .header("content-type", "application/json")
+        .json(&MessagesRequest {
+            model,
+            max_tokens: 1024,
+            messages: vec![Message { role: "user", content: prompt }],
+        });
+
+    let (status, body) = send(PROVIDER, request).await?;
+    parse_response(status, &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const SUCCESS: &str = r#"{"id":"msg_01","type":"message","role":"assistant","model":"test-model",
+        "content":[{"type":"text","text":"feat: add tag deletion"}],"stop_reason":"end_turn",
+        "usage":{"input_tokens":12,"output_tokens":6}}"#;
+
+    #[test]
+    fn extracts_the_text_block() {
+        assert_eq!(parse_response(200, SUCCESS).unwrap(), "feat: add tag deletion");
+    }
+
+    #[test]
+    fn maps_error_bodies() {
+        let auth = r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#;
+        assert_eq!(
+            parse_response(401, auth),
+            Err(AiError::Auth { provider: PROVIDER, message: "invalid x-api-key".to_string() })
+        );
+        let rate = r#"{"type":"error","error":{"type":"rate_limit_error","message":"Too many requests"}}"#;
+        assert!(matches!(parse_response(429, rate), Err(AiError::RateLimited { .. })));
+        let overloaded = r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        assert!(matches!(parse_response(529, overloaded), Err(AiError::RateLimited { .. })));
+        assert_eq!(
+            parse_response(502, "Bad Gateway"),
+            Err(AiError::Api { provider: PROVIDER, status: 502, message: "Bad Gateway".to_string() })
+        );
+    }
+
+    #[test]
+    fn maps_refusals_and_empty_replies() {
+        let refusal = r#"{"content":[],"stop_reason":"refusal"}"#;
+        assert_eq!(parse_response(200, refusal), Err(AiError::ContentFiltered { provider: PROVIDER }));
+        let empty = r#"{"content":[{"type":"text","text":"  "}],"stop_reason":"end_turn"}"#;
+        assert_eq!(parse_response(200, empty), Err(AiError::EmptyResponse { provider: PROVIDER }));
+        assert!(matches!(parse_response(200, "<html>"), Err(AiError::InvalidResponse { .. })));
+    }
+
+    #[tokio::test]
+    async fn sends_the_key_and_version_headers() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .and(header("x-api-key", "secret"))
+            .and(header("anthropic-version", "2023-06-01"))
+            .and(body_partial_json(serde_json::json!({
+                "model": "test-model",
+                "messages": [{ "role": "user", "content": "the diff" }]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_string(SUCCESS))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let url = format!("{}/v1/messages", server.uri());
+        let reply = generate(&Client::new(), &url, "secret", "test-model", "the diff").await;
+        assert_eq!(reply.unwrap(), "feat: add tag deletion");
+    }
+
+    #[tokio::test]
+    async fn maps_a_rejected_key_from_the_server() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401).set_body_string(
+                r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let reply = generate(&Client::new(), &server.uri(), "wrong", "test-model", "the diff").await;
+        assert!(matches!(reply, Err(AiError::Auth { .. })));
+    }
+}
@@ -0,0 +1,160 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use super::{send, status_error, AiError};
+
+const PROVIDER: &str = "Gemini";
+pub const API_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+#[derive(Serialize)]
+struct GenerateRequest<'a> {
+    contents: Vec<Content<'a>>,
+}
+
+#[derive(Serialize)]
+struct Content<'a> {
+    parts: Vec<RequestPart<'a>>,
+}
+
+#[derive(Serialize)]
+struct RequestPart<'a> {
+    text: &'a str,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerateResponse {
+    #[serde(default)]
+    candidates: Vec<Candidate>,
+    prompt_feedback: Option<PromptFeedback>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Candidate {
+    content: Option<CandidateContent>,
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CandidateContent {
+    #[serde(default)]
+    parts: Vec<ResponsePart>,
+}
+
+#[derive(Deserialize)]
+struct ResponsePart {
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptFeedback {
+    block_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: ErrorDetail,
+}
+
+#[derive(Deserialize)]
+struct ErrorDetail {
+    message: String,
+}
+
+fn parse_response(status: u16, body: &str) -> Result<String, AiError> {
+    if !(200..300).contains(&status) {
+        let message = serde_json::from_str::<ErrorResponse>(body)
+            .map(|e| e.error.message)
+            .unwrap_or_else(|_| body.to_string());
+        // Gemini reports a bad key as 400 INVALID_ARGUMENT
+        if status == 400 && message.to_lowercase().contains("api key") {
+            return Err(AiError::Auth { provider: PROVIDER, message });
+        }
+        return Err(status_error(PROVIDER, status, message));
+    }
+
+    let parsed: GenerateResponse = serde_json::from_str(body).map_err(|e| AiError::InvalidResponse {
+        provider: PROVIDER,
+        message: e.to_string(),
+    })?;
+
+    if parsed.prompt_feedback.and_then(|f| f.block_reason).is_some() {
+        return Err(AiError::ContentFiltered { provider: PROVIDER });
+    }
+
+    let candidate = parsed.candidates.into_iter().next().ok_or(AiError::EmptyResponse { provider: PROVIDER })?;
+    let text = candidate
+        .content
+        .and_then(|c| c.parts.into_iter().find_map(|p| p.text));
+
+    match text {
+        Some(text) if !text.trim().is_empty() => Ok(text),
+        _ if candidate.finish_reason.as_deref() == Some("SAFETY") => Err(AiError::ContentFiltered { provider: PROVIDER }),
+        _ => Err(AiError::EmptyResponse { provider: PROVIDER }),
+    }
+}
+
+/// `base_url` is the API root, `API_URL` outside of tests.
+pub async fn generate(client: &Client, base_url: &str, api_key: &str, model: &str, prompt: &str) -> Result<String, AiError> {
+    let url = format!("{}/models/{}:generateContent?key={}", base_url, model, api_key);
+    let request = client.post(&url).json(&GenerateRequest {
+        contents: vec![Content {
+            parts: vec![RequestPart { text: prompt }],
+        }],
+    });
+
+    let (status, body) = send(PROVIDER, request).await?;
+    parse_response(status, &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const SUCCESS: &str = r#"{"candidates":[{"content":{"parts":[{"text":"docs: explain the backend setting"}],"role":"model"},
+        "finishReason":"STOP","index":0}],"usageMetadata":{"promptTokenCount":9,"candidatesTokenCount":6}}"#;
+
+    #[test]
+    fn extracts_the_first_candidate() {
+        assert_eq!(parse_response(200, SUCCESS).unwrap(), "docs: explain the backend setting");
+    }
+
+    #[test]
+    fn maps_error_bodies() {
+        let bad_key = r#"{"error":{"code":400,"message":"API key not valid. Please pass a valid API key.","status":"INVALID_ARGUMENT"}}"#;
+        assert!(matches!(parse_response(400, bad_key), Err(AiError::Auth { .. })));
+        let rate = r#"{"error":{"code":429,"message":"Resource has been exhausted","status":"RESOURCE_EXHAUSTED"}}"#;
+        assert!(matches!(parse_response(429, rate), Err(AiError::RateLimited { .. })));
+        let bad_request = r#"{"error":{"code":400,"message":"Invalid JSON payload","status":"INVALID_ARGUMENT"}}"#;
+        assert!(matches!(parse_response(400, bad_request), Err(AiError::Api { status: 400, .. })));
+    }
+
+    #[test]
+    fn maps_blocked_and_empty_candidates() {
+        let blocked_prompt = r#"{"promptFeedback":{"blockReason":"SAFETY"}}"#;
+        assert_eq!(parse_response(200, blocked_prompt), Err(AiError::ContentFiltered { provider: PROVIDER }));
+        let blocked_reply = r#"{"candidates":[{"finishReason":"SAFETY","index":0}]}"#;
+        assert_eq!(parse_response(200, blocked_reply), Err(AiError::ContentFiltered { provider: PROVIDER }));
+        assert_eq!(parse_response(200, r#"{"candidates":[]}"#), Err(AiError::EmptyResponse { provider: PROVIDER }));
+    }
+
+    #[tokio::test]
+    async fn sends_the_key_and_model_in_the_url() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/models/test-model:generateContent"))
+            .and(query_param("key", "secret"))
+            .and(body_partial_json(serde_json::json!({ "contents": [{ "parts": [{ "text": "the diff" }] }] })))
+            .respond_with(ResponseTemplate::new(200).set_body_string(SUCCESS))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let reply = generate(&Client::new(), &server.uri(), "secret", "test-model", "the diff").await;
+        assert_eq!(reply.unwrap(), "docs: explain the backend setting");
+    }
+}
@@ -0,0 +1,106 @@
+mod anthropic;
+mod gemini;
+pub mod ollama;
+mod openai;
+
+use reqwest::Client;
+
+use crate::AiConfig;
+
+/// Failure talking to an AI provider, mapped from the HTTP status and error body so the
+/// message tells the user what to fix.
+#[derive(Debug, PartialEq)]
+pub enum AiError {
+    Connection { provider: &'static str, message: String },
+    Auth { provider: &'static str, message: String },
+    RateLimited { provider: &'static str, message: String },
+    ContentFiltered { provider: &'static str },
+    EmptyResponse { provider: &'static str },
+    Api { provider: &'static str, status: u16, message: String },
+    InvalidResponse { provider: &'static str, message: String },
+    ModelNotFound { provider: &'static str, model: String },
+    DiskFull { provider: &'static str },
+    UnknownProvider,
+}
+
+impl std::fmt::Display for AiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AiError::Connection { provider, message } => match *provider {
+                ollama::PROVIDER => write!(f, "Failed to connect to local Ollama (is it running on port 11434?): {}", message),
+                openai::LMSTUDIO => write!(f, "Failed to connect to local LM Studio (is it running on port 1234?): {}", message),
+                _ => write!(f, "Failed to connect to {}: {}", provider, message),
+            },
+            AiError::Auth { provider, message } => write!(f, "{} API error: {} Check your API key.", provider, message),
+            AiError::RateLimited { provider, message } => {
+                write!(f, "{} rate limit reached, try again in a moment: {}", provider, message)
+            }
+            AiError::ContentFiltered { provider } => write!(f, "{} refused to answer (content filter)", provider),
+            AiError::EmptyResponse { provider } => write!(f, "{} returned an empty response", provider),
+            AiError::Api { provider, status, message } => write!(f, "{} API error ({}): {}", provider, status, message),
+            AiError::InvalidResponse { provider, message } => write!(f, "Failed to parse {} response: {}", provider, message),
+            AiError::ModelNotFound { provider, model } => {
+                write!(f, "{} has no model called \"{}\"; check the name in its model library", provider, model)
+            }
+            AiError::DiskFull { provider } => write!(f, "Not enough disk space for {} to download the model", provider),
+            AiError::UnknownProvider => write!(f, "Unknown AI provider"),
+        }
+    }
+}
+
+impl From<AiError> for String {
+    fn from(err: AiError) -> Self {
+        err.to_string()
+    }
+}
+
+/// Maps a non-success HTTP status to an error, given the message extracted from the body.
+fn status_error(provider: &'static str, status: u16, message: String) -> AiError {
+    match status {
+        401 | 403 => AiError::Auth { provider, message },
+        429 => AiError::RateLimited { provider, message },
+        _ => AiError::Api { provider, status, message },
+    }
+}
+
+/// Sends a prepared request and returns the status code and raw body for the provider's parser.
+async fn send(provider: &'static str, request: reqwest::RequestBuilder) -> Result<(u16, String), AiError> {
+    let res = request.send().await.map_err(|e| AiError::Connection {
+        provider,
+        message: e.to_string(),
+    })?;
+    let status = res.status().as_u16();
+    let body = res.text().await.map_err(|e| AiError::InvalidResponse {
+        provider,
+        message: e.to_string(),
+    })?;
+    Ok((status, body))
+}
+
+/// Sends `prompt` to the configured provider and returns the trimmed text of its reply.
+pub async fn send_ai_prompt(config: AiConfig, prompt: String) -> Result<String, String> {
+    let client = Client::builder()
+        .timeout(std::time::Duration::from_secs(45))
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let api_key = config.api_key.as_deref().unwrap_or_default();
+    let reply = match config.provider.as_str() {
+        "ollama" => ollama::generate(&client, ollama::BASE_URL, &config.model, &prompt).await,
+        "lmstudio" => {
+            openai::generate(&client, openai::LMSTUDIO, "http://localhost:1234/v1/chat/completions", None, &config.model, &prompt).await
+        }
+        "openai" => {
+            openai::generate(&client, openai::OPENAI, "https://api.openai.com/v1/chat/completions", Some(api_key), &config.model, &prompt).await
+        }
+        "gemini" => gemini::generate(&client, gemini::API_URL, api_key, &config.model, &prompt).await,
+        "anthropic" => anthropic::generate(&client, anthropic::API_URL, api_key, &config.model, &prompt).await,
+        "custom" => {
+            let url = openai::chat_completions_url(config.custom_api_url.as_deref());
+            openai::generate(&client, openai::CUSTOM, &url, Some(api_key), &config.model, &prompt).await
+        }
+        _ => Err(AiError::UnknownProvider),
+    };
+
+    Ok(reply?.trim().to_string())
+}
@@ -0,0 +1,209 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use super::{send, status_error, AiError};
+
+pub const PROVIDER: &str = "Ollama";
+pub const BASE_URL: &str = "http://localhost:11434";
+
+#[derive(Serialize)]
+struct GenerateRequest<'a> {
+    model: &'a str,
+    prompt: &'a str,
+    stream: bool,
+}
+
+#[derive(Deserialize)]
+struct GenerateResponse {
+    response: String,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+#[derive(Serialize)]
+struct PullRequest<'a> {
+    model: &'a str,
+    stream: bool,
+}
+
+/// One line of the `/api/pull` stream. Byte counts are only sent while a layer downloads.
+#[derive(Deserialize, Debug)]
+pub struct PullProgress {
+    #[serde(default)]
+    pub status: String,
+    pub completed: Option<u64>,
+    pub total: Option<u64>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TagsResponse {
+    models: Vec<TagsModel>,
+}
+
+#[derive(Deserialize)]
+struct TagsModel {
+    name: String,
+}
+
+fn parse_response(status: u16, body: &str) -> Result<String, AiError> {
+    if !(200..300).contains(&status) {
+        let message = serde_json::from_str::<ErrorResponse>(body)
+            .map(|e| e.error)
+            .unwrap_or_else(|_| body.to_string());
+        return Err(status_error(PROVIDER, status, message));
+    }
+
+    let parsed: GenerateResponse = serde_json::from_str(body).map_err(|e| AiError::InvalidResponse {
+        provider: PROVIDER,
+        message: e.to_string(),
+    })?;
+    if parsed.response.trim().is_empty() {
+        return Err(AiError::EmptyResponse { provider: PROVIDER });
+    }
+    Ok(parsed.response)
+}
+
+/// `base_url` is the server root, `BASE_URL` outside of tests.
+pub async fn generate(client: &Client, base_url: &str, model: &str, prompt: &str) -> Result<String, AiError> {
+    let request = client.post(format!("{}/api/generate", base_url)).json(&GenerateRequest {
+        model,
+        prompt,
+        stream: false,
+    });
+
+    let (status, body) = send(PROVIDER, request).await?;
+    parse_response(status, &body)
+}
+
+/// Ollama reports pull failures as plain strings; the two users can act on get their own error.
+fn pull_error(model: &str, message: String) -> AiError {
+    let lower = message.to_lowercase();
+    if lower.contains("no space left") || lower.contains("not enough space") || lower.contains("disk full") {
+        AiError::DiskFull { provider: PROVIDER }
+    } else if lower.contains("file does not exist") || lower.contains("not found") {
+        AiError::ModelNotFound { provider: PROVIDER, model: model.to_string() }
+    } else {
+        AiError::Api { provider: PROVIDER, status: 200, message }
+    }
+}
+
+/// Downloads `model`, calling `on_progress` for every status line Ollama streams back.
+pub async fn pull(client: &Client, model: &str, mut on_progress: impl FnMut(&PullProgress)) -> Result<(), AiError> {
+    let mut res = client
+        .post(format!("{}/api/pull", BASE_URL))
+        .json(&PullRequest { model, stream: true })
+        .send()
+        .await
+        .map_err(|e| AiError::Connection { provider: PROVIDER, message: e.to_string() })?;
+
+    let status = res.status().as_u16();
+    if !(200..300).contains(&status) {
+        let body = res.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<ErrorResponse>(&body).map(|e| e.error).unwrap_or(body);
+        return Err(match status {
+            404 => AiError::ModelNotFound { provider: PROVIDER, model: model.to_string() },
+            500 => pull_error(model, message),
+            _ => status_error(PROVIDER, status, message),
+        });
+    }
+
+    // Newline-delimited JSON; a chunk can end partway through a line
+    let mut pending = Vec::new();
+    while let Some(chunk) = res
+        .chunk()
+        .await
+        .map_err(|e| AiError::Connection { provider: PROVIDER, message: e.to_string() })?
+    {
+        pending.extend_from_slice(&chunk);
+        while let Some(end) = pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=end).collect();
+            let Ok(progress) = serde_json::from_slice::<PullProgress>(&line) else {
+                continue;
+            };
+            if let Some(error) = progress.error {
+                return Err(pull_error(model, error));
+            }
+            on_progress(&progress);
+        }
+    }
+    Ok(())
+}
+
+/// Names of the models Ollama has installed, as `name:tag`.
+pub async fn installed_models(client: &Client) -> Result<Vec<String>, AiError> {
+    let (status, body) = send(PROVIDER, client.get(format!("{}/api/tags", BASE_URL))).await?;
+    if !(200..300).contains(&status) {
+        return Err(status_error(PROVIDER, status, body));
+    }
+    let tags: TagsResponse = serde_json::from_str(&body).map_err(|e| AiError::InvalidResponse {
+        provider: PROVIDER,
+        message: e.to_string(),
+    })?;
+    Ok(tags.models.into_iter().map(|m| m.name).collect())
+}
+
+/// Whether two model names refer to the same model, where a name without a tag means `:latest`.
+pub fn same_model(a: &str, b: &str) -> bool {
+    let with_tag = |name: &str| if name.contains(':') { name.to_string() } else { format!("{}:latest", name) };
+    with_tag(a) == with_tag(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const SUCCESS: &str = r#"{"model":"test-model","created_at":"2024-05-01T10:00:00Z",
+        "response":"chore: bump dependencies","done":true,"done_reason":"stop"}"#;
+
+    #[test]
+    fn extracts_the_response() {
+        assert_eq!(parse_response(200, SUCCESS).unwrap(), "chore: bump dependencies");
+    }
+
+    #[test]
+    fn maps_error_and_empty_bodies() {
+        let missing = r#"{"error":"model \"nope\" not found, try pulling it first"}"#;
+        assert_eq!(
+            parse_response(404, missing),
+            Err(AiError::Api { provider: PROVIDER, status: 404, message: "model \"nope\" not found, try pulling it first".to_string() })
+        );
+        let empty = r#"{"model":"test-model","response":"","done":true}"#;
+        assert_eq!(parse_response(200, empty), Err(AiError::EmptyResponse { provider: PROVIDER }));
+    }
+
+    #[test]
+    fn maps_pull_failures() {
+        let disk = pull_error("m", "write /models/blobs: no space left on device".to_string());
+        assert_eq!(disk, AiError::DiskFull { provider: PROVIDER });
+        let missing = pull_error("nope", "pull model manifest: file does not exist".to_string());
+        assert_eq!(missing, AiError::ModelNotFound { provider: PROVIDER, model: "nope".to_string() });
+        assert!(matches!(pull_error("m", "unexpected EOF".to_string()), AiError::Api { .. }));
+    }
+
+    #[test]
+    fn matches_models_with_and_without_a_tag() {
+        assert!(same_model("llama3", "llama3:latest"));
+        assert!(!same_model("llama3:8b", "llama3"));
+    }
+
+    #[tokio::test]
+    async fn asks_for_a_single_non_streamed_reply() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(serde_json::json!({ "model": "test-model", "prompt": "the diff", "stream": false })))
+            .respond_with(ResponseTemplate::new(200).set_body_string(SUCCESS))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let reply = generate(&Client::new(), &server.uri(), "test-model", "the diff").await;
+        assert_eq!(reply.unwrap(), "chore: bump dependencies");
+    }
+}
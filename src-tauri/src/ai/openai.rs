@@ -0,0 +1,178 @@
+//! OpenAI chat completions, also used for LM Studio and custom OpenAI-compatible endpoints.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use super::{send, status_error, AiError};
+
+pub const OPENAI: &str = "OpenAI";
+pub const LMSTUDIO: &str = "LM Studio";
+pub const CUSTOM: &str = "Custom endpoint";
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage<'a>>,
+}
+
+#[derive(Serialize)]
+struct ChatMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    #[serde(default)]
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: Option<ChoiceMessage>,
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChoiceMessage {
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: ErrorDetail,
+}
+
+#[derive(Deserialize)]
+struct ErrorDetail {
+    message: String,
+}
+
+/// Accepts a base URL (`https://host/v1`) or a full `/chat/completions` URL.
+pub fn chat_completions_url(base_url: Option<&str>) -> String {
+    let base_url = base_url.unwrap_or("https://api.openai.com/v1");
+    if base_url.ends_with("/chat/completions") {
+        base_url.to_string()
+    } else if base_url.ends_with('/') {
+        format!("{}chat/completions", base_url)
+    } else {
+        format!("{}/chat/completions", base_url)
+    }
+}
+
+fn parse_response(provider: &'static str, status: u16, body: &str) -> Result<String, AiError> {
+    if !(200..300).contains(&status) {
+        let message = serde_json::from_str::<ErrorResponse>(body)
+            .map(|e| e.error.message)
+            .unwrap_or_else(|_| body.to_string());
+        return Err(status_error(provider, status, message));
+    }
+
+    let parsed: ChatResponse = serde_json::from_str(body).map_err(|e| AiError::InvalidResponse {
+        provider,
+        message: e.to_string(),
+    })?;
+    let choice = parsed.choices.into_iter().next().ok_or(AiError::EmptyResponse { provider })?;
+
+    match choice.message.and_then(|m| m.content) {
+        Some(content) if !content.trim().is_empty() => Ok(content),
+        _ if choice.finish_reason.as_deref() == Some("content_filter") => Err(AiError::ContentFiltered { provider }),
+        _ => Err(AiError::EmptyResponse { provider }),
+    }
+}
+
+pub async fn generate(client: &Client, provider: &'static str, url: &str, api_key: Option<&str>, model: &str, prompt: &str) -> Result<String, AiError> {
+    let mut request = client.post(url).json(&ChatRequest {
+        model,
+        messages: vec![ChatMessage { role: "user", content: prompt }],
+    });
+    if let Some(key) = api_key {
+        request = request.bearer_auth(key);
+    }
+
+    let (status, body) = send(provider, request).await?;
+    parse_response(provider, status, &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const SUCCESS: &str = r#"{"id":"chatcmpl-1","object":"chat.completion","created":1700000000,"model":"test-model",
+        "choices":[{"index":0,"message":{"role":"assistant","content":"fix: handle empty diffs"},"finish_reason":"stop"}],
+        "usage":{"prompt_tokens":10,"completion_tokens":5,"total_tokens":15}}"#;
+
+    #[test]
+    fn extracts_the_first_choice() {
+        assert_eq!(parse_response(OPENAI, 200, SUCCESS).unwrap(), "fix: handle empty diffs");
+    }
+
+    #[test]
+    fn maps_error_bodies() {
+        let auth = r#"{"error":{"message":"Incorrect API key provided: sk-xx.","type":"invalid_request_error","param":null,"code":"invalid_api_key"}}"#;
+        assert_eq!(
+            parse_response(OPENAI, 401, auth),
+            Err(AiError::Auth { provider: OPENAI, message: "Incorrect API key provided: sk-xx.".to_string() })
+        );
+        let rate = r#"{"error":{"message":"Rate limit reached","type":"requests","code":"rate_limit_exceeded"}}"#;
+        assert!(matches!(parse_response(OPENAI, 429, rate), Err(AiError::RateLimited { provider: OPENAI, .. })));
+        assert_eq!(
+            parse_response(CUSTOM, 500, "upstream timeout"),
+            Err(AiError::Api { provider: CUSTOM, status: 500, message: "upstream timeout".to_string() })
+        );
+    }
+
+    #[test]
+    fn maps_filtered_and_empty_choices() {
+        let filtered = r#"{"choices":[{"index":0,"message":{"role":"assistant","content":null},"finish_reason":"content_filter"}]}"#;
+        assert_eq!(parse_response(OPENAI, 200, filtered), Err(AiError::ContentFiltered { provider: OPENAI }));
+        assert_eq!(parse_response(LMSTUDIO, 200, r#"{"choices":[]}"#), Err(AiError::EmptyResponse { provider: LMSTUDIO }));
+    }
+
+    #[test]
+    fn builds_the_completions_url() {
+        assert_eq!(chat_completions_url(None), "https://api.openai.com/v1/chat/completions");
+        assert_eq!(chat_completions_url(Some("http://host/v1/")), "http://host/v1/chat/completions");
+        assert_eq!(chat_completions_url(Some("http://host/v1/chat/completions")), "http://host/v1/chat/completions");
+    }
+
+    #[tokio::test]
+    async fn sends_a_bearer_token_to_a_compatible_endpoint() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("authorization", "Bearer secret"))
+            .and(body_partial_json(serde_json::json!({
+                "model": "test-model",
+                "messages": [{ "role": "user", "content": "the diff" }]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_string(SUCCESS))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let url = chat_completions_url(Some(&format!("{}/v1", server.uri())));
+        let reply = generate(&Client::new(), CUSTOM, &url, Some("secret"), "test-model", "the diff").await;
+        assert_eq!(reply.unwrap(), "fix: handle empty diffs");
+    }
+
+    #[tokio::test]
+    async fn maps_a_rate_limit_from_the_server() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).set_body_string(r#"{"error":{"message":"Slow down"}}"#))
+            .mount(&server)
+            .await;
+
+        let reply = generate(&Client::new(), LMSTUDIO, &server.uri(), None, "test-model", "the diff").await;
+        assert_eq!(reply, Err(AiError::RateLimited { provider: LMSTUDIO, message: "Slow down".to_string() }));
+    }
+
+    #[tokio::test]
+    async fn reports_an_unreachable_server_as_a_connection_error() {
+        let reply = generate(&Client::new(), LMSTUDIO, "http://127.0.0.1:9/v1/chat/completions", None, "m", "p").await;
+        assert!(matches!(reply, Err(AiError::Connection { provider: LMSTUDIO, .. })));
+    }
+}
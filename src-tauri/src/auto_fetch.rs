@@ -0,0 +1,221 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::read_only::ReadOnlyRepos;
+use crate::settings::SettingsService;
+use crate::{build_hidden_cmd, git_path, repo_key, run_git, spawn_error};
+
+const ENABLED_KEY: &str = "autoFetch";
+const INTERVAL_KEY: &str = "autoFetchMinutes";
+const DEFAULT_MINUTES: u64 = 15;
+const MIN_MINUTES: u64 = 5;
+/// Backoff after repeated failures never waits longer than this.
+const MAX_BACKOFF: Duration = Duration::from_secs(4 * 60 * 60);
+/// How often the scheduler wakes up to see whether a repository is due.
+const TICK: Duration = Duration::from_secs(60);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+const UPDATED_EVENT: &str = "repo://remote-updated";
+const FAILED_EVENT: &str = "repo://auto-fetch-failed";
+
+#[derive(Default)]
+struct RepoSchedule {
+    /// When the last background attempt ran; the next one waits at least an interval after
+    last_attempt: Option<Instant>,
+    /// Consecutive failures, doubling the wait each time
+    failures: u32,
+    /// Whether the current run of failures has already been reported
+    warned: bool,
+}
+
+/// Repositories open in GitPop and when each was last fetched in the background.
+#[derive(Default)]
+pub struct AutoFetch(Mutex<HashMap<String, RepoSchedule>>);
+
+#[derive(Serialize, Clone)]
+struct RemoteUpdated {
+    path: String,
+}
+
+#[derive(Serialize, Clone)]
+struct FetchFailed {
+    path: String,
+    message: String,
+}
+
+/// The configured interval, or `None` when background fetching is off (the default).
+fn interval(settings: &Map<String, Value>) -> Option<Duration> {
+    if settings.get(ENABLED_KEY).and_then(Value::as_bool) != Some(true) {
+        return None;
+    }
+    let minutes = match settings.get(INTERVAL_KEY) {
+        Some(Value::Number(n)) => n.as_u64().unwrap_or(DEFAULT_MINUTES),
+        Some(Value::String(s)) => s.trim().parse().unwrap_or(DEFAULT_MINUTES),
+        _ => DEFAULT_MINUTES,
+    };
+    Some(Duration::from_secs(minutes.max(MIN_MINUTES) * 60))
+}
+
+/// How long ago anything (GitPop, the command line, an IDE) last fetched, from `FETCH_HEAD`.
+fn since_last_fetch(path: &str) -> Option<Duration> {
+    let modified = std::fs::metadata(git_path(path, "FETCH_HEAD").ok()?).ok()?.modified().ok()?;
+    SystemTime::now().duration_since(modified).ok()
+}
+
+/// Another git process holds the index, e.g. a commit or rebase running in a terminal.
+fn repo_busy(path: &str) -> bool {
+    git_path(path, "index.lock").is_ok_and(|lock| lock.exists())
+}
+
+/// `host:port` to probe for the first remote's URL, covering `https://`, `ssh://` and scp-style
+/// `git@host:owner/repo` URLs. Local remotes need no network and yield `None`.
+fn remote_address(url: &str) -> Option<String> {
+    let (default_port, rest) = if let Some(rest) = url.strip_prefix("https://") {
+        (443, rest)
+    } else if let Some(rest) = url.strip_prefix("http://") {
+        (80, rest)
+    } else if let Some(rest) = url.strip_prefix("ssh://") {
+        (22, rest)
+    } else if let Some(rest) = url.strip_prefix("git://") {
+        (9418, rest)
+    } else if !url.contains("://") && url.contains(':') && !url.starts_with('/') && url.find(':') != Some(1) {
+        let host = url.split(':').next()?;
+        return Some(format!("{}:22", host.rsplit('@').next()?));
+    } else {
+        return None;
+    };
+    let authority = rest.split('/').next()?.rsplit('@').next()?;
+    if authority.is_empty() {
+        return None;
+    }
+    match authority.rsplit_once(':') {
+        Some((_, port)) if port.parse::<u16>().is_ok() => Some(authority.to_string()),
+        _ => Some(format!("{}:{}", authority, default_port)),
+    }
+}
+
+/// A quick TCP connect to the remote, so an offline laptop doesn't rack up fetch failures.
+/// Metered connections can't be detected portably, so they aren't skipped.
+fn remote_reachable(path: &str) -> bool {
+    let Ok(remotes) = run_git(path, &["remote"]) else {
+        return false;
+    };
+    let Some(remote) = remotes.lines().next() else {
+        return false;
+    };
+    let Ok(url) = run_git(path, &["remote", "get-url", remote]) else {
+        return false;
+    };
+    let Some(address) = remote_address(url.trim()) else {
+        return true;
+    };
+    let Ok(mut addrs) = address.to_socket_addrs() else {
+        return false;
+    };
+    addrs.any(|addr| TcpStream::connect_timeout(&addr, PROBE_TIMEOUT).is_ok())
+}
+
+fn remote_refs(path: &str) -> String {
+    run_git(path, &["for-each-ref", "--format=%(refname) %(objectname)", "refs/remotes"]).unwrap_or_default()
+}
+
+fn fetch(path: &str) -> Result<(), String> {
+    let output = build_hidden_cmd("git")
+        .current_dir(path)
+        .args(["fetch", "--all", "--prune", "--quiet"])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(std::process::Stdio::null())
+        .output()
+        .map_err(spawn_error)?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(())
+}
+
+impl AutoFetch {
+    /// Starts the background thread. It only fetches repositories registered through
+    /// `watch_auto_fetch` while the setting is on.
+    pub fn spawn(app: AppHandle) {
+        std::thread::spawn(move || loop {
+            std::thread::sleep(TICK);
+            let Ok(settings) = tauri::async_runtime::block_on(app.state::<SettingsService>().get()) else {
+                continue;
+            };
+            if !crate::backend::git_found() {
+                continue;
+            }
+            if let Some(interval) = interval(&settings) {
+                app.state::<AutoFetch>().tick(&app, interval);
+            }
+        });
+    }
+
+    fn tick(&self, app: &AppHandle, interval: Duration) {
+        let due: Vec<String> = {
+            let repos = self.0.lock().unwrap();
+            repos
+                .iter()
+                .filter(|(_, schedule)| {
+                    let wait = interval.saturating_mul(1 << schedule.failures.min(16)).min(MAX_BACKOFF.max(interval));
+                    schedule.last_attempt.is_none_or(|at| at.elapsed() >= wait)
+                })
+                .map(|(path, _)| path.clone())
+                .collect()
+        };
+
+        for path in due {
+            // Fetching writes remote-tracking refs, so read-only repositories are left alone
+            if app.state::<ReadOnlyRepos>().is_read_only(&path) || repo_busy(&path) {
+                continue;
+            }
+            if since_last_fetch(&path).is_some_and(|age| age < interval) {
+                continue;
+            }
+            if !remote_reachable(&path) {
+                continue;
+            }
+
+            let before = remote_refs(&path);
+            let result = fetch(&path);
+            let changed = result.is_ok() && remote_refs(&path) != before;
+            let mut repos = self.0.lock().unwrap();
+            let Some(schedule) = repos.get_mut(&path) else {
+                continue;
+            };
+            schedule.last_attempt = Some(Instant::now());
+            match result {
+                Ok(()) => {
+                    schedule.failures = 0;
+                    schedule.warned = false;
+                    if changed {
+                        let _ = app.emit(UPDATED_EVENT, RemoteUpdated { path: path.clone() });
+                    }
+                }
+                Err(message) => {
+                    schedule.failures += 1;
+                    if !schedule.warned {
+                        schedule.warned = true;
+                        let _ = app.emit(FAILED_EVENT, FetchFailed { path: path.clone(), message });
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Adds the repository at `path` to the background fetch schedule.
+#[tauri::command]
+pub fn watch_auto_fetch(path: &str, auto_fetch: State<'_, AutoFetch>) {
+    auto_fetch.0.lock().unwrap().entry(repo_key(path)).or_default();
+}
+
+#[tauri::command]
+pub fn unwatch_auto_fetch(path: &str, auto_fetch: State<'_, AutoFetch>) {
+    auto_fetch.0.lock().unwrap().remove(&repo_key(path));
+}
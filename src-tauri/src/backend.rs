@@ -0,0 +1,265 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+#[cfg(test)]
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::State;
+
+use crate::activity::Operation;
+use crate::diff::{self, DiffOptions, DiffScope};
+use crate::error::CommandError;
+use crate::history::CommitEntry;
+use crate::settings::SettingsService;
+use crate::{build_hidden_cmd, CommitOptions, CommitRequest, CommitResult, GitFileStatus, SyncStatus};
+
+/// Settings key holding the backend preference, `"cli"` (the default) or `"libgit2"`.
+pub const BACKEND_KEY: &str = "gitBackend";
+
+/// Set once a git executable has been found; a missing git is re-checked on every call so
+/// installing it mid-session is picked up.
+static GIT_FOUND: AtomicBool = AtomicBool::new(false);
+
+/// Set from `BACKEND_KEY` at startup and by `set_git_backend`.
+static PREFER_LIBGIT2: AtomicBool = AtomicBool::new(false);
+
+/// Which backend the user asked for; git being missing overrides it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendPreference {
+    Cli,
+    Libgit2,
+}
+
+/// What GitPop talks to the repository through.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Backend {
+    /// The git executable; everything is available
+    Git,
+    /// Chosen in settings: status, diffs, history and plain commits go through libgit2, which
+    /// saves starting a git process on every refresh; git still does everything else
+    Libgit2,
+    /// No git found, so libgit2 is used: status, diffs, history and plain commits only
+    Libgit2Only,
+    /// No git found, so gitoxide is used to look at the repository; nothing can be changed
+    EmbeddedReadonly,
+    /// No git found and this build has no embedded backend
+    Unavailable,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RepoInfo {
+    backend: Backend,
+    /// Work tree root, null when `path` isn't inside a repository
+    root: Option<String>,
+    /// Shown as a persistent banner while git is missing
+    notice: Option<&'static str>,
+}
+
+/// What GitPop reads and commits a repository through. Every implementation returns the same
+/// shapes as the git executable (`GitFileStatus` lists, `git diff` text), so callers and the
+/// frontend don't need to know which one is in use.
+pub(crate) trait GitBackend: Sync {
+    /// Same as `git rev-parse --show-toplevel`, forward slashes included.
+    fn repo_root(&self, path: &str) -> Result<String, String>;
+
+    /// What `get_git_status` returns.
+    fn status(&self, path: &str, include_ignored: bool) -> Result<Vec<GitFileStatus>, String>;
+
+    /// One side of the changes to `file` (every changed file when `None`) as `git diff` text:
+    /// the staged side with `staged`, otherwise work tree against index with untracked files
+    /// diffed against `/dev/null`.
+    fn file_diff(&self, path: &str, file: Option<&str>, staged: bool, options: DiffOptions) -> Result<String, String>;
+
+    /// Size of the blob `blob` (possibly abbreviated) names, or of `file` in the work tree when
+    /// it isn't in the object database; see `diff::describe_binaries`.
+    fn blob_size(&self, path: &str, blob: &str, file: &str) -> Option<u64>;
+
+    /// Summary and diff of `scope`, as `diff::collect_diff` describes them.
+    fn diff(&self, path: &str, scope: &DiffScope, options: DiffOptions) -> Result<String, String> {
+        diff::assemble_diff(self, path, scope, options)
+    }
+
+    /// The untracked entries of `files` that .gitignore rules exclude.
+    fn ignored(&self, path: &str, files: &[String]) -> Vec<String> {
+        let Ok(statuses) = self.status(path, true) else {
+            return Vec::new();
+        };
+        let ignored: Vec<String> = statuses.into_iter().filter(|s| s.status == "I").map(|s| s.path).collect();
+        // A wholly ignored directory is listed once, as `dir/`
+        files
+            .iter()
+            .filter(|file| ignored.iter().any(|i| *file == i || (i.ends_with('/') && file.starts_with(i.as_str()))))
+            .cloned()
+            .collect()
+    }
+
+    fn sync_status(&self, path: &str) -> Result<SyncStatus, String>;
+
+    fn history(&self, path: &str, limit: usize) -> Result<Vec<CommitEntry>, String>;
+
+    /// Whether `commit` can commit `files` with `options` in `path` the way git would;
+    /// `commit_changes` falls back to git otherwise.
+    fn can_commit(&self, path: &str, options: &CommitOptions, files: &[String]) -> bool;
+
+    /// Fails with `IdentityNotConfigured` when there's no author name or email to commit with.
+    fn ensure_identity(&self, path: &str) -> Result<(), CommandError>;
+
+    /// Commits the selection of a checked `request`, leaving the index as it was on failure.
+    fn commit(&self, path: &str, request: CommitRequest, op: &mut Operation) -> Result<CommitResult, CommandError>;
+}
+
+/// The git executable.
+pub(crate) struct Cli;
+
+impl GitBackend for Cli {
+    fn repo_root(&self, path: &str) -> Result<String, String> {
+        crate::git_repo_root(path)
+    }
+
+    fn status(&self, path: &str, include_ignored: bool) -> Result<Vec<GitFileStatus>, String> {
+        crate::git_status(path, include_ignored)
+    }
+
+    fn file_diff(&self, path: &str, file: Option<&str>, staged: bool, options: DiffOptions) -> Result<String, String> {
+        diff::file_diff(path, file, staged, options)
+    }
+
+    fn blob_size(&self, path: &str, blob: &str, file: &str) -> Option<u64> {
+        diff::git_blob_size(path, blob, file)
+    }
+
+    fn diff(&self, path: &str, scope: &DiffScope, options: DiffOptions) -> Result<String, String> {
+        diff::collect_git_diff(path, scope, options)
+    }
+
+    fn ignored(&self, path: &str, files: &[String]) -> Vec<String> {
+        crate::ignored_files(path, files)
+    }
+
+    fn sync_status(&self, path: &str) -> Result<SyncStatus, String> {
+        crate::git_sync_status(path)
+    }
+
+    fn history(&self, path: &str, limit: usize) -> Result<Vec<CommitEntry>, String> {
+        crate::history::git_history(path, limit)
+    }
+
+    fn can_commit(&self, _path: &str, _options: &CommitOptions, _files: &[String]) -> bool {
+        true
+    }
+
+    fn ensure_identity(&self, path: &str) -> Result<(), CommandError> {
+        crate::git_config::ensure_identity(path)
+    }
+
+    fn commit(&self, path: &str, request: CommitRequest, op: &mut Operation) -> Result<CommitResult, CommandError> {
+        crate::commit_with_git(path, request, op)
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Makes `git_found` report git missing on the current test's thread only.
+    pub(crate) static PRETEND_GIT_MISSING: Cell<bool> = const { Cell::new(false) };
+}
+
+pub fn git_found() -> bool {
+    #[cfg(test)]
+    if PRETEND_GIT_MISSING.with(Cell::get) {
+        return false;
+    }
+    if GIT_FOUND.load(Ordering::Relaxed) {
+        return true;
+    }
+    let found = build_hidden_cmd("git")
+        .arg("--version")
+        .output()
+        .is_ok_and(|out| out.status.success());
+    GIT_FOUND.store(found, Ordering::Relaxed);
+    found
+}
+
+pub fn current() -> Backend {
+    let libgit2 = cfg!(feature = "libgit2");
+    if git_found() {
+        match libgit2 && PREFER_LIBGIT2.load(Ordering::Relaxed) {
+            true => Backend::Libgit2,
+            false => Backend::Git,
+        }
+    } else if libgit2 {
+        Backend::Libgit2Only
+    } else if cfg!(feature = "embedded-git") {
+        Backend::EmbeddedReadonly
+    } else {
+        Backend::Unavailable
+    }
+}
+
+/// The backend status, diffs and history are read through. Without any, git is still used so
+/// commands fail with `GitNotInstalled`.
+pub(crate) fn active() -> &'static dyn GitBackend {
+    match current() {
+        #[cfg(feature = "libgit2")]
+        Backend::Libgit2 | Backend::Libgit2Only => &crate::libgit2::Libgit2,
+        #[cfg(feature = "embedded-git")]
+        Backend::EmbeddedReadonly => &crate::embedded::Gix,
+        _ => &Cli,
+    }
+}
+
+/// The backend `commit_changes` commits through: the active one when it can make this commit,
+/// otherwise git, or `GitNotInstalled` when that's missing.
+pub(crate) fn for_commit(path: &str, options: &CommitOptions, files: &[String]) -> Result<&'static dyn GitBackend, CommandError> {
+    let backend = active();
+    if backend.can_commit(path, options, files) {
+        Ok(backend)
+    } else if git_found() {
+        Ok(&Cli)
+    } else {
+        Err(CommandError::GitNotInstalled)
+    }
+}
+
+/// Applies the saved preference; called once at startup.
+pub fn load_preference(settings: &Map<String, Value>) {
+    let libgit2 = settings.get(BACKEND_KEY).and_then(Value::as_str) == Some("libgit2");
+    PREFER_LIBGIT2.store(libgit2, Ordering::Relaxed);
+}
+
+/// Switches between the git executable and libgit2 for status, diffs, history and plain
+/// commits, and saves the choice. Without git libgit2 is used whatever is chosen.
+#[tauri::command]
+pub async fn set_git_backend(
+    preference: BackendPreference,
+    settings: State<'_, SettingsService>,
+) -> Result<Backend, String> {
+    if preference == BackendPreference::Libgit2 && !cfg!(feature = "libgit2") {
+        return Err("This build of GitPop doesn't include the libgit2 backend".to_string());
+    }
+    let mut changes = Map::new();
+    changes.insert(BACKEND_KEY.to_string(), serde_json::to_value(preference).map_err(|e| e.to_string())?);
+    settings.update(changes).await?;
+    PREFER_LIBGIT2.store(preference == BackendPreference::Libgit2, Ordering::Relaxed);
+    Ok(current())
+}
+
+#[tauri::command]
+pub fn get_repo_info(path: &str) -> RepoInfo {
+    let backend = current();
+    let root = match backend {
+        Backend::Unavailable => None,
+        _ => active().repo_root(path).ok(),
+    };
+    let notice = match backend {
+        Backend::Git | Backend::Libgit2 => None,
+        Backend::Libgit2Only => Some(
+            "Git isn't installed, so GitPop can only show changes, diffs and history and make plain commits. Install git to push, pull and everything else.",
+        ),
+        Backend::EmbeddedReadonly => Some(
+            "Git isn't installed, so GitPop is read-only: you can browse changes, diffs and history. Install git to commit and push.",
+        ),
+        Backend::Unavailable => Some("Git isn't installed. Install git to use GitPop."),
+    };
+    RepoInfo { backend, root, notice }
+}
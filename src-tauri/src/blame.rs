@@ -0,0 +1,173 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::run_git;
+
+/// The commit hash `git blame` gives lines that aren't committed yet.
+const UNCOMMITTED: &str = "0000000000000000000000000000000000000000";
+
+#[derive(Serialize, Debug)]
+pub struct BlameLine {
+    line_no: u32,
+    content: String,
+    commit: String,
+    author: String,
+    /// Author time as a unix timestamp
+    author_time: i64,
+    summary: String,
+    /// The commit is where history stops (a root commit or the edge of a range), so the line
+    /// may be older than it
+    is_boundary: bool,
+    /// Changed in the work tree (or index) and not committed yet
+    uncommitted: bool,
+}
+
+#[derive(Default, Clone)]
+struct CommitMeta {
+    author: String,
+    author_time: i64,
+    summary: String,
+    is_boundary: bool,
+}
+
+/// Parses `git blame --porcelain`. Each line starts with `<sha> <orig> <final> [<count>]`;
+/// the first time a commit appears its metadata (`author`, `summary`, ...) follows, later lines
+/// from the same commit only repeat the sha. The line's content comes last, after a tab.
+fn parse_porcelain(out: &str) -> Vec<BlameLine> {
+    let mut commits: HashMap<String, CommitMeta> = HashMap::new();
+    let mut lines = Vec::new();
+    let mut current: Option<(String, u32)> = None;
+
+    for line in out.lines() {
+        if let Some(content) = line.strip_prefix('\t') {
+            let Some((commit, line_no)) = current.take() else {
+                continue;
+            };
+            let meta = commits.get(&commit).cloned().unwrap_or_default();
+            lines.push(BlameLine {
+                line_no,
+                content: content.to_string(),
+                uncommitted: commit == UNCOMMITTED,
+                commit,
+                author: meta.author,
+                author_time: meta.author_time,
+                summary: meta.summary,
+                is_boundary: meta.is_boundary,
+            });
+            continue;
+        }
+
+        let Some((commit, _)) = &current else {
+            let mut fields = line.split(' ');
+            let (Some(sha), Some(_orig), Some(final_line)) = (fields.next(), fields.next(), fields.next()) else {
+                continue;
+            };
+            if sha.len() >= 40 && sha.chars().all(|c| c.is_ascii_hexdigit()) {
+                commits.entry(sha.to_string()).or_default();
+                current = Some((sha.to_string(), final_line.parse().unwrap_or(0)));
+            }
+            continue;
+        };
+        let meta = commits.entry(commit.clone()).or_default();
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        match key {
+            "author" => meta.author = value.to_string(),
+            "author-time" => meta.author_time = value.parse().unwrap_or(0),
+            "summary" => meta.summary = value.to_string(),
+            "boundary" => meta.is_boundary = true,
+            _ => {}
+        }
+    }
+    lines
+}
+
+/// Who last changed each line of `file`, as of `revision` (the work tree if not given),
+/// optionally only lines `start_line` to `end_line` for big files.
+#[tauri::command]
+pub async fn get_blame(
+    path: String,
+    file: String,
+    revision: Option<String>,
+    start_line: Option<u32>,
+    end_line: Option<u32>,
+) -> Result<Vec<BlameLine>, String> {
+    let revision = revision.filter(|r| !r.trim().is_empty());
+    if let Some(revision) = revision.as_deref().filter(|r| r.starts_with('-')) {
+        return Err(format!("Invalid revision: {}", revision));
+    }
+    let range = match (start_line, end_line) {
+        (Some(start), Some(end)) if start == 0 || end < start => {
+            return Err(format!("Invalid line range {}-{}", start, end));
+        }
+        (Some(start), Some(end)) => Some(format!("-L{},{}", start, end)),
+        (Some(start), None) => Some(format!("-L{},", start.max(1))),
+        (None, Some(end)) => Some(format!("-L1,{}", end)),
+        (None, None) => None,
+    };
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut args = vec!["blame", "--porcelain"];
+        args.extend(range.as_deref());
+        args.extend(revision.as_deref());
+        args.extend(["--", &file]);
+        Ok(parse_porcelain(&run_git(&path, &args)?))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestRepo;
+
+    const A: &str = "1111111111111111111111111111111111111111";
+    const B: &str = "2222222222222222222222222222222222222222";
+
+    /// Line number, content, commit summary and whether it's uncommitted.
+    fn fields(lines: &[BlameLine]) -> Vec<(u32, &str, &str, bool)> {
+        lines.iter().map(|l| (l.line_no, l.content.as_str(), l.summary.as_str(), l.uncommitted)).collect()
+    }
+
+    #[test]
+    fn later_lines_reuse_the_commit_header() {
+        let out = format!(
+            "{A} 1 1 2\nauthor Ada\nauthor-mail <ada@example.com>\nauthor-time 1700000000\nsummary Initial commit\nboundary\nfilename a.txt\n\tfirst\n\
+             {A} 2 2\n\t\tindented\n\
+             {B} 2 3 1\nauthor Grace\nauthor-time 1700000500\nsummary Add third\nprevious {A} a.txt\nfilename a.txt\n\tauthor Mallory\n\
+             {A} 3 4 1\n\tfourth\n\
+             {UNCOMMITTED} 4 5 1\nauthor Not Committed Yet\nauthor-time 1700001000\nsummary Version of a.txt from a.txt\nfilename a.txt\n\tnew\n"
+        );
+
+        let lines = parse_porcelain(&out);
+        assert_eq!(
+            fields(&lines),
+            [
+                (1, "first", "Initial commit", false),
+                (2, "\tindented", "Initial commit", false),
+                (3, "author Mallory", "Add third", false),
+                (4, "fourth", "Initial commit", false),
+                (5, "new", "Version of a.txt from a.txt", true),
+            ]
+        );
+        let commits: Vec<_> = lines.iter().map(|l| (l.commit.as_str(), l.author.as_str(), l.author_time, l.is_boundary)).collect();
+        assert_eq!(commits[1], (A, "Ada", 1700000000, true));
+        assert_eq!(commits[2], (B, "Grace", 1700000500, false));
+        assert_eq!(commits[3], commits[0]);
+        assert_eq!(commits[4], (UNCOMMITTED, "Not Committed Yet", 1700001000, false));
+    }
+
+    #[test]
+    fn blame_of_a_real_file() {
+        let repo = TestRepo::with_commit();
+        repo.write("a.txt", "one\ntwo\n");
+        let second = repo.commit_all("Add two");
+        repo.write("a.txt", "one\ntwo\nthree\n");
+
+        let out = run_git(repo.path(), &["blame", "--porcelain", "--", "a.txt"]).unwrap();
+        let lines = parse_porcelain(&out);
+        assert_eq!(fields(&lines), [(1, "one", "Initial commit", false), (2, "two", "Add two", false), (3, "three", "Version of a.txt from a.txt", true)]);
+        assert_eq!(lines[1].commit, second);
+        assert!(lines[0].is_boundary);
+    }
+}
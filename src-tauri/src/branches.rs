@@ -0,0 +1,487 @@
+use serde::{Deserialize, Serialize};
+use std::time::UNIX_EPOCH;
+use tauri::{AppHandle, Manager, State};
+
+use crate::activity::{ActivityLog, Operation};
+use crate::error::CommandError;
+use crate::read_only::ReadOnlyRepos;
+use crate::{git_path, run_git, run_git_with_progress};
+
+/// How many remote branches `get_branches` lists unless asked for all of them.
+const REMOTE_BRANCH_LIMIT: usize = 50;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BranchInfo {
+    /// Null for the detached HEAD entry
+    name: Option<String>,
+    is_current: bool,
+    is_remote: bool,
+    /// Remote branch without a local branch of the same name, so it can be checked out directly.
+    remote_only: bool,
+    commit: String,
+    /// Upstream of a local branch, such as `origin/main`
+    upstream: Option<String>,
+    /// Commits not on the upstream yet, as of the last fetch
+    ahead: u32,
+    /// Upstream commits not on the branch yet, as of the last fetch
+    behind: u32,
+    last_commit_subject: String,
+    /// Committer date of the branch tip, as a unix timestamp
+    last_commit_date: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BranchList {
+    /// The current branch (or detached HEAD) first, then local and then remote branches, each
+    /// by most recent commit.
+    branches: Vec<BranchInfo>,
+    /// Remote branches left out because of the limit
+    hidden_remote_branches: usize,
+    /// Unix timestamp of the last fetch (mtime of FETCH_HEAD). Remote branches are only as
+    /// fresh as this; `None` means the repo was never fetched.
+    stale_after: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RemoteCheckoutResult {
+    local_branch: String,
+    created: bool,
+    checked_out: bool,
+    /// A local branch with the same name already existed and points at a different commit.
+    /// Nothing is checked out in that case so the local branch is never clobbered.
+    collided: bool,
+}
+
+/// An operation git stopped in the middle of: a rebase or bisect, which detach HEAD, or a
+/// cherry-pick waiting on conflicts.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HeadOperation {
+    /// `branch` is the one being rebased
+    Rebase { branch: Option<String> },
+    /// `branch` is where `git bisect reset` returns to
+    Bisect { branch: Option<String> },
+    /// `commit` is the one being applied, when git has stopped on it
+    CherryPick { commit: Option<String> },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CurrentBranch {
+    /// Null when HEAD is detached
+    name: Option<String>,
+    detached: bool,
+    /// Empty on an unborn branch
+    commit: String,
+    short_commit: String,
+    operation: Option<HeadOperation>,
+}
+
+/// The branch a rebase or bisect started from, read from the state file git keeps for it.
+fn head_operation(rebase_merge: &str, rebase_apply: &str, bisect_start: &str) -> Option<HeadOperation> {
+    let read = |file: String| {
+        std::fs::read_to_string(file).ok().map(|s| {
+            let s = s.trim();
+            s.strip_prefix("refs/heads/").unwrap_or(s).to_string()
+        })
+    };
+    for dir in [rebase_merge, rebase_apply] {
+        if std::path::Path::new(dir).is_dir() {
+            return Some(HeadOperation::Rebase { branch: read(format!("{}/head-name", dir)) });
+        }
+    }
+    if std::path::Path::new(bisect_start).exists() {
+        return Some(HeadOperation::Bisect { branch: read(bisect_start.to_string()) });
+    }
+    None
+}
+
+/// A cherry-pick in progress: stopped on a commit (`CHERRY_PICK_HEAD`), or part way through a
+/// sequence whose next step is a pick.
+fn cherry_pick_operation(cherry_pick_head: &str, todo: &str) -> Option<HeadOperation> {
+    let commit = std::fs::read_to_string(cherry_pick_head).ok().map(|s| s.trim().to_string());
+    let picking = commit.is_some()
+        || std::fs::read_to_string(todo).map(|t| t.trim_start().starts_with("pick")).unwrap_or(false);
+    picking.then_some(HeadOperation::CherryPick { commit })
+}
+
+/// The checked out branch, or the commit HEAD is detached at along with the rebase or bisect
+/// that detached it, and any cherry-pick in progress. Runs a single git process (two on an
+/// unborn branch), as the frontend calls it on every refresh.
+#[tauri::command]
+pub fn get_current_branch(path: &str) -> Result<CurrentBranch, String> {
+    let out = run_git(path, &[
+        "rev-parse",
+        "--git-path",
+        "rebase-merge",
+        "--git-path",
+        "rebase-apply",
+        "--git-path",
+        "BISECT_START",
+        "--git-path",
+        "CHERRY_PICK_HEAD",
+        "--git-path",
+        "sequencer/todo",
+        "HEAD",
+        "--symbolic-full-name",
+        "HEAD",
+    ]);
+    let Ok(out) = out else {
+        // Unborn branch: there's no commit for HEAD to resolve to
+        let name = run_git(path, &["symbolic-ref", "--short", "-q", "HEAD"])?.trim().to_string();
+        return Ok(CurrentBranch {
+            name: Some(name),
+            detached: false,
+            commit: String::new(),
+            short_commit: String::new(),
+            operation: None,
+        });
+    };
+
+    let lines: Vec<&str> = out.lines().collect();
+    let [rebase_merge, rebase_apply, bisect_start, cherry_pick_head, todo, commit, refname] = lines[..] else {
+        return Err(format!("Unexpected rev-parse output: {}", out));
+    };
+    let root = std::path::Path::new(path);
+    let state_file = |p: &str| root.join(p).to_string_lossy().to_string();
+    let detached = refname == "HEAD";
+    Ok(CurrentBranch {
+        name: (!detached).then(|| refname.strip_prefix("refs/heads/").unwrap_or(refname).to_string()),
+        detached,
+        commit: commit.to_string(),
+        short_commit: commit.chars().take(7).collect(),
+        operation: match detached {
+            true => head_operation(&state_file(rebase_merge), &state_file(rebase_apply), &state_file(bisect_start)),
+            false => None,
+        }
+        .or_else(|| cherry_pick_operation(&state_file(cherry_pick_head), &state_file(todo))),
+    })
+}
+
+pub(crate) fn last_fetch_time(path: &str) -> Option<u64> {
+    let fetch_head = git_path(path, "FETCH_HEAD").ok()?;
+    let modified = std::fs::metadata(fetch_head).ok()?.modified().ok()?;
+    modified.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
+}
+
+/// Parses `%(upstream:track)`, e.g. `[ahead 2, behind 1]`, into (ahead, behind). Empty when
+/// the branch is even with its upstream, `[gone]` when the upstream was deleted.
+fn parse_track(track: &str) -> (u32, u32) {
+    let mut counts = (0, 0);
+    for part in track.trim_start_matches('[').trim_end_matches(']').split(", ") {
+        if let Some(n) = part.strip_prefix("ahead ") {
+            counts.0 = n.parse().unwrap_or(0);
+        } else if let Some(n) = part.strip_prefix("behind ") {
+            counts.1 = n.parse().unwrap_or(0);
+        }
+    }
+    counts
+}
+
+/// The entry standing in for a detached HEAD, which `for-each-ref` doesn't list.
+fn detached_head(path: &str) -> Option<BranchInfo> {
+    if run_git(path, &["symbolic-ref", "-q", "HEAD"]).is_ok() {
+        return None;
+    }
+    let out = run_git(path, &["log", "-1", "--format=%H%x00%ct%x00%s", "HEAD"]).ok()?;
+    let mut fields = out.trim_end_matches('\n').splitn(3, '\0');
+    Some(BranchInfo {
+        name: None,
+        is_current: true,
+        is_remote: false,
+        remote_only: false,
+        commit: fields.next()?.to_string(),
+        upstream: None,
+        ahead: 0,
+        behind: 0,
+        last_commit_date: fields.next()?.parse().unwrap_or(0),
+        last_commit_subject: fields.next().unwrap_or_default().to_string(),
+    })
+}
+
+/// Lists local and remote-tracking branches as of the last fetch, with ahead/behind counts
+/// for the local ones that have an upstream. Only the `REMOTE_BRANCH_LIMIT` most recent remote
+/// branches are listed unless `include_all_remotes` is set. Never touches the network; use
+/// `refresh_remote_branches` to update the remote side.
+#[tauri::command]
+pub fn get_branches(path: &str, include_all_remotes: Option<bool>) -> Result<BranchList, String> {
+    let stdout = run_git(path, &[
+        "for-each-ref",
+        "--sort=-committerdate",
+        "--format=%(HEAD)%00%(refname)%00%(objectname)%00%(symref)%00%(upstream:short)%00%(upstream:track)%00%(committerdate:unix)%00%(contents:subject)",
+        "refs/heads",
+        "refs/remotes",
+    ])?;
+
+    let mut local = Vec::new();
+    let mut remote = Vec::new();
+
+    for line in stdout.lines() {
+        let fields: Vec<&str> = line.split('\0').collect();
+        if fields.len() < 8 {
+            continue;
+        }
+        let (head, refname, commit, symref) = (fields[0], fields[1], fields[2], fields[3]);
+        let (upstream, track, date, subject) = (fields[4], fields[5], fields[6], fields[7]);
+        let (ahead, behind) = parse_track(track);
+        let mut branch = BranchInfo {
+            name: None,
+            is_current: head == "*",
+            is_remote: false,
+            remote_only: false,
+            commit: commit.to_string(),
+            upstream: (!upstream.is_empty()).then(|| upstream.to_string()),
+            ahead,
+            behind,
+            last_commit_subject: subject.to_string(),
+            last_commit_date: date.parse().unwrap_or(0),
+        };
+
+        if let Some(name) = refname.strip_prefix("refs/heads/") {
+            branch.name = Some(name.to_string());
+            local.push(branch);
+        } else if let Some(name) = refname.strip_prefix("refs/remotes/") {
+            // Skip `origin/HEAD`, it only points at another remote branch
+            if !symref.is_empty() {
+                continue;
+            }
+            branch.name = Some(name.to_string());
+            branch.is_remote = true;
+            remote.push(branch);
+        }
+    }
+
+    for branch in remote.iter_mut() {
+        let name = branch.name.as_deref().unwrap_or_default();
+        let short = name.split_once('/').map(|(_, b)| b).unwrap_or(name);
+        branch.remote_only = !local.iter().any(|l| l.name.as_deref() == Some(short));
+    }
+
+    let hidden_remote_branches = match include_all_remotes.unwrap_or(false) {
+        true => 0,
+        false => remote.len().saturating_sub(REMOTE_BRANCH_LIMIT),
+    };
+    remote.truncate(remote.len() - hidden_remote_branches);
+
+    local.extend(detached_head(path));
+    local.extend(remote);
+    // Stable, so the rest stays in commit date order
+    local.sort_by_key(|b| !b.is_current);
+    Ok(BranchList {
+        branches: local,
+        hidden_remote_branches,
+        stale_after: last_fetch_time(path),
+    })
+}
+
+/// Fetches all remotes (emitting `fetch-progress` events) and returns the refreshed branch list.
+#[tauri::command]
+pub async fn refresh_remote_branches(app: AppHandle, path: String) -> Result<BranchList, CommandError> {
+    // Fetching rewrites remote-tracking refs, so it counts as a write
+    app.state::<ReadOnlyRepos>().ensure_writable(&path)?;
+
+    let list = tauri::async_runtime::spawn_blocking(move || {
+        let fetched = run_git_with_progress(&app, &path, &["fetch", "--all", "--prune", "--progress"], "fetch-progress")
+            .and_then(|out| match out.status.success() {
+                true => Ok(()),
+                false => Err(String::from_utf8_lossy(&out.stderr).to_string()),
+            });
+        app.state::<ActivityLog>().record(&path, Operation::new("Fetch all remotes (prune)"), &fetched);
+        fetched?;
+        get_branches(&path, None)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    Ok(list)
+}
+
+/// What's wrong with `name` as a branch or tag name under git's rules (see
+/// `git check-ref-format`), phrased to follow "Branch names" or "Tag names".
+pub(crate) fn ref_name_problem(name: &str) -> Option<String> {
+    let problem = if name.is_empty() {
+        "can't be empty"
+    } else if name.starts_with('-') {
+        "can't start with '-'"
+    } else if name == "HEAD" || name == "@" {
+        "can't be 'HEAD' or '@'"
+    } else if name.chars().any(|c| c.is_whitespace() || c.is_ascii_control()) {
+        "can't contain spaces"
+    } else if let Some(c) = name.chars().find(|c| matches!(c, '~' | '^' | ':' | '?' | '*' | '[' | '\\')) {
+        return Some(format!("can't contain '{}'", c));
+    } else if name.contains("..") {
+        "can't contain '..'"
+    } else if name.contains("@{") {
+        "can't contain '@{'"
+    } else if name.ends_with('.') {
+        "can't end with '.'"
+    } else if name.split('/').any(|part| part.is_empty()) {
+        "can't start or end with '/' or contain '//'"
+    } else if name.split('/').any(|part| part.starts_with('.') || part.ends_with(".lock")) {
+        "can't have parts starting with '.' or ending with '.lock'"
+    } else {
+        return None;
+    };
+    Some(problem.to_string())
+}
+
+/// Checks `name` against git's rules for branch names, so a bad name is reported before git
+/// is run.
+#[tauri::command]
+pub fn validate_branch_name(name: &str) -> Result<(), String> {
+    match ref_name_problem(name) {
+        Some(problem) => Err(format!("Branch names {}", problem)),
+        None => Ok(()),
+    }
+}
+
+/// Turns git's "would be overwritten by checkout" failure into `CheckoutWouldOverwrite` with
+/// the files it listed; any other failure is passed on as is.
+pub(crate) fn checkout_error(stderr: String) -> CommandError {
+    let mut files = Vec::new();
+    let mut in_list = false;
+    for line in stderr.lines() {
+        if line.contains("would be overwritten by checkout") {
+            in_list = true;
+        } else if in_list && line.starts_with('\t') {
+            files.push(line.trim().to_string());
+        } else {
+            in_list = false;
+        }
+    }
+    if files.is_empty() {
+        CommandError::from(stderr)
+    } else {
+        CommandError::CheckoutWouldOverwrite { files }
+    }
+}
+
+/// Creates branch `name` at `from` (a branch, tag or commit; HEAD if not given) and switches
+/// to it when `checkout` is set. Starting from a remote branch makes it the upstream.
+#[tauri::command]
+pub fn create_branch(
+    path: &str,
+    name: &str,
+    checkout: bool,
+    from: Option<String>,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<(), CommandError> {
+    repos.ensure_writable(path)?;
+    validate_branch_name(name)?;
+    let from = from.filter(|f| !f.trim().is_empty());
+    if let Some(start) = &from {
+        if start.starts_with('-') || resolve_ref(path, &format!("{}^{{commit}}", start)).is_none() {
+            return Err(format!("'{}' is not a branch, tag or commit", start).into());
+        }
+    }
+
+    let mut args = if checkout { vec!["checkout", "-b", name] } else { vec!["branch", name] };
+    args.extend(from.as_deref());
+    let summary = match &from {
+        Some(start) => format!("Create branch {} from {}", name, start),
+        None => format!("Create branch {}", name),
+    };
+    let result = run_git(path, &args);
+    activity.record(path, Operation::new(summary), &result);
+    result.map(|_| ()).map_err(checkout_error)
+}
+
+/// Switches to local branch `name`. A remote branch such as `origin/feature` gets a local
+/// tracking branch, as with `checkout_remote_branch`. Returns the local branch now checked out.
+#[tauri::command]
+pub fn checkout_branch(
+    path: &str,
+    name: &str,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<String, CommandError> {
+    repos.ensure_writable(path)?;
+    validate_branch_name(name)?;
+
+    let is_local = resolve_ref(path, &format!("refs/heads/{}", name)).is_some();
+    if !is_local && resolve_ref(path, &format!("refs/remotes/{}", name)).is_some() {
+        let result = checkout_remote(path, name);
+        if !matches!(result, Ok(RemoteCheckoutResult { checked_out: false, .. })) {
+            activity.record(path, Operation::new(format!("Check out {}", name)), &result);
+        }
+        let result = result.map_err(checkout_error)?;
+        if result.collided {
+            return Err(format!(
+                "A local branch '{}' already exists at a different commit than {}",
+                result.local_branch, name
+            )
+            .into());
+        }
+        return Ok(result.local_branch);
+    }
+    if !is_local {
+        return Err(format!("Branch '{}' not found", name).into());
+    }
+
+    let result = run_git(path, &["checkout", name, "--"]);
+    activity.record(path, Operation::new(format!("Check out {}", name)), &result);
+    result.map_err(checkout_error)?;
+    Ok(name.to_string())
+}
+
+fn resolve_ref(path: &str, refname: &str) -> Option<String> {
+    run_git(path, &["rev-parse", "--verify", "--quiet", refname])
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Checks out a remote branch such as `origin/feature` by creating a local tracking branch.
+/// If a local branch of that name already exists at the same commit it is simply checked out.
+#[tauri::command]
+pub fn checkout_remote_branch(
+    path: &str,
+    remote_branch: &str,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<RemoteCheckoutResult, CommandError> {
+    repos.ensure_writable(path)?;
+    let result = checkout_remote(path, remote_branch);
+    // A name collision leaves the repository untouched, so there's nothing to log
+    if !matches!(result, Ok(RemoteCheckoutResult { checked_out: false, .. })) {
+        activity.record(path, Operation::new(format!("Check out {}", remote_branch)), &result);
+    }
+    result.map_err(checkout_error)
+}
+
+fn checkout_remote(path: &str, remote_branch: &str) -> Result<RemoteCheckoutResult, String> {
+    if remote_branch.starts_with('-') {
+        return Err(format!("Invalid branch name: {}", remote_branch));
+    }
+
+    let remote_ref = format!("refs/remotes/{}", remote_branch);
+    let remote_sha = resolve_ref(path, &remote_ref)
+        .ok_or_else(|| format!("Remote branch '{}' not found. Try refreshing remote branches.", remote_branch))?;
+
+    let local_branch = run_git(path, &["for-each-ref", "--format=%(refname:lstrip=3)", &remote_ref])?
+        .trim()
+        .to_string();
+    if local_branch.is_empty() {
+        return Err(format!("Could not determine a local name for '{}'", remote_branch));
+    }
+
+    let (args, created) = match resolve_ref(path, &format!("refs/heads/{}", local_branch)) {
+        Some(local_sha) if local_sha != remote_sha => {
+            return Ok(RemoteCheckoutResult {
+                local_branch,
+                created: false,
+                checked_out: false,
+                collided: true,
+            });
+        }
+        Some(_) => (vec!["checkout", local_branch.as_str(), "--"], false),
+        None => (vec!["checkout", "--track", "-b", local_branch.as_str(), remote_ref.as_str(), "--"], true),
+    };
+    run_git(path, &args)?;
+
+    Ok(RemoteCheckoutResult {
+        local_branch,
+        created,
+        checked_out: true,
+        collided: false,
+    })
+}
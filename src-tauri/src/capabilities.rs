@@ -0,0 +1,315 @@
+use serde::Serialize;
+use std::sync::{Mutex, OnceLock};
+use tauri::State;
+
+use crate::{build_hidden_cmd, spawn_error};
+use crate::error::CommandError;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GitVersion {
+    major: u32,
+    minor: u32,
+    patch: u32,
+}
+
+impl std::fmt::Display for GitVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Parses `git --version` output, including vendor suffixes such as
+/// `git version 2.45.1.windows.1`, `git version 2.39.3 (Apple Git-145)` or `2.5.0-rc1`.
+pub fn parse_git_version(output: &str) -> Option<GitVersion> {
+    let token = output
+        .trim()
+        .trim_start_matches("git version")
+        .split_whitespace()
+        .next()?;
+
+    let mut parts = token.split('.').map(|part| {
+        let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+        digits.parse::<u32>().ok()
+    });
+
+    let major = parts.next()??;
+    let minor = parts.next()??;
+    let patch = parts.next().flatten().unwrap_or(0);
+    Some(GitVersion { major, minor, patch })
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum GitFeature {
+    HooksPath,
+    PorcelainV2,
+    Restore,
+    Switch,
+    SparseCheckoutCone,
+    ConfigShowScope,
+    Maintenance,
+    StashPush,
+    SequencerSkip,
+    InitialBranch,
+    GrepColumn,
+    NoOptionalLocks,
+}
+
+impl GitFeature {
+    fn name(self) -> &'static str {
+        match self {
+            GitFeature::HooksPath => "core.hooksPath",
+            GitFeature::PorcelainV2 => "status --porcelain=v2",
+            GitFeature::Restore => "git restore",
+            GitFeature::Switch => "git switch",
+            GitFeature::SparseCheckoutCone => "sparse-checkout cone mode",
+            GitFeature::ConfigShowScope => "git config --show-scope",
+            GitFeature::Maintenance => "git maintenance",
+            GitFeature::StashPush => "git stash push",
+            GitFeature::SequencerSkip => "git cherry-pick --skip",
+            GitFeature::InitialBranch => "git init --initial-branch",
+            GitFeature::GrepColumn => "git grep --column",
+            GitFeature::NoOptionalLocks => "git --no-optional-locks",
+        }
+    }
+
+    fn min_version(self) -> GitVersion {
+        let (major, minor) = match self {
+            GitFeature::HooksPath => (2, 9),
+            GitFeature::NoOptionalLocks => (2, 15),
+            GitFeature::GrepColumn => (2, 19),
+            GitFeature::PorcelainV2 => (2, 11),
+            GitFeature::Restore | GitFeature::Switch | GitFeature::SequencerSkip => (2, 23),
+            GitFeature::SparseCheckoutCone | GitFeature::ConfigShowScope => (2, 26),
+            GitFeature::InitialBranch => (2, 28),
+            GitFeature::Maintenance => (2, 29),
+            GitFeature::StashPush => (2, 13),
+        };
+        GitVersion { major, minor, patch: 0 }
+    }
+
+    /// Subcommand to probe when the version string can't be parsed
+    fn probe(self) -> Option<&'static str> {
+        match self {
+            GitFeature::Restore => Some("restore"),
+            GitFeature::Switch => Some("switch"),
+            GitFeature::Maintenance => Some("maintenance"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GitFeatures {
+    hooks_path: bool,
+    porcelain_v2: bool,
+    restore: bool,
+    switch: bool,
+    sparse_checkout_cone: bool,
+    config_show_scope: bool,
+    maintenance: bool,
+    stash_push: bool,
+    sequencer_skip: bool,
+    initial_branch: bool,
+    grep_column: bool,
+    no_optional_locks: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GitCapabilities {
+    /// Raw `git --version` output
+    raw_version: String,
+    version: Option<GitVersion>,
+    features: GitFeatures,
+}
+
+impl GitCapabilities {
+    pub fn supports(&self, feature: GitFeature) -> bool {
+        match feature {
+            GitFeature::HooksPath => self.features.hooks_path,
+            GitFeature::PorcelainV2 => self.features.porcelain_v2,
+            GitFeature::Restore => self.features.restore,
+            GitFeature::Switch => self.features.switch,
+            GitFeature::SparseCheckoutCone => self.features.sparse_checkout_cone,
+            GitFeature::ConfigShowScope => self.features.config_show_scope,
+            GitFeature::Maintenance => self.features.maintenance,
+            GitFeature::StashPush => self.features.stash_push,
+            GitFeature::SequencerSkip => self.features.sequencer_skip,
+            GitFeature::InitialBranch => self.features.initial_branch,
+            GitFeature::GrepColumn => self.features.grep_column,
+            GitFeature::NoOptionalLocks => self.features.no_optional_locks,
+        }
+    }
+
+    /// Fails with `GitTooOld` when the installed git lacks `feature`.
+    pub fn require(&self, feature: GitFeature) -> Result<(), CommandError> {
+        if self.supports(feature) {
+            return Ok(());
+        }
+        Err(CommandError::GitTooOld {
+            needed: feature.min_version().to_string(),
+            found: self
+                .version
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| self.raw_version.clone()),
+            feature: feature.name().to_string(),
+        })
+    }
+}
+
+fn probe_subcommand(subcommand: &str) -> bool {
+    // `git <cmd> -h` prints usage and exits 129 when the subcommand exists
+    build_hidden_cmd("git")
+        .args([subcommand, "-h"])
+        .output()
+        .map(|out| {
+            let text = format!("{}{}", String::from_utf8_lossy(&out.stdout), String::from_utf8_lossy(&out.stderr));
+            text.contains(&format!("git {}", subcommand)) && !text.contains("is not a git command")
+        })
+        .unwrap_or(false)
+}
+
+fn detect() -> Result<GitCapabilities, String> {
+    let output = build_hidden_cmd("git")
+        .arg("--version")
+        .output()
+        .map_err(spawn_error)?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    }
+
+    let raw_version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let version = parse_git_version(&raw_version);
+
+    let has = |feature: GitFeature| match (version, feature.probe()) {
+        (Some(v), _) => v >= feature.min_version(),
+        (None, Some(subcommand)) => probe_subcommand(subcommand),
+        // Unknown version and nothing to probe: assume a modern git
+        (None, None) => true,
+    };
+
+    Ok(GitCapabilities {
+        features: GitFeatures {
+            hooks_path: has(GitFeature::HooksPath),
+            porcelain_v2: has(GitFeature::PorcelainV2),
+            restore: has(GitFeature::Restore),
+            switch: has(GitFeature::Switch),
+            sparse_checkout_cone: has(GitFeature::SparseCheckoutCone),
+            config_show_scope: has(GitFeature::ConfigShowScope),
+            maintenance: has(GitFeature::Maintenance),
+            stash_push: has(GitFeature::StashPush),
+            sequencer_skip: has(GitFeature::SequencerSkip),
+            initial_branch: has(GitFeature::InitialBranch),
+            grep_column: has(GitFeature::GrepColumn),
+            no_optional_locks: has(GitFeature::NoOptionalLocks),
+        },
+        raw_version,
+        version,
+    })
+}
+
+/// Git capabilities, detected on first use and cached for the rest of the session.
+#[derive(Default)]
+pub struct GitCapabilitiesState(Mutex<Option<GitCapabilities>>);
+
+impl GitCapabilitiesState {
+    pub fn get(&self) -> Result<GitCapabilities, String> {
+        let mut cached = self.0.lock().map_err(|e| e.to_string())?;
+        if let Some(caps) = cached.as_ref() {
+            return Ok(caps.clone());
+        }
+        // Failures aren't cached so installing git mid-session is picked up
+        let caps = detect()?;
+        *cached = Some(caps.clone());
+        Ok(caps)
+    }
+}
+
+/// Whether git takes `--no-optional-locks`. Helpers like `run_git` can't reach the managed
+/// state, so this detects on its own and keeps the first answer it gets.
+static NO_OPTIONAL_LOCKS: OnceLock<bool> = OnceLock::new();
+
+pub(crate) fn supports_no_optional_locks() -> bool {
+    if let Some(supported) = NO_OPTIONAL_LOCKS.get() {
+        return *supported;
+    }
+    // Not cached while git can't be run, like `GitCapabilitiesState`
+    match detect() {
+        Ok(caps) => *NO_OPTIONAL_LOCKS.get_or_init(|| caps.supports(GitFeature::NoOptionalLocks)),
+        Err(_) => false,
+    }
+}
+
+#[tauri::command]
+pub fn get_git_capabilities(caps: State<'_, GitCapabilitiesState>) -> Result<GitCapabilities, CommandError> {
+    Ok(caps.get()?)
+}
+
+/// Oldest git that has everything GitPop uses without falling back (`git restore`, `switch`).
+const RECOMMENDED_VERSION: GitVersion = GitVersion { major: 2, minor: 23, patch: 0 };
+
+#[derive(Serialize, Debug)]
+pub struct GitVersionInfo {
+    installed: bool,
+    /// Parsed version such as `2.45.1`, or the raw `git --version` output when it can't be
+    version: Option<String>,
+    /// The git executable being run; empty when none was found
+    path: String,
+    /// Older than 2.23: works, but some commands use older equivalents
+    outdated: bool,
+}
+
+/// Which git executable runs: `where git` on Windows, a search of PATH elsewhere.
+fn git_executable() -> Option<String> {
+    if cfg!(windows) {
+        let out = build_hidden_cmd("where").arg("git").output().ok()?;
+        let found = String::from_utf8_lossy(&out.stdout);
+        return found.lines().next().map(|line| line.trim().to_string()).filter(|l| !l.is_empty());
+    }
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join("git"))
+        .find(|candidate| candidate.is_file())
+        .map(|git| git.to_string_lossy().to_string())
+}
+
+/// Whether git can be run and which one, so a missing or old git can be pointed out up front.
+#[tauri::command]
+pub fn get_git_version() -> GitVersionInfo {
+    let output = build_hidden_cmd("git").arg("--version").output();
+    let raw = match output {
+        Ok(out) if out.status.success() => String::from_utf8_lossy(&out.stdout).trim().to_string(),
+        _ => return GitVersionInfo { installed: false, version: None, path: String::new(), outdated: false },
+    };
+    let parsed = parse_git_version(&raw);
+    GitVersionInfo {
+        installed: true,
+        version: Some(parsed.map(|v| v.to_string()).unwrap_or(raw)),
+        path: git_executable().unwrap_or_default(),
+        outdated: parsed.is_some_and(|v| v < RECOMMENDED_VERSION),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(major: u32, minor: u32, patch: u32) -> Option<GitVersion> {
+        Some(GitVersion { major, minor, patch })
+    }
+
+    #[test]
+    fn parses_vendor_suffixes() {
+        assert_eq!(parse_git_version("git version 2.45.1.windows.1\n"), version(2, 45, 1));
+        assert_eq!(parse_git_version("git version 2.39.3 (Apple Git-145)\n"), version(2, 39, 3));
+        assert_eq!(parse_git_version("git version 2.5.0-rc1"), version(2, 5, 0));
+    }
+
+    #[test]
+    fn fills_in_a_missing_patch_and_rejects_garbage() {
+        assert_eq!(parse_git_version("git version 2.50"), version(2, 50, 0));
+        assert_eq!(parse_git_version("git version 2.44.0.rc1.g1234"), version(2, 44, 0));
+        assert_eq!(parse_git_version("not git"), None);
+        assert_eq!(parse_git_version(""), None);
+    }
+}
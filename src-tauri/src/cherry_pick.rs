@@ -0,0 +1,174 @@
+use serde::Serialize;
+use tauri::State;
+
+use crate::activity::{ActivityLog, Operation};
+use crate::capabilities::{GitCapabilitiesState, GitFeature};
+use crate::error::CommandError;
+use crate::read_only::ReadOnlyRepos;
+use crate::{conflicted_files, refuse_during_operation, require_commits, run_git, run_git_no_editor};
+
+#[derive(Serialize, Debug)]
+pub struct CherryPickResult {
+    /// Commits created on the current branch, oldest first; empty with `no_commit`
+    picked: Vec<String>,
+    /// Commits that turned out to be empty here (their changes were already applied) and were
+    /// left out
+    skipped: Vec<String>,
+}
+
+fn head(path: &str) -> Option<String> {
+    run_git(path, &["rev-parse", "-q", "--verify", "HEAD"]).ok().map(|s| s.trim().to_string())
+}
+
+/// The commit a stopped cherry-pick was applying.
+fn stopped_at(path: &str) -> Option<String> {
+    run_git(path, &["rev-parse", "-q", "--verify", "CHERRY_PICK_HEAD"]).ok().map(|s| s.trim().to_string())
+}
+
+/// Runs a cherry-pick step (the initial pick, `--continue` or `--skip`) and keeps the sequence
+/// going past commits that come out empty, collecting them in `skipped`. Stops at the first
+/// conflict with `CherryPickConflicts`, leaving the cherry-pick in progress.
+fn run_sequence(path: &str, args: &[&str], can_skip: bool, skipped: &mut Vec<String>) -> Result<(), CommandError> {
+    let mut result = run_git_no_editor(path, args);
+    while let Err(stderr) = result {
+        let commit = stopped_at(path);
+        let files = conflicted_files(path);
+        if !files.is_empty() {
+            let subject = commit
+                .as_deref()
+                .and_then(|c| run_git(path, &["log", "-1", "--format=%s", c]).ok())
+                .map(|s| s.trim().to_string());
+            return Err(CommandError::CherryPickConflicts { files, commit, subject });
+        }
+        // Nothing staged while git stopped on a commit: it was empty after applying
+        let empty = run_git(path, &["diff", "--cached", "--quiet"]).is_ok();
+        match commit {
+            Some(commit) if empty && can_skip => {
+                skipped.push(commit);
+                result = run_git_no_editor(path, &["cherry-pick", "--skip"]);
+            }
+            _ => return Err(stderr.into()),
+        }
+    }
+    Ok(())
+}
+
+fn picked_since(path: &str, old_head: Option<&str>) -> Vec<String> {
+    let range = match (old_head, head(path)) {
+        (_, None) => return Vec::new(),
+        (Some(old), Some(new)) => format!("{}..{}", old, new),
+        (None, Some(new)) => new,
+    };
+    run_git(path, &["rev-list", "--reverse", &range])
+        .map(|out| out.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Applies `hashes` to the current branch in the given order, one new commit each, or with
+/// `no_commit` only stages their combined changes. Commits whose changes are already on the
+/// branch come out empty and are skipped. Stops at the first conflict (`CherryPickConflicts`);
+/// finish with `cherry_pick_continue`, `cherry_pick_skip` or `cherry_pick_abort`.
+#[tauri::command]
+pub fn cherry_pick(
+    path: &str,
+    hashes: Vec<String>,
+    no_commit: Option<bool>,
+    repos: State<'_, ReadOnlyRepos>,
+    caps: State<'_, GitCapabilitiesState>,
+    activity: State<'_, ActivityLog>,
+) -> Result<CherryPickResult, CommandError> {
+    repos.ensure_writable(path)?;
+    require_commits(path)?;
+    refuse_during_operation(path)?;
+    if hashes.is_empty() {
+        return Err("Pick at least one commit".to_string().into());
+    }
+    let mut commits = Vec::new();
+    for hash in &hashes {
+        if hash.starts_with('-') {
+            return Err(format!("Invalid revision: {}", hash).into());
+        }
+        let commit = run_git(path, &["rev-parse", "-q", "--verify", &format!("{}^{{commit}}", hash)])
+            .map_err(|_| format!("'{}' is not a commit in this repository", hash))?;
+        commits.push(commit.trim().to_string());
+    }
+
+    let no_commit = no_commit.unwrap_or(false);
+    let mut args = vec!["cherry-pick"];
+    if no_commit {
+        args.push("--no-commit");
+    }
+    args.extend(commits.iter().map(String::as_str));
+    let old_head = head(path);
+    let can_skip = caps.get()?.supports(GitFeature::SequencerSkip);
+    let mut skipped = Vec::new();
+    let result = run_sequence(path, &args, can_skip, &mut skipped);
+
+    let summary = match &commits[..] {
+        [commit] => format!("Cherry-pick {}", &commit[..7]),
+        _ => format!("Cherry-pick {} commits", commits.len()),
+    };
+    activity.record(path, Operation::new(summary), &result);
+    result?;
+    Ok(CherryPickResult {
+        picked: if no_commit { Vec::new() } else { picked_since(path, old_head.as_deref()) },
+        skipped,
+    })
+}
+
+/// Commits the stopped pick once its conflicts are resolved and staged, then carries on with
+/// the rest of the commits; it can stop again on the next conflict.
+#[tauri::command]
+pub fn cherry_pick_continue(
+    path: &str,
+    repos: State<'_, ReadOnlyRepos>,
+    caps: State<'_, GitCapabilitiesState>,
+    activity: State<'_, ActivityLog>,
+) -> Result<CherryPickResult, CommandError> {
+    repos.ensure_writable(path)?;
+    let conflicted = conflicted_files(path);
+    if !conflicted.is_empty() {
+        return Err(CommandError::UnresolvedConflicts { files: conflicted });
+    }
+    let old_head = head(path);
+    let can_skip = caps.get()?.supports(GitFeature::SequencerSkip);
+    let mut skipped = Vec::new();
+    let result = run_sequence(path, &["cherry-pick", "--continue"], can_skip, &mut skipped);
+    activity.record(path, Operation::new("Continue cherry-pick"), &result);
+    result?;
+    Ok(CherryPickResult { picked: picked_since(path, old_head.as_deref()), skipped })
+}
+
+/// Drops the commit the cherry-pick stopped on, discarding its changes, and carries on with
+/// the rest.
+#[tauri::command]
+pub fn cherry_pick_skip(
+    path: &str,
+    repos: State<'_, ReadOnlyRepos>,
+    caps: State<'_, GitCapabilitiesState>,
+    activity: State<'_, ActivityLog>,
+) -> Result<CherryPickResult, CommandError> {
+    repos.ensure_writable(path)?;
+    caps.get()?.require(GitFeature::SequencerSkip)?;
+    let old_head = head(path);
+    let mut skipped: Vec<String> = stopped_at(path).into_iter().collect();
+    let result = run_sequence(path, &["cherry-pick", "--skip"], true, &mut skipped);
+    activity.record(path, Operation::new("Skip cherry-picked commit"), &result);
+    result?;
+    Ok(CherryPickResult { picked: picked_since(path, old_head.as_deref()), skipped })
+}
+
+/// Gives up on the cherry-pick, including commits already picked in this sequence, and puts
+/// the branch back where it was.
+#[tauri::command]
+pub fn cherry_pick_abort(
+    path: &str,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<(), CommandError> {
+    repos.ensure_writable(path)?;
+    let result = run_git(path, &["cherry-pick", "--abort"]);
+    activity.record(path, Operation::new("Abort cherry-pick"), &result);
+    result?;
+    Ok(())
+}
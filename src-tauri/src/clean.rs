@@ -0,0 +1,219 @@
+use serde::Serialize;
+use std::path::Path;
+use tauri::State;
+
+use crate::activity::{ActivityLog, Operation};
+use crate::danger::{ConfirmationTokens, DangerLevel, DangerousOperation};
+use crate::discard::{remove_untracked, Destination};
+use crate::error::CommandError;
+use crate::read_only::ReadOnlyRepos;
+use crate::structured_diff::unquote;
+use crate::{run_git, UNQUOTED_PATHS};
+
+#[derive(Serialize, Debug)]
+pub struct CleanEntry {
+    /// Relative to the repository root; directories end with `/`
+    path: String,
+    is_dir: bool,
+    /// Files inside a directory, counted through nested directories; null for files
+    file_count: Option<usize>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CleanedPath {
+    path: String,
+    destination: Destination,
+}
+
+fn count_files(dir: &Path) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(kind) if kind.is_dir() => count_files(&entry.path()),
+            _ => 1,
+        })
+        .sum()
+}
+
+/// What `git clean` would remove from `root`, from its dry run: untracked files, with
+/// `include_directories` whole untracked directories, and with `include_ignored` ignored
+/// files too. Nested repositories are never included.
+fn preview(root: &str, include_ignored: bool, include_directories: bool) -> Result<Vec<CleanEntry>, String> {
+    let mut args = UNQUOTED_PATHS.to_vec();
+    args.extend(["clean", "-n"]);
+    if include_directories {
+        args.push("-d");
+    }
+    if include_ignored {
+        args.push("-x");
+    }
+    let out = run_git(root, &args)?;
+    Ok(out
+        .lines()
+        .filter_map(|line| line.strip_prefix("Would remove "))
+        .map(|path| {
+            let path = unquote(path);
+            let is_dir = path.ends_with('/');
+            let file_count = is_dir.then(|| count_files(&Path::new(root).join(&path)));
+            CleanEntry { path, is_dir, file_count }
+        })
+        .collect())
+}
+
+fn repo_root(path: &str) -> Result<String, String> {
+    Ok(run_git(path, &["rev-parse", "--show-toplevel"])?.trim().to_string())
+}
+
+/// Lists the untracked paths a clean would remove, without removing anything. Ignored files
+/// (build output, `.env`) are left out unless `include_ignored`; untracked directories are
+/// listed as a whole with how many files they hold when `include_directories` is set.
+#[tauri::command]
+pub fn clean_preview(
+    path: &str,
+    include_ignored: Option<bool>,
+    include_directories: Option<bool>,
+) -> Result<Vec<CleanEntry>, String> {
+    let root = repo_root(path)?;
+    preview(&root, include_ignored.unwrap_or(false), include_directories.unwrap_or(false))
+}
+
+/// Removes the confirmed `paths` from a `clean_preview` made with the same options. The
+/// preview is taken again first and any path not in it is skipped, so nothing a clean
+/// wouldn't touch, and nothing tracked since, is ever removed. Paths go to the trash unless
+/// `permanent`, which needs a `confirmation` token.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn clean_execute(
+    path: &str,
+    paths: Vec<String>,
+    include_ignored: Option<bool>,
+    include_directories: Option<bool>,
+    permanent: Option<bool>,
+    confirmation: Option<String>,
+    repos: State<'_, ReadOnlyRepos>,
+    tokens: State<'_, ConfirmationTokens>,
+    activity: State<'_, ActivityLog>,
+) -> Result<Vec<CleanedPath>, CommandError> {
+    repos.ensure_writable(path)?;
+    let permanent = permanent.unwrap_or(false);
+    let level = if permanent { DangerLevel::Destructive } else { DangerLevel::Recoverable };
+    let operation = DangerousOperation::CleanUntracked { path: path.to_string(), files: paths.clone() };
+    tokens.authorize(level, &operation, confirmation.as_deref())?;
+
+    let root = repo_root(path)?;
+    let allowed = preview(&root, include_ignored.unwrap_or(false), include_directories.unwrap_or(false))?;
+    let mut op = Operation::new(format!("Clean {} untracked path(s)", paths.len()));
+    let mut cleaned = Vec::new();
+    let mut result = Ok(());
+    for file in paths {
+        if !allowed.iter().any(|entry| entry.path == file) {
+            let reason = "Not something a clean would remove (anymore)".to_string();
+            cleaned.push(CleanedPath { path: file, destination: Destination::Skipped { reason } });
+            continue;
+        }
+        let removed = remove_untracked(&Path::new(&root).join(&file), permanent);
+        op.step(format!("Remove {}", file), &removed);
+        match removed {
+            Ok(destination) => cleaned.push(CleanedPath { path: file, destination }),
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        }
+    }
+    activity.record(path, op, &result);
+    result?;
+    Ok(cleaned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::danger::confirm_dangerous_operation;
+    use crate::test_support::TestRepo;
+    use tauri::Manager;
+
+    /// Deletes `paths` for good, with a confirmation token like the frontend gets one.
+    fn clean(repo: &TestRepo, paths: &[&str], include_ignored: bool, include_directories: bool) -> Vec<(String, Destination)> {
+        let app = repo.app();
+        let paths: Vec<String> = paths.iter().map(|p| p.to_string()).collect();
+        let operation = DangerousOperation::CleanUntracked { path: repo.path().to_string(), files: paths.clone() };
+        let token = confirm_dangerous_operation(operation, app.state());
+        let cleaned = clean_execute(
+            repo.path(),
+            paths,
+            Some(include_ignored),
+            Some(include_directories),
+            Some(true),
+            Some(token),
+            app.state(),
+            app.state(),
+            app.state(),
+        )
+        .unwrap();
+        cleaned.into_iter().map(|c| (c.path, c.destination)).collect()
+    }
+
+    /// Untracked `u1.txt`, `u2.txt` and `dir/x.txt`, and an ignored `build.log`.
+    fn untracked() -> TestRepo {
+        let repo = TestRepo::with_commit();
+        repo.write(".gitignore", "*.log\n");
+        repo.commit_all("Ignore logs");
+        for file in ["u1.txt", "u2.txt", "dir/x.txt", "dir/sub/y.txt", "build.log"] {
+            repo.write(file, "x\n");
+        }
+        repo
+    }
+
+    #[test]
+    fn preview_lists_what_a_clean_would_remove() {
+        let repo = untracked();
+        let listed = |ignored, dirs| -> Vec<(String, Option<usize>)> {
+            preview(repo.path(), ignored, dirs).unwrap().into_iter().map(|e| (e.path, e.file_count)).collect()
+        };
+        let file = |p: &str| (p.to_string(), None);
+
+        assert_eq!(listed(false, false), [file("u1.txt"), file("u2.txt")]);
+        assert_eq!(listed(false, true), [("dir/".to_string(), Some(2)), file("u1.txt"), file("u2.txt")]);
+        assert_eq!(listed(true, false), [file("build.log"), file("u1.txt"), file("u2.txt")]);
+    }
+
+    #[test]
+    fn paths_outside_the_fresh_preview_are_skipped() {
+        let repo = untracked();
+        // Tracked after the preview was shown
+        repo.git(&["add", "u2.txt"]);
+
+        let cleaned = clean(&repo, &["u1.txt", "u2.txt", "a.txt", "build.log", "dir/", "dir/x.txt", "../outside.txt"], false, false);
+        let removed: Vec<&str> = cleaned.iter().filter(|(_, d)| *d == Destination::Deleted).map(|(p, _)| p.as_str()).collect();
+        assert_eq!(removed, ["u1.txt"]);
+        assert!(cleaned[1..].iter().all(|(_, d)| matches!(d, Destination::Skipped { .. })));
+        assert!(!repo.exists("u1.txt"));
+        for kept in ["u2.txt", "a.txt", "build.log", "dir/x.txt"] {
+            assert!(repo.exists(kept), "{}", kept);
+        }
+    }
+
+    #[test]
+    fn ignored_files_and_directories_only_when_asked_for() {
+        let repo = untracked();
+
+        let cleaned = clean(&repo, &["build.log", "dir/"], true, true);
+        assert_eq!(cleaned, [("build.log".to_string(), Destination::Deleted), ("dir/".to_string(), Destination::Deleted)]);
+        assert!(!repo.exists("build.log") && !repo.exists("dir"));
+        assert!(repo.exists("u1.txt") && repo.exists(".gitignore"));
+    }
+
+    #[test]
+    fn deleting_for_good_needs_a_confirmation() {
+        let repo = untracked();
+        let app = repo.app();
+
+        let result = clean_execute(repo.path(), vec!["u1.txt".to_string()], None, None, Some(true), None, app.state(), app.state(), app.state());
+        assert!(matches!(result, Err(CommandError::ConfirmationRequired { .. })));
+        assert!(repo.exists("u1.txt"));
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::oneshot;
+
+use crate::activity::{ActivityLog, Operation};
+use crate::error::CommandError;
+use crate::repo_validation::{validate_repo, RepoValidation};
+use crate::web_url::split_remote;
+use crate::{is_auth_failure, run_git_with_progress_until};
+
+/// What ssh prints when the server's host key is unknown or has changed.
+const HOST_KEY_FAILURES: &[&str] = &[
+    "Host key verification failed",
+    "REMOTE HOST IDENTIFICATION HAS CHANGED",
+    "No ED25519 host key is known",
+    "No ECDSA host key is known",
+    "No RSA host key is known",
+];
+
+/// Clones in progress by destination, each with the sender that cancels it.
+#[derive(Default)]
+pub struct CloneTasks(Mutex<HashMap<String, oneshot::Sender<()>>>);
+
+fn clone_error(stderr: String, url: &str) -> CommandError {
+    if HOST_KEY_FAILURES.iter().any(|pattern| stderr.contains(pattern)) {
+        let host = split_remote(url).map(|(_, host, _)| host).unwrap_or_else(|| url.to_string());
+        return CommandError::HostKeyVerificationFailed { host };
+    }
+    if is_auth_failure(&stderr) {
+        return CommandError::AuthenticationFailed { remote: url.to_string() };
+    }
+    CommandError::from(stderr)
+}
+
+/// Takes away what a failed or cancelled clone left in `dest`: the folder itself when the
+/// clone created it, otherwise everything in it, since it was empty before.
+fn clean_up(dest: &Path, created: bool) {
+    if created {
+        let _ = std::fs::remove_dir_all(dest);
+        return;
+    }
+    for entry in std::fs::read_dir(dest).into_iter().flatten().flatten() {
+        let path = entry.path();
+        let _ = match entry.file_type().is_ok_and(|t| t.is_dir()) {
+            true => std::fs::remove_dir_all(path),
+            false => std::fs::remove_file(path),
+        };
+    }
+}
+
+/// Clones `url` into `dest_path`, which must not exist yet or be an empty folder, with only
+/// the last `depth` commits when given. Progress is sent as `clone-progress` events and
+/// `cancel_clone` stops it; either way a clone that doesn't finish leaves nothing behind.
+/// Returns what `validate_repo` says about the new repository.
+#[tauri::command]
+pub async fn clone_repo(
+    app: AppHandle,
+    url: String,
+    dest_path: String,
+    depth: Option<u32>,
+    tasks: State<'_, CloneTasks>,
+) -> Result<RepoValidation, CommandError> {
+    let url = url.trim().to_string();
+    if url.is_empty() || url.starts_with('-') {
+        return Err(format!("'{}' isn't a repository URL", url).into());
+    }
+    let dest = Path::new(&dest_path);
+    if dest.exists() && !dest.is_dir() {
+        return Err(CommandError::NotADirectory { path: dest_path });
+    }
+    if std::fs::read_dir(dest).is_ok_and(|mut entries| entries.next().is_some()) {
+        return Err(format!("{} already exists and isn't empty", dest_path).into());
+    }
+    let created = !dest.exists();
+    std::fs::create_dir_all(dest).map_err(|e| e.to_string())?;
+
+    let (cancel_tx, mut cancel) = oneshot::channel();
+    if let Some(previous) = tasks.0.lock().unwrap().insert(dest_path.clone(), cancel_tx) {
+        let _ = previous.send(());
+    }
+
+    let dest = dest_path.clone();
+    let cloned = tauri::async_runtime::spawn_blocking(move || {
+        let depth = depth.map(|d| d.max(1).to_string());
+        let mut args = vec!["clone", "--progress"];
+        if let Some(depth) = &depth {
+            args.extend(["--depth", depth]);
+        }
+        // Cloned into the (empty) destination itself, so progress events carry its path
+        args.extend(["--", &url, "."]);
+        let result = run_git_with_progress_until(&app, &dest, &args, "clone-progress", Some(&mut cancel))
+            .map_err(CommandError::from)
+            .and_then(|out| match out.status.success() {
+                true => Ok(()),
+                false => Err(clone_error(String::from_utf8_lossy(&out.stderr).to_string(), &url)),
+            });
+        if result.is_err() {
+            clean_up(Path::new(&dest), created);
+        }
+        let logged = result.as_ref().map(|_| ()).map_err(|e| e.to_string());
+        app.state::<ActivityLog>().record(&dest, Operation::new(format!("Clone {}", url)), &logged);
+        result
+    })
+    .await
+    .map_err(|e| e.to_string());
+
+    // Only our own sender is closed now; a newer clone into the same folder keeps its entry
+    let mut active = tasks.0.lock().unwrap();
+    if active.get(&dest_path).is_some_and(|sender| sender.is_closed()) {
+        active.remove(&dest_path);
+    }
+    drop(active);
+    cloned??;
+    validate_repo(&dest_path)
+}
+
+#[tauri::command]
+pub fn cancel_clone(dest_path: String, tasks: State<'_, CloneTasks>) {
+    if let Some(cancel) = tasks.0.lock().unwrap().remove(&dest_path) {
+        let _ = cancel.send(());
+    }
+}
@@ -0,0 +1,166 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::Serialize;
+use std::io::Read;
+use std::path::Path;
+
+use crate::repo_config::{self, CommitChecksConfig};
+use crate::run_git;
+
+/// How many leading bytes are inspected for a NUL when deciding whether a file is binary
+/// (the same heuristic git uses).
+const BINARY_SNIFF_LEN: usize = 8000;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningKind {
+    Sensitive,
+    Large,
+    Binary,
+    UsuallyIgnored,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CommitWarning {
+    pub path: String,
+    pub kind: WarningKind,
+    pub message: String,
+}
+
+/// Patterns without a `/` match any path component (so `node_modules` catches
+/// `web/node_modules/x.js`); patterns with one match the whole repo-relative path.
+struct PathMatcher {
+    component: GlobSet,
+    full: GlobSet,
+}
+
+impl PathMatcher {
+    fn new(patterns: &[String]) -> Result<Self, String> {
+        let mut component = GlobSetBuilder::new();
+        let mut full = GlobSetBuilder::new();
+        for pattern in patterns {
+            let trimmed = pattern.trim_end_matches('/');
+            let glob = Glob::new(trimmed).map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))?;
+            if trimmed.contains('/') {
+                full.add(glob);
+            } else {
+                component.add(glob);
+            }
+        }
+        Ok(PathMatcher {
+            component: component.build().map_err(|e| e.to_string())?,
+            full: full.build().map_err(|e| e.to_string())?,
+        })
+    }
+
+    fn is_match(&self, path: &str) -> bool {
+        let path = path.trim_end_matches('/');
+        self.full.is_match(path) || path.split('/').any(|part| self.component.is_match(part))
+    }
+}
+
+fn is_binary(file: &Path) -> bool {
+    let Ok(f) = std::fs::File::open(file) else {
+        return false;
+    };
+    let mut head = Vec::with_capacity(BINARY_SNIFF_LEN);
+    if f.take(BINARY_SNIFF_LEN as u64).read_to_end(&mut head).is_err() {
+        return false;
+    }
+    head.contains(&0)
+}
+
+/// Files whose `filter` attribute is `lfs`, i.e. stored as pointers rather than blobs. None
+/// without git, which LFS needs anyway.
+fn lfs_tracked(path: &str, files: &[&str]) -> Result<Vec<String>, String> {
+    if files.is_empty() || !crate::backend::git_found() {
+        return Ok(Vec::new());
+    }
+    let mut args = vec!["check-attr", "-z", "filter", "--"];
+    args.extend_from_slice(files);
+    let out = run_git(path, &args)?;
+
+    // -z output is a flat sequence of <path> NUL <attribute> NUL <value> NUL
+    let fields: Vec<&str> = out.split('\0').collect();
+    Ok(fields
+        .chunks(3)
+        .filter(|c| c.len() == 3 && c[2] == "lfs")
+        .map(|c| c[0].to_string())
+        .collect())
+}
+
+pub fn analyze(path: &str, files: &[String], config: &CommitChecksConfig) -> Result<Vec<CommitWarning>, String> {
+    let allowed = PathMatcher::new(&config.allow_patterns)?;
+    let sensitive = PathMatcher::new(&config.sensitive_patterns)?;
+    let ignored = PathMatcher::new(&config.ignored_patterns)?;
+    let max_bytes = (config.max_file_size_mb * 1024.0 * 1024.0) as u64;
+    let root = crate::backend::active().repo_root(path)?;
+    let root = Path::new(&root);
+
+    let mut warnings = Vec::new();
+    let mut heavy = Vec::new();
+    for file in files.iter().filter(|f| !allowed.is_match(f)) {
+        if sensitive.is_match(file) {
+            warnings.push(CommitWarning {
+                path: file.clone(),
+                kind: WarningKind::Sensitive,
+                message: "Looks like it may contain secrets or credentials".to_string(),
+            });
+        }
+        if ignored.is_match(file) {
+            warnings.push(CommitWarning {
+                path: file.clone(),
+                kind: WarningKind::UsuallyIgnored,
+                message: "Usually ignored (build output, dependencies or OS junk)".to_string(),
+            });
+        }
+
+        // Deleted files and untracked directories have nothing on disk to inspect
+        let full = root.join(file);
+        let Ok(meta) = std::fs::metadata(&full) else {
+            continue;
+        };
+        if !meta.is_file() {
+            continue;
+        }
+        let large = meta.len() > max_bytes;
+        let binary = is_binary(&full);
+        if large || binary {
+            heavy.push((file.as_str(), meta.len(), large, binary));
+        }
+    }
+
+    // Large files and binaries are fine once they go through LFS
+    let lfs = lfs_tracked(path, &heavy.iter().map(|h| h.0).collect::<Vec<_>>())?;
+    for (file, size, large, binary) in heavy {
+        if lfs.iter().any(|l| l == file) {
+            continue;
+        }
+        if large {
+            warnings.push(CommitWarning {
+                path: file.to_string(),
+                kind: WarningKind::Large,
+                message: format!(
+                    "{:.1} MB is over the {} MB limit",
+                    size as f64 / (1024.0 * 1024.0),
+                    config.max_file_size_mb
+                ),
+            });
+        } else if binary {
+            warnings.push(CommitWarning {
+                path: file.to_string(),
+                kind: WarningKind::Binary,
+                message: "Binary file not tracked by Git LFS".to_string(),
+            });
+        }
+    }
+
+    Ok(warnings)
+}
+
+/// Local, no-AI review of the files about to be committed. The UI shows the result in a
+/// confirmation step; `commit_changes` refuses a flagged selection unless told it was seen.
+#[tauri::command]
+pub fn analyze_commit_selection(path: &str, files: Vec<String>) -> Result<Vec<CommitWarning>, String> {
+    let config = repo_config::load(path)?;
+    analyze(path, &files, &config.commit_checks)
+}
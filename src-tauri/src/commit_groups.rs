@@ -0,0 +1,234 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::Mutex;
+use std::task::Poll;
+use tauri::State;
+use tokio::sync::oneshot;
+
+use crate::ai::send_ai_prompt;
+use crate::diff::{self, DiffScope};
+use crate::{run_git, AiConfig};
+
+/// Above this many changed files, files are first bucketed by directory and the model
+/// clusters the buckets instead, which keeps the prompt small enough for local models.
+const MAX_FILES_FOR_PER_FILE_GROUPING: usize = 30;
+/// Changed lines shown per file in the prompt.
+const EXCERPT_LINES: usize = 12;
+/// Error message of work stopped by the user.
+pub(crate) const CANCELLED: &str = "Cancelled";
+
+/// The in-flight `suggest_commit_groups` call, if any. Dropping the sender cancels it, so
+/// starting a new suggestion also cancels the previous one.
+#[derive(Default)]
+pub struct CommitGroupsTask(Mutex<Option<oneshot::Sender<()>>>);
+
+#[derive(Serialize, Debug)]
+pub struct CommitGroup {
+    message: String,
+    files: Vec<String>,
+}
+
+/// One thing the model is asked to place in a group: a single file, or for large change
+/// sets a directory bucket of files.
+struct Unit {
+    label: String,
+    files: Vec<String>,
+    excerpt: String,
+}
+
+#[derive(Deserialize)]
+struct ModelReply {
+    groups: Vec<ModelGroup>,
+}
+
+#[derive(Deserialize)]
+struct ModelGroup {
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    units: Vec<usize>,
+}
+
+/// Every changed path (staged, unstaged or untracked, with untracked directories expanded)
+/// paired with its porcelain status code.
+fn changed_files(path: &str) -> Result<Vec<(String, String)>, String> {
+    let out = run_git(path, &["status", "--porcelain", "-z", "-uall"])?;
+    let mut entries = out.split('\0').filter(|e| !e.is_empty());
+    let mut files = Vec::new();
+    while let Some(entry) = entries.next() {
+        if entry.len() < 4 {
+            continue;
+        }
+        let code = entry[..2].trim().to_string();
+        // Renames and copies are followed by the original path, which isn't a change of its own
+        if code.contains('R') || code.contains('C') {
+            entries.next();
+        }
+        files.push((entry[3..].to_string(), code));
+    }
+    Ok(files)
+}
+
+/// Splits a combined diff into the changed lines of each file, keyed by the `b/` path.
+fn excerpts_by_file(full_diff: &str) -> HashMap<String, String> {
+    let mut excerpts = HashMap::new();
+    for section in full_diff.split("\ndiff --git ").skip(1) {
+        let header = section.lines().next().unwrap_or_default();
+        let Some((_, file)) = header.rsplit_once(" b/") else {
+            continue;
+        };
+        let lines: Vec<&str> = section
+            .lines()
+            .filter(|l| (l.starts_with('+') || l.starts_with('-')) && !l.starts_with("+++") && !l.starts_with("---"))
+            .take(EXCERPT_LINES)
+            .collect();
+        excerpts.insert(file.to_string(), lines.join("\n"));
+    }
+    excerpts
+}
+
+fn directory_bucket(file: &str) -> String {
+    let parts: Vec<&str> = file.trim_end_matches('/').split('/').collect();
+    match parts.len() {
+        1 => "(root)".to_string(),
+        2 => parts[0].to_string(),
+        _ => format!("{}/{}", parts[0], parts[1]),
+    }
+}
+
+fn build_units(path: &str) -> Result<Vec<Unit>, String> {
+    let files = changed_files(path)?;
+    if files.is_empty() {
+        return Err("There are no changes to group".to_string());
+    }
+
+    if files.len() > MAX_FILES_FOR_PER_FILE_GROUPING {
+        let mut buckets: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (file, _) in files {
+            buckets.entry(directory_bucket(&file)).or_default().push(file);
+        }
+        return Ok(buckets
+            .into_iter()
+            .map(|(dir, files)| {
+                let sample: Vec<&str> = files.iter().take(8).map(String::as_str).collect();
+                Unit {
+                    label: format!("{} ({} files)", dir, files.len()),
+                    excerpt: sample.join("\n"),
+                    files,
+                }
+            })
+            .collect());
+    }
+
+    let full_diff = diff::collect_diff(path, &DiffScope::All)?;
+    let excerpts = excerpts_by_file(&format!("\n{}", full_diff));
+    Ok(files
+        .into_iter()
+        .map(|(file, code)| Unit {
+            label: format!("{} [{}]", file, code),
+            excerpt: excerpts.get(&file).cloned().unwrap_or_default(),
+            files: vec![file],
+        })
+        .collect())
+}
+
+fn build_prompt(units: &[Unit]) -> String {
+    let mut listing = String::new();
+    for (id, unit) in units.iter().enumerate() {
+        listing.push_str(&format!("[{}] {}\n", id, unit.label));
+        for line in unit.excerpt.lines() {
+            listing.push_str(&format!("    {}\n", line));
+        }
+    }
+
+    format!(
+        "You are an expert developer splitting a working tree into small, logical git commits.
+Group the numbered changes below so that each group is one coherent commit, and write a conventional commit message ('<type>: <subject>') for each group.
+Every number must appear in exactly one group. Return ONLY JSON in this exact shape, without markdown ticks or explanations:
+{{\"groups\": [{{\"message\": \"feat: ...\", \"units\": [0, 2]}}]}}
+
+Changes:
+{}",
+        listing
+    )
+}
+
+/// Parses the model's JSON (tolerating code fences or chatter around it) and repairs it so
+/// every unit lands in exactly one group: unknown and repeated ids are dropped, and anything
+/// the model left out goes into a final catch-all group.
+fn parse_groups(reply: &str, units: Vec<Unit>) -> Result<Vec<CommitGroup>, String> {
+    let start = reply.find('{').ok_or("The AI response did not contain any groups")?;
+    let end = reply.rfind('}').ok_or("The AI response did not contain any groups")?;
+    let parsed: ModelReply = serde_json::from_str(&reply[start..=end])
+        .map_err(|e| format!("Could not read the AI's grouping: {}", e))?;
+
+    let mut units: Vec<Option<Unit>> = units.into_iter().map(Some).collect();
+    let mut groups = Vec::new();
+    for group in parsed.groups {
+        let files: Vec<String> = group
+            .units
+            .iter()
+            .filter_map(|&id| units.get_mut(id).and_then(Option::take))
+            .flat_map(|unit| unit.files)
+            .collect();
+        if files.is_empty() {
+            continue;
+        }
+        let message = match group.message.trim() {
+            "" => "chore: update files".to_string(),
+            message => message.to_string(),
+        };
+        groups.push(CommitGroup { message, files });
+    }
+
+    let leftover: Vec<String> = units.into_iter().flatten().flat_map(|unit| unit.files).collect();
+    if !leftover.is_empty() {
+        groups.push(CommitGroup {
+            message: "chore: update remaining files".to_string(),
+            files: leftover,
+        });
+    }
+    Ok(groups)
+}
+
+/// Resolves to the result of `work`, or to a "Cancelled" error as soon as `cancel` fires or
+/// its sender is dropped.
+pub(crate) async fn cancellable<T, F>(work: F, cancel: &mut oneshot::Receiver<()>) -> Result<T, String>
+where
+    F: Future<Output = Result<T, String>>,
+{
+    let mut work = std::pin::pin!(work);
+    std::future::poll_fn(|cx| {
+        if std::pin::Pin::new(&mut *cancel).poll(cx).is_ready() {
+            return Poll::Ready(Err(CANCELLED.to_string()));
+        }
+        work.as_mut().poll(cx)
+    })
+    .await
+}
+
+/// Asks the AI to split the current changes into logical commits. Each group comes back
+/// with a proposed message and its files; committing them one by one is done through the
+/// usual selected-files commit.
+#[tauri::command]
+pub async fn suggest_commit_groups(
+    path: String,
+    config: AiConfig,
+    task: State<'_, CommitGroupsTask>,
+) -> Result<Vec<CommitGroup>, String> {
+    let (cancel_tx, mut cancel) = oneshot::channel();
+    *task.0.lock().unwrap() = Some(cancel_tx);
+
+    let collect = tauri::async_runtime::spawn_blocking(move || build_units(&path));
+    let units = cancellable(async { collect.await.map_err(|e| e.to_string())? }, &mut cancel).await?;
+    let reply = cancellable(send_ai_prompt(config, build_prompt(&units)), &mut cancel).await?;
+    parse_groups(&reply, units)
+}
+
+#[tauri::command]
+pub fn cancel_commit_groups(task: State<'_, CommitGroupsTask>) {
+    if let Some(cancel) = task.0.lock().unwrap().take() {
+        let _ = cancel.send(());
+    }
+}
@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+
+use crate::run_git;
+
+/// The character git starts comment lines with; `auto` and unset both mean `#` here, since
+/// a template is read before there's a message to pick a free character for.
+fn comment_char(path: &str) -> char {
+    let configured = run_git(path, &["config", "--get", "core.commentChar"]).unwrap_or_default();
+    let mut chars = configured.trim().chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => c,
+        _ => '#',
+    }
+}
+
+/// Text of the `commit.template` file with comment lines removed, or `None` when none is set,
+/// the file can't be read, or nothing but comments is left.
+pub(crate) fn load(path: &str) -> Option<String> {
+    // `--path` expands `~/` and `~user/` the way git does when it reads the template
+    let configured = run_git(path, &["config", "--path", "--get", "commit.template"]).ok()?;
+    let mut file = PathBuf::from(configured.trim());
+    if file.as_os_str().is_empty() {
+        return None;
+    }
+    if file.is_relative() {
+        file = PathBuf::from(run_git(path, &["rev-parse", "--show-toplevel"]).ok()?.trim()).join(file);
+    }
+    let text = std::fs::read_to_string(file).ok()?;
+
+    let comment = comment_char(path);
+    let lines: Vec<&str> = text
+        .lines()
+        .map(|l| l.trim_end_matches('\r'))
+        .filter(|l| !l.starts_with(comment))
+        .collect();
+    let template = lines.join("\n").trim_end().to_string();
+    (!template.trim().is_empty()).then_some(template)
+}
+
+/// The repository's commit message template (`commit.template`), to start a new message from.
+#[tauri::command]
+pub fn get_commit_template(path: &str) -> Option<String> {
+    load(path)
+}
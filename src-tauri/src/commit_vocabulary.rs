@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::State;
+
+use crate::repo_config;
+use crate::settings::SettingsService;
+
+const TYPES_KEY: &str = "commitTypes";
+const SCOPES_KEY: &str = "allowedScopes";
+/// Subjects longer than this get a warning; most tools truncate them in one-line views.
+const MAX_SUBJECT_LEN: usize = 72;
+
+const DEFAULT_TYPES: &[(&str, &str)] = &[
+    ("feat", "A new feature"),
+    ("fix", "A bug fix"),
+    ("docs", "Documentation only"),
+    ("style", "Formatting, no code change"),
+    ("refactor", "Code change that neither fixes a bug nor adds a feature"),
+    ("perf", "Performance improvement"),
+    ("test", "Adding or fixing tests"),
+    ("build", "Build system or dependencies"),
+    ("ci", "CI configuration"),
+    ("chore", "Other changes that don't touch source or tests"),
+    ("revert", "Reverts a previous commit"),
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CommitType {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// The commit types and scopes that generation offers and the linter accepts.
+#[derive(Serialize, Debug)]
+pub struct CommitVocabulary {
+    types: Vec<CommitType>,
+    /// `None` means any scope is fine
+    allowed_scopes: Option<Vec<String>>,
+    /// Types came from the repo or the user rather than the built-in list, so others are errors
+    custom_types: bool,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LintSeverity {
+    Error,
+    Warning,
+}
+
+#[derive(Serialize, Debug)]
+pub struct LintIssue {
+    severity: LintSeverity,
+    message: String,
+}
+
+fn from_settings<T: for<'de> Deserialize<'de>>(settings: &Map<String, Value>, key: &str) -> Option<T> {
+    serde_json::from_value(settings.get(key)?.clone()).ok()
+}
+
+/// The repo's `.gitpop.toml` wins over the user's settings, so a team-wide list can't be
+/// bypassed locally; the built-in conventional-commit types apply when neither sets one.
+pub fn load(path: &str, settings: &Map<String, Value>) -> Result<CommitVocabulary, String> {
+    let config = repo_config::load(path)?;
+    let types = config
+        .commit_types
+        .or_else(|| from_settings::<Vec<CommitType>>(settings, TYPES_KEY))
+        .filter(|types| !types.is_empty());
+    let allowed_scopes = config.allowed_scopes.or_else(|| from_settings(settings, SCOPES_KEY));
+
+    Ok(CommitVocabulary {
+        custom_types: types.is_some(),
+        types: types.unwrap_or_else(|| {
+            DEFAULT_TYPES
+                .iter()
+                .map(|(name, description)| CommitType { name: name.to_string(), description: description.to_string() })
+                .collect()
+        }),
+        allowed_scopes,
+    })
+}
+
+/// The part of the generation prompt that tells the model which types and scopes to use.
+pub fn prompt_instructions(vocabulary: &CommitVocabulary) -> String {
+    let types: Vec<String> = vocabulary
+        .types
+        .iter()
+        .map(|t| match t.description.is_empty() {
+            true => format!("- {}", t.name),
+            false => format!("- {}: {}", t.name, t.description),
+        })
+        .collect();
+    let mut text = format!("The <type> MUST be one of the following:\n{}", types.join("\n"));
+    if let Some(scopes) = &vocabulary.allowed_scopes {
+        text.push_str(&format!(
+            "\nIf a scope fits, write '<type>(<scope>): <subject>' using one of these scopes: {}.",
+            scopes.join(", ")
+        ));
+    }
+    text
+}
+
+/// Splits `type(scope)!: subject` into its parts; `None` when the header isn't in that form.
+fn parse_header(header: &str) -> Option<(&str, Option<&str>, &str)> {
+    let (prefix, subject) = header.split_once(':')?;
+    let prefix = prefix.trim_end_matches('!');
+    let (kind, scope) = match prefix.split_once('(') {
+        Some((kind, rest)) => (kind, Some(rest.strip_suffix(')')?)),
+        None => (prefix, None),
+    };
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return None;
+    }
+    Some((kind, scope, subject.trim()))
+}
+
+pub fn lint(message: &str, vocabulary: &CommitVocabulary) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    let mut issue = |severity, message: String| issues.push(LintIssue { severity, message });
+    let header = message.lines().next().unwrap_or_default().trim();
+    if header.is_empty() {
+        issue(LintSeverity::Error, "The commit message is empty".to_string());
+        return issues;
+    }
+
+    let Some((kind, scope, subject)) = parse_header(header) else {
+        let severity = if vocabulary.custom_types { LintSeverity::Error } else { LintSeverity::Warning };
+        issue(severity, "The first line should look like '<type>: <subject>'".to_string());
+        return issues;
+    };
+
+    if !vocabulary.types.iter().any(|t| t.name == kind) {
+        let names: Vec<&str> = vocabulary.types.iter().map(|t| t.name.as_str()).collect();
+        let severity = if vocabulary.custom_types { LintSeverity::Error } else { LintSeverity::Warning };
+        issue(severity, format!("Unknown type '{}'; use one of {}", kind, names.join(", ")));
+    }
+    if let (Some(scope), Some(allowed)) = (scope, &vocabulary.allowed_scopes) {
+        if !allowed.iter().any(|s| s == scope) {
+            issue(LintSeverity::Warning, format!("Unknown scope '{}'; allowed scopes are {}", scope, allowed.join(", ")));
+        }
+    }
+    if subject.is_empty() {
+        issue(LintSeverity::Error, "The subject after the type is empty".to_string());
+    }
+    if header.chars().count() > MAX_SUBJECT_LEN {
+        issue(LintSeverity::Warning, format!("The first line is longer than {} characters", MAX_SUBJECT_LEN));
+    }
+    if message.lines().nth(1).is_some_and(|line| !line.trim().is_empty()) {
+        issue(LintSeverity::Warning, "Leave a blank line between the subject and the body".to_string());
+    }
+    issues
+}
+
+#[tauri::command]
+pub async fn get_commit_vocabulary(path: String, settings: State<'_, SettingsService>) -> Result<CommitVocabulary, String> {
+    let settings = settings.get().await?;
+    load(&path, &settings)
+}
+
+#[tauri::command]
+pub async fn lint_commit_message(
+    path: String,
+    message: String,
+    settings: State<'_, SettingsService>,
+) -> Result<Vec<LintIssue>, String> {
+    let settings = settings.get().await?;
+    Ok(lint(&message, &load(&path, &settings)?))
+}
@@ -0,0 +1,234 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::State;
+
+use crate::activity::{ActivityLog, Operation};
+use crate::error::CommandError;
+use crate::read_only::ReadOnlyRepos;
+use crate::{decode_path, git_cmd, git_status, run_git, spawn_error, GitFileStatus};
+
+/// Largest version of a conflicted file `get_conflict_versions` returns the content of, in bytes.
+const MAX_CONFLICT_FILE_LEN: usize = 1024 * 1024;
+
+/// Which side of a conflict to keep. During a rebase "ours" is the branch being rebased onto
+/// and "theirs" the commit being replayed, as with git itself.
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictSide {
+    Ours,
+    Theirs,
+}
+
+impl ConflictSide {
+    /// The index stage holding this side of an unmerged entry.
+    fn stage(self) -> &'static str {
+        match self {
+            ConflictSide::Ours => "2",
+            ConflictSide::Theirs => "3",
+        }
+    }
+
+    fn flag(self) -> &'static str {
+        match self {
+            ConflictSide::Ours => "--ours",
+            ConflictSide::Theirs => "--theirs",
+        }
+    }
+}
+
+/// The index stages `file` has while unmerged (`1` base, `2` ours, `3` theirs), from
+/// `ls-files -u`. A side that deleted the file has no stage. Fails when the file isn't
+/// in conflict.
+fn unmerged_stages(path: &str, file: &str) -> Result<Vec<String>, String> {
+    let out = run_git(path, &["ls-files", "-u", "-z", "--", file])?;
+    // Entries are `<mode> <sha> <stage>\t<path>`
+    let stages: Vec<String> = out
+        .split('\0')
+        .filter_map(|entry| entry.split_once('\t'))
+        .filter(|(_, name)| *name == file)
+        .filter_map(|(info, _)| info.rsplit(' ').next().map(str::to_string))
+        .collect();
+    if stages.is_empty() {
+        return Err(format!("{} has no unresolved conflict", file));
+    }
+    Ok(stages)
+}
+
+fn status_of(path: &str, file: &str) -> Result<Option<GitFileStatus>, String> {
+    Ok(git_status(path, false)?.into_iter().find(|s| s.path == file))
+}
+
+/// Resolves the conflict in `file` by keeping one side as a whole and stages the result.
+/// When that side deleted the file (a modify/delete conflict), the file is removed. Returns
+/// the file's refreshed status row, null when nothing about it differs from HEAD anymore.
+#[tauri::command]
+pub fn resolve_conflict(
+    path: &str,
+    file: &str,
+    side: ConflictSide,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<Option<GitFileStatus>, CommandError> {
+    repos.ensure_writable(path)?;
+    let file = decode_path(path, file);
+    let stages = unmerged_stages(path, &file)?;
+
+    let label = match side {
+        ConflictSide::Ours => "Keep our side of",
+        ConflictSide::Theirs => "Keep their side of",
+    };
+    let mut op = Operation::new(format!("{} {}", label, file));
+    let result = if stages.iter().any(|s| s == side.stage()) {
+        let checkout = run_git(path, &["checkout", side.flag(), "--", &file]);
+        op.step("Check out the chosen side", &checkout);
+        checkout.and_then(|_| run_git(path, &["add", "--", &file]))
+    } else {
+        run_git(path, &["rm", "-q", "--", &file])
+    };
+    activity.record(path, op, &result);
+    result?;
+    Ok(status_of(path, &file)?)
+}
+
+/// Marks a conflict the user resolved by editing `file` as resolved, staging the file as it
+/// is in the work tree (or its deletion). Returns the refreshed status row, if any.
+#[tauri::command]
+pub fn mark_resolved(
+    path: &str,
+    file: &str,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<Option<GitFileStatus>, CommandError> {
+    repos.ensure_writable(path)?;
+    let file = decode_path(path, file);
+    unmerged_stages(path, &file)?;
+    let result = run_git(path, &["add", "-A", "--", &file]);
+    activity.record(path, Operation::new(format!("Mark {} resolved", file)), &result);
+    result?;
+    Ok(status_of(path, &file)?)
+}
+
+#[derive(Serialize, Debug)]
+pub struct ConflictSizes {
+    base: Option<usize>,
+    ours: Option<usize>,
+    theirs: Option<usize>,
+    merged: usize,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ConflictVersions {
+    /// The common ancestor's version; null when both sides added the file
+    base: Option<String>,
+    /// Null when our side deleted the file
+    ours: Option<String>,
+    /// Null when their side deleted the file
+    theirs: Option<String>,
+    /// The work tree file as git left it, with conflict markers
+    merged: String,
+    /// Some version is binary or bigger than `MAX_CONFLICT_FILE_LEN`: the contents are left out
+    /// (null, and `merged` empty) and only `sizes` are filled in
+    binary: bool,
+    too_large: bool,
+    sizes: ConflictSizes,
+}
+
+/// The blob at `:<stage>:<file>` in the index.
+fn stage_content(path: &str, stage: &str, file: &str) -> Result<Vec<u8>, String> {
+    let output = git_cmd(path, &["show", &format!(":{}:{}", stage, file)])
+        .output()
+        .map_err(spawn_error)?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    }
+    Ok(output.stdout)
+}
+
+/// Each side of the conflict in `file`, for a three-way merge view: the common base, ours and
+/// theirs from the index stages, and the work tree file with its conflict markers. A side that
+/// deleted the file, or a base when both sides added it, is null.
+#[tauri::command]
+pub fn get_conflict_versions(path: &str, file: &str) -> Result<ConflictVersions, String> {
+    let file = decode_path(path, file);
+    let stages = unmerged_stages(path, &file)?;
+    let version = |stage: &str| -> Result<Option<Vec<u8>>, String> {
+        match stages.iter().any(|s| s == stage) {
+            true => stage_content(path, stage, &file).map(Some),
+            false => Ok(None),
+        }
+    };
+    let (base, ours, theirs) = (version("1")?, version("2")?, version("3")?);
+    // Missing when the conflict was resolved by deleting the file
+    let merged = std::fs::read(Path::new(path).join(&file)).unwrap_or_default();
+
+    let sizes = ConflictSizes {
+        base: base.as_ref().map(Vec::len),
+        ours: ours.as_ref().map(Vec::len),
+        theirs: theirs.as_ref().map(Vec::len),
+        merged: merged.len(),
+    };
+    let all = [base.as_deref(), ours.as_deref(), theirs.as_deref(), Some(merged.as_slice())];
+    let binary = all.iter().flatten().any(|bytes| bytes.contains(&0));
+    let too_large = all.iter().flatten().any(|bytes| bytes.len() > MAX_CONFLICT_FILE_LEN);
+    if binary || too_large {
+        let merged = String::new();
+        return Ok(ConflictVersions { base: None, ours: None, theirs: None, merged, binary, too_large, sizes });
+    }
+    let text = |bytes: Vec<u8>| String::from_utf8_lossy(&bytes).to_string();
+    Ok(ConflictVersions {
+        base: base.map(text),
+        ours: ours.map(text),
+        theirs: theirs.map(text),
+        merged: text(merged),
+        binary,
+        too_large,
+        sizes,
+    })
+}
+
+/// Replaces `target` with `content` through a temporary file and a rename, so it's never left
+/// half written, keeping the file's permissions.
+fn write_atomic(target: &Path, content: &str) -> Result<(), String> {
+    let mut tmp = target.as_os_str().to_owned();
+    tmp.push(".gitpop-tmp");
+    std::fs::write(&tmp, content).map_err(|e| e.to_string())?;
+    if let Ok(meta) = std::fs::metadata(target) {
+        let _ = std::fs::set_permissions(&tmp, meta.permissions());
+    }
+    std::fs::rename(&tmp, target).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp);
+        e.to_string()
+    })
+}
+
+/// Writes the resolved `content` of a conflicted file from the merge view and stages it. If
+/// staging fails the file is put back as it was, conflict markers and all.
+#[tauri::command]
+pub fn save_resolved_file(
+    path: &str,
+    file: &str,
+    content: &str,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<Option<GitFileStatus>, CommandError> {
+    repos.ensure_writable(path)?;
+    let file = decode_path(path, file);
+    unmerged_stages(path, &file)?;
+    let target = Path::new(path).join(&file);
+    let previous = std::fs::read(&target).ok();
+
+    let mut op = Operation::new(format!("Save resolved {}", file));
+    let write = write_atomic(&target, content);
+    op.step("Write the resolution", &write);
+    let result = write.and_then(|_| run_git(path, &["add", "--", &file]));
+    if result.is_err() {
+        let restored = match &previous {
+            Some(bytes) => std::fs::write(&target, bytes).map_err(|e| e.to_string()),
+            None => std::fs::remove_file(&target).map_err(|e| e.to_string()),
+        };
+        op.step("Put the file back", &restored);
+    }
+    activity.record(path, op, &result);
+    result?;
+    Ok(status_of(path, &file)?)
+}
@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+use crate::error::CommandError;
+use crate::repo_key;
+
+/// How long a confirmation stays valid after the user agreed to it.
+const TOKEN_LIFETIME: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DangerLevel {
+    /// Can be undone from within git or the OS (trash, stash backup)
+    Recoverable,
+    /// Loses data for good; needs a confirmation token
+    Destructive,
+}
+
+/// Exactly what the user is asked to confirm. A token only authorizes the operation it was
+/// issued for, down to the repository and the list of files.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DangerousOperation {
+    DiscardChanges { path: String, files: Vec<String>, permanent: bool },
+    /// Deleting untracked (and possibly ignored) paths for good with `clean_execute`
+    CleanUntracked { path: String, files: Vec<String> },
+    /// Force-removing a linked worktree along with its uncommitted changes
+    RemoveWorktree { path: String, worktree: String },
+    /// Replacing commits on a remote branch with `push`'s `force` or `force_unsafe`. `remote`
+    /// and `branch` are as passed to `push`, null meaning the upstream's
+    ForcePush { path: String, remote: Option<String>, branch: Option<String> },
+    /// Deleting tag `name` from `remote` with `delete_tag`'s `also_remote`, for everyone who
+    /// fetches from there
+    DeleteRemoteTag { path: String, remote: String, name: String },
+}
+
+impl DangerousOperation {
+    /// Same operation with the repository given by its root, so a token requested from a
+    /// subdirectory still matches.
+    fn normalized(&self) -> Self {
+        match self {
+            DangerousOperation::DiscardChanges { path, files, permanent } => {
+                let mut files = files.clone();
+                files.sort();
+                DangerousOperation::DiscardChanges { path: repo_key(path), files, permanent: *permanent }
+            }
+            DangerousOperation::CleanUntracked { path, files } => {
+                let mut files = files.clone();
+                files.sort();
+                DangerousOperation::CleanUntracked { path: repo_key(path), files }
+            }
+            DangerousOperation::RemoveWorktree { path, worktree } => {
+                DangerousOperation::RemoveWorktree { path: repo_key(path), worktree: worktree.clone() }
+            }
+            DangerousOperation::ForcePush { path, remote, branch } => {
+                DangerousOperation::ForcePush { path: repo_key(path), remote: remote.clone(), branch: branch.clone() }
+            }
+            DangerousOperation::DeleteRemoteTag { path, remote, name } => {
+                DangerousOperation::DeleteRemoteTag { path: repo_key(path), remote: remote.clone(), name: name.clone() }
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            DangerousOperation::DiscardChanges { files, permanent: true, .. } => {
+                format!("Permanently delete the changes to {} file(s)", files.len())
+            }
+            DangerousOperation::DiscardChanges { files, .. } => {
+                format!("Discard the changes to {} file(s) without a backup", files.len())
+            }
+            DangerousOperation::CleanUntracked { files, .. } => {
+                format!("Permanently delete {} untracked path(s)", files.len())
+            }
+            DangerousOperation::RemoveWorktree { worktree, .. } => {
+                format!("Remove the worktree at {} and delete its uncommitted changes", worktree)
+            }
+            DangerousOperation::ForcePush { remote, branch, .. } => format!(
+                "Force push {} to {}, replacing the commits there",
+                branch.as_deref().unwrap_or("the current branch"),
+                remote.as_deref().unwrap_or("its upstream")
+            ),
+            DangerousOperation::DeleteRemoteTag { remote, name, .. } => {
+                format!("Delete the tag {} from {} for everyone", name, remote)
+            }
+        }
+    }
+}
+
+/// Single-use confirmation tokens handed out by `confirm_dangerous_operation`. This is the one
+/// place destructive commands check, so a frontend dialog that gets skipped or auto-accepted
+/// can't run one on its own.
+#[derive(Default)]
+pub struct ConfirmationTokens {
+    tokens: Mutex<HashMap<String, (DangerousOperation, Instant)>>,
+    counter: AtomicU64,
+}
+
+impl ConfirmationTokens {
+    fn issue(&self, operation: DangerousOperation) -> String {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+        let mut hasher = Sha256::new();
+        hasher.update(nanos.to_le_bytes());
+        hasher.update(self.counter.fetch_add(1, Ordering::Relaxed).to_le_bytes());
+        hasher.update(std::process::id().to_le_bytes());
+        let token: String = hasher.finalize().iter().take(16).map(|b| format!("{:02x}", b)).collect();
+
+        let mut tokens = self.tokens.lock().unwrap();
+        tokens.retain(|_, (_, issued)| issued.elapsed() < TOKEN_LIFETIME);
+        tokens.insert(token.clone(), (operation, Instant::now()));
+        token
+    }
+
+    /// Lets `operation` through when it's merely recoverable, or when `token` was issued for
+    /// this exact operation less than 30 seconds ago. A presented token is used up either way.
+    pub fn authorize(&self, level: DangerLevel, operation: &DangerousOperation, token: Option<&str>) -> Result<(), CommandError> {
+        let issued = token.and_then(|token| self.tokens.lock().unwrap().remove(token));
+        if level != DangerLevel::Destructive {
+            return Ok(());
+        }
+
+        let operation = operation.normalized();
+        match issued {
+            Some((confirmed, at)) if confirmed == operation && at.elapsed() < TOKEN_LIFETIME => Ok(()),
+            _ => Err(CommandError::ConfirmationRequired {
+                message: operation.describe(),
+                operation,
+            }),
+        }
+    }
+}
+
+/// Called by the frontend after the user accepted its "are you sure?" dialog. Returns the
+/// token to pass to the destructive command.
+#[tauri::command]
+pub fn confirm_dangerous_operation(operation: DangerousOperation, tokens: State<'_, ConfirmationTokens>) -> String {
+    tokens.issue(operation.normalized())
+}
@@ -0,0 +1,579 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::backend::GitBackend;
+use crate::{build_hidden_cmd, git_status, run_git, spawn_error, GitFileStatus, UNQUOTED_PATHS};
+
+/// Which changes a generated message should describe. Sent from the frontend as
+/// `{ kind: "selected_files", files: [...] }`, `{ kind: "staged" }` or `{ kind: "all" }`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "kind", content = "files", rename_all = "snake_case")]
+pub enum DiffScope {
+    SelectedFiles(Vec<String>),
+    Staged,
+    All,
+}
+
+/// Staged and unstaged diffs of the same files, kept apart.
+#[derive(Serialize, Debug)]
+pub struct SplitDiff {
+    pub staged: String,
+    /// Work tree changes, with untracked files diffed against an empty file
+    pub unstaged: String,
+}
+
+/// The selected files whose work tree version gets committed: the ones with nothing staged.
+/// Files with staged changes are committed as staged, so their unstaged edits are left out.
+fn committed_from_worktree<'a>(path: &str, files: &'a [String]) -> Result<Vec<&'a str>, String> {
+    let statuses = git_status(path, false)?;
+    Ok(files
+        .iter()
+        .filter(|file| !statuses.iter().any(|s| &&s.path == file && s.index_status.is_some() && !s.conflicted))
+        .map(String::as_str)
+        .collect())
+}
+
+/// Settings key for leaving whitespace-only changes out of diffs shown and sent to the AI.
+pub const IGNORE_WHITESPACE_KEY: &str = "ignoreWhitespace";
+/// Larger context requests are capped to this many lines.
+pub const MAX_CONTEXT_LINES: u32 = 100;
+
+/// How diff text is produced; the default matches plain `git diff`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DiffOptions {
+    /// Leave out changes that only touch whitespace or blank lines (`-w --ignore-blank-lines`)
+    pub ignore_whitespace: bool,
+    /// Lines of context around each change (`-U<n>`), 3 when unset; 0 gives bare hunks
+    pub context_lines: Option<u32>,
+    /// Emit `--word-diff=porcelain` instead of line hunks, for `word_diff::parse`
+    pub word_diff: bool,
+}
+
+impl DiffOptions {
+    pub fn flags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+        if self.ignore_whitespace {
+            flags.extend(["-w".to_string(), "--ignore-blank-lines".to_string()]);
+        }
+        if let Some(lines) = self.context_lines {
+            flags.push(format!("-U{}", lines.min(MAX_CONTEXT_LINES)));
+        }
+        if self.word_diff {
+            flags.push("--word-diff=porcelain".to_string());
+        }
+        flags
+    }
+
+    /// `flags` followed by these options, as arguments for `git_diff`.
+    fn with<'a>(flags: &[&'a str], options: &'a [String]) -> Vec<&'a str> {
+        flags.iter().copied().chain(options.iter().map(String::as_str)).collect()
+    }
+}
+
+/// Builds a name-status summary followed by the full diff for `scope`, without touching the
+/// index. For selected files this is what committing them would record: the staged side of
+/// files with staged changes and the work tree side of the rest. Untracked files are diffed
+/// against an empty file so new files show up too.
+pub fn collect_diff(path: &str, scope: &DiffScope) -> Result<String, String> {
+    crate::backend::active().diff(path, scope, DiffOptions::default())
+}
+
+/// `collect_diff` through git, with other `options`. Files whose changes are all whitespace
+/// are still listed in the summary when whitespace is ignored.
+pub fn collect_git_diff(path: &str, scope: &DiffScope, options: DiffOptions) -> Result<String, String> {
+    let options = options.flags();
+    let pathspec: Vec<&str> = match scope {
+        DiffScope::SelectedFiles(files) if files.is_empty() => return Err("No files selected".to_string()),
+        DiffScope::SelectedFiles(files) => files.iter().map(String::as_str).collect(),
+        DiffScope::Staged | DiffScope::All => Vec::new(),
+    };
+    let worktree_pathspec = match scope {
+        // An empty pathspec would mean every file, so no such files means no work tree diff
+        DiffScope::SelectedFiles(files) => Some(committed_from_worktree(path, files)?).filter(|p| !p.is_empty()),
+        DiffScope::All => Some(Vec::new()),
+        DiffScope::Staged => None,
+    };
+
+    let mut summary = git_diff(path, &["--cached", "--name-status"], &pathspec)?;
+    let mut diff = git_diff(path, &DiffOptions::with(&["--cached"], &options), &pathspec)?;
+
+    if let Some(pathspec) = worktree_pathspec {
+        summary.push_str(&git_diff(path, &["--name-status"], &pathspec)?);
+        diff.push_str(&git_diff(path, &DiffOptions::with(&[], &options), &pathspec)?);
+
+        for file in untracked_files(path, &pathspec)? {
+            summary.push_str(&format!("A\t{}\n", file));
+            diff.push_str(&untracked_diff(path, &file)?);
+        }
+    }
+
+    let mut full_diff = summary;
+    full_diff.push_str("\n\n");
+    full_diff.push_str(&describe_binaries(path, &diff));
+    Ok(full_diff)
+}
+
+/// Staged and unstaged changes of `files` (everything when empty) as two separate diffs.
+pub fn collect_split_diff(path: &str, files: &[String]) -> Result<SplitDiff, String> {
+    let pathspec: Vec<&str> = files.iter().map(String::as_str).collect();
+    let staged = git_diff(path, &["--cached"], &pathspec)?;
+    let mut unstaged = git_diff(path, &[], &pathspec)?;
+    for file in untracked_files(path, &pathspec)? {
+        unstaged.push_str(&untracked_diff(path, &file)?);
+    }
+    Ok(SplitDiff { staged: describe_binaries(path, &staged), unstaged: describe_binaries(path, &unstaged) })
+}
+
+/// The diff of one file from the status list (every file when `None`): its staged side with
+/// `staged`, otherwise its unstaged side. Untracked files (or every file in an untracked
+/// `dir/`) are diffed against `/dev/null`, and a staged rename is shown as old -> new instead
+/// of a delete and an add.
+pub fn file_diff(path: &str, file: Option<&str>, staged: bool, options: DiffOptions) -> Result<String, String> {
+    let options = options.flags();
+    if staged {
+        let Some(file) = file else {
+            return git_diff(path, &DiffOptions::with(&["--cached", "-M"], &options), &[]);
+        };
+        let old_path = git_status(path, false)?
+            .into_iter()
+            .find(|status| status.path == file)
+            .and_then(|status| status.old_path);
+        return match old_path {
+            Some(old) => git_diff(path, &DiffOptions::with(&["--cached", "-M"], &options), &[&old, file]),
+            None => git_diff(path, &DiffOptions::with(&["--cached"], &options), &[file]),
+        };
+    }
+
+    let pathspec: Vec<&str> = file.into_iter().collect();
+    let untracked = untracked_files(path, &pathspec)?;
+    // A single untracked file or directory has no tracked side to diff
+    let mut diff = match file.is_some() && !untracked.is_empty() {
+        true => String::new(),
+        false => git_diff(path, &DiffOptions::with(&[], &options), &pathspec)?,
+    };
+    for new_file in untracked {
+        diff.push_str(&untracked_diff_with(path, &new_file, &options)?);
+    }
+    Ok(diff)
+}
+
+/// `collect_diff` for backends that only read single files: the same summary and diff,
+/// put together from `status` and `file_diff` one file at a time.
+pub fn assemble_diff<B: GitBackend + ?Sized>(backend: &B, path: &str, scope: &DiffScope, options: DiffOptions) -> Result<String, String> {
+    if matches!(scope, DiffScope::SelectedFiles(files) if files.is_empty()) {
+        return Err("No files selected".to_string());
+    }
+    let statuses: Vec<GitFileStatus> = backend
+        .status(path, false)?
+        .into_iter()
+        .filter(|s| match scope {
+            DiffScope::SelectedFiles(files) => files.contains(&s.path),
+            DiffScope::Staged | DiffScope::All => true,
+        })
+        .collect();
+    let (mut summary, mut diff) = (String::new(), String::new());
+    let mut add = |code: &str, status: &GitFileStatus, staged: bool| -> Result<(), String> {
+        match &status.old_path {
+            Some(old) => summary.push_str(&format!("{}\t{}\t{}\n", code, old, status.path)),
+            None => summary.push_str(&format!("{}\t{}\n", code, status.path)),
+        }
+        diff.push_str(&backend.file_diff(path, Some(&status.path), staged, options)?);
+        Ok(())
+    };
+
+    let staged = |s: &GitFileStatus| s.index_status.is_some() && !s.conflicted;
+    for status in statuses.iter().filter(|s| staged(s)) {
+        add(status.index_status.as_deref().unwrap_or_default(), status, true)?;
+    }
+    if !matches!(scope, DiffScope::Staged) {
+        // Selected files with staged changes are committed as staged, like `committed_from_worktree`
+        let from_worktree = |s: &&GitFileStatus| !(matches!(scope, DiffScope::SelectedFiles(_)) && staged(s));
+        let worktree: Vec<&GitFileStatus> = statuses.iter().filter(from_worktree).collect();
+        for status in worktree.iter().filter(|s| s.worktree_status.as_deref().is_some_and(|c| c != "?")) {
+            add(status.worktree_status.as_deref().unwrap_or_default(), status, false)?;
+        }
+        for status in worktree.iter().filter(|s| s.worktree_status.as_deref() == Some("?")) {
+            add("A", status, false)?;
+        }
+    }
+
+    let mut full_diff = summary;
+    full_diff.push_str("\n\n");
+    full_diff.push_str(&describe_binaries_with(&diff, |blob, file| backend.blob_size(path, blob, file)));
+    Ok(full_diff)
+}
+
+fn git_diff(path: &str, flags: &[&str], pathspec: &[&str]) -> Result<String, String> {
+    let mut args = UNQUOTED_PATHS.to_vec();
+    args.extend(["diff", "--no-textconv", "--no-ext-diff"]);
+    args.extend_from_slice(flags);
+    args.push("--");
+    args.extend_from_slice(pathspec);
+    run_git(path, &args)
+}
+
+pub fn untracked_files(path: &str, pathspec: &[&str]) -> Result<Vec<String>, String> {
+    let mut args = vec!["ls-files", "--others", "--exclude-standard", "-z", "--"];
+    args.extend_from_slice(pathspec);
+    let out = run_git(path, &args)?;
+    Ok(out.split('\0').filter(|f| !f.is_empty()).map(str::to_string).collect())
+}
+
+/// Settings key for how much of each new file `limit_new_files` keeps, in KB.
+pub const NEW_FILE_LIMIT_KEY: &str = "newFileDiffLimitKb";
+pub const DEFAULT_NEW_FILE_LIMIT_KB: u64 = 16;
+
+/// `git diff --no-index` exits 1 when the files differ, which for a new file is always.
+pub fn untracked_diff(path: &str, file: &str) -> Result<String, String> {
+    untracked_diff_with(path, file, &[])
+}
+
+fn untracked_diff_with(path: &str, file: &str, options: &[String]) -> Result<String, String> {
+    let output = build_hidden_cmd("git")
+        .current_dir(path)
+        .args(UNQUOTED_PATHS)
+        .args(["diff", "--no-index"])
+        .args(options)
+        .args(["--", "/dev/null", file])
+        .output()
+        .map_err(spawn_error)?;
+
+    if output.status.code() == Some(1) || output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).to_string())
+    }
+}
+
+/// Cuts every new-file section of a diff down to about `max_bytes`, so a handful of large new
+/// files can't crowd the actual edits out of the prompt. Changes to existing files are kept.
+pub fn limit_new_files(diff: &str, max_bytes: usize) -> String {
+    let mut out = String::new();
+    for (i, section) in diff.split("\ndiff --git ").enumerate() {
+        if i > 0 {
+            out.push_str("\ndiff --git ");
+        }
+        if section.len() <= max_bytes || !section.contains("\nnew file mode ") {
+            out.push_str(section);
+            continue;
+        }
+        let mut kept = 0;
+        for line in section.split_inclusive('\n') {
+            if kept + line.len() > max_bytes {
+                break;
+            }
+            out.push_str(line);
+            kept += line.len();
+        }
+        let hidden = section[kept..].lines().count();
+        out.push_str(&format!("... [{} more lines of this new file not shown]", hidden));
+        if section.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Bytes set aside per hunk for `[... N lines omitted]` and a possible missing newline.
+const MARKER_RESERVE: usize = 32;
+
+/// One `diff --git` section split into the lines that always survive truncation (file header,
+/// hunk headers) and the hunk bodies that can be trimmed.
+struct Section<'a> {
+    header: &'a str,
+    hunks: Vec<(&'a str, Vec<&'a str>)>,
+}
+
+impl<'a> Section<'a> {
+    fn parse(text: &'a str) -> Self {
+        let mut header_len = 0;
+        let mut hunks: Vec<(&str, Vec<&str>)> = Vec::new();
+        for line in text.split_inclusive('\n') {
+            if line.starts_with("@@") {
+                hunks.push((line, Vec::new()));
+            } else if let Some((_, body)) = hunks.last_mut() {
+                body.push(line);
+            } else {
+                header_len += line.len();
+            }
+        }
+        Section { header: &text[..header_len], hunks }
+    }
+
+    /// Headers plus room for an omission marker in every hunk.
+    fn fixed_len(&self) -> usize {
+        self.header.len() + self.hunks.iter().map(|(h, _)| h.len() + MARKER_RESERVE).sum::<usize>()
+    }
+
+    fn body_len(&self) -> usize {
+        self.hunks.iter().flat_map(|(_, body)| body).map(|l| l.len()).sum()
+    }
+
+    /// Keeps hunk lines in order until `budget` bytes of body are used, with a marker in every
+    /// hunk that lost lines.
+    fn render(&self, mut budget: usize, out: &mut String) {
+        out.push_str(self.header);
+        for (hunk_header, body) in &self.hunks {
+            out.push_str(hunk_header);
+            let mut kept = 0;
+            for line in body {
+                if line.len() > budget {
+                    break;
+                }
+                budget -= line.len();
+                out.push_str(line);
+                kept += 1;
+            }
+            if kept < body.len() {
+                if !out.ends_with('\n') {
+                    out.push('\n');
+                }
+                out.push_str(&format!("[... {} lines omitted]\n", body.len() - kept));
+            }
+        }
+    }
+}
+
+/// Shrinks a diff to roughly `budget` bytes without losing any file. Every file header and
+/// hunk header is kept; the remaining budget is shared out so small files stay whole and the
+/// largest ones are trimmed first, with `[... N lines omitted]` where lines were dropped.
+/// The result can still exceed `budget` when the headers alone don't fit.
+pub fn fit_diff(diff: &str, budget: usize) -> String {
+    if diff.len() <= budget {
+        return diff.to_string();
+    }
+    let (preamble, rest) = match diff.find("diff --git ") {
+        Some(start) => diff.split_at(start),
+        None => (diff, ""),
+    };
+    let mut starts: Vec<usize> = rest.match_indices("\ndiff --git ").map(|(i, _)| i + 1).collect();
+    starts.insert(0, 0);
+    starts.push(rest.len());
+    let sections: Vec<Section> = starts.windows(2).map(|w| Section::parse(&rest[w[0]..w[1]])).collect();
+
+    let fixed = preamble.len() + sections.iter().map(Section::fixed_len).sum::<usize>();
+    let mut remaining = budget.saturating_sub(fixed);
+
+    // Smallest bodies first: each gets an equal share of what's left or less if it needs less
+    let mut order: Vec<usize> = (0..sections.len()).collect();
+    order.sort_by_key(|&i| sections[i].body_len());
+    let mut allowance = vec![0; sections.len()];
+    for (n, &i) in order.iter().enumerate() {
+        let share = remaining / (sections.len() - n);
+        allowance[i] = sections[i].body_len().min(share);
+        remaining -= allowance[i];
+    }
+
+    let mut out = preamble.to_string();
+    for (section, budget) in sections.iter().zip(allowance) {
+        section.render(budget, &mut out);
+    }
+    out
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes < 1024 {
+        true => format!("{} B", bytes),
+        false => format!("{} KB", bytes.div_ceil(1024)),
+    }
+}
+
+/// Size of one side of a binary change through git: the blob from the object database, or
+/// for work tree content (whose blob isn't written yet) the file on disk.
+pub fn git_blob_size(path: &str, blob: &str, file: &str) -> Option<u64> {
+    match run_git(path, &["cat-file", "-s", blob]) {
+        Ok(size) => size.trim().parse().ok(),
+        Err(_) => {
+            let root = run_git(path, &["rev-parse", "--show-toplevel"]).ok()?;
+            std::fs::metadata(Path::new(root.trim()).join(file)).map(|m| m.len()).ok()
+        }
+    }
+}
+
+/// Replaces the "Binary files a/x and b/x differ" line (or a `GIT binary patch`) of every
+/// binary file with one line saying what happened and how the size changed, e.g.
+/// `Binary file changed: logo.png (12 KB -> 14 KB)`, so no binary data reaches a prompt.
+/// The `diff --git` and `index` lines stay.
+pub fn describe_binaries(path: &str, diff: &str) -> String {
+    describe_binaries_with(diff, |blob, file| git_blob_size(path, blob, file))
+}
+
+/// `describe_binaries` with sizes from `blob_size(blob, file)`; the all-zero id of a side that
+/// doesn't exist is never looked up.
+pub fn describe_binaries_with(diff: &str, blob_size: impl Fn(&str, &str) -> Option<u64>) -> String {
+    if !diff.contains("\nBinary files ") && !diff.contains("\nGIT binary patch") {
+        return diff.to_string();
+    }
+    let size = |blob: &str, file: &str| match blob.chars().all(|c| c == '0') {
+        true => None,
+        false => blob_size(blob, file),
+    };
+
+    let mut out = String::new();
+    for (i, section) in diff.split("\ndiff --git ").enumerate() {
+        if i > 0 {
+            out.push_str("\ndiff --git ");
+        }
+        let binary_at = section
+            .match_indices('\n')
+            .map(|(at, _)| at + 1)
+            .find(|&at| section[at..].starts_with("Binary files ") || section[at..].starts_with("GIT binary patch"));
+        let Some(binary_at) = binary_at else {
+            out.push_str(section);
+            continue;
+        };
+
+        let header = section.lines().next().unwrap_or_default();
+        let file = header.rsplit_once(" b/").map(|(_, f)| f).unwrap_or(header);
+        let (old, new) = section
+            .lines()
+            .find_map(|l| l.strip_prefix("index "))
+            .and_then(|ids| ids.split_whitespace().next()?.split_once(".."))
+            .map(|(old, new)| (size(old, file), size(new, file)))
+            .unwrap_or((None, None));
+        let summary = match (old, new) {
+            (None, Some(new)) => format!("Binary file added: {} ({})", file, format_size(new)),
+            (Some(old), None) => format!("Binary file deleted: {} ({})", file, format_size(old)),
+            (Some(old), Some(new)) => {
+                format!("Binary file changed: {} ({} -> {})", file, format_size(old), format_size(new))
+            }
+            (None, None) => format!("Binary file changed: {}", file),
+        };
+        out.push_str(&section[..binary_at]);
+        out.push_str(&summary);
+        if section.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestRepo;
+
+    fn file_diff(name: &str, lines: usize) -> String {
+        let mut diff = format!(
+            "diff --git a/{0} b/{0}\nindex 1111111..2222222 100644\n--- a/{0}\n+++ b/{0}\n@@ -0,0 +1,{1} @@\n",
+            name, lines
+        );
+        for i in 0..lines {
+            diff.push_str(&format!("+line {} of {}\n", i, name));
+        }
+        diff
+    }
+
+    /// A PNG signature padded with zeros to `len` bytes, which git treats as binary.
+    fn png(len: usize) -> Vec<u8> {
+        let mut bytes = b"\x89PNG\r\n\x1a\n".to_vec();
+        bytes.resize(len, 0);
+        bytes
+    }
+
+    #[test]
+    fn binary_sizes_from_a_real_repository() {
+        let repo = TestRepo::with_commit();
+        let root = Path::new(repo.path());
+        std::fs::write(root.join("logo.png"), png(2 * 1024)).unwrap();
+        repo.commit_all("Add the logo");
+        std::fs::write(root.join("logo.png"), png(3 * 1024)).unwrap();
+        std::fs::write(root.join("icon.png"), png(300)).unwrap();
+
+        // The new logo isn't in the object database yet, so its size comes from the file
+        let diff = collect_git_diff(repo.path(), &DiffScope::All, DiffOptions::default()).unwrap();
+        assert!(diff.contains("\nBinary file changed: logo.png (2 KB -> 3 KB)\n"), "{}", diff);
+        assert!(diff.contains("\nBinary file added: icon.png (300 B)"), "{}", diff);
+        assert!(!diff.contains("Binary files ") && !diff.contains("PNG"), "{}", diff);
+
+        repo.git(&["add", "logo.png"]);
+        let staged = collect_git_diff(repo.path(), &DiffScope::Staged, DiffOptions::default()).unwrap();
+        assert!(staged.contains("\nBinary file changed: logo.png (2 KB -> 3 KB)\n"), "{}", staged);
+    }
+
+    #[test]
+    fn context_lines_are_capped() {
+        let flags = |context_lines| DiffOptions { context_lines, ..Default::default() }.flags();
+        assert_eq!(flags(None), Vec::<String>::new());
+        assert_eq!(flags(Some(0)), ["-U0"]);
+        assert_eq!(flags(Some(MAX_CONTEXT_LINES)), [format!("-U{}", MAX_CONTEXT_LINES)]);
+        assert_eq!(flags(Some(u32::MAX)), [format!("-U{}", MAX_CONTEXT_LINES)]);
+    }
+
+    #[test]
+    fn binary_changes_are_summarised_with_sizes() {
+        let diff = "\
+diff --git a/logo.png b/logo.png
+index 1111111..2222222 100644
+Binary files a/logo.png and b/logo.png differ
+diff --git a/icon.ico b/icon.ico
+new file mode 100644
+index 0000000..3333333
+Binary files /dev/null and b/icon.ico differ
+diff --git a/old.bin b/old.bin
+deleted file mode 100644
+index 4444444..0000000
+GIT binary patch
+literal 0
+HcmV?d00001
+
+diff --git a/a.txt b/a.txt
+index 5555555..6666666 100644
+--- a/a.txt
++++ b/a.txt
+@@ -1 +1 @@
+-one
++two
+";
+        let sizes = |blob: &str, _file: &str| match blob {
+            "1111111" => Some(12 * 1024),
+            "2222222" => Some(14 * 1024 - 100),
+            "3333333" => Some(300),
+            "4444444" => Some(2048),
+            _ => None,
+        };
+
+        let described = describe_binaries_with(diff, sizes);
+        assert!(described.contains("index 1111111..2222222 100644\nBinary file changed: logo.png (12 KB -> 14 KB)\n"));
+        assert!(described.contains("index 0000000..3333333\nBinary file added: icon.ico (300 B)\n"));
+        assert!(described.contains("index 4444444..0000000\nBinary file deleted: old.bin (2 KB)\n"));
+        assert!(!described.contains("GIT binary patch") && !described.contains("HcmV"));
+        assert!(described.ends_with("@@ -1 +1 @@\n-one\n+two\n"));
+
+        let unknown = describe_binaries_with(diff, |_, _| None);
+        assert!(unknown.contains("Binary file changed: logo.png\n"));
+        assert_eq!(describe_binaries_with("diff --git a/a b/a\n", sizes), "diff --git a/a b/a\n");
+    }
+
+    #[test]
+    fn fit_diff_keeps_every_file_and_trims_the_largest() {
+        let small: Vec<String> = (0..5).map(|i| file_diff(&format!("small{}.rs", i), 3)).collect();
+        let large = file_diff("generated.lock", 18_000);
+        assert!(large.len() > 500 * 1024, "{}", large.len());
+        let diff = format!("{}{}{}", small[..2].concat(), large, small[2..].concat());
+
+        let fitted = fit_diff(&diff, crate::MAX_DIFF_LEN);
+        assert!(fitted.len() <= crate::MAX_DIFF_LEN, "{}", fitted.len());
+        for section in small.iter().chain([&large]) {
+            let header: String = section.split_inclusive('\n').take(5).collect();
+            assert!(fitted.contains(&header), "missing {}", header);
+        }
+        for section in &small {
+            assert!(fitted.contains(section.as_str()));
+        }
+        let kept = fitted.matches("+line ").count() - 5 * 3;
+        assert!(kept > 0 && fitted.contains(&format!("[... {} lines omitted]\n", 18_000 - kept)), "{}", fitted);
+    }
+
+    #[test]
+    fn fit_diff_keeps_headers_even_when_they_overflow() {
+        let diff: String = (0..50).map(|i| file_diff(&format!("f{}.rs", i), 10)).collect();
+
+        let fitted = fit_diff(&diff, 100);
+        assert_eq!(fitted.matches("diff --git ").count(), 50);
+        assert_eq!(fitted.matches("@@ -0,0 +1,10 @@").count(), 50);
+        assert_eq!(fitted.matches("[... 10 lines omitted]").count(), 50);
+        assert!(!fitted.contains("+line"));
+        assert_eq!(fit_diff(&diff, diff.len()), diff);
+    }
+}
@@ -0,0 +1,82 @@
+use serde::Serialize;
+
+use crate::{run_git, UNQUOTED_PATHS};
+
+/// Lines added and removed in one file on one side (staged or unstaged). A file with both
+/// staged and unstaged changes has an entry for each.
+#[derive(Serialize, Debug)]
+pub struct FileDiffStat {
+    path: String,
+    additions: u32,
+    deletions: u32,
+    staged: bool,
+    /// Git can't count lines in binary files; both counts are 0 then
+    binary: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DiffStats {
+    files: Vec<FileDiffStat>,
+    /// Distinct paths, so a partially staged file counts once
+    files_changed: usize,
+    insertions: u32,
+    deletions: u32,
+}
+
+/// Parses `git diff --numstat -z`. Each record is `added\tdeleted\tpath\0`, or for a rename
+/// `added\tdeleted\t\0old\0new\0`; binary files show `-` for both counts.
+fn parse_numstat(out: &str, staged: bool) -> Vec<FileDiffStat> {
+    let mut stats = Vec::new();
+    let mut records = out.split('\0');
+    while let Some(record) = records.next() {
+        let mut fields = record.splitn(3, '\t');
+        let (Some(added), Some(deleted), Some(path)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        let path = match path.is_empty() {
+            true => {
+                records.next();
+                records.next().unwrap_or_default()
+            }
+            false => path,
+        };
+        let binary = added == "-" && deleted == "-";
+        stats.push(FileDiffStat {
+            path: path.to_string(),
+            additions: added.parse().unwrap_or(0),
+            deletions: deleted.parse().unwrap_or(0),
+            staged,
+            binary,
+        });
+    }
+    stats
+}
+
+fn numstat(path: &str, staged: bool) -> Result<Vec<FileDiffStat>, String> {
+    let mut args = UNQUOTED_PATHS.to_vec();
+    args.extend(["diff", "--no-textconv", "--numstat", "-z", "-M"]);
+    if staged {
+        args.push("--cached");
+    }
+    Ok(parse_numstat(&run_git(path, &args)?, staged))
+}
+
+/// Insertions and deletions per changed tracked file, staged and unstaged, with totals.
+/// Untracked files aren't included.
+#[tauri::command]
+pub fn get_diff_stats(path: &str) -> Result<DiffStats, String> {
+    let mut files = numstat(path, true)?;
+    files.extend(numstat(path, false)?);
+
+    let mut paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    paths.sort_unstable();
+    paths.dedup();
+    let files_changed = paths.len();
+
+    Ok(DiffStats {
+        files_changed,
+        insertions: files.iter().map(|f| f.additions).sum(),
+        deletions: files.iter().map(|f| f.deletions).sum(),
+        files,
+    })
+}
@@ -0,0 +1,245 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+use crate::activity::{ActivityLog, Operation};
+use crate::danger::{ConfirmationTokens, DangerLevel, DangerousOperation};
+use crate::error::CommandError;
+use crate::read_only::ReadOnlyRepos;
+use crate::run_git;
+use crate::settings::SettingsService;
+
+/// Message of the stash entries holding tracked-file content from before a discard.
+const BACKUP_LABEL: &str = "GitPop discard backup";
+/// Settings key for how many days backups are kept; 0 turns them off.
+const BACKUP_DAYS_KEY: &str = "discardBackupDays";
+const DEFAULT_BACKUP_DAYS: u64 = 7;
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Destination {
+    /// Moved to the OS trash / Recycle Bin
+    Trash,
+    /// Deleted outright because `permanent` was requested
+    Deleted,
+    /// The trash refused the file (common on network drives), so it was deleted outright
+    DeletedAfterTrashFailed { reason: String },
+    /// A tracked file put back to its committed content
+    RestoredFromHead,
+    /// A file put back to its staged content, with only the work tree changes discarded
+    RestoredFromIndex,
+    /// Left untouched, e.g. because it no longer has changes
+    Skipped { reason: String },
+}
+
+#[derive(Serialize, Debug)]
+pub struct DiscardedFile {
+    path: String,
+    destination: Destination,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DiscardResult {
+    files: Vec<DiscardedFile>,
+    /// Commit of the stash entry holding the tracked changes from before the discard, if one
+    /// was made; `git stash apply <commit>` brings them back
+    backup: Option<String>,
+}
+
+/// Files with changes, keyed by path relative to the repo root, with their porcelain code and
+/// for renames and copies the original path. Untracked directories are reported as `dir/`,
+/// the same as `get_git_status` shows them.
+fn changed_paths(root: &str) -> Result<HashMap<String, (String, Option<String>)>, String> {
+    let out = run_git(root, &["status", "--porcelain", "-z"])?;
+    let mut entries = out.split('\0').filter(|e| !e.is_empty());
+    let mut changed = HashMap::new();
+    while let Some(entry) = entries.next() {
+        if entry.len() < 4 {
+            continue;
+        }
+        let code = entry[..2].to_string();
+        // Renames and copies are followed by the original path
+        let source = if code.contains('R') || code.contains('C') { entries.next().map(str::to_string) } else { None };
+        changed.insert(entry[3..].to_string(), (code, source));
+    }
+    Ok(changed)
+}
+
+fn backup_days(settings: &Map<String, Value>) -> u64 {
+    match settings.get(BACKUP_DAYS_KEY) {
+        Some(Value::Number(n)) => n.as_u64().unwrap_or(DEFAULT_BACKUP_DAYS),
+        Some(Value::String(s)) => s.trim().parse().unwrap_or(DEFAULT_BACKUP_DAYS),
+        _ => DEFAULT_BACKUP_DAYS,
+    }
+}
+
+/// Records the current tracked changes as a stash entry without touching the work tree.
+/// Returns `None` when there is nothing to back up (or no HEAD to base a stash on).
+fn create_backup(root: &str) -> Result<Option<String>, String> {
+    let commit = match run_git(root, &["stash", "create", BACKUP_LABEL]) {
+        Ok(out) => out.trim().to_string(),
+        Err(_) => return Ok(None),
+    };
+    if commit.is_empty() {
+        return Ok(None);
+    }
+    run_git(root, &["stash", "store", "-m", BACKUP_LABEL, &commit])?;
+    Ok(Some(commit))
+}
+
+/// Drops GitPop's own backups older than `days`. Other stash entries are never touched.
+fn prune_backups(root: &str, days: u64) -> Result<(), String> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let cutoff = now.saturating_sub(days * 24 * 60 * 60);
+    let list = run_git(root, &["stash", "list", "--format=%gd%x00%gs%x00%ct"])?;
+
+    let expired: Vec<&str> = list
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\0');
+            let (reference, subject, time) = (fields.next()?, fields.next()?, fields.next()?);
+            let old = time.trim().parse::<u64>().ok()? < cutoff;
+            (old && subject.ends_with(BACKUP_LABEL)).then_some(reference)
+        })
+        .collect();
+    // Newest first in the list, so dropping from the end keeps the earlier refs valid
+    for reference in expired.iter().rev() {
+        run_git(root, &["stash", "drop", "-q", reference])?;
+    }
+    Ok(())
+}
+
+/// Sends an untracked file or directory to the trash unless `permanent`, deleting it outright
+/// when the trash isn't available.
+pub(crate) fn remove_untracked(file: &Path, permanent: bool) -> Result<Destination, String> {
+    let destination = if permanent {
+        Destination::Deleted
+    } else {
+        match trash::delete(file) {
+            Ok(()) => return Ok(Destination::Trash),
+            Err(e) => Destination::DeletedAfterTrashFailed { reason: e.to_string() },
+        }
+    };
+    let removed = if file.is_dir() { std::fs::remove_dir_all(file) } else { std::fs::remove_file(file) };
+    removed.map_err(|e| format!("Could not delete {}: {}", file.display(), e))?;
+    Ok(destination)
+}
+
+fn discard(
+    root: &str,
+    files: &[String],
+    staged: bool,
+    permanent: bool,
+    backup_days: u64,
+    op: &mut Operation,
+) -> Result<DiscardResult, String> {
+    let changed = changed_paths(root)?;
+
+    let backup = if backup_days > 0 && files.iter().any(|f| changed.get(f).is_some_and(|(code, _)| code != "??")) {
+        let backup = create_backup(root);
+        op.step("Back up tracked changes", &backup);
+        let pruned = prune_backups(root, backup_days);
+        op.step("Drop expired backups", &pruned);
+        backup?
+    } else {
+        None
+    };
+
+    let mut results = Vec::new();
+    for file in files {
+        let Some((code, source)) = changed.get(file) else {
+            results.push(DiscardedFile {
+                path: file.clone(),
+                destination: Destination::Skipped { reason: "No changes to discard".to_string() },
+            });
+            continue;
+        };
+
+        let destination = if code == "??" {
+            let removed = remove_untracked(&Path::new(root).join(file), permanent);
+            op.step(format!("Remove {}", file), &removed);
+            removed?
+        } else if !staged {
+            // Back to the staged content, which stays staged
+            if code.ends_with(' ') {
+                Destination::Skipped { reason: "Only staged changes, nothing to discard in the work tree".to_string() }
+            } else {
+                let restored = run_git(root, &["checkout", "-q", "--", file]);
+                op.step(format!("Restore {} from the index", file), &restored);
+                restored?;
+                Destination::RestoredFromIndex
+            }
+        } else if code.starts_with(['A', 'R', 'C']) {
+            // Only exists in the index: unstage it, then treat it like an untracked file
+            let unstaged = run_git(root, &["rm", "--cached", "-q", "-f", "--", file]);
+            op.step(format!("Unstage {}", file), &unstaged);
+            unstaged?;
+            let destination = if Path::new(root).join(file).exists() {
+                let removed = remove_untracked(&Path::new(root).join(file), permanent);
+                op.step(format!("Remove {}", file), &removed);
+                removed?
+            } else {
+                Destination::Deleted
+            };
+            // A rename also staged the removal of the old path, which has to come back
+            if let (Some(old), true) = (source, code.starts_with('R')) {
+                let restored = run_git(root, &["checkout", "-q", "HEAD", "--", old]);
+                op.step(format!("Restore {}", old), &restored);
+                restored?;
+                results.push(DiscardedFile { path: old.clone(), destination: Destination::RestoredFromHead });
+            }
+            destination
+        } else {
+            let restored = run_git(root, &["checkout", "-q", "HEAD", "--", file]);
+            op.step(format!("Restore {}", file), &restored);
+            restored?;
+            Destination::RestoredFromHead
+        };
+        results.push(DiscardedFile { path: file.clone(), destination });
+    }
+
+    Ok(DiscardResult { files: results, backup })
+}
+
+/// Throws away the changes to `files`. Untracked files go to the trash unless `permanent`
+/// is set; tracked files are restored from HEAD after their current content is saved as a
+/// "GitPop discard backup" stash entry (see the `discardBackupDays` setting). With `staged`
+/// false only work tree changes are discarded and staged content is kept. A renamed file's
+/// old path is restored along with it. Deleting permanently, or discarding with backups
+/// turned off, needs a `confirmation` token.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn discard_changes(
+    path: String,
+    files: Vec<String>,
+    staged: Option<bool>,
+    permanent: Option<bool>,
+    confirmation: Option<String>,
+    settings: State<'_, SettingsService>,
+    repos: State<'_, ReadOnlyRepos>,
+    tokens: State<'_, ConfirmationTokens>,
+    activity: State<'_, ActivityLog>,
+) -> Result<DiscardResult, CommandError> {
+    repos.ensure_writable(&path)?;
+    let permanent = permanent.unwrap_or(false);
+    let days = backup_days(&settings.get().await?);
+    let level = if permanent || days == 0 { DangerLevel::Destructive } else { DangerLevel::Recoverable };
+    let operation = DangerousOperation::DiscardChanges { path: path.clone(), files: files.clone(), permanent };
+    tokens.authorize(level, &operation, confirmation.as_deref())?;
+    let root = run_git(&path, &["rev-parse", "--show-toplevel"])?.trim().to_string();
+
+    let summary = format!("Discard changes to {} file(s)", files.len());
+    let (op, result) = tauri::async_runtime::spawn_blocking(move || {
+        let mut op = Operation::new(summary);
+        let result = discard(&root, &files, staged.unwrap_or(true), permanent, days, &mut op);
+        (op, result)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    activity.record(&path, op, &result);
+    Ok(result?)
+}
@@ -0,0 +1,416 @@
+//! Read-only repository access through gitoxide, used when no git executable is installed.
+//! Covers what's needed to look at a repository (root, branch, status, diffs, history);
+//! commits go to git or libgit2, and anything else that writes is refused earlier by
+//! `ReadOnlyRepos::ensure_writable`.
+
+use gix::bstr::{BStr, BString, ByteSlice};
+use gix::diff::blob::unified_diff::{ConsumeBinaryHunk, ContextSize};
+use gix::diff::blob::{intern::InternedInput, Algorithm, UnifiedDiff};
+use gix::dir::walk::EmissionMode;
+use gix::status::index_worktree::Item as WorktreeItem;
+use gix::ObjectId;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::activity::Operation;
+use crate::backend::GitBackend;
+use crate::diff::DiffOptions;
+use crate::error::CommandError;
+use crate::history::CommitEntry;
+use crate::{CommitOptions, CommitRequest, CommitResult, GitFileStatus, SyncStatus};
+
+/// The gitoxide backend.
+pub(crate) struct Gix;
+
+fn open(path: &str) -> Result<gix::Repository, String> {
+    gix::discover(path).map_err(|e| e.to_string())
+}
+
+fn workdir(repo: &gix::Repository) -> Result<PathBuf, String> {
+    repo.workdir()
+        .map(PathBuf::from)
+        .ok_or_else(|| "Bare repositories can't be opened without git".to_string())
+}
+
+/// What `status` learns about one path; the tree-index and index-worktree passes each fill in
+/// their side.
+#[derive(Default)]
+struct Entry {
+    index: Option<char>,
+    worktree: Option<char>,
+    old_path: Option<String>,
+    submodule: bool,
+    /// Git's two-letter code for an unmerged path
+    conflict: Option<&'static str>,
+}
+
+fn conflict_code(conflict: gix::status::plumbing::index_as_worktree::Conflict) -> &'static str {
+    use gix::status::plumbing::index_as_worktree::Conflict;
+    match conflict {
+        Conflict::BothDeleted => "DD",
+        Conflict::AddedByUs => "AU",
+        Conflict::DeletedByThem => "UD",
+        Conflict::AddedByThem => "UA",
+        Conflict::DeletedByUs => "DU",
+        Conflict::BothAdded => "AA",
+        Conflict::BothModified => "UU",
+    }
+}
+
+/// Untracked or ignored directory contents, with `/` after a directory listed as a whole.
+fn dir_entry_path(entry: &gix::dir::Entry) -> String {
+    let mut file = entry.rela_path.to_str_lossy().to_string();
+    if entry.disk_kind == Some(gix::dir::entry::Kind::Directory) {
+        file.push('/');
+    }
+    file
+}
+
+fn read_status(path: &str, include_ignored: bool) -> Result<Vec<GitFileStatus>, String> {
+    let repo = open(path)?;
+    let ignored = include_ignored.then_some(EmissionMode::CollapseDirectory);
+    let iter = repo
+        .status(gix::progress::Discard)
+        .map_err(|e| e.to_string())?
+        .dirwalk_options(|options| options.emit_ignored(ignored))
+        .into_iter(Vec::<BString>::new())
+        .map_err(|e| e.to_string())?;
+
+    let mut entries: BTreeMap<String, Entry> = BTreeMap::new();
+    let (mut untracked, mut ignored) = (Vec::new(), Vec::new());
+    for item in iter {
+        match item.map_err(|e| e.to_string())? {
+            gix::status::Item::TreeIndex(change) => {
+                use gix::diff::index::ChangeRef;
+                let (code, old_path) = match &change {
+                    ChangeRef::Addition { .. } => ('A', None),
+                    ChangeRef::Deletion { .. } => ('D', None),
+                    ChangeRef::Modification { .. } => ('M', None),
+                    ChangeRef::Rewrite { source_location, copy, .. } => {
+                        (if *copy { 'C' } else { 'R' }, Some(source_location.to_str_lossy().to_string()))
+                    }
+                };
+                let entry = entries.entry(change.location().to_str_lossy().to_string()).or_default();
+                entry.index = Some(code);
+                entry.old_path = old_path;
+                entry.submodule |= change.entry_mode().is_submodule();
+            }
+            gix::status::Item::IndexWorktree(item) => {
+                use gix::status::plumbing::index_as_worktree::{Change, EntryStatus};
+                match item {
+                    WorktreeItem::Modification { rela_path, status, entry: index_entry, .. } => {
+                        let entry = entries.entry(rela_path.to_str_lossy().to_string()).or_default();
+                        entry.submodule |= index_entry.mode.is_submodule();
+                        match status {
+                            EntryStatus::Conflict { summary, .. } => entry.conflict = Some(conflict_code(summary)),
+                            EntryStatus::Change(Change::Removed) => entry.worktree = Some('D'),
+                            EntryStatus::Change(Change::Type { .. }) => entry.worktree = Some('T'),
+                            EntryStatus::Change(Change::Modification { .. } | Change::SubmoduleModification(_)) => {
+                                entry.worktree = Some('M')
+                            }
+                            // Git shows `git add --intent-to-add` files as added in the work tree
+                            EntryStatus::IntentToAdd => entry.worktree = Some('A'),
+                            EntryStatus::NeedsUpdate(_) => {}
+                        }
+                    }
+                    WorktreeItem::DirectoryContents { entry, .. } => match entry.status {
+                        gix::dir::entry::Status::Untracked => untracked.push(dir_entry_path(&entry)),
+                        gix::dir::entry::Status::Ignored(_) => ignored.push(dir_entry_path(&entry)),
+                        _ => {}
+                    },
+                    // Only reported with work tree rename tracking, which isn't turned on
+                    WorktreeItem::Rewrite { dirwalk_entry, .. } => untracked.push(dir_entry_path(&dirwalk_entry)),
+                }
+            }
+        }
+    }
+
+    // Same order as `git status --porcelain=v2`: changes, then untracked, then ignored files
+    let mut files: Vec<GitFileStatus> = entries
+        .into_iter()
+        .filter(|(_, e)| e.index.is_some() || e.worktree.is_some() || e.conflict.is_some())
+        .map(|(file, e)| match e.conflict {
+            Some(code) => {
+                let mut codes = code.chars();
+                let xy = (codes.next().unwrap_or('U'), codes.next().unwrap_or('U'));
+                GitFileStatus::changed(file, xy, e.submodule, None, true)
+            }
+            None => GitFileStatus::changed(file, (e.index.unwrap_or('.'), e.worktree.unwrap_or('.')), e.submodule, e.old_path, false),
+        })
+        .collect();
+    untracked.sort();
+    ignored.sort();
+    files.extend(untracked.into_iter().map(GitFileStatus::untracked));
+    files.extend(ignored.into_iter().map(GitFileStatus::ignored));
+    Ok(files)
+}
+
+/// Every untracked file under `dir`, one by one; .gitignore rules apply as they do for git.
+fn untracked_files(repo: &gix::Repository, dir: &str) -> Result<Vec<String>, String> {
+    let iter = repo
+        .status(gix::progress::Discard)
+        .map_err(|e| e.to_string())?
+        .untracked_files(gix::status::UntrackedFiles::Files)
+        .into_index_worktree_iter(vec![BString::from(dir.trim_end_matches('/'))])
+        .map_err(|e| e.to_string())?;
+    let mut files = Vec::new();
+    for item in iter {
+        if let WorktreeItem::DirectoryContents { entry, .. } = item.map_err(|e| e.to_string())? {
+            if entry.status == gix::dir::entry::Status::Untracked {
+                files.push(entry.rela_path.to_str_lossy().to_string());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// One side of a file diff: its blob id, mode and content.
+struct Side {
+    id: ObjectId,
+    mode: &'static str,
+    data: Vec<u8>,
+}
+
+fn mode_of(mode: gix::index::entry::Mode) -> &'static str {
+    match mode {
+        gix::index::entry::Mode::FILE_EXECUTABLE => "100755",
+        gix::index::entry::Mode::SYMLINK => "120000",
+        gix::index::entry::Mode::COMMIT => "160000",
+        _ => "100644",
+    }
+}
+
+fn blob_side(repo: &gix::Repository, id: ObjectId, mode: &'static str) -> Result<Side, String> {
+    let data = repo.find_object(id).map_err(|e| e.to_string())?.detach().data;
+    Ok(Side { id, mode, data })
+}
+
+fn head_side(repo: &gix::Repository, file: &str) -> Result<Option<Side>, String> {
+    let Ok(tree) = repo.head_tree() else {
+        return Ok(None);
+    };
+    let Some(entry) = tree.lookup_entry_by_path(file).map_err(|e| e.to_string())? else {
+        return Ok(None);
+    };
+    let mode = match entry.mode().kind() {
+        gix::object::tree::EntryKind::BlobExecutable => "100755",
+        gix::object::tree::EntryKind::Link => "120000",
+        gix::object::tree::EntryKind::Commit => "160000",
+        _ => "100644",
+    };
+    blob_side(repo, entry.object_id(), mode).map(Some)
+}
+
+fn index_side(repo: &gix::Repository, index: &gix::index::File, file: &str) -> Result<Option<Side>, String> {
+    match index.entry_by_path(BStr::new(file)) {
+        Some(entry) => blob_side(repo, entry.id, mode_of(entry.mode)).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn worktree_side(repo: &gix::Repository, root: &Path, file: &str, mode: &'static str) -> Result<Option<Side>, String> {
+    let full = root.join(file);
+    let Ok(meta) = std::fs::symlink_metadata(&full) else {
+        return Ok(None);
+    };
+    let data = if meta.file_type().is_symlink() {
+        let target = std::fs::read_link(&full).map_err(|e| e.to_string())?;
+        target.to_string_lossy().replace('\\', "/").into_bytes()
+    } else {
+        std::fs::read(&full).map_err(|e| e.to_string())?
+    };
+    let id = gix::objs::compute_hash(repo.object_hash(), gix::objs::Kind::Blob, &data).map_err(|e| e.to_string())?;
+    Ok(Some(Side { id, mode, data }))
+}
+
+fn short(id: &ObjectId) -> String {
+    id.to_hex_with_len(7).to_string()
+}
+
+/// The change from `before` (at `old`) to `after` (at `new`) in `git diff` format. With
+/// `ignore_whitespace`, a file whose changes are all whitespace is left out entirely.
+fn blob_diff(old: &str, new: &str, before: Option<Side>, after: Option<Side>, options: DiffOptions) -> Result<String, String> {
+    let unchanged = match (&before, &after) {
+        (Some(b), Some(a)) => b.id == a.id && b.mode == a.mode && old == new,
+        (None, None) => true,
+        _ => false,
+    };
+    if unchanged {
+        return Ok(String::new());
+    }
+    if options.ignore_whitespace {
+        let squeeze = |side: &Option<Side>| side.as_ref().map(|s| s.data.iter().filter(|b| !b.is_ascii_whitespace()).copied().collect::<Vec<u8>>());
+        if before.is_some() && after.is_some() && squeeze(&before) == squeeze(&after) {
+            return Ok(String::new());
+        }
+    }
+
+    let mut text = format!("diff --git a/{} b/{}\n", old, new);
+    match (&before, &after) {
+        (None, Some(a)) => text.push_str(&format!("new file mode {}\n", a.mode)),
+        (Some(b), None) => text.push_str(&format!("deleted file mode {}\n", b.mode)),
+        (Some(b), Some(a)) if b.mode != a.mode => text.push_str(&format!("old mode {}\nnew mode {}\n", b.mode, a.mode)),
+        _ => {}
+    }
+    let zero = ObjectId::null(gix::hash::Kind::Sha1);
+    let (old_id, new_id) = (before.as_ref().map_or(zero, |s| s.id), after.as_ref().map_or(zero, |s| s.id));
+    if old != new {
+        if old_id == new_id {
+            text.push_str("similarity index 100%\n");
+        }
+        text.push_str(&format!("rename from {}\nrename to {}\n", old, new));
+    }
+    if old_id == new_id {
+        return Ok(text);
+    }
+    match (&before, &after) {
+        (Some(b), Some(a)) if b.mode == a.mode => text.push_str(&format!("index {}..{} {}\n", short(&old_id), short(&new_id), a.mode)),
+        _ => text.push_str(&format!("index {}..{}\n", short(&old_id), short(&new_id))),
+    }
+
+    let old_name = if before.is_some() { format!("a/{}", old) } else { "/dev/null".to_string() };
+    let new_name = if after.is_some() { format!("b/{}", new) } else { "/dev/null".to_string() };
+    let (before, after) = (before.map(|s| s.data).unwrap_or_default(), after.map(|s| s.data).unwrap_or_default());
+    if before.contains(&0) || after.contains(&0) {
+        text.push_str(&format!("Binary files {} and {} differ\n", old_name, new_name));
+        return Ok(text);
+    }
+    text.push_str(&format!("--- {}\n+++ {}\n", old_name, new_name));
+
+    let context = options.context_lines.unwrap_or(3).min(crate::diff::MAX_CONTEXT_LINES);
+    let input = InternedInput::new(before.as_slice(), after.as_slice());
+    let hunks = gix::diff::blob::diff(
+        Algorithm::Histogram,
+        &input,
+        UnifiedDiff::new(&input, ConsumeBinaryHunk::new(String::new(), "\n"), ContextSize::symmetrical(context)),
+    )
+    .map_err(|e| e.to_string())?;
+    for line in hunks.split_inclusive('\n') {
+        match line.strip_prefix("@@ ").and_then(|l| l.split_once(" @@")) {
+            Some((ranges, rest)) => {
+                let ranges: Vec<String> = ranges.split(' ').map(git_range).collect();
+                text.push_str(&format!("@@ {} @@{}", ranges.join(" "), rest));
+            }
+            None => text.push_str(line),
+        }
+    }
+    Ok(text)
+}
+
+/// A hunk range the way git writes it: `-3` rather than `-3,1`, and an empty side as `-0,0`
+/// rather than starting at line 1.
+fn git_range(range: &str) -> String {
+    let (sign, range) = range.split_at(1);
+    match range.split_once(',') {
+        Some((_, "1")) => format!("{}{}", sign, range.split(',').next().unwrap_or_default()),
+        Some((start, "0")) => format!("{}{},0", sign, start.parse::<u32>().map_or(0, |s| s.saturating_sub(1))),
+        _ => format!("{}{}", sign, range),
+    }
+}
+
+impl GitBackend for Gix {
+    fn repo_root(&self, path: &str) -> Result<String, String> {
+        let root = workdir(&open(path)?)?;
+        Ok(root.to_string_lossy().replace('\\', "/"))
+    }
+
+    fn status(&self, path: &str, include_ignored: bool) -> Result<Vec<GitFileStatus>, String> {
+        read_status(path, include_ignored)
+    }
+
+    /// `ignore_whitespace` only leaves out files whose changes are all whitespace.
+    fn file_diff(&self, path: &str, file: Option<&str>, staged: bool, options: DiffOptions) -> Result<String, String> {
+        if options.word_diff {
+            return Err(crate::GIT_NOT_INSTALLED.to_string());
+        }
+        let repo = open(path)?;
+        let root = workdir(&repo)?;
+        let index = repo.index_or_empty().map_err(|e| e.to_string())?;
+        let statuses: Vec<GitFileStatus> = read_status(path, false)?
+            .into_iter()
+            .filter(|s| file.is_none_or(|f| f == s.path))
+            .collect();
+
+        let mut text = String::new();
+        for status in statuses.iter().filter(|s| !s.conflicted) {
+            let file = status.path.as_str();
+            if staged {
+                if status.index_status.is_none() {
+                    continue;
+                }
+                let old = status.old_path.as_deref().unwrap_or(file);
+                text.push_str(&blob_diff(old, file, head_side(&repo, old)?, index_side(&repo, &index, file)?, options)?);
+            } else if status.worktree_status.as_deref() == Some("?") {
+                let files = match file.ends_with('/') {
+                    true => untracked_files(&repo, file)?,
+                    false => vec![file.to_string()],
+                };
+                for file in files {
+                    text.push_str(&blob_diff(&file, &file, None, worktree_side(&repo, &root, &file, "100644")?, options)?);
+                }
+            } else if status.worktree_status.is_some() {
+                let before = index_side(&repo, &index, file)?;
+                let mode = before.as_ref().map_or("100644", |s| s.mode);
+                text.push_str(&blob_diff(file, file, before, worktree_side(&repo, &root, file, mode)?, options)?);
+            }
+        }
+        Ok(text)
+    }
+
+    fn blob_size(&self, path: &str, blob: &str, file: &str) -> Option<u64> {
+        let repo = open(path).ok()?;
+        if let Some(object) = repo.rev_parse_single(blob).ok().and_then(|id| id.object().ok()) {
+            return Some(object.data.len() as u64);
+        }
+        std::fs::metadata(workdir(&repo).ok()?.join(file)).map(|m| m.len()).ok()
+    }
+
+    /// Branch, whether it has commits and whether there are remotes. Without git there's no
+    /// upstream tracking to compare against, so nothing is ever reported as ahead or behind.
+    fn sync_status(&self, path: &str) -> Result<SyncStatus, String> {
+        let repo = open(path)?;
+        let branch = repo.head_name().ok().flatten().map(|name| name.shorten().to_str_lossy().to_string());
+        Ok(SyncStatus {
+            branch: branch.unwrap_or_else(|| "HEAD".to_string()),
+            has_commits: repo.head_id().is_ok(),
+            has_remote: !repo.remote_names().is_empty(),
+            ..Default::default()
+        })
+    }
+
+    fn history(&self, path: &str, limit: usize) -> Result<Vec<CommitEntry>, String> {
+        let repo = open(path)?;
+        let Ok(head) = repo.head_id() else {
+            return Ok(Vec::new());
+        };
+        let walk = head.ancestors().all().map_err(|e| e.to_string())?;
+
+        let mut commits = Vec::new();
+        for info in walk.take(limit) {
+            let info = info.map_err(|e| e.to_string())?;
+            let commit = info.object().map_err(|e| e.to_string())?;
+            let message = commit.message().map_err(|e| e.to_string())?;
+            let author = commit.author().map_err(|e| e.to_string())?;
+            commits.push(CommitEntry {
+                sha: info.id.to_string(),
+                author: author.name.to_str_lossy().to_string(),
+                timestamp: commit.time().map(|t| t.seconds).unwrap_or(0),
+                subject: message.summary().to_str_lossy().to_string(),
+            });
+        }
+        Ok(commits)
+    }
+
+    fn can_commit(&self, _path: &str, _options: &CommitOptions, _files: &[String]) -> bool {
+        false
+    }
+
+    fn ensure_identity(&self, _path: &str) -> Result<(), CommandError> {
+        Err(CommandError::GitNotInstalled)
+    }
+
+    fn commit(&self, _path: &str, _request: CommitRequest, _op: &mut Operation) -> Result<CommitResult, CommandError> {
+        Err(CommandError::GitNotInstalled)
+    }
+}
@@ -0,0 +1,249 @@
+use serde::Serialize;
+
+use crate::commit_checks::CommitWarning;
+use crate::danger::DangerousOperation;
+use crate::fingerprint::DiffChangeSummary;
+use crate::patch_commit::HunkFailure;
+use crate::index_lock;
+use crate::GIT_NOT_INSTALLED;
+
+/// Error returned by commands whose failures the frontend needs to tell apart.
+/// Serialized as `{ "kind": "...", ...fields }`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CommandError {
+    /// Any other git or I/O failure, carrying git's stderr or the OS error verbatim
+    Git { message: String },
+    GitTooOld { needed: String, found: String, feature: String },
+    /// A commit failed after restaging began; `index_restored` tells whether the staging the
+    /// user had before the attempt was put back
+    CommitFailed { message: String, index_restored: bool },
+    /// The repository is marked read-only and the command would have written to it
+    ReadOnlyRepository { path: String },
+    /// The current branch is unborn (freshly initialized repo), so there is no HEAD to act on
+    NoCommitsYet,
+    /// A destructive command was called without a valid token from
+    /// `confirm_dangerous_operation`; `operation` is what has to be confirmed
+    ConfirmationRequired { operation: DangerousOperation, message: String },
+    /// The diff a commit message was generated from no longer matches the files being
+    /// committed; the summary is empty when the original diff isn't known anymore
+    DiffChangedSinceGeneration { changes: DiffChangeSummary },
+    /// No git executable was found; only the read-only embedded backend (if built in) works
+    GitNotInstalled,
+    /// The folder to open doesn't exist (anymore)
+    PathNotFound { path: String },
+    /// The path to open is a file, not a folder
+    NotADirectory { path: String },
+    /// The folder to initialize is inside the repository at `root`; `init_repo` needs
+    /// `allow_nested` to create a repository in it anyway
+    InsideExistingRepo { root: String },
+    /// `file` is `size` bytes, more than the `limit` GitPop loads to show it
+    FileTooLarge { file: String, size: u64, limit: u64 },
+    /// A patch passed to `commit_patch` doesn't apply cleanly to HEAD
+    PatchDoesNotApply { failures: Vec<HunkFailure> },
+    /// Selected files still have merge conflicts; they have to be resolved before committing
+    UnresolvedConflicts { files: Vec<String> },
+    /// Selected files are ignored by .gitignore; `commit_changes` needs `force_ignored` for them
+    IgnoredFilesSelected { files: Vec<String> },
+    /// The pre-commit checks flagged selected files (secrets, large files, binaries);
+    /// `commit_changes` needs `acknowledge_warnings` once the user has seen them
+    CommitWarnings { warnings: Vec<CommitWarning> },
+    /// The commit to amend is already on these remote-tracking branches; `commit_changes`
+    /// needs `amend_pushed` to rewrite it anyway
+    AmendingPushedCommit { remotes: Vec<String> },
+    /// The commit to undo is already on these remote-tracking branches; `undo_last_commit`
+    /// needs `allow_pushed` to take it back anyway
+    UndoingPushedCommit { remotes: Vec<String> },
+    /// Reverting a commit stopped on conflicts in `files`; `message` is the revert message git
+    /// prepared for when they're resolved
+    RevertConflicts { files: Vec<String>, message: String },
+    /// Merging a branch stopped on conflicts in `files`; `message` is the merge message git
+    /// prepared for when they're resolved
+    MergeConflict { files: Vec<String>, message: String },
+    /// Applying a stash left merge conflicts in `files`; the stash entry itself was kept
+    StashConflicts { files: Vec<String> },
+    /// Switching branches would overwrite local changes to `files` (tracked or untracked);
+    /// nothing was checked out, so they can be stashed or committed first
+    CheckoutWouldOverwrite { files: Vec<String> },
+    /// The remote has commits on `branches` that aren't here yet, so pushing them isn't a
+    /// fast-forward; pulling first fixes it
+    PushRejected { branches: Vec<String> },
+    /// The remote wanted credentials and got none (prompts are disabled) or refused them
+    AuthenticationFailed { remote: String },
+    /// ssh didn't accept `host`'s key: it isn't in `known_hosts` yet or doesn't match the one
+    /// there. Connecting once from a terminal shows the fingerprint to check and save.
+    HostKeyVerificationFailed { host: String },
+    /// A pull stopped on merge conflicts in `files`; resolving them finishes the merge or rebase
+    PullConflicts { files: Vec<String> },
+    /// A force push was refused because `branch` moved on the remote since it was last seen;
+    /// `remote_sha` is where it is now, when the remote could be asked
+    ForcePushLeaseFailed { branch: String, remote_sha: Option<String> },
+    /// A rebase stopped while replaying `commit` (`subject`), with conflicts in `files`; it
+    /// stays in progress until it's continued or aborted
+    RebaseConflict { files: Vec<String>, commit: Option<String>, subject: Option<String> },
+    /// Continuing a rebase found nothing to commit for `commit` (`subject`): its changes are
+    /// already on the new base, so it can be skipped
+    RebaseNothingToCommit { commit: Option<String>, subject: Option<String> },
+    /// A rebase plan includes `commits` that are already on `upstream`; rewriting them would
+    /// need a force push and disrupt anyone who pulled them
+    RebasingPushedCommits { upstream: String, commits: Vec<String> },
+    /// A cherry-pick stopped while applying `commit` (`subject`), with conflicts in `files`;
+    /// the commits after it are picked once it's continued
+    CherryPickConflicts { files: Vec<String>, commit: Option<String>, subject: Option<String> },
+    /// Git doesn't know who to commit as: `missing` lists the unset keys (`user.name`,
+    /// `user.email`), which `set_git_identity` fills in
+    IdentityNotConfigured { missing: Vec<String> },
+    /// Git found `index.lock`, created `age_seconds` ago, and couldn't go ahead.
+    /// `probably_stale` means it's old and no git is running, so a crashed git left it behind
+    /// and `remove_index_lock` can clear it
+    IndexLocked { age_seconds: u64, probably_stale: bool },
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::Git { message } => write!(f, "{}", message),
+            CommandError::GitTooOld { needed, found, feature } => write!(
+                f,
+                "{} requires git {} or newer, but git {} is installed",
+                feature, needed, found
+            ),
+            CommandError::CommitFailed { message, .. } => write!(f, "{}", message),
+            CommandError::ReadOnlyRepository { path } => {
+                write!(f, "{} is open in read-only mode; turn it off to make changes", path)
+            }
+            CommandError::NoCommitsYet => write!(f, "This repository has no commits yet"),
+            CommandError::ConfirmationRequired { message, .. } => write!(f, "{} needs to be confirmed first", message),
+            CommandError::DiffChangedSinceGeneration { .. } => {
+                write!(f, "The changes were modified after the commit message was generated")
+            }
+            CommandError::GitNotInstalled => write!(f, "Git isn't installed; install it to make changes"),
+            CommandError::PathNotFound { path } => write!(f, "{} doesn't exist", path),
+            CommandError::NotADirectory { path } => write!(f, "{} is a file, not a folder", path),
+            CommandError::InsideExistingRepo { root } => {
+                write!(f, "This folder is already inside the repository at {}", root)
+            }
+            CommandError::FileTooLarge { file, size, limit } => write!(
+                f,
+                "{} is {} MB, too large to show (the limit is {} MB)",
+                file,
+                size.div_ceil(1024 * 1024),
+                limit / (1024 * 1024)
+            ),
+            CommandError::PatchDoesNotApply { failures } => {
+                write!(f, "The selected changes no longer apply to HEAD ({} failed)", failures.len())
+            }
+            CommandError::UnresolvedConflicts { files } => {
+                write!(f, "Resolve the merge conflicts in {} before committing", files.join(", "))
+            }
+            CommandError::IgnoredFilesSelected { files } => {
+                write!(f, "{} are ignored by .gitignore; confirm to commit them anyway", files.join(", "))
+            }
+            CommandError::CommitWarnings { warnings } => write!(
+                f,
+                "{} selected file(s) were flagged by the pre-commit checks; review them before committing",
+                warnings.len()
+            ),
+            CommandError::AmendingPushedCommit { remotes } => write!(
+                f,
+                "The last commit was already pushed to {}; amending it rewrites published history",
+                remotes.join(", ")
+            ),
+            CommandError::UndoingPushedCommit { remotes } => write!(
+                f,
+                "The last commit was already pushed to {}; undoing it means force pushing later",
+                remotes.join(", ")
+            ),
+            CommandError::RevertConflicts { files, .. } => write!(
+                f,
+                "Reverting left conflicts in {}; resolve them, then continue or abort the revert",
+                files.join(", ")
+            ),
+            CommandError::MergeConflict { files, .. } => write!(
+                f,
+                "Merging left conflicts in {}; resolve them, then continue or abort the merge",
+                files.join(", ")
+            ),
+            CommandError::StashConflicts { files } => write!(
+                f,
+                "Applying the stash left conflicts in {}; the stash entry was kept",
+                files.join(", ")
+            ),
+            CommandError::CheckoutWouldOverwrite { files } => write!(
+                f,
+                "Switching branches would overwrite local changes to {}; commit or stash them first",
+                files.join(", ")
+            ),
+            CommandError::PushRejected { branches } => write!(
+                f,
+                "The remote has changes to {} that you don't have yet; pull before pushing",
+                branches.join(", ")
+            ),
+            CommandError::AuthenticationFailed { remote } => {
+                write!(f, "Authentication with {} failed; check your git credentials", remote)
+            }
+            CommandError::HostKeyVerificationFailed { host } => write!(
+                f,
+                "The SSH host key of {} isn't trusted yet or has changed; connect once from a terminal to verify it",
+                host
+            ),
+            CommandError::PullConflicts { files } => {
+                write!(f, "Pulling left conflicts in {}; resolve them to finish", files.join(", "))
+            }
+            CommandError::RebaseConflict { files, subject, .. } => write!(
+                f,
+                "The rebase stopped{} with conflicts in {}; resolve them, then continue or abort the rebase",
+                subject.as_ref().map(|s| format!(" at \"{}\"", s)).unwrap_or_default(),
+                files.join(", ")
+            ),
+            CommandError::RebaseNothingToCommit { subject, .. } => write!(
+                f,
+                "Nothing is left to commit for {} after resolving; skip it to carry on with the rebase",
+                subject.as_ref().map(|s| format!("\"{}\"", s)).unwrap_or_else(|| "this commit".to_string())
+            ),
+            CommandError::RebasingPushedCommits { upstream, commits } => write!(
+                f,
+                "{} of these commits are already on {}; only commits that haven't been pushed can be rebased",
+                commits.len(),
+                upstream
+            ),
+            CommandError::CherryPickConflicts { files, subject, .. } => write!(
+                f,
+                "Cherry-picking{} left conflicts in {}; resolve them, then continue, skip or abort the cherry-pick",
+                subject.as_ref().map(|s| format!(" \"{}\"", s)).unwrap_or_default(),
+                files.join(", ")
+            ),
+            CommandError::IdentityNotConfigured { missing } => write!(
+                f,
+                "Git doesn't know who you are: set {} before committing",
+                missing.join(" and ")
+            ),
+            CommandError::IndexLocked { age_seconds, probably_stale: true } => write!(
+                f,
+                "The repository's index.lock is {}s old and looks left over from a git that crashed",
+                age_seconds
+            ),
+            CommandError::IndexLocked { age_seconds, probably_stale: false } => {
+                write!(f, "Another git command has held the repository's index.lock for {}s", age_seconds)
+            }
+            CommandError::ForcePushLeaseFailed { branch, remote_sha } => write!(
+                f,
+                "Someone else pushed to {}{} since your last fetch; fetch first",
+                branch,
+                remote_sha.as_ref().map(|sha| format!(" (now at {})", &sha[..7.min(sha.len())])).unwrap_or_default()
+            ),
+        }
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        if message == GIT_NOT_INSTALLED {
+            return CommandError::GitNotInstalled;
+        }
+        if let Some(locked) = index_lock::detect(&message) {
+            return locked;
+        }
+        CommandError::Git { message }
+    }
+}
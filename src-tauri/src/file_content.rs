@@ -0,0 +1,151 @@
+use serde::Serialize;
+use std::path::{Component, Path};
+
+use crate::error::CommandError;
+use crate::{git_cmd, has_commits, run_git, run_git_with_input, spawn_error};
+
+/// Biggest blob returned; larger ones are refused with `FileTooLarge`.
+const MAX_FILE_LEN: u64 = 5 * 1024 * 1024;
+
+/// Bytes looked at for a NUL to decide a blob is binary, as git does.
+const BINARY_SNIFF_LEN: usize = 8000;
+
+#[derive(Serialize, Debug)]
+pub struct FileContent {
+    /// The text; null for a binary blob
+    content: Option<String>,
+    binary: bool,
+    /// Size of the blob in bytes
+    size: u64,
+}
+
+/// Refuses anything but a plain path inside the work tree, relative to its root.
+fn validate_file(file: &str) -> Result<(), String> {
+    let path = Path::new(file);
+    let plain = !file.is_empty()
+        && !file.contains('\n')
+        && path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    match plain {
+        true => Ok(()),
+        false => Err(format!("{} isn't a path relative to the repository root", file)),
+    }
+}
+
+/// `file` as it is in `revision`, or `None` when it isn't there.
+fn file_at(path: &str, revision: &str, file: &str) -> Result<Option<FileContent>, CommandError> {
+    validate_file(file)?;
+    let file = file.replace('\\', "/");
+    if revision.starts_with('-') {
+        return Err(format!("{} isn't a commit", revision).into());
+    }
+    let commit = run_git(path, &["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", revision)])
+        .map_err(|_| format!("{} isn't a commit", revision))?;
+
+    // Passed on stdin, so names with spaces or a leading dash need no quoting
+    let spec = format!("{}:{}\n", commit.trim(), file);
+    // The format leaves the path out of a found object's line; a missing one is still echoed
+    // back as `<spec> missing`, spaces in the path and all
+    let info = run_git_with_input(path, &["cat-file", "--batch-check=%(objectname) %(objecttype) %(objectsize)"], &spec)?;
+    if info.trim_end().ends_with(" missing") {
+        return Ok(None);
+    }
+    let mut fields = info.split_whitespace();
+    let (Some(blob), Some(kind), Some(size)) = (fields.next(), fields.next(), fields.next()) else {
+        return Ok(None);
+    };
+    if kind != "blob" {
+        return Err(format!("{} is a {} in {}, not a file", file, kind, revision).into());
+    }
+    let size: u64 = size.parse().map_err(|_| format!("Unexpected output from git cat-file: {}", info))?;
+    if size > MAX_FILE_LEN {
+        return Err(CommandError::FileTooLarge { file, size, limit: MAX_FILE_LEN });
+    }
+
+    let output = git_cmd(path, &["cat-file", "blob", blob])
+        .output()
+        .map_err(spawn_error)?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string().into());
+    }
+    let bytes = output.stdout;
+    let binary = bytes[..bytes.len().min(BINARY_SNIFF_LEN)].contains(&0);
+    let content = (!binary).then(|| String::from_utf8_lossy(&bytes).into_owned());
+    Ok(Some(FileContent { content, binary, size }))
+}
+
+/// `file` (relative to the repository root) as it was in the last commit, for the old side of
+/// a side-by-side diff. Null when it isn't in HEAD, e.g. a newly added file, or when there
+/// are no commits yet.
+#[tauri::command]
+pub fn get_file_at_head(path: &str, file: &str) -> Result<Option<FileContent>, CommandError> {
+    if !has_commits(path) {
+        validate_file(file)?;
+        return Ok(None);
+    }
+    file_at(path, "HEAD", file)
+}
+
+/// `get_file_at_head` for any commit, branch or tag.
+#[tauri::command]
+pub fn get_file_at_revision(path: &str, revision: &str, file: &str) -> Result<Option<FileContent>, CommandError> {
+    file_at(path, revision, file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestRepo;
+
+    fn fields(content: Option<FileContent>) -> Option<(Option<String>, bool, u64)> {
+        content.map(|c| (c.content, c.binary, c.size))
+    }
+
+    #[test]
+    fn missing_and_binary_files() {
+        let repo = TestRepo::with_commit();
+        let first = repo.head();
+        let logo = "\u{89}PNG\r\n\u{1a}\n\0\0\0\rIHDR";
+        repo.write("logo.png", logo);
+        repo.write("dir/b.txt", "b\n");
+        repo.commit_all("Add logo and dir");
+        repo.write("new.txt", "not committed\n");
+
+        assert_eq!(fields(get_file_at_head(repo.path(), "a.txt").unwrap()), Some((Some("one\n".to_string()), false, 4)));
+        assert_eq!(fields(get_file_at_head(repo.path(), "logo.png").unwrap()), Some((None, true, logo.len() as u64)));
+        assert_eq!(fields(get_file_at_head(repo.path(), "new.txt").unwrap()), None);
+        assert_eq!(fields(get_file_at_revision(repo.path(), &first, "logo.png").unwrap()), None);
+        assert_eq!(fields(get_file_at_revision(repo.path(), "main~1", "a.txt").unwrap()).map(|f| f.2), Some(4));
+
+        assert!(get_file_at_head(repo.path(), "dir").is_err());
+        assert!(get_file_at_head(repo.path(), "../a.txt").is_err());
+        assert!(get_file_at_head(repo.path(), "/etc/passwd").is_err());
+        assert!(get_file_at_revision(repo.path(), "--output=x", "a.txt").is_err());
+        assert!(get_file_at_revision(repo.path(), "no-such-branch", "a.txt").is_err());
+    }
+
+    #[test]
+    fn paths_with_spaces() {
+        let repo = TestRepo::with_commit();
+        let first = repo.head();
+        repo.write("dir with spaces/a file.txt", "spaced\n");
+        repo.commit_all("Add a spaced path");
+        repo.write("dir with spaces/new file.txt", "not committed\n");
+
+        let present = Some((Some("spaced\n".to_string()), false, 7));
+        assert_eq!(fields(get_file_at_head(repo.path(), "dir with spaces/a file.txt").unwrap()), present);
+        assert_eq!(fields(get_file_at_revision(repo.path(), "main", "dir with spaces/a file.txt").unwrap()), present);
+        assert_eq!(fields(get_file_at_head(repo.path(), "dir with spaces/new file.txt").unwrap()), None);
+        assert_eq!(fields(get_file_at_revision(repo.path(), &first, "dir with spaces/a file.txt").unwrap()), None);
+        assert!(get_file_at_head(repo.path(), "dir with spaces").is_err());
+    }
+
+    #[test]
+    fn nothing_at_head_before_the_first_commit() {
+        let repo = TestRepo::init();
+        repo.write("a.txt", "one\n");
+        repo.git(&["add", "a.txt"]);
+
+        assert_eq!(fields(get_file_at_head(repo.path(), "a.txt").unwrap()), None);
+        assert!(get_file_at_head(repo.path(), "../a.txt").is_err());
+    }
+}
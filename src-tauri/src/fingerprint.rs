@@ -0,0 +1,138 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+
+use crate::diff::{self, DiffScope};
+use crate::error::CommandError;
+
+/// Snapshots kept for `commit_changes` to compare against; older ones are forgotten.
+const MAX_SNAPSHOTS: usize = 16;
+
+/// What changed between generating a message and committing, for the
+/// `DiffChangedSinceGeneration` error.
+#[derive(Serialize, Debug, Default)]
+pub struct DiffChangeSummary {
+    /// Files that have changes now but didn't when the message was generated
+    added_files: Vec<String>,
+    /// Files that had changes then but no longer do
+    removed_files: Vec<String>,
+    /// Files in both whose diff is different
+    modified_files: Vec<String>,
+    /// Added and removed lines present in only one of the two diffs
+    lines_changed: usize,
+}
+
+/// The changed lines of each file in a diff produced by `diff::collect_diff`.
+struct DiffSnapshot {
+    path: String,
+    scope: DiffScope,
+    files: BTreeMap<String, Vec<String>>,
+}
+
+/// Snapshots of the diffs messages were generated from, keyed by fingerprint.
+#[derive(Default)]
+pub struct DiffFingerprints(Mutex<VecDeque<(String, DiffSnapshot)>>);
+
+fn split_by_file(full_diff: &str) -> BTreeMap<String, Vec<String>> {
+    let mut files: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    // Staged and unstaged sections of the same file are merged under one entry
+    for section in format!("\n{}", full_diff).split("\ndiff --git ").skip(1) {
+        let header = section.lines().next().unwrap_or_default();
+        let Some((_, file)) = header.rsplit_once(" b/") else {
+            continue;
+        };
+        let lines = files.entry(file.to_string()).or_default();
+        lines.extend(
+            section
+                .lines()
+                .filter(|l| (l.starts_with('+') || l.starts_with('-')) && !l.starts_with("+++") && !l.starts_with("---"))
+                .map(str::to_string),
+        );
+    }
+    files
+}
+
+fn fingerprint_of(full_diff: &str) -> String {
+    Sha256::digest(full_diff.as_bytes()).iter().take(16).map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Lines in only one of `a` and `b`, counting repeated lines individually.
+fn lines_differing(a: &[String], b: &[String]) -> usize {
+    let mut counts: BTreeMap<&str, isize> = BTreeMap::new();
+    for line in a {
+        *counts.entry(line).or_default() += 1;
+    }
+    for line in b {
+        *counts.entry(line).or_default() -= 1;
+    }
+    counts.values().map(|c| c.unsigned_abs()).sum()
+}
+
+fn summarize(before: &BTreeMap<String, Vec<String>>, now: &BTreeMap<String, Vec<String>>) -> DiffChangeSummary {
+    let mut summary = DiffChangeSummary::default();
+    for (file, lines) in now {
+        match before.get(file) {
+            None => {
+                summary.added_files.push(file.clone());
+                summary.lines_changed += lines.len();
+            }
+            Some(old) if old != lines => {
+                summary.modified_files.push(file.clone());
+                summary.lines_changed += lines_differing(old, lines);
+            }
+            Some(_) => {}
+        }
+    }
+    for (file, lines) in before {
+        if !now.contains_key(file) {
+            summary.removed_files.push(file.clone());
+            summary.lines_changed += lines.len();
+        }
+    }
+    summary
+}
+
+impl DiffFingerprints {
+    /// Fingerprints the diff a message is being generated from, which must come from
+    /// `diff::collect_diff` for the same `scope`, and remembers it for `verify`.
+    pub fn record(&self, path: &str, scope: &DiffScope, full_diff: &str) -> String {
+        let fingerprint = fingerprint_of(full_diff);
+        let snapshot = DiffSnapshot {
+            path: path.to_string(),
+            scope: scope.clone(),
+            files: split_by_file(full_diff),
+        };
+        let mut snapshots = self.0.lock().unwrap();
+        snapshots.retain(|(known, _)| *known != fingerprint);
+        snapshots.push_back((fingerprint.clone(), snapshot));
+        while snapshots.len() > MAX_SNAPSHOTS {
+            snapshots.pop_front();
+        }
+        fingerprint
+    }
+
+    /// Rebuilds the diff the same way generation did and fails with
+    /// `DiffChangedSinceGeneration` if it no longer matches `expected`. Only the files in the
+    /// generation scope are compared, so unrelated edits elsewhere don't count.
+    pub fn verify(&self, path: &str, expected: &str) -> Result<(), CommandError> {
+        let snapshot = self.0.lock().unwrap().iter().find(|(known, _)| known == expected).map(|(_, s)| {
+            (s.path.clone(), s.scope.clone(), s.files.clone())
+        });
+        let Some((generated_in, scope, before)) = snapshot else {
+            // Forgotten or never issued: nothing to compare with, so the caller has to decide
+            return Err(CommandError::DiffChangedSinceGeneration { changes: DiffChangeSummary::default() });
+        };
+        if generated_in != path {
+            return Err(CommandError::DiffChangedSinceGeneration { changes: DiffChangeSummary::default() });
+        }
+
+        let full_diff = diff::collect_diff(path, &scope)?;
+        if fingerprint_of(&full_diff) == expected {
+            return Ok(());
+        }
+        Err(CommandError::DiffChangedSinceGeneration {
+            changes: summarize(&before, &split_by_file(&full_diff)),
+        })
+    }
+}
@@ -0,0 +1,415 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::process::Command;
+use tauri::State;
+
+use crate::activity::{ActivityLog, Operation};
+use crate::capabilities::{GitCapabilitiesState, GitFeature};
+use crate::error::CommandError;
+use crate::read_only::ReadOnlyRepos;
+use crate::{build_hidden_cmd, run_git, spawn_error};
+
+/// Keys `set_git_config` may write, with the values each accepts (`None` = free text).
+/// Anything else is refused: keys like `core.sshCommand` or `core.pager` run programs, so
+/// letting the frontend set arbitrary keys would make this an injection vector.
+const ALLOWED_KEYS: &[(&str, Option<&[&str]>)] = &[
+    ("pull.rebase", Some(&["true", "false", "merges"])),
+    ("pull.ff", Some(&["true", "false", "only"])),
+    ("core.autocrlf", Some(&["true", "false", "input"])),
+    ("fetch.prune", Some(&["true", "false"])),
+    ("push.autoSetupRemote", Some(&["true", "false"])),
+    ("rebase.autoStash", Some(&["true", "false"])),
+    ("commit.gpgsign", Some(&["true", "false"])),
+    ("init.defaultBranch", None),
+];
+
+/// Sections whose every key `set_git_config` may write as free text (one line, which may
+/// contain spaces, as names do). None of their keys make git run anything.
+const ALLOWED_SECTIONS: &[&str] = &["user", "commit", "pull"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigScope {
+    Local,
+    Global,
+}
+
+impl ConfigScope {
+    fn flag(self) -> &'static str {
+        match self {
+            ConfigScope::Local => "--local",
+            ConfigScope::Global => "--global",
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct ConfigValue {
+    /// Effective value after git's precedence rules, or null when the key is unset
+    value: Option<String>,
+    /// Every value set for the key, in the order git reads them (so `value` is the last);
+    /// more than one for multi-valued keys like `remote.origin.fetch`
+    values: Vec<String>,
+    /// `system`, `global`, `local`, `worktree` or `command` of the effective value; null
+    /// when unset or when git is too old to report it (< 2.26)
+    scope: Option<String>,
+    /// File the effective value came from, e.g. `file:.git/config`
+    origin: Option<String>,
+}
+
+fn allowed_values(key: &str) -> Result<Option<&'static [&'static str]>, CommandError> {
+    // Section and variable names are case-insensitive in git
+    if let Some((_, values)) = ALLOWED_KEYS.iter().find(|(allowed, _)| allowed.eq_ignore_ascii_case(key)) {
+        return Ok(*values);
+    }
+    match key.split_once('.') {
+        Some((section, name)) if !name.contains('.') && ALLOWED_SECTIONS.iter().any(|s| s.eq_ignore_ascii_case(section)) => Ok(None),
+        _ => Err(format!("'{}' can't be changed from GitPop", key).into()),
+    }
+}
+
+/// Free-text keys of `ALLOWED_SECTIONS` (names, paths) may contain spaces; the others hold
+/// single words like branch names.
+fn allows_spaces(key: &str) -> bool {
+    key.split_once('.').is_some_and(|(section, _)| ALLOWED_SECTIONS.iter().any(|s| s.eq_ignore_ascii_case(section)))
+}
+
+fn validate_key(key: &str) -> Result<(), String> {
+    let valid = key.contains('.')
+        && !key.starts_with(['-', '.'])
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid config key: {}", key))
+    }
+}
+
+fn read_key(path: &str, key: &str, scope: Option<ConfigScope>, show_scope: bool) -> Result<ConfigValue, String> {
+    let mut git = build_hidden_cmd("git");
+    git.current_dir(path);
+    read_key_with(git, key, scope, show_scope)
+}
+
+/// `read_key` through a prepared `git` command, which tests point at their own global config.
+fn read_key_with(mut git: Command, key: &str, scope: Option<ConfigScope>, show_scope: bool) -> Result<ConfigValue, String> {
+    validate_key(key)?;
+    let mut args = vec!["config", "-z", "--show-origin"];
+    args.extend(scope.map(ConfigScope::flag));
+    if show_scope {
+        args.push("--show-scope");
+    }
+    args.extend(["--get-all", key]);
+
+    let output = git.args(&args).output().map_err(spawn_error)?;
+
+    // Exit code 1 means the key simply isn't set anywhere
+    if output.status.code() == Some(1) {
+        return Ok(ConfigValue::default());
+    }
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    }
+
+    // `-z` puts each entry's scope, origin and value in NUL-terminated fields
+    let out = String::from_utf8_lossy(&output.stdout).to_string();
+    let fields: Vec<&str> = out.split_terminator('\0').collect();
+    let mut config = ConfigValue::default();
+    for entry in fields.chunks_exact(if show_scope { 3 } else { 2 }) {
+        let (entry_scope, origin, value) = match entry {
+            [scope, origin, value] => (Some(scope), origin, value),
+            [origin, value] => (None, origin, value),
+            _ => continue,
+        };
+        config.scope = entry_scope.map(|s| s.to_string());
+        config.origin = Some(origin.to_string());
+        config.value = Some(value.to_string());
+        config.values.push(value.to_string());
+    }
+    Ok(config)
+}
+
+/// Value, every value, scope and origin of each key, as seen from the repository at `path`:
+/// from all of git's config files, or only the one `scope` names. Keys are returned as asked
+/// for; unset ones map to an empty value.
+#[tauri::command]
+pub fn get_git_config(
+    path: &str,
+    keys: Vec<String>,
+    scope: Option<ConfigScope>,
+    caps: State<'_, GitCapabilitiesState>,
+) -> Result<BTreeMap<String, ConfigValue>, String> {
+    let show_scope = caps.get()?.supports(GitFeature::ConfigShowScope);
+    keys.into_iter()
+        .map(|key| Ok((key.clone(), read_key(path, &key, scope, show_scope)?)))
+        .collect()
+}
+
+/// Sets (or with a null value, unsets) one of the allow-listed keys in the repo's own
+/// config or the user's global config.
+#[tauri::command]
+pub fn set_git_config(
+    path: &str,
+    key: &str,
+    value: Option<String>,
+    scope: ConfigScope,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<(), CommandError> {
+    let accepted = allowed_values(key)?;
+    if scope == ConfigScope::Local {
+        repos.ensure_writable(path)?;
+    }
+    let scope_flag = scope.flag();
+
+    let Some(value) = value else {
+        let output = build_hidden_cmd("git")
+            .current_dir(path)
+            .args(["config", scope_flag, "--unset-all", key])
+            .output()
+            .map_err(spawn_error)?;
+        // Exit code 5 means there was nothing to unset
+        let result = match output.status.success() || output.status.code() == Some(5) {
+            true => Ok(()),
+            false => Err(String::from_utf8_lossy(&output.stderr).to_string()),
+        };
+        activity.record(path, Operation::new(format!("Unset {} ({})", key, scope_flag.trim_start_matches('-'))), &result);
+        return Ok(result?);
+    };
+
+    let value = value.trim();
+    match accepted {
+        Some(values) if !values.contains(&value) => {
+            return Err(format!("'{}' is not a valid value for {} (expected {})", value, key, values.join(", ")).into());
+        }
+        None if value.is_empty() || value.starts_with('-') || value.contains(char::is_control) => {
+            return Err(format!("'{}' is not a valid value for {}", value, key).into());
+        }
+        None if !allows_spaces(key) && value.contains(char::is_whitespace) => {
+            return Err(format!("'{}' is not a valid value for {}", value, key).into());
+        }
+        _ => {}
+    }
+
+    // Replaces every value a multi-valued key had, where plain `git config key value` refuses
+    let result = run_git(path, &["config", scope_flag, "--replace-all", key, value]);
+    activity.record(path, Operation::new(format!("Set {} = {} ({})", key, value, scope_flag.trim_start_matches('-'))), &result);
+    result?;
+    Ok(())
+}
+
+#[derive(Serialize, Debug)]
+pub struct GitIdentity {
+    /// Effective `user.name`, or null when it isn't set anywhere
+    name: Option<String>,
+    email: Option<String>,
+    /// Where `name` came from; null when unset or set elsewhere (system config, `-c`)
+    name_scope: Option<ConfigScope>,
+    email_scope: Option<ConfigScope>,
+}
+
+fn config_value(path: &str, scope: Option<&str>, key: &str) -> Option<String> {
+    let mut args = vec!["config"];
+    args.extend(scope);
+    args.extend(["--get", key]);
+    run_git(path, &args).ok().map(|v| v.trim_end_matches('\n').to_string())
+}
+
+/// The repo's own config overrides the user's global one, so the first that has `key` wins.
+fn identity_scope(path: &str, key: &str) -> Option<ConfigScope> {
+    if config_value(path, Some("--local"), key).is_some() {
+        Some(ConfigScope::Local)
+    } else if config_value(path, Some("--global"), key).is_some() {
+        Some(ConfigScope::Global)
+    } else {
+        None
+    }
+}
+
+/// Fails with `IdentityNotConfigured` when git can't work out who the author and committer
+/// are, which would otherwise fail the commit with "Please tell me who you are".
+pub(crate) fn ensure_identity(path: &str) -> Result<(), CommandError> {
+    let known = ["GIT_AUTHOR_IDENT", "GIT_COMMITTER_IDENT"]
+        .iter()
+        .try_for_each(|ident| run_git(path, &["var", ident]).map(|_| ()));
+    let Err(stderr) = known else {
+        return Ok(());
+    };
+    let missing: Vec<String> = ["user.name", "user.email"]
+        .into_iter()
+        .filter(|key| config_value(path, None, key).is_none_or(|v| v.trim().is_empty()))
+        .map(str::to_string)
+        .collect();
+    match missing.is_empty() {
+        true => Err(stderr.into()),
+        false => Err(CommandError::IdentityNotConfigured { missing }),
+    }
+}
+
+/// The name and email git commits with in the repository at `path`, and which config each
+/// comes from.
+#[tauri::command]
+pub fn get_git_identity(path: &str) -> GitIdentity {
+    GitIdentity {
+        name: config_value(path, None, "user.name"),
+        email: config_value(path, None, "user.email"),
+        name_scope: identity_scope(path, "user.name"),
+        email_scope: identity_scope(path, "user.email"),
+    }
+}
+
+/// Sets `user.name` and `user.email`, for every repository with `global` or only this one
+/// otherwise.
+#[tauri::command]
+pub fn set_git_identity(
+    path: &str,
+    name: &str,
+    email: &str,
+    global: bool,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<(), CommandError> {
+    let (name, email) = (name.trim(), email.trim());
+    if name.is_empty() || name.starts_with('-') || name.contains(['\n', '\r']) {
+        return Err(format!("'{}' is not a valid name", name).into());
+    }
+    let valid_email = email.split_once('@').is_some_and(|(user, domain)| !user.is_empty() && !domain.is_empty())
+        && !email.starts_with('-')
+        && !email.contains(|c: char| c.is_whitespace() || matches!(c, '<' | '>'));
+    if !valid_email {
+        return Err(format!("'{}' is not a valid email address", email).into());
+    }
+    let scope_flag = match global {
+        true => "--global",
+        false => {
+            repos.ensure_writable(path)?;
+            "--local"
+        }
+    };
+
+    let mut op = Operation::new(format!("Set identity to {} <{}> ({})", name, email, scope_flag.trim_start_matches('-')));
+    let result = run_git(path, &["config", scope_flag, "user.name", name]);
+    op.step("Set user.name", &result);
+    let result = result.and_then(|_| run_git(path, &["config", scope_flag, "user.email", email]));
+    op.step("Set user.email", &result);
+    activity.record(path, op, &result);
+    result?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestRepo;
+    use std::path::Path;
+    use tauri::Manager;
+    use tempfile::TempDir;
+
+    fn set(repo: &TestRepo, key: &str, value: &str) -> Result<(), CommandError> {
+        let app = repo.app();
+        set_git_config(repo.path(), key, Some(value.to_string()), ConfigScope::Local, app.state(), app.state())
+    }
+
+    #[test]
+    fn multi_valued_keys_keep_every_value_in_order() {
+        let repo = TestRepo::init();
+        repo.git(&["config", "--add", "remote.origin.fetch", "+refs/heads/*:refs/remotes/origin/*"]);
+        repo.git(&["config", "--add", "remote.origin.fetch", "+refs/tags/*:refs/tags/*"]);
+
+        for show_scope in [true, false] {
+            let config = read_key(repo.path(), "remote.origin.fetch", Some(ConfigScope::Local), show_scope).unwrap();
+            assert_eq!(config.values, ["+refs/heads/*:refs/remotes/origin/*", "+refs/tags/*:refs/tags/*"]);
+            assert_eq!(config.value.as_deref(), Some("+refs/tags/*:refs/tags/*"));
+            assert_eq!(config.scope.as_deref(), show_scope.then_some("local"));
+            assert_eq!(config.origin.as_deref(), Some("file:.git/config"));
+        }
+    }
+
+    #[test]
+    fn values_with_spaces_and_unset_keys() {
+        let repo = TestRepo::init();
+        repo.git(&["config", "user.name", "Ada  Lovelace"]);
+
+        let name = read_key(repo.path(), "user.name", Some(ConfigScope::Local), true).unwrap();
+        assert_eq!(name.values, ["Ada  Lovelace"]);
+        let unset = read_key(repo.path(), "gitpop.unset", None, true).unwrap();
+        assert_eq!((unset.value, unset.values.len(), unset.scope, unset.origin), (None, 0, None, None));
+    }
+
+    /// Git in `repo` with `global` as the user's global config file.
+    fn with_global(repo: &TestRepo, global: &Path) -> Command {
+        let mut git = build_hidden_cmd("git");
+        git.current_dir(repo.path()).env("GIT_CONFIG_GLOBAL", global).env("GIT_CONFIG_NOSYSTEM", "1");
+        git
+    }
+
+    #[test]
+    fn keys_set_only_globally() {
+        let repo = TestRepo::init();
+        let home = TempDir::new().unwrap();
+        let global = home.path().join(".gitconfig");
+        std::fs::write(&global, "[gitpop]\n\tonlyGlobal = from home\n").unwrap();
+
+        let config = read_key_with(with_global(&repo, &global), "gitpop.onlyGlobal", None, true).unwrap();
+        assert_eq!(config.values, ["from home"]);
+        assert_eq!(config.scope.as_deref(), Some("global"));
+        let origin = config.origin.unwrap();
+        assert!(origin.starts_with("file:") && origin.ends_with(".gitconfig"), "{}", origin);
+        let scoped = read_key_with(with_global(&repo, &global), "gitpop.onlyGlobal", Some(ConfigScope::Global), false).unwrap();
+        assert_eq!(scoped.value.as_deref(), Some("from home"));
+        let local = read_key_with(with_global(&repo, &global), "gitpop.onlyGlobal", Some(ConfigScope::Local), true).unwrap();
+        assert_eq!((local.value, local.scope), (None, None));
+    }
+
+    #[test]
+    fn local_values_override_global_ones() {
+        let repo = TestRepo::init();
+        let home = TempDir::new().unwrap();
+        let global = home.path().join(".gitconfig");
+        std::fs::write(&global, "[pull]\n\trebase = false\n").unwrap();
+        set(&repo, "pull.rebase", "merges").unwrap();
+
+        let effective = read_key_with(with_global(&repo, &global), "pull.rebase", None, true).unwrap();
+        assert_eq!(effective.values, ["false", "merges"]);
+        assert_eq!((effective.value.as_deref(), effective.scope.as_deref()), (Some("merges"), Some("local")));
+        assert_eq!(effective.origin.as_deref(), Some("file:.git/config"));
+        for (scope, value, name) in [(ConfigScope::Global, "false", "global"), (ConfigScope::Local, "merges", "local")] {
+            let config = read_key_with(with_global(&repo, &global), "pull.rebase", Some(scope), true).unwrap();
+            assert_eq!((config.value.as_deref(), config.scope.as_deref()), (Some(value), Some(name)));
+        }
+    }
+
+    #[test]
+    fn allow_list() {
+        assert_eq!(allowed_values("pull.rebase").unwrap(), Some(&["true", "false", "merges"][..]));
+        assert_eq!(allowed_values("Fetch.Prune").unwrap(), Some(&["true", "false"][..]));
+        for free_text in ["user.name", "user.signingKey", "commit.template", "pull.twohead"] {
+            assert_eq!(allowed_values(free_text).unwrap(), None, "{}", free_text);
+        }
+        for refused in ["core.sshCommand", "core.editor", "user", "remote.origin.url", "includeIf.gitdir:~/.path"] {
+            assert!(allowed_values(refused).is_err(), "{}", refused);
+        }
+
+        let repo = TestRepo::init();
+        assert!(set(&repo, "pull.rebase", "sometimes").is_err());
+        assert!(set(&repo, "init.defaultBranch", "my branch").is_err());
+        assert!(set(&repo, "user.email", "--global").is_err());
+        set(&repo, "pull.rebase", "merges").unwrap();
+        set(&repo, "user.name", "Ada Lovelace").unwrap();
+        assert_eq!(repo.git(&["config", "--local", "pull.rebase"]), "merges\n");
+        assert_eq!(repo.git(&["config", "--local", "user.name"]), "Ada Lovelace\n");
+    }
+
+    #[test]
+    fn keys_that_run_programs_are_refused() {
+        let repo = TestRepo::init();
+        for key in ["core.sshCommand", "core.pager", "CORE.SSHCOMMAND", "alias.st", "user.signing.key"] {
+            let err = set(&repo, key, "touch pwned").unwrap_err();
+            assert!(err.to_string().contains("can't be changed"), "{}: {}", key, err);
+            assert_eq!(read_key(repo.path(), key, Some(ConfigScope::Local), false).unwrap().value, None, "{}", key);
+        }
+
+        set(&repo, "fetch.prune", "true").unwrap();
+        assert_eq!(repo.git(&["config", "--local", "fetch.prune"]), "true\n");
+    }
+}
@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+use crate::activity::{ActivityLog, Operation};
+use crate::error::CommandError;
+use crate::read_only::ReadOnlyRepos;
+use crate::{git_path, run_git};
+
+/// Which ignore file patterns are added to.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IgnoreScope {
+    /// The `.gitignore` at the root of the work tree
+    Root,
+    /// The `.gitignore` next to each file, so the rule travels with its directory
+    Directory,
+    /// `.git/info/exclude`: only this clone, never committed
+    Exclude,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct IgnoreResult {
+    /// Patterns written, by ignore file (relative to the work tree root, or `.git/info/exclude`);
+    /// ones already there are left out
+    added: BTreeMap<String, Vec<String>>,
+    /// Tracked files the new patterns match. Ignore rules don't apply to tracked files, so
+    /// these need `git rm --cached` to stop being tracked
+    still_tracked: Vec<String>,
+}
+
+/// Turns a file path into a pattern matching only that path: anchored with a leading `/`
+/// (which also keeps a leading `#` or `!` from reading as a comment or negation), with glob
+/// characters and trailing spaces escaped. A trailing `/` (an untracked directory in the
+/// status list) is kept, so only a directory matches.
+fn escape_path(file: &str) -> String {
+    let (file, dir) = match file.strip_suffix('/') {
+        Some(file) => (file, "/"),
+        None => (file, ""),
+    };
+    let mut pattern = String::from("/");
+    for c in file.chars() {
+        if matches!(c, '*' | '?' | '[' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    // A pattern's trailing spaces are ignored unless escaped
+    let kept = pattern.trim_end_matches(' ').len();
+    let spaces = pattern.len() - kept;
+    pattern.truncate(kept);
+    pattern.push_str(&"\\ ".repeat(spaces));
+    pattern + dir
+}
+
+/// Tracked files some ignore rule matches, relative to the work tree root.
+fn tracked_but_ignored(path: &str) -> Result<BTreeSet<String>, String> {
+    let out = run_git(path, &["ls-files", "-z", "--cached", "--ignored", "--exclude-standard", "--full-name"])?;
+    Ok(out.split('\0').filter(|f| !f.is_empty()).map(str::to_string).collect())
+}
+
+/// A line of an ignore file as git reads it: without its line ending and the trailing
+/// spaces git drops, keeping one that's escaped.
+fn significant(line: &str) -> &str {
+    let line = line.trim_end_matches('\r');
+    let trimmed = line.trim_end_matches(' ');
+    match trimmed.ends_with('\\') && trimmed.len() < line.len() {
+        true => &line[..trimmed.len() + 1],
+        false => trimmed,
+    }
+}
+
+/// Appends `patterns` that aren't there yet to `file`, creating it if needed. The file's own
+/// line endings are kept; a new file gets CRLF only where git checks text files out that way.
+fn append_patterns(path: &str, file: &Path, patterns: &[String]) -> Result<Vec<String>, String> {
+    let existing = std::fs::read_to_string(file).unwrap_or_default();
+    let present: BTreeSet<&str> = existing.lines().map(significant).collect();
+    let mut added: Vec<String> = Vec::new();
+    for pattern in patterns {
+        if !present.contains(significant(pattern)) && !added.contains(pattern) {
+            added.push(pattern.clone());
+        }
+    }
+    if added.is_empty() {
+        return Ok(added);
+    }
+
+    let crlf = match existing.is_empty() {
+        true => cfg!(windows) && run_git(path, &["config", "--get", "core.autocrlf"]).is_ok_and(|v| v.trim() == "true"),
+        false => existing.contains("\r\n"),
+    };
+    let newline = if crlf { "\r\n" } else { "\n" };
+    let mut text = existing.clone();
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push_str(newline);
+    }
+    for pattern in &added {
+        text.push_str(pattern);
+        text.push_str(newline);
+    }
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(file, text).map_err(|e| e.to_string())?;
+    Ok(added)
+}
+
+/// Adds ignore rules for `patterns` to the ignore file `scope` names, skipping any already
+/// there. With `from_paths` the entries are file paths from the status list rather than
+/// patterns: each is escaped so it matches only itself, and with the `directory` scope it goes
+/// into the `.gitignore` in its own directory. The `directory` scope needs `from_paths`, since
+/// a plain pattern has no directory of its own.
+#[tauri::command]
+pub fn add_to_gitignore(
+    path: &str,
+    patterns: Vec<String>,
+    scope: IgnoreScope,
+    from_paths: Option<bool>,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<IgnoreResult, CommandError> {
+    repos.ensure_writable(path)?;
+    let from_paths = from_paths.unwrap_or(false);
+    if scope == IgnoreScope::Directory && !from_paths {
+        return Err("Patterns can only go into the nearest .gitignore when they come from file paths".to_string().into());
+    }
+    let root = PathBuf::from(run_git(path, &["rev-parse", "--show-toplevel"])?.trim());
+
+    // Ignore file (as shown to the user) -> where it is -> patterns for it
+    let mut targets: BTreeMap<String, (PathBuf, Vec<String>)> = BTreeMap::new();
+    // File names may really end in spaces, so only patterns are trimmed
+    let entries = patterns.iter().map(|p| if from_paths { p.as_str() } else { p.trim() });
+    for entry in entries.filter(|p| !p.is_empty()) {
+        let (name, file, pattern) = match scope {
+            IgnoreScope::Directory => {
+                let (dir, name) = match entry.trim_end_matches('/').rsplit_once('/') {
+                    Some((dir, _)) => (dir, &entry[dir.len() + 1..]),
+                    None => ("", entry),
+                };
+                let gitignore = if dir.is_empty() { ".gitignore".to_string() } else { format!("{}/.gitignore", dir) };
+                (gitignore.clone(), root.join(&gitignore), escape_path(name))
+            }
+            IgnoreScope::Root => {
+                let pattern = if from_paths { escape_path(entry) } else { entry.to_string() };
+                (".gitignore".to_string(), root.join(".gitignore"), pattern)
+            }
+            IgnoreScope::Exclude => {
+                let pattern = if from_paths { escape_path(entry) } else { entry.to_string() };
+                (".git/info/exclude".to_string(), git_path(path, "info/exclude")?, pattern)
+            }
+        };
+        targets.entry(name).or_insert_with(|| (file, Vec::new())).1.push(pattern);
+    }
+    if targets.is_empty() {
+        return Err("No patterns to add".to_string().into());
+    }
+
+    let tracked_before = tracked_but_ignored(path)?;
+    let mut op = Operation::new(format!("Ignore {}", patterns.join(", ")));
+    let mut result = IgnoreResult::default();
+    let mut written = Ok(());
+    for (name, (file, patterns)) in targets {
+        let appended = append_patterns(path, &file, &patterns);
+        op.step(format!("Update {}", name), &appended);
+        match appended {
+            Ok(added) if added.is_empty() => {}
+            Ok(added) => {
+                result.added.insert(name, added);
+            }
+            Err(e) => {
+                written = Err(e);
+                break;
+            }
+        }
+    }
+    activity.record(path, op, &written);
+    written?;
+
+    result.still_tracked = tracked_but_ignored(path)?.difference(&tracked_before).cloned().collect();
+    Ok(result)
+}
@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::capabilities::{GitCapabilitiesState, GitFeature};
+use crate::hooks::resolve_hook_dirs;
+use crate::run_git;
+
+/// A config change that resolves an issue, applied by the UI through `set_git_config`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HealthFix {
+    key: String,
+    value: String,
+    scope: String, // "local" or "global"
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HealthIssue {
+    id: String,
+    severity: String, // "info", "warning" or "error"
+    message: String,
+    fix: Option<HealthFix>,
+}
+
+/// Looks for repository misconfigurations that make git silently misbehave.
+#[tauri::command]
+pub fn check_repo_health(path: &str, caps: State<'_, GitCapabilitiesState>) -> Result<Vec<HealthIssue>, String> {
+    let mut issues = Vec::new();
+    let caps = caps.get()?;
+
+    let hook_dirs = resolve_hook_dirs(path)?;
+    if let Some(dir) = hook_dirs.configured_dir {
+        if let Err(too_old) = caps.require(GitFeature::HooksPath) {
+            issues.push(HealthIssue {
+                id: "hooks_path_unsupported".to_string(),
+                severity: "warning".to_string(),
+                message: format!("{}. Hooks are still run from .git/hooks.", too_old),
+                fix: None,
+            });
+        } else if !dir.is_dir() {
+            issues.push(HealthIssue {
+                id: "missing_hooks_path".to_string(),
+                severity: "warning".to_string(),
+                message: format!(
+                    "core.hooksPath points to '{}', which does not exist. Git hooks are silently disabled for this repository.",
+                    dir.to_string_lossy()
+                ),
+                fix: None,
+            });
+        }
+    }
+
+    let has_remotes = run_git(path, &["remote"]).map(|r| !r.trim().is_empty()).unwrap_or(false);
+    let prunes = run_git(path, &["config", "--bool", "--get", "fetch.prune"])
+        .map(|v| v.trim() == "true")
+        .unwrap_or(false);
+    if has_remotes && !prunes {
+        issues.push(HealthIssue {
+            id: "fetch_prune_disabled".to_string(),
+            severity: "info".to_string(),
+            message: "fetch.prune is off, so branches deleted on the remote stay listed here until pruned by hand.".to_string(),
+            fix: Some(HealthFix {
+                key: "fetch.prune".to_string(),
+                value: "true".to_string(),
+                scope: "global".to_string(),
+            }),
+        });
+    }
+
+    Ok(issues)
+}
@@ -0,0 +1,382 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{has_commits, run_git, truncate_diff, MAX_DIFF_LEN, UNQUOTED_PATHS};
+
+const DEFAULT_LIMIT: usize = 100;
+/// Fields per commit in `git_log`'s format, all separated by NUL.
+const LOG_FIELDS: usize = 9;
+
+#[derive(Serialize, Debug)]
+pub struct CommitEntry {
+    pub(crate) sha: String,
+    pub(crate) author: String,
+    /// Commit time as a unix timestamp
+    pub(crate) timestamp: i64,
+    pub(crate) subject: String,
+}
+
+/// `git log` format that `parse_entries` reads.
+pub(crate) const ENTRY_FORMAT: &str = "--format=%H%x00%an%x00%ct%x00%s";
+
+pub(crate) fn parse_entries(out: &str) -> Vec<CommitEntry> {
+    out.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, '\0');
+            Some(CommitEntry {
+                sha: fields.next()?.to_string(),
+                author: fields.next()?.to_string(),
+                timestamp: fields.next()?.parse().ok()?,
+                subject: fields.next().unwrap_or_default().to_string(),
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn git_history(path: &str, limit: usize) -> Result<Vec<CommitEntry>, String> {
+    if !has_commits(path) {
+        return Ok(Vec::new());
+    }
+    let out = run_git(path, &["log", &format!("-n{}", limit), ENTRY_FORMAT])?;
+    Ok(parse_entries(&out))
+}
+
+/// The most recent commits on the current branch, newest first.
+#[tauri::command]
+pub async fn get_commit_history(path: String, limit: Option<usize>) -> Result<Vec<CommitEntry>, String> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    tauri::async_runtime::spawn_blocking(move || crate::backend::active().history(&path, limit))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[derive(Serialize, Debug)]
+pub struct CommitInfo {
+    hash: String,
+    short_hash: String,
+    subject: String,
+    /// Message after the subject line, empty for one-line messages
+    body: String,
+    author_name: String,
+    author_email: String,
+    /// Commit time as a unix timestamp
+    date: i64,
+    /// Empty for a root commit, two or more for a merge
+    parents: Vec<String>,
+    /// Branches and tags pointing here, as git decorates them: `HEAD`, `main`,
+    /// `origin/main`, `tag: v1.0`
+    refs: Vec<String>,
+}
+
+fn parse_log(out: &str) -> Vec<CommitInfo> {
+    let fields: Vec<&str> = out.split('\0').collect();
+    fields
+        .chunks_exact(LOG_FIELDS)
+        .map(|f| CommitInfo {
+            hash: f[0].to_string(),
+            short_hash: f[1].to_string(),
+            subject: f[2].to_string(),
+            body: f[3].trim_end().to_string(),
+            author_name: f[4].to_string(),
+            author_email: f[5].to_string(),
+            date: f[6].parse().unwrap_or(0),
+            parents: f[7].split_whitespace().map(str::to_string).collect(),
+            refs: f[8]
+                .split(", ")
+                .flat_map(|r| r.split(" -> "))
+                .filter(|r| !r.is_empty())
+                .map(str::to_string)
+                .collect(),
+        })
+        .collect()
+}
+
+/// Runs `git log` with `options` (and revisions) in front of `--`, parsed into `CommitInfo`s.
+fn log_commits(path: &str, options: &[&str]) -> Result<Vec<CommitInfo>, String> {
+    if !has_commits(path) {
+        return Ok(Vec::new());
+    }
+    // `-z` ends each commit with NUL too, so the output is one flat list of fields
+    let mut args = vec!["log", "-z", "--format=%H%x00%h%x00%s%x00%b%x00%an%x00%ae%x00%ct%x00%P%x00%D"];
+    args.extend_from_slice(options);
+    args.push("--");
+    Ok(parse_log(&run_git(path, &args)?))
+}
+
+fn git_log(path: &str, skip: u32, limit: u32, branch: Option<&str>) -> Result<Vec<CommitInfo>, String> {
+    let (skip, max_count) = (format!("--skip={}", skip), format!("--max-count={}", limit));
+    let mut options = vec![skip.as_str(), max_count.as_str()];
+    options.extend(branch);
+    log_commits(path, &options)
+}
+
+/// One page of history, newest first: `limit` commits after skipping the first `skip`, on
+/// `branch` or the current branch. Empty on a repository without commits.
+#[tauri::command]
+pub async fn get_git_log(path: String, skip: u32, limit: u32, branch: Option<String>) -> Result<Vec<CommitInfo>, String> {
+    let branch = branch.filter(|b| !b.trim().is_empty());
+    if let Some(branch) = branch.as_deref().filter(|b| b.starts_with('-')) {
+        return Err(format!("Invalid branch name: {}", branch));
+    }
+    tauri::async_runtime::spawn_blocking(move || git_log(&path, skip, limit, branch.as_deref()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[derive(Serialize, Debug)]
+pub struct GraphCommit {
+    #[serde(flatten)]
+    info: CommitInfo,
+    /// Local and remote branches pointing here, e.g. `main`, `origin/main`
+    branches: Vec<String>,
+    tags: Vec<String>,
+    /// Whether HEAD points here, on a branch or detached
+    head: bool,
+    /// Column the commit is drawn in, 0 leftmost
+    lane: u32,
+    /// Per parent, in `parents` order: the lane the edge to it runs down in below this row.
+    /// The edge keeps to that lane until the parent's row, then joins the parent's lane
+    parent_lanes: Vec<u32>,
+}
+
+/// Splits `%D` decorations into branches and tags, and whether HEAD is among them.
+fn split_refs(refs: &[String]) -> (Vec<String>, Vec<String>, bool) {
+    let (mut branches, mut tags, mut head) = (Vec::new(), Vec::new(), false);
+    for r in refs {
+        match r.strip_prefix("tag: ") {
+            Some(tag) => tags.push(tag.to_string()),
+            None if r == "HEAD" => head = true,
+            None => branches.push(r.clone()),
+        }
+    }
+    (branches, tags, head)
+}
+
+/// Packs `commits` (in topological order, newest first) into lanes. Each lane waits for one
+/// commit; a commit takes the leftmost lane waiting for it, or the leftmost free one, and
+/// hands it on to its first parent. Further parents reuse a lane already waiting for them or
+/// open a new one, and lanes also waiting for this commit close here.
+fn assign_lanes(commits: Vec<CommitInfo>) -> Vec<GraphCommit> {
+    let mut lanes: Vec<Option<String>> = Vec::new();
+    let mut graph = Vec::with_capacity(commits.len());
+    for info in commits {
+        let waiting = |hash: &str, lanes: &[Option<String>]| lanes.iter().position(|l| l.as_deref() == Some(hash));
+        let lane = match waiting(&info.hash, &lanes) {
+            Some(lane) => lane,
+            None => match lanes.iter().position(Option::is_none) {
+                Some(free) => free,
+                None => {
+                    lanes.push(None);
+                    lanes.len() - 1
+                }
+            },
+        };
+        for other in lanes.iter_mut().filter(|l| l.as_deref() == Some(info.hash.as_str())) {
+            *other = None;
+        }
+
+        let mut parent_lanes = Vec::with_capacity(info.parents.len());
+        lanes[lane] = info.parents.first().cloned();
+        for (i, parent) in info.parents.iter().enumerate() {
+            let parent_lane = match (i, waiting(parent, &lanes)) {
+                (0, _) => lane,
+                (_, Some(existing)) => existing,
+                (_, None) => {
+                    let free = lanes.iter().position(Option::is_none).unwrap_or(lanes.len());
+                    if free == lanes.len() {
+                        lanes.push(None);
+                    }
+                    lanes[free] = Some(parent.clone());
+                    free
+                }
+            };
+            parent_lanes.push(parent_lane as u32);
+        }
+        while lanes.last().is_some_and(Option::is_none) {
+            lanes.pop();
+        }
+
+        let (branches, tags, head) = split_refs(&info.refs);
+        graph.push(GraphCommit { info, branches, tags, head, lane: lane as u32, parent_lanes });
+    }
+    graph
+}
+
+/// One page of the commit graph, newest first in topological order, with each commit's lane
+/// and the lanes of the edges to its parents. Covers every branch, remote branch and tag with
+/// `all_refs`, otherwise what's reachable from HEAD. Lanes are laid out from the newest commit
+/// down on every call, so they line up from one page to the next.
+#[tauri::command]
+pub async fn get_commit_graph(path: String, limit: u32, skip: u32, all_refs: bool) -> Result<Vec<GraphCommit>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let max_count = format!("--max-count={}", skip.saturating_add(limit));
+        let mut options = vec!["--topo-order", max_count.as_str()];
+        if all_refs {
+            options.push("--all");
+        }
+        let graph = assign_lanes(log_commits(&path, &options)?);
+        Ok(graph.into_iter().skip(skip as usize).collect())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[derive(Serialize, Debug)]
+pub struct CommitFileChange {
+    path: String,
+    /// Where a renamed or copied file came from
+    old_path: Option<String>,
+    /// Git's status letter: A, M, D, R, C or T
+    status: String,
+    additions: u32,
+    deletions: u32,
+    /// Git can't count lines in binary files; both counts are 0 then
+    binary: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CommitDetails {
+    #[serde(flatten)]
+    info: CommitInfo,
+    /// Changes against the first parent, so a merge lists what it brought in
+    files: Vec<CommitFileChange>,
+}
+
+/// Accepts full or abbreviated commit hashes only, so nothing that could be read as an
+/// option or a revision expression reaches git.
+pub(crate) fn validate_hash(hash: &str) -> Result<(), String> {
+    let hash = hash.trim();
+    if (4..=64).contains(&hash.len()) && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(())
+    } else {
+        Err(format!("'{}' is not a commit hash", hash))
+    }
+}
+
+/// Parses `git show -z --raw --numstat`: a raw record (`:<modes> <shas> <status>\0<path>\0`,
+/// with the old path first for renames and copies) per file, then the numstat records in the
+/// same order (`added\tdeleted\tpath\0`, or `added\tdeleted\t\0old\0new\0`).
+fn parse_raw_numstat(out: &str) -> Vec<CommitFileChange> {
+    let mut files: Vec<CommitFileChange> = Vec::new();
+    let mut counted = 0;
+    let mut tokens = out.split('\0').map(|t| t.trim_start_matches('\n'));
+    while let Some(token) = tokens.next() {
+        if let Some(raw) = token.strip_prefix(':') {
+            let status = raw.rsplit(' ').next().unwrap_or_default();
+            let first = tokens.next().unwrap_or_default().to_string();
+            let (old_path, path) = match status.starts_with(['R', 'C']) {
+                true => (Some(first), tokens.next().unwrap_or_default().to_string()),
+                false => (None, first),
+            };
+            files.push(CommitFileChange {
+                path,
+                old_path,
+                status: status.chars().take(1).collect(),
+                additions: 0,
+                deletions: 0,
+                binary: false,
+            });
+        } else if let Some((added, rest)) = token.split_once('\t') {
+            let deleted = rest.split('\t').next().unwrap_or_default();
+            if rest.ends_with('\t') {
+                // Rename: the old and new paths follow as their own records
+                tokens.next();
+                tokens.next();
+            }
+            if let Some(file) = files.get_mut(counted) {
+                file.binary = added == "-" && deleted == "-";
+                file.additions = added.parse().unwrap_or(0);
+                file.deletions = deleted.parse().unwrap_or(0);
+            }
+            counted += 1;
+        }
+    }
+    files
+}
+
+/// Everything `get_git_log` has on the commit, plus the files it changed with line counts.
+#[tauri::command]
+pub async fn get_commit(path: String, hash: String) -> Result<CommitDetails, String> {
+    validate_hash(&hash)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let info = git_log(&path, 0, 1, Some(hash.trim()))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("Commit {} not found", hash))?;
+        let mut args = UNQUOTED_PATHS.to_vec();
+        // `-m --first-parent` rather than `--diff-merges`, which needs git 2.31
+        args.extend(["show", "-z", "--format=", "--raw", "--numstat", "-M", "-m", "--first-parent", &info.hash, "--"]);
+        let files = parse_raw_numstat(&run_git(&path, &args)?);
+        Ok(CommitDetails { info, files })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// The patch a commit introduced, for one `file` or all of them, truncated like the working
+/// tree diffs. A merge is diffed against its first parent unless `combined` asks for git's
+/// combined diff, which only shows the lines that were changed while resolving.
+#[tauri::command]
+pub async fn get_commit_diff(
+    path: String,
+    hash: String,
+    file: Option<String>,
+    combined: Option<bool>,
+) -> Result<String, String> {
+    validate_hash(&hash)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut args = vec!["show", "--format=", "-M"];
+        match combined.unwrap_or(false) {
+            true => args.push("--cc"),
+            false => args.extend(["-m", "--first-parent"]),
+        }
+        args.extend([hash.trim(), "--"]);
+        args.extend(file.as_deref());
+        let mut diff = run_git(&path, &args)?;
+        truncate_diff(&mut diff, MAX_DIFF_LEN);
+        Ok(diff)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchMode {
+    /// `-S`: commits that change how many times the text occurs, i.e. add or remove it
+    String,
+    /// `-G`: commits whose added or removed lines match the regex
+    Regex,
+    /// `--grep`: commits whose message matches
+    Message,
+}
+
+/// Commits on the current branch, newest first, that added or removed `query` or whose message
+/// mentions it, depending on `mode`. At most `limit` are returned; none found is an empty list.
+#[tauri::command]
+pub async fn search_history(
+    path: String,
+    query: String,
+    mode: SearchMode,
+    limit: Option<u32>,
+    ignore_case: Option<bool>,
+) -> Result<Vec<CommitInfo>, String> {
+    if query.is_empty() {
+        return Err("Enter something to search for".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        // Always a single argument, so the query is never read as an option
+        let search = match mode {
+            SearchMode::String => format!("-S{}", query),
+            SearchMode::Regex => format!("-G{}", query),
+            SearchMode::Message => format!("--grep={}", query),
+        };
+        let max_count = format!("--max-count={}", limit.unwrap_or(DEFAULT_LIMIT as u32));
+        let mut options = vec![search.as_str(), max_count.as_str()];
+        if ignore_case.unwrap_or(false) {
+            options.push("--regexp-ignore-case");
+        }
+        log_commits(&path, &options)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
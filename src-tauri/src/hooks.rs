@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+use crate::capabilities::{GitCapabilitiesState, GitFeature};
+use crate::run_git;
+
+const KNOWN_HOOKS: &[&str] = &[
+    "applypatch-msg",
+    "pre-applypatch",
+    "post-applypatch",
+    "pre-commit",
+    "pre-merge-commit",
+    "prepare-commit-msg",
+    "commit-msg",
+    "post-commit",
+    "pre-rebase",
+    "post-checkout",
+    "post-merge",
+    "pre-push",
+    "post-rewrite",
+    "pre-auto-gc",
+    "reference-transaction",
+];
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HookInfo {
+    name: String,
+    /// "default" for `.git/hooks`, "configured" for the `core.hooksPath` directory
+    location: String,
+    path: String,
+    /// Whether git will actually run this hook (it lives in the effective hooks directory)
+    active: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HooksInfo {
+    default_dir: String,
+    /// Resolved `core.hooksPath`, only set when it differs from the default location
+    configured_dir: Option<String>,
+    configured_dir_exists: bool,
+    uses_husky: bool,
+    hooks: Vec<HookInfo>,
+}
+
+pub(crate) struct HookDirs {
+    pub default_dir: PathBuf,
+    pub configured_dir: Option<PathBuf>,
+}
+
+/// Resolves the default hooks directory and `core.hooksPath`. A relative hooksPath is taken
+/// relative to the repository root, which is where git runs hooks from.
+pub(crate) fn resolve_hook_dirs(path: &str) -> Result<HookDirs, String> {
+    let common_dir = run_git(path, &["rev-parse", "--git-common-dir"])?;
+    let default_dir = Path::new(path).join(common_dir.trim()).join("hooks");
+
+    let configured = run_git(path, &["config", "--get", "core.hooksPath"])
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default();
+
+    let configured_dir = if configured.is_empty() {
+        None
+    } else if let Some(rest) = configured.strip_prefix("~/") {
+        let home = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")).unwrap_or_default();
+        Some(Path::new(&home).join(rest))
+    } else if Path::new(&configured).is_absolute() {
+        Some(PathBuf::from(&configured))
+    } else {
+        let root = run_git(path, &["rev-parse", "--show-toplevel"]).unwrap_or_else(|_| path.to_string());
+        Some(Path::new(root.trim()).join(&configured))
+    };
+
+    let same_as_default = match (&configured_dir, default_dir.canonicalize()) {
+        (Some(dir), Ok(default)) => dir.canonicalize().map(|d| d == default).unwrap_or(false),
+        _ => false,
+    };
+
+    Ok(HookDirs {
+        default_dir,
+        configured_dir: if same_as_default { None } else { configured_dir },
+    })
+}
+
+fn list_hooks_in(dir: &Path, location: &str, active: bool) -> Vec<HookInfo> {
+    KNOWN_HOOKS
+        .iter()
+        .map(|name| dir.join(name))
+        .filter(|hook| hook.is_file())
+        .map(|hook| HookInfo {
+            name: hook.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+            location: location.to_string(),
+            active: active && is_executable(&hook),
+            path: hook.to_string_lossy().into_owned(),
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+fn is_executable(hook: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(hook).map(|m| m.permissions().mode() & 0o111 != 0).unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_hook: &Path) -> bool {
+    // Git for Windows runs hooks through its bundled sh regardless of permissions
+    true
+}
+
+/// Lists the hooks git will run for this repo, showing both `.git/hooks` and the
+/// `core.hooksPath` directory (e.g. husky) when they differ.
+#[tauri::command]
+pub fn get_hooks(path: &str, caps: State<'_, GitCapabilitiesState>) -> Result<HooksInfo, String> {
+    let dirs = resolve_hook_dirs(path)?;
+    // Gits older than 2.9 ignore core.hooksPath and keep running .git/hooks
+    let honors_hooks_path = caps.get()?.supports(GitFeature::HooksPath);
+
+    let mut hooks = list_hooks_in(&dirs.default_dir, "default", dirs.configured_dir.is_none() || !honors_hooks_path);
+    let mut configured_dir_exists = false;
+    let mut uses_husky = false;
+
+    if let Some(dir) = &dirs.configured_dir {
+        configured_dir_exists = dir.is_dir();
+        uses_husky = dir.components().any(|c| c.as_os_str() == ".husky");
+        hooks.extend(list_hooks_in(dir, "configured", honors_hooks_path));
+    }
+
+    Ok(HooksInfo {
+        default_dir: dirs.default_dir.to_string_lossy().into_owned(),
+        configured_dir: dirs.configured_dir.map(|d| d.to_string_lossy().into_owned()),
+        configured_dir_exists,
+        uses_husky,
+        hooks,
+    })
+}
@@ -0,0 +1,105 @@
+use tauri::State;
+
+use crate::activity::{ActivityLog, Operation};
+use crate::diff;
+use crate::error::CommandError;
+use crate::read_only::ReadOnlyRepos;
+use crate::run_git_with_input;
+use crate::structured_diff::hunk_ranges;
+
+/// Builds a patch with only the hunks at `indices` from the single-file `diff`. Leaving a hunk
+/// out shifts every later one, so the side the patch produces is renumbered: the new side
+/// normally, the old side when `reverse` (the patch is then applied to the new side).
+fn select_hunks(diff: &str, indices: &[usize], reverse: bool) -> Result<String, String> {
+    if diff.matches("diff --git ").count() > 1 {
+        return Err("Hunks can only be staged one file at a time".to_string());
+    }
+    if diff.lines().any(|line| line.starts_with("Binary files ")) {
+        return Err("Binary files can't be staged by hunk".to_string());
+    }
+
+    let mut header = String::new();
+    let mut hunks: Vec<String> = Vec::new();
+    for line in diff.split_inclusive('\n') {
+        if line.starts_with("@@") {
+            hunks.push(String::new());
+        }
+        match hunks.last_mut() {
+            Some(hunk) => hunk.push_str(line),
+            None => header.push_str(line),
+        }
+    }
+    if let Some(&missing) = indices.iter().find(|&&i| i >= hunks.len()) {
+        return Err(format!("Hunk {} doesn't exist; the file has {} hunks now", missing, hunks.len()));
+    }
+
+    // Net lines added by the hunks left out so far
+    let mut skipped: i64 = 0;
+    let mut patch = header;
+    for (i, hunk) in hunks.iter().enumerate() {
+        let (first, body) = hunk.split_once('\n').unwrap_or((hunk, ""));
+        let ((old_start, old_count), (new_start, new_count)) = hunk_ranges(first);
+        if !indices.contains(&i) {
+            skipped += new_count as i64 - old_count as i64;
+            continue;
+        }
+        let (old_start, new_start) = if reverse {
+            ((old_start as i64 + skipped).max(0), new_start as i64)
+        } else {
+            (old_start as i64, (new_start as i64 - skipped).max(0))
+        };
+        let context = first.splitn(3, "@@").nth(2).unwrap_or_default();
+        patch.push_str(&format!("@@ -{},{} +{},{} @@{}\n{}", old_start, old_count, new_start, new_count, context, body));
+    }
+    Ok(patch)
+}
+
+fn apply_hunks(path: &str, file: &str, indices: &[usize], staged: bool) -> Result<(), String> {
+    if indices.is_empty() {
+        return Err("No hunks selected".to_string());
+    }
+    let diff = diff::file_diff(path, Some(file), staged, Default::default())?;
+    if diff.trim().is_empty() {
+        return Err(format!("{} has no {} changes", file, if staged { "staged" } else { "unstaged" }));
+    }
+    let patch = select_hunks(&diff, indices, staged)?;
+    let mut args = vec!["apply", "--cached"];
+    if staged {
+        args.push("--reverse");
+    }
+    args.push("-");
+    run_git_with_input(path, &args, &patch).map(|_| ())
+}
+
+/// Stages the hunks at `hunk_indices` of the file's unstaged diff (as `get_file_diff` returns
+/// it with `staged` false), leaving its other changes unstaged. A new file's diff is a single
+/// hunk covering the whole file.
+#[tauri::command]
+pub fn stage_hunks(
+    path: &str,
+    file: &str,
+    hunk_indices: Vec<usize>,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<(), CommandError> {
+    repos.ensure_writable(path)?;
+    let result = apply_hunks(path, file, &hunk_indices, false);
+    activity.record(path, Operation::new(format!("Stage {} hunk(s) of {}", hunk_indices.len(), file)), &result);
+    Ok(result?)
+}
+
+/// Takes the hunks at `hunk_indices` of the file's staged diff back out of the index; the
+/// work tree keeps them.
+#[tauri::command]
+pub fn unstage_hunks(
+    path: &str,
+    file: &str,
+    hunk_indices: Vec<usize>,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<(), CommandError> {
+    repos.ensure_writable(path)?;
+    let result = apply_hunks(path, file, &hunk_indices, true);
+    activity.record(path, Operation::new(format!("Unstage {} hunk(s) of {}", hunk_indices.len(), file)), &result);
+    Ok(result?)
+}
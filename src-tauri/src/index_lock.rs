@@ -0,0 +1,74 @@
+use std::path::Path;
+use std::time::Duration;
+use tauri::State;
+
+use crate::activity::{ActivityLog, Operation};
+use crate::error::CommandError;
+use crate::read_only::ReadOnlyRepos;
+use crate::{build_hidden_cmd, git_path};
+
+/// A lock younger than this may still belong to a git command that's just slow, so it isn't
+/// called stale and `remove_index_lock` leaves it alone.
+const STALE_AFTER: Duration = Duration::from_secs(30);
+
+/// Whether any git process is running, from anywhere (GitPop, a terminal, an editor). False
+/// when the process list can't be read.
+fn git_running() -> bool {
+    #[cfg(target_os = "windows")]
+    let listed = build_hidden_cmd("tasklist")
+        .args(["/FI", "IMAGENAME eq git.exe", "/FO", "CSV", "/NH"])
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_ascii_lowercase().contains("\"git.exe\""));
+    #[cfg(not(target_os = "windows"))]
+    let listed = build_hidden_cmd("ps")
+        .args(["-A", "-o", "comm="])
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).lines().any(|c| c.trim().rsplit('/').next() == Some("git")));
+    listed.unwrap_or(false)
+}
+
+fn lock_age(lock: &Path) -> Option<Duration> {
+    let modified = std::fs::metadata(lock).ok()?.modified().ok()?;
+    Some(modified.elapsed().unwrap_or_default())
+}
+
+/// Turns git's "Unable to create '.../index.lock': File exists" into `IndexLocked`, saying how
+/// old the lock is and whether it looks left behind by a git that crashed. `None` for other
+/// messages, or when the lock is already gone again.
+pub(crate) fn detect(message: &str) -> Option<CommandError> {
+    let (_, rest) = message.split_once("Unable to create '")?;
+    let (lock, _) = rest.split_once("': File exists")?;
+    if !lock.ends_with("index.lock") {
+        return None;
+    }
+    let age = lock_age(Path::new(lock))?;
+    Some(CommandError::IndexLocked {
+        age_seconds: age.as_secs(),
+        probably_stale: age >= STALE_AFTER && !git_running(),
+    })
+}
+
+/// Deletes the repository's `index.lock`, which a crashed git can leave behind and which then
+/// blocks every command that touches the index. Meant to be called once the user confirmed;
+/// refuses while the lock is recent or any git process is running, since it may be in use.
+#[tauri::command]
+pub fn remove_index_lock(path: &str, repos: State<'_, ReadOnlyRepos>, activity: State<'_, ActivityLog>) -> Result<(), CommandError> {
+    repos.ensure_writable(path)?;
+    let lock = git_path(path, "index.lock")?;
+    let Some(age) = lock_age(&lock) else {
+        return Ok(());
+    };
+    if age < STALE_AFTER {
+        return Err(format!(
+            "The index lock is only {}s old; another git command is probably still working. Try again in a moment",
+            age.as_secs()
+        )
+        .into());
+    }
+    if git_running() {
+        return Err("A git process is still running and may be using the index lock; close it first".to_string().into());
+    }
+    let result = std::fs::remove_file(&lock).map_err(|e| e.to_string());
+    activity.record(path, Operation::new("Remove stale index.lock"), &result);
+    Ok(result?)
+}
@@ -0,0 +1,137 @@
+use serde::Serialize;
+use std::path::Path;
+use tauri::State;
+
+use crate::activity::{ActivityLog, Operation};
+use crate::branches::validate_branch_name;
+use crate::capabilities::{GitCapabilitiesState, GitFeature};
+use crate::error::CommandError;
+use crate::repo_validation::{validate_repo, RepoValidation};
+use crate::{get_git_status, run_git, GitFileStatus};
+
+/// Starter `.gitignore` files `init_repo` can write, by name.
+const GITIGNORE_TEMPLATES: &[(&str, &str)] = &[
+    ("node", "node_modules/\ndist/\nbuild/\n.env\n.env.local\nnpm-debug.log*\nyarn-debug.log*\nyarn-error.log*\n.DS_Store\n"),
+    ("rust", "/target/\n**/*.rs.bk\n.env\n.DS_Store\n"),
+    ("python", "__pycache__/\n*.py[cod]\n.venv/\nvenv/\n.env\ndist/\nbuild/\n*.egg-info/\n.pytest_cache/\n.DS_Store\n"),
+    ("go", "/bin/\n*.exe\n*.test\n*.out\n.env\n.DS_Store\n"),
+    ("java", "target/\nbuild/\n.gradle/\n*.class\n*.jar\n.idea/\n*.iml\n.DS_Store\n"),
+    ("dotnet", "bin/\nobj/\n.vs/\n*.user\n*.suo\nTestResults/\n"),
+];
+
+#[derive(Serialize, Debug)]
+pub struct InitResult {
+    repo: RepoValidation,
+    /// The new repository's status, so the main view can be shown without another round trip
+    status: Vec<GitFileStatus>,
+}
+
+/// Names of the starter `.gitignore` templates `init_repo` accepts.
+#[tauri::command]
+pub fn get_gitignore_templates() -> Vec<&'static str> {
+    GITIGNORE_TEMPLATES.iter().map(|(name, _)| *name).collect()
+}
+
+fn same_dir(a: &str, b: &str) -> bool {
+    match (Path::new(a).canonicalize(), Path::new(b).canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Creates the branch-to-be with `-b` where git supports it; older git gets HEAD pointed at it
+/// after a plain `init`.
+fn init(path: &str, branch: Option<&str>, caps: &GitCapabilitiesState, op: &mut Operation) -> Result<(), String> {
+    let with_flag = caps.get().is_ok_and(|c| c.supports(GitFeature::InitialBranch));
+    let init = match branch {
+        Some(branch) if with_flag => run_git(path, &["init", "-b", branch]),
+        _ => run_git(path, &["init"]),
+    };
+    op.step("git init", &init);
+    init.map_err(|e| format!("git init failed: {}", e))?;
+
+    if let Some(branch) = branch.filter(|_| !with_flag) {
+        let head = run_git(path, &["symbolic-ref", "HEAD", &format!("refs/heads/{}", branch)]);
+        op.step(format!("Name the first branch {}", branch), &head);
+        head?;
+    }
+    Ok(())
+}
+
+/// Makes `path` a new repository, optionally naming its first branch, adding `origin` and
+/// writing a starter `.gitignore` (an existing one is kept). Inside another repository this
+/// is refused with `InsideExistingRepo` unless `allow_nested`, since a repository nested in
+/// another is almost always a mistake. Running it again on an existing repository's root is
+/// harmless, as with `git init`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn init_repo(
+    path: &str,
+    remote_url: Option<String>,
+    default_branch: Option<String>,
+    gitignore_template: Option<String>,
+    allow_nested: Option<bool>,
+    caps: State<'_, GitCapabilitiesState>,
+    activity: State<'_, ActivityLog>,
+) -> Result<InitResult, CommandError> {
+    let existing = validate_repo(path)?;
+    let is_root = existing.root.as_deref().is_some_and(|root| same_dir(root, path));
+    if existing.is_repo && !is_root && !allow_nested.unwrap_or(false) {
+        let root = existing.root.unwrap_or(existing.git_dir);
+        return Err(CommandError::InsideExistingRepo { root });
+    }
+    let branch = default_branch.as_deref().map(str::trim).filter(|b| !b.is_empty());
+    if let Some(branch) = branch {
+        validate_branch_name(branch)?;
+    }
+    let template = match gitignore_template.as_deref().filter(|t| !t.is_empty()) {
+        Some(name) => Some(
+            GITIGNORE_TEMPLATES
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, content)| *content)
+                .ok_or_else(|| format!("There is no .gitignore template called {}", name))?,
+        ),
+        None => None,
+    };
+
+    let mut op = Operation::new("Initialize repository");
+    let result = init_with_remote(path, remote_url, branch, template, &caps, &mut op);
+    activity.record(path, op, &result);
+    result?;
+
+    Ok(InitResult { repo: validate_repo(path)?, status: get_git_status(path, None)? })
+}
+
+fn init_with_remote(
+    path: &str,
+    remote_url: Option<String>,
+    branch: Option<&str>,
+    template: Option<&str>,
+    caps: &GitCapabilitiesState,
+    op: &mut Operation,
+) -> Result<(), String> {
+    init(path, branch, caps, op)?;
+
+    if let Some(content) = template {
+        let gitignore = Path::new(path).join(".gitignore");
+        if !gitignore.exists() {
+            let written = std::fs::write(&gitignore, content).map_err(|e| e.to_string());
+            op.step("Write .gitignore", &written);
+            written?;
+        }
+    }
+
+    // Optionally add remote
+    if let Some(url) = remote_url {
+        let trimmed = url.trim();
+        if !trimmed.is_empty() {
+            let added = run_git(path, &["remote", "add", "origin", trimmed])
+                .map_err(|e| format!("git remote add failed: {}", e));
+            op.step("Add remote origin", &added);
+            added?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,9 +1,86 @@
 use serde::{Deserialize, Serialize};
-use std::process::Command;
-use reqwest::Client;
+use std::io::{BufReader, Read, Write};
+use std::process::{Command, Output, Stdio};
+use tauri::{AppHandle, Emitter, Manager, Runtime, State};
 use winreg::enums::*;
 use winreg::RegKey;
 
+mod activity;
+mod ai;
+mod auto_fetch;
+mod backend;
+mod blame;
+mod branches;
+mod capabilities;
+mod cherry_pick;
+mod clean;
+mod clone;
+mod commit_checks;
+mod commit_groups;
+mod commit_template;
+mod commit_vocabulary;
+mod conflicts;
+mod danger;
+mod diff;
+mod diff_stats;
+mod discard;
+#[cfg(feature = "embedded-git")]
+mod embedded;
+mod error;
+mod file_content;
+mod fingerprint;
+mod git_config;
+mod gitignore;
+mod health;
+mod history;
+mod hooks;
+mod hunks;
+mod index_lock;
+mod init;
+#[cfg(feature = "libgit2")]
+mod libgit2;
+mod merge;
+mod ollama_pull;
+mod patch_commit;
+mod pull;
+mod push;
+mod read_only;
+mod rebase;
+mod reflog;
+mod reset;
+mod revert;
+mod release;
+mod repo_config;
+mod repo_state;
+mod repo_validation;
+mod search;
+mod settings;
+mod signing;
+mod snippets;
+mod staging;
+mod stash;
+mod stats;
+mod status_summary;
+mod structured_diff;
+mod submodules;
+mod summary;
+mod tags;
+#[cfg(test)]
+mod test_support;
+mod trailers;
+mod web_url;
+mod word_diff;
+mod worktrees;
+
+use activity::{first_line, ActivityLog, Operation};
+use ai::send_ai_prompt;
+use capabilities::{GitCapabilities, GitCapabilitiesState, GitFeature};
+use diff::{DiffOptions, DiffScope};
+use error::CommandError;
+use fingerprint::DiffFingerprints;
+use read_only::ReadOnlyRepos;
+use signing::SignMode;
+
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 
@@ -17,16 +94,311 @@ fn build_hidden_cmd(program: &str) -> Command {
     cmd
 }
 
-#[derive(Serialize, Deserialize)]
-struct OllamaRequest {
-    model: String,
-    prompt: String,
-    stream: bool,
+/// Goes before the subcommand so non-ASCII paths come out as UTF-8 rather than `"h\303\251llo"`.
+const UNQUOTED_PATHS: [&str; 2] = ["-c", "core.quotepath=false"];
+
+/// Context lines around changes in the diff sent for commit message generation.
+const AI_CONTEXT_LINES: u32 = 8;
+
+/// Largest diff `get_git_diff` returns, in bytes; generation uses smaller per-provider limits.
+const MAX_DIFF_LEN: usize = 40_000;
+
+/// Error message for git not being on the PATH; `CommandError` turns it into `GitNotInstalled`.
+const GIT_NOT_INSTALLED: &str = "Git isn't installed or isn't on the PATH";
+
+/// For a failure to start git: a missing executable becomes `GIT_NOT_INSTALLED` instead of the
+/// OS's "program not found". A missing working directory fails the same way, hence the check.
+fn spawn_error(e: std::io::Error) -> String {
+    match e.kind() {
+        std::io::ErrorKind::NotFound if !backend::git_found() => GIT_NOT_INSTALLED.to_string(),
+        _ => e.to_string(),
+    }
 }
 
-#[derive(Serialize, Deserialize)]
-struct OllamaResponse {
-    response: String,
+/// Subcommands that only read. Some (`status`, `diff`) still refresh the index and take
+/// `index.lock` to save the result, which makes other tools' git fail while GitPop refreshes.
+const READ_ONLY_SUBCOMMANDS: &[&str] = &[
+    "status", "diff", "log", "show", "for-each-ref", "rev-list", "ls-files", "grep", "blame", "shortlog", "describe",
+    "cat-file",
+];
+
+/// `args` with `--no-optional-locks` in front when they run a read-only subcommand, so those
+/// skip the optional index refresh instead of locking the index. Global options before the
+/// subcommand (`-c key=value`, `-C dir`) are looked past.
+fn git_args<'a>(args: &[&'a str]) -> Vec<&'a str> {
+    let mut rest = args.iter();
+    let subcommand = loop {
+        match rest.next() {
+            Some(&"-c") | Some(&"-C") => {
+                rest.next();
+            }
+            Some(arg) if arg.starts_with('-') => {}
+            other => break other,
+        }
+    };
+    let mut full = Vec::with_capacity(args.len() + 1);
+    if subcommand.is_some_and(|s| READ_ONLY_SUBCOMMANDS.contains(s)) && capabilities::supports_no_optional_locks() {
+        full.push("--no-optional-locks");
+    }
+    full.extend_from_slice(args);
+    full
+}
+
+/// A hidden `git` command running `args` in `path`, with `git_args`' global options added.
+fn git_cmd(path: &str, args: &[&str]) -> Command {
+    let mut cmd = build_hidden_cmd("git");
+    cmd.current_dir(path).args(git_args(args));
+    cmd
+}
+
+/// Runs git in `path` and returns its stdout, or its stderr as the error when git exits non-zero.
+fn run_git(path: &str, args: &[&str]) -> Result<String, String> {
+    let output = git_cmd(path, args)
+        .output()
+        .map_err(spawn_error)?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// `run_git` with `input` fed to git's stdin, for commands that read a patch or message from `-`.
+fn run_git_with_input(path: &str, args: &[&str], input: &str) -> Result<String, String> {
+    let mut child = git_cmd(path, args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(spawn_error)?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// `run_git` with the editor disabled, so steps that would open one (`--continue`) take git's
+/// prepared message.
+fn run_git_no_editor(path: &str, args: &[&str]) -> Result<String, String> {
+    let output = git_cmd(path, args)
+        .env("GIT_EDITOR", "true")
+        .output()
+        .map_err(spawn_error)?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Identifies a repo by its work tree root, so per-repo state set from a subdirectory applies
+/// to the whole repository. Falls back to `path` itself outside a repository.
+fn repo_key(path: &str) -> String {
+    run_git(path, &["rev-parse", "--show-toplevel"])
+        .map(|root| root.trim().to_string())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+/// Resolves a path inside the git directory (e.g. `FETCH_HEAD`), honoring worktrees and `GIT_DIR`.
+fn git_path(path: &str, name: &str) -> Result<std::path::PathBuf, String> {
+    let resolved = run_git(path, &["rev-parse", "--git-path", name])?;
+    Ok(std::path::Path::new(path).join(resolved.trim()))
+}
+
+/// False on a freshly initialized repo whose branch is still unborn, where anything that
+/// names `HEAD` fails with "unknown revision".
+fn has_commits(path: &str) -> bool {
+    build_hidden_cmd("git")
+        .current_dir(path)
+        .args(["rev-parse", "--verify", "--quiet", "HEAD"])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// For commands that only make sense once the first commit exists (push, amend, undo).
+fn require_commits(path: &str) -> Result<(), CommandError> {
+    if has_commits(path) {
+        Ok(())
+    } else {
+        Err(CommandError::NoCommitsYet)
+    }
+}
+
+/// Unstages everything, using `git reset` on gits that predate `git restore` (2.23). Before
+/// the first commit there is no HEAD to restore from, so the index is emptied instead.
+fn unstage_all(path: &str, caps: &GitCapabilities) {
+    let args: &[&str] = if !has_commits(path) {
+        &["rm", "--cached", "-r", "-q", "--ignore-unmatch", "--", "."]
+    } else if caps.supports(GitFeature::Restore) {
+        &["restore", "--staged", "--", "."]
+    } else {
+        &["reset", "-q", "--", "."]
+    };
+    let _ = build_hidden_cmd("git")
+        .current_dir(path)
+        .args(args)
+        .output();
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GitProgress {
+    path: String,
+    phase: String,
+    percent: Option<u32>,
+    detail: String,
+}
+
+/// Turns a git progress line like `Receiving objects:  45% (450/1000)` into a progress event.
+fn parse_progress_line(path: &str, line: &str) -> GitProgress {
+    let text = line.trim().trim_start_matches("remote:").trim();
+    let (phase, rest) = match text.split_once(':') {
+        Some((phase, rest)) => (phase.trim().to_string(), rest),
+        None => (String::new(), text),
+    };
+    let percent = rest
+        .split_once('%')
+        .and_then(|(before, _)| before.trim().rsplit(' ').next())
+        .and_then(|n| n.parse::<u32>().ok());
+
+    GitProgress {
+        path: path.to_string(),
+        phase,
+        percent,
+        detail: text.to_string(),
+    }
+}
+
+/// How long a network operation may go without printing anything before it's assumed to be
+/// stuck on an unreachable remote and stopped.
+const STALL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(90);
+
+/// How often a cancellable network operation checks whether it was cancelled.
+const CANCEL_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// What git's stderr says when the remote wanted credentials it didn't get or rejected them.
+const AUTH_FAILURES: &[&str] = &[
+    "Authentication failed",
+    "could not read Username",
+    "could not read Password",
+    "terminal prompts disabled",
+    "Permission denied (publickey",
+    "HTTP Basic: Access denied",
+    "Invalid username or password",
+    "The requested URL returned error: 403",
+];
+
+/// Whether a failed fetch, pull or push was turned away for missing or wrong credentials.
+fn is_auth_failure(stderr: &str) -> bool {
+    AUTH_FAILURES.iter().any(|pattern| stderr.contains(pattern))
+}
+
+/// Runs a long git operation (fetch, push, clone, ...) and forwards each `--progress` line
+/// on stderr to the frontend as `event`. Credential prompts are disabled so a missing
+/// credential fails instead of hanging the hidden process, and git is killed once it has been
+/// silent for `STALL_TIMEOUT`. The returned stderr only keeps completed lines, not the `\r`
+/// progress redraws.
+fn run_git_with_progress<R: Runtime>(app: &AppHandle<R>, path: &str, args: &[&str], event: &str) -> Result<Output, String> {
+    run_git_with_progress_until(app, path, args, event, None)
+}
+
+/// `run_git_with_progress` that also kills git and fails with "Cancelled" as soon as `cancel`
+/// fires or its sender is dropped.
+fn run_git_with_progress_until<R: Runtime>(
+    app: &AppHandle<R>,
+    path: &str,
+    args: &[&str],
+    event: &str,
+    mut cancel: Option<&mut tokio::sync::oneshot::Receiver<()>>,
+) -> Result<Output, String> {
+    let mut child = build_hidden_cmd("git")
+        .current_dir(path)
+        .args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(spawn_error)?;
+
+    let mut stdout = child.stdout.take().ok_or("Failed to capture git stdout")?;
+    let stdout_reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stdout.read_to_end(&mut buf);
+        buf
+    });
+
+    // Read on a separate thread so a silent git can be timed out; a remote helper git spawned
+    // may keep the pipe open after git itself is killed
+    let stderr = child.stderr.take().ok_or("Failed to capture git stderr")?;
+    let (lines, received) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut current = Vec::new();
+        for byte in BufReader::new(stderr).bytes() {
+            let Ok(byte) = byte else { break };
+            if byte != b'\r' && byte != b'\n' {
+                current.push(byte);
+                continue;
+            }
+            let line = String::from_utf8_lossy(&current).to_string();
+            current.clear();
+            if lines.send((line, byte == b'\n')).is_err() {
+                return;
+            }
+        }
+        if !current.is_empty() {
+            let _ = lines.send((String::from_utf8_lossy(&current).to_string(), true));
+        }
+    });
+
+    let mut stderr_lines = String::new();
+    let mut last_output = std::time::Instant::now();
+    loop {
+        let cancelled = cancel.as_deref_mut().is_some_and(|cancel| {
+            !matches!(cancel.try_recv(), Err(tokio::sync::oneshot::error::TryRecvError::Empty))
+        });
+        if cancelled {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(commit_groups::CANCELLED.to_string());
+        }
+        match received.recv_timeout(CANCEL_POLL_INTERVAL) {
+            Ok((line, complete)) => {
+                last_output = std::time::Instant::now();
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let _ = app.emit(event, parse_progress_line(path, &line));
+                if complete {
+                    stderr_lines.push_str(&line);
+                    stderr_lines.push('\n');
+                }
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) if last_output.elapsed() < STALL_TIMEOUT => {}
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!(
+                    "git {} made no progress for {} seconds and was stopped; is the remote reachable?",
+                    args.first().unwrap_or(&""),
+                    STALL_TIMEOUT.as_secs()
+                ));
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    let status = child.wait().map_err(|e| e.to_string())?;
+    let stdout = stdout_reader.join().unwrap_or_default();
+
+    Ok(Output {
+        status,
+        stdout,
+        stderr: stderr_lines.into_bytes(),
+    })
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -42,8 +414,74 @@ pub struct OllamaTagsResponse {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GitFileStatus {
     path: String,
+    /// `M`, `A`, `D`, `R`, `C` (copied), `U` (untracked), `X` (unresolved merge conflict),
+    /// `S` (a submodule whose commit moved or that has changes inside) or `I` (ignored, only
+    /// with `include_ignored`)
     status: String,
     staged: bool,
+    /// Where a renamed (`R`) or copied (`C`) file came from
+    old_path: Option<String>,
+    /// Git's code for the staged side (`M`, `A`, `D`, `R`, ...), null when nothing is staged
+    index_status: Option<String>,
+    /// Git's code for unstaged changes (`?` when untracked), null when there are none. When both
+    /// sides are set the file is partially staged and committing it keeps the unstaged part out.
+    worktree_status: Option<String>,
+    /// Unmerged after a merge, rebase or cherry-pick; `index_status` and `worktree_status`
+    /// hold git's pair (`UU`, `AA`, `DU`, ...) saying which side added, deleted or changed it
+    conflicted: bool,
+}
+
+impl GitFileStatus {
+    /// A tracked file from git's `XY` pair, `.` meaning that side is unchanged. The embedded
+    /// backends build their entries through this too, so `status` and `staged` come out the
+    /// same whichever one read the repository.
+    pub(crate) fn changed(path: String, xy: (char, char), submodule: bool, old_path: Option<String>, conflicted: bool) -> Self {
+        let (index_status, work_status) = xy;
+        let either = |c: char| index_status == c || work_status == c;
+        let side = |c: char| (c != '.').then(|| c.to_string());
+        let status = match index_status {
+            _ if conflicted => "X",
+            'R' => "R",
+            'C' => "C",
+            _ if either('A') => "A",
+            _ if either('D') => "D",
+            _ if submodule => "S",
+            _ => "M",
+        };
+        GitFileStatus {
+            path,
+            status: status.to_string(),
+            staged: !conflicted && index_status != '.',
+            old_path,
+            index_status: side(index_status),
+            worktree_status: side(work_status),
+            conflicted,
+        }
+    }
+
+    pub(crate) fn untracked(path: String) -> Self {
+        GitFileStatus {
+            path,
+            status: "U".to_string(),
+            staged: false,
+            old_path: None,
+            index_status: None,
+            worktree_status: Some("?".to_string()),
+            conflicted: false,
+        }
+    }
+
+    pub(crate) fn ignored(path: String) -> Self {
+        GitFileStatus {
+            path,
+            status: "I".to_string(),
+            staged: false,
+            old_path: None,
+            index_status: None,
+            worktree_status: Some("!".to_string()),
+            conflicted: false,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -54,133 +492,730 @@ pub struct AiConfig {
     custom_api_url: Option<String>,
 }
 
+/// Parses `git status --porcelain=v2 -z`. Records are NUL-separated and paths are never
+/// quoted, so spaces, tabs, quotes and newlines in file names come through unchanged.
+fn parse_porcelain_v2(out: &str) -> Vec<GitFileStatus> {
+    let mut files = Vec::new();
+    let mut records = out.split('\0').filter(|r| !r.is_empty());
+    while let Some(record) = records.next() {
+        let (kind, rest) = record.split_at(1);
+        let rest = rest.strip_prefix(' ').unwrap_or(rest);
+        // Changed entries: `1 XY sub mH mI mW hH hI path`; renames and copies add a score
+        // field and are followed by a record holding the original path
+        let (xy, sub, path, old_path) = match kind {
+            "1" => {
+                let fields: Vec<&str> = rest.splitn(8, ' ').collect();
+                let [xy, sub, .., path] = fields[..] else { continue };
+                (xy, sub, path, None)
+            }
+            "2" => {
+                let fields: Vec<&str> = rest.splitn(9, ' ').collect();
+                let [xy, sub, .., path] = fields[..] else { continue };
+                (xy, sub, path, records.next().map(str::to_string))
+            }
+            "u" => {
+                let fields: Vec<&str> = rest.splitn(10, ' ').collect();
+                let [xy, sub, .., path] = fields[..] else { continue };
+                (xy, sub, path, None)
+            }
+            "?" => {
+                files.push(GitFileStatus::untracked(rest.to_string()));
+                continue;
+            }
+            "!" => {
+                files.push(GitFileStatus::ignored(rest.to_string()));
+                continue;
+            }
+            _ => continue,
+        };
+
+        let mut codes = xy.chars();
+        let (index_status, work_status) = (codes.next().unwrap_or('.'), codes.next().unwrap_or('.'));
+        // `sub` is `S<c><m><u>` for a submodule: a moved commit, or changes inside it
+        let submodule = sub.starts_with('S');
+        files.push(GitFileStatus::changed(path.to_string(), (index_status, work_status), submodule, old_path, kind == "u"));
+    }
+    files
+}
+
+/// With `include_ignored`, ignored files are listed too as `I`. A directory that is ignored as
+/// a whole (`node_modules/`, `target/`) comes back as one entry ending in `/`.
 #[tauri::command]
-fn get_git_status(path: &str) -> Result<Vec<GitFileStatus>, String> {
-    let output = build_hidden_cmd("git")
-        .current_dir(path)
-        .args(["status", "--porcelain"])
-        .output()
-        .map_err(|e| e.to_string())?;
+fn get_git_status(path: &str, include_ignored: Option<bool>) -> Result<Vec<GitFileStatus>, String> {
+    backend::active().status(path, include_ignored.unwrap_or(false))
+}
+
+/// `get_git_status` through the git executable.
+fn git_status(path: &str, include_ignored: bool) -> Result<Vec<GitFileStatus>, String> {
+    let mut args = UNQUOTED_PATHS.to_vec();
+    args.extend(["status", "--porcelain=v2", "-z"]);
+    if include_ignored {
+        args.push("--ignored=traditional");
+    }
+    let output = git_cmd(path, &args).output().map_err(spawn_error)?;
 
     if !output.status.success() {
         return Err(String::from_utf8_lossy(&output.stderr).to_string());
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut files = Vec::new();
+    Ok(parse_porcelain_v2(&String::from_utf8_lossy(&output.stdout)))
+}
 
-    for line in stdout.lines() {
-        if line.len() < 4 { continue; }
-        let status_chars = &line[0..2];
-        let file_path = &line[3..];
-
-        let index_status = status_chars.chars().nth(0).unwrap();
-        let work_status = status_chars.chars().nth(1).unwrap();
-        let staged = index_status != ' ' && index_status != '?';
-
-        let mut status = "U".to_string();
-        if status_chars == "??" { status = "U".to_string(); }
-        else if index_status == 'A' || work_status == 'A' { status = "A".to_string(); }
-        else if index_status == 'M' || work_status == 'M' { status = "M".to_string(); }
-        else if index_status == 'D' || work_status == 'D' { status = "D".to_string(); }
-
-        files.push(GitFileStatus {
-            path: file_path.trim().to_string(),
-            status,
-            staged,
-        });
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkingTreeState {
+    Clean,
+    /// Tracked files differ from HEAD (staged or not), no untracked files
+    DirtyTracked,
+    /// There are untracked files, whether or not tracked files changed too
+    DirtyWithUntracked,
+}
+
+/// Runs `git diff --quiet`-style commands: exit 0 means no differences, 1 means differences.
+fn git_has_differences(path: &str, args: &[&str]) -> Result<bool, String> {
+    let out = git_cmd(path, args).output().map_err(spawn_error)?;
+
+    match out.status.code() {
+        Some(0) => Ok(false),
+        Some(1) => Ok(true),
+        _ => Err(String::from_utf8_lossy(&out.stderr).to_string()),
+    }
+}
+
+/// Reads only the first untracked path and stops git there instead of listing them all.
+fn has_untracked_files(path: &str) -> Result<bool, String> {
+    let mut child = git_cmd(path, &["ls-files", "--others", "--exclude-standard", "--directory", "--no-empty-directory", "-z"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(spawn_error)?;
+
+    let mut first = [0u8; 1];
+    let found = match child.stdout.take() {
+        Some(mut stdout) => stdout.read(&mut first).map_err(|e| e.to_string())? > 0,
+        None => false,
+    };
+    if found {
+        let _ = child.kill();
+    }
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if !found && !status.success() {
+        return Err("git ls-files failed".to_string());
     }
-    Ok(files)
+    Ok(found)
 }
 
+/// Cheap "is there anything to commit?" check for callers that don't need the full status.
+/// Both `git diff --quiet` calls stop at the first difference. During a merge, unmerged
+/// index entries count as tracked changes, and a pending MERGE_HEAD makes the tree dirty
+/// even when the resolved result happens to match HEAD, since a merge commit is still due.
 #[tauri::command]
-fn get_git_diff(path: &str, files: Vec<String>) -> Result<String, String> {
-    // 1. Unstage everything to get a clean state
-    let _ = build_hidden_cmd("git")
-        .current_dir(path)
-        .args(["restore", "--staged", "."])
-        .output();
+fn is_working_tree_dirty(path: &str) -> Result<WorkingTreeState, String> {
+    if has_untracked_files(path)? {
+        return Ok(WorkingTreeState::DirtyWithUntracked);
+    }
 
-    // 2. Stage exactly the files they selected in the UI
-    for file in files {
-        let out = build_hidden_cmd("git")
-            .current_dir(path)
-            .args(["add", &file])
-            .output()
-            .map_err(|e| e.to_string())?;
-        if !out.status.success() {
-            return Err(String::from_utf8_lossy(&out.stderr).to_string());
-        }
+    let merging = git_path(path, "MERGE_HEAD").map(|p| p.exists()).unwrap_or(false);
+    if merging
+        || git_has_differences(path, &["diff", "--quiet"])?
+        || git_has_differences(path, &["diff", "--cached", "--quiet"])?
+    {
+        return Ok(WorkingTreeState::DirtyTracked);
     }
 
-    // 3. Get file name summaries (Table of contents)
-    let summary_output = build_hidden_cmd("git")
-        .current_dir(path)
-        .args(["diff", "--cached", "--name-status"])
-        .output()
-        .map_err(|e| e.to_string())?;
-        
-    // 4. Get the deep code diff
-    let diff_output = build_hidden_cmd("git")
-        .current_dir(path)
-        .args(["diff", "--cached"])
-        .output()
-        .map_err(|e| e.to_string())?;
+    Ok(WorkingTreeState::Clean)
+}
 
-    let mut full_diff = String::from_utf8_lossy(&summary_output.stdout).to_string();
-    full_diff.push_str("\n\n");
-    full_diff.push_str(&String::from_utf8_lossy(&diff_output.stdout));
-    
-    // Increased truncation limit for modern models
-    if full_diff.len() > 40000 {
-        full_diff.truncate(40000);
-        full_diff.push_str("\n... [Diff truncated due to length limitations]");
-    }
-    
-    // 5. Restore back to original state so we don't accidentally leave things staged if they cancel
-    let _ = build_hidden_cmd("git")
-        .current_dir(path)
-        .args(["restore", "--staged", "."])
-        .output();
-        
+#[tauri::command]
+fn get_git_diff(
+    path: &str,
+    files: Vec<String>,
+    ignore_whitespace: Option<bool>,
+    context_lines: Option<u32>,
+) -> Result<String, String> {
+    let options = DiffOptions {
+        ignore_whitespace: ignore_whitespace.unwrap_or(false),
+        context_lines,
+        ..Default::default()
+    };
+    let mut full_diff = backend::active().diff(path, &DiffScope::SelectedFiles(files), options)?;
+
+    truncate_diff(&mut full_diff, MAX_DIFF_LEN);
     Ok(full_diff)
 }
 
+/// Like `get_git_diff`, but with staged and unstaged changes apart so a file with both shows
+/// up once in each; each side is truncated on its own.
 #[tauri::command]
-fn commit_changes(path: &str, message: &str, files: Vec<String>) -> Result<(), String> {
-    // Unstage everything first to match our UI state
-    let _ = build_hidden_cmd("git")
-        .current_dir(path)
-        .args(["restore", "--staged", "."])
-        .output();
+fn get_git_diff_split(path: &str, files: Vec<String>) -> Result<diff::SplitDiff, String> {
+    let mut split = diff::collect_split_diff(path, &files)?;
+    truncate_diff(&mut split.staged, MAX_DIFF_LEN);
+    truncate_diff(&mut split.unstaged, MAX_DIFF_LEN);
+    Ok(split)
+}
 
-    // Stage selected files
+/// Fits `diff` into `max_len` bytes: first by trimming hunk bodies across files with
+/// `diff::fit_diff`, then, if the headers alone are too long, by cutting at the previous
+/// character boundary so multi-byte characters (emoji, CJK comments) are never split.
+fn truncate_diff(diff: &mut String, max_len: usize) {
+    if diff.len() <= max_len {
+        return;
+    }
+    *diff = diff::fit_diff(diff, max_len);
+    if diff.len() <= max_len {
+        return;
+    }
+    let mut cut = max_len;
+    while !diff.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    let dropped = diff.len() - cut;
+    diff.truncate(cut);
+    diff.push_str(&format!("\n... [Diff truncated due to length limitations, {} bytes omitted]", dropped));
+}
+
+#[tauri::command]
+fn get_file_diff(
+    path: &str,
+    file: &str,
+    staged: bool,
+    ignore_whitespace: Option<bool>,
+    context_lines: Option<u32>,
+) -> Result<String, String> {
+    let options = DiffOptions {
+        ignore_whitespace: ignore_whitespace.unwrap_or(false),
+        context_lines,
+        ..Default::default()
+    };
+    backend::active().file_diff(path, Some(file), staged, options)
+}
+
+/// Copy of the index file taken before a command starts restaging, so a failure halfway
+/// through can put the user's staging back exactly as it was (including unmerged entries).
+struct IndexSnapshot {
+    index: std::path::PathBuf,
+    backup: Option<std::path::PathBuf>,
+}
+
+impl IndexSnapshot {
+    fn take(path: &str) -> Result<Self, String> {
+        let index = git_path(path, "index")?;
+        if !index.exists() {
+            // Fresh repo with nothing staged yet; restoring means removing the index again
+            return Ok(IndexSnapshot { index, backup: None });
+        }
+        let backup = git_path(path, "gitpop-index.bak")?;
+        std::fs::copy(&index, &backup).map_err(|e| format!("Failed to back up the index: {}", e))?;
+        Ok(IndexSnapshot { index, backup: Some(backup) })
+    }
+
+    fn restore(self) -> bool {
+        match &self.backup {
+            Some(backup) => std::fs::copy(backup, &self.index).is_ok(),
+            None => !self.index.exists() || std::fs::remove_file(&self.index).is_ok(),
+        }
+    }
+}
+
+impl Drop for IndexSnapshot {
+    fn drop(&mut self) {
+        if let Some(backup) = &self.backup {
+            let _ = std::fs::remove_file(backup);
+        }
+    }
+}
+
+/// Stages each of `files` as it is in the work tree, including deletions (`-A`, which git
+/// 1.x needs for a removed path). `force` stages files even if they are ignored (`git add -f`).
+fn stage_files(path: &str, files: &[String], force: bool) -> Result<(), String> {
+    let add = if force { vec!["add", "-A", "-f", "--"] } else { vec!["add", "-A", "--"] };
     for file in files {
         let out = build_hidden_cmd("git")
             .current_dir(path)
-            .args(["add", &file])
+            .args(&add)
+            .arg(file)
             .output()
-            .map_err(|e| e.to_string())?;
+            .map_err(spawn_error)?;
         if !out.status.success() {
-            return Err(String::from_utf8_lossy(&out.stderr).to_string());
+            return Err(format!("Could not stage {}: {}", file, String::from_utf8_lossy(&out.stderr).trim()));
+        }
+    }
+    Ok(())
+}
+
+/// Adds the source path of every staged rename among `files`. Unstaging everything splits a
+/// rename into a deletion and a new file, so both halves have to be staged again.
+fn with_rename_sources(path: &str, mut files: Vec<String>) -> Vec<String> {
+    let Ok(statuses) = git_status(path, false) else {
+        return files;
+    };
+    for status in statuses {
+        if status.status != "R" || !files.contains(&status.path) {
+            continue;
+        }
+        if let Some(old) = status.old_path.filter(|old| !old.is_empty() && !files.contains(old)) {
+            files.push(old);
         }
     }
+    files
+}
 
-    // Commit
-    let commit_out = build_hidden_cmd("git")
-        .current_dir(path)
-        .args(["commit", "-m", message])
-        .output()
-        .map_err(|e| e.to_string())?;
+/// Paths should arrive unquoted, but one copied from git output with an unusual `core.quotepath`
+/// setup can still be in C-quoted form; decode it unless a file by the literal name exists.
+fn decode_path(path: &str, file: &str) -> String {
+    if std::path::Path::new(path).join(file).exists() {
+        return file.to_string();
+    }
+    structured_diff::unquote(file)
+}
+
+/// The untracked entries of `files` that .gitignore rules exclude. Tracked files never count,
+/// even when they match a pattern.
+fn ignored_files(path: &str, files: &[String]) -> Vec<String> {
+    if files.is_empty() {
+        return Vec::new();
+    }
+    let mut args = vec!["check-ignore", "--"];
+    args.extend(files.iter().map(String::as_str));
+    // Exits with 1 when nothing is ignored
+    run_git(path, &args).unwrap_or_default().lines().map(str::to_string).collect()
+}
+
+/// Mode and blob of `file` in the index.
+fn index_entry(path: &str, file: &str) -> Option<(String, String)> {
+    let entry = run_git(path, &["ls-files", "-s", "--", file]).ok()?;
+    let mut fields = entry.lines().next()?.split_whitespace();
+    Some((fields.next()?.to_string(), fields.next()?.to_string()))
+}
+
+/// Mode, blob and path of the selected files that are partially staged, so their staged
+/// content can be put back after restaging.
+fn partially_staged_entries(path: &str, files: &[String]) -> Vec<(String, String, String)> {
+    let Ok(statuses) = git_status(path, false) else {
+        return Vec::new();
+    };
+    statuses
+        .into_iter()
+        .filter(|s| s.index_status.is_some() && s.worktree_status.is_some() && files.contains(&s.path))
+        .filter_map(|s| {
+            let (mode, sha) = index_entry(path, &s.path)?;
+            Some((mode, sha, s.path))
+        })
+        .collect()
+}
+
+/// Staged changes to files outside the selection, as index entries to put back once the
+/// selection is committed: mode and blob, or `None` where a deletion was staged. A staged
+/// rename keeps both its new path and the removal of its old one.
+fn staged_outside_selection(path: &str, files: &[String]) -> Vec<(Option<(String, String)>, String)> {
+    let Ok(statuses) = git_status(path, false) else {
+        return Vec::new();
+    };
+    let mut kept = Vec::new();
+    for status in statuses.into_iter().filter(|s| !s.conflicted && !files.contains(&s.path)) {
+        match status.index_status.as_deref() {
+            None => continue,
+            Some("D") => kept.push((None, status.path)),
+            Some(code) => {
+                if let (Some(old), "R") = (&status.old_path, code) {
+                    kept.push((None, old.clone()));
+                }
+                if let Some(entry) = index_entry(path, &status.path) {
+                    kept.push((Some(entry), status.path));
+                }
+            }
+        }
+    }
+    kept
+}
+
+fn stage_and_commit(
+    path: &str,
+    message: &str,
+    files: Vec<String>,
+    force_ignored: bool,
+    options: CommitOptions,
+    caps: &GitCapabilities,
+    op: &mut Operation,
+) -> Result<(), String> {
+    let files = with_rename_sources(path, files);
+    let partial = partially_staged_entries(path, &files);
+
+    // During a merge the index holds the merge result, which the merge commit has to record
+    // as a whole; the selected files are only staged on top of it
+    let merging = git_path(path, "MERGE_HEAD").map(|p| p.exists()).unwrap_or(false);
+    let kept = if merging || options.reset_index { Vec::new() } else { staged_outside_selection(path, &files) };
+
+    // Unstage everything first so only the selection is committed
+    if !merging {
+        unstage_all(path, caps);
+        op.step::<(), String>("Unstage all", &Ok(()));
+    }
+
+    // Stage selected files; an empty commit has none
+    if !(files.is_empty() && options.allow_empty) {
+        let staged = stage_files(path, &files, force_ignored);
+        op.step(format!("Stage {} file(s)", files.len()), &staged);
+        staged?;
+    }
+
+    // Partially staged files go back to their staged content; the unstaged edits stay unstaged
+    for (mode, sha, file) in &partial {
+        let restored = run_git(path, &["update-index", "--cacheinfo", &format!("{},{},{}", mode, sha, file)]);
+        op.step(format!("Keep staged content of {}", file), &restored);
+        restored?;
+    }
+
+    let committed = git_commit(path, message, options);
+    op.step(if options.amend { "Amend last commit" } else { "Commit" }, &committed);
+    committed?;
+
+    // What was staged for other files is staged again. The commit already exists, so a
+    // failure here is only logged; the change is still in the work tree
+    for (entry, file) in &kept {
+        let restored = match entry {
+            Some((mode, sha)) => run_git(path, &["update-index", "--add", "--cacheinfo", &format!("{},{},{}", mode, sha, file)]),
+            None => run_git(path, &["update-index", "--force-remove", "--", file]),
+        };
+        op.step(format!("Keep staged changes to {}", file), &restored);
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct CommitOptions {
+    amend: bool,
+    sign: SignMode,
+    /// Skips the pre-commit and commit-msg hooks
+    no_verify: bool,
+    /// Commits even when nothing changed, e.g. to trigger CI
+    allow_empty: bool,
+    /// Leaves files outside the selection unstaged afterwards, as GitPop used to, instead of
+    /// putting back what was staged for them
+    reset_index: bool,
+}
+
+fn git_commit(path: &str, message: &str, options: CommitOptions) -> Result<(), String> {
+    let mut args = vec!["commit", "-m", message];
+    if options.amend {
+        args.push("--amend");
+    }
+    args.extend(options.sign.flag());
+    if options.no_verify {
+        args.push("--no-verify");
+    }
+    if options.allow_empty {
+        args.push("--allow-empty");
+    }
+
+    // A console pinentry needs a console, so signing commits don't get CREATE_NO_WINDOW
+    let mut cmd = if options.sign.signs(path) {
+        let mut cmd = Command::new("git");
+        signing::allow_pinentry(&mut cmd);
+        cmd
+    } else {
+        build_hidden_cmd("git")
+    };
+    let commit_out = cmd.current_dir(path).args(&args).output().map_err(spawn_error)?;
 
     if !commit_out.status.success() {
-        return Err(String::from_utf8_lossy(&commit_out.stderr).to_string());
+        // A rejecting hook's lint output can be on either stream, and "nothing to commit" is
+        // on stdout only, so both are kept
+        let output: Vec<String> = [&commit_out.stderr, &commit_out.stdout]
+            .iter()
+            .map(|stream| String::from_utf8_lossy(stream).trim().to_string())
+            .filter(|text| !text.is_empty())
+            .collect();
+        return Err(if output.is_empty() { "git commit failed".to_string() } else { output.join("\n") });
     }
     Ok(())
 }
 
+/// Remote-tracking branches that already contain HEAD, i.e. where the last commit was pushed.
+fn remotes_containing_head(path: &str) -> Vec<String> {
+    run_git(path, &["for-each-ref", "--contains", "HEAD", "--format=%(refname:short)", "refs/remotes"])
+        .map(|out| {
+            out.lines()
+                .filter(|r| !r.is_empty() && !r.ends_with("/HEAD"))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Full message of the last commit, to prefill the message box when switching to amend.
+#[tauri::command]
+fn get_last_commit_message(path: &str) -> Result<String, CommandError> {
+    require_commits(path)?;
+    let message = run_git(path, &["log", "-1", "--format=%B", "HEAD"])?;
+    Ok(message.trim_end().to_string())
+}
+
+/// Files with unresolved merge conflicts, as a merge, rebase, revert or stash apply leaves them.
+fn conflicted_files(path: &str) -> Vec<String> {
+    run_git(path, &["diff", "--name-only", "--diff-filter=U", "-z"])
+        .unwrap_or_default()
+        .split('\0')
+        .filter(|f| !f.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Fails while a merge, rebase, cherry-pick or revert is in progress, where moving HEAD would
+/// leave it half done.
+fn refuse_during_operation(path: &str) -> Result<(), String> {
+    let busy = ["MERGE_HEAD", "CHERRY_PICK_HEAD", "REVERT_HEAD", "rebase-merge", "rebase-apply"]
+        .iter()
+        .any(|name| git_path(path, name).map(|p| p.exists()).unwrap_or(false));
+    match busy {
+        true => Err("Finish or abort the merge, rebase, cherry-pick or revert in progress first".to_string()),
+        false => Ok(()),
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct UndoneCommit {
+    hash: String,
+    /// Full message, to put back into the message box
+    message: String,
+}
+
+/// Undoes the last commit with `reset --soft`, so its changes end up staged again; for the
+/// first commit the branch goes back to unborn. A commit that is already on a remote needs
+/// `allow_pushed`, since the next push would then have to rewrite it. Refused while a merge,
+/// rebase, cherry-pick or revert is in progress.
+#[tauri::command]
+fn undo_last_commit(
+    path: &str,
+    allow_pushed: Option<bool>,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<UndoneCommit, CommandError> {
+    repos.ensure_writable(path)?;
+    require_commits(path)?;
+    refuse_during_operation(path)?;
+    if !allow_pushed.unwrap_or(false) {
+        let remotes = remotes_containing_head(path);
+        if !remotes.is_empty() {
+            return Err(CommandError::UndoingPushedCommit { remotes });
+        }
+    }
+
+    let hash = run_git(path, &["rev-parse", "HEAD"])?.trim().to_string();
+    let message = run_git(path, &["log", "-1", "--format=%B", "HEAD"])?.trim_end().to_string();
+    let has_parent = run_git(path, &["rev-parse", "--verify", "-q", "HEAD~1"]).is_ok();
+    let result = match has_parent {
+        true => run_git(path, &["reset", "--soft", "HEAD~1"]),
+        // Deleting the branch ref makes it unborn again; the index keeps the files staged
+        false => run_git(path, &["update-ref", "-d", "HEAD"]),
+    };
+    activity.record(path, Operation::new(format!("Undo commit \"{}\"", first_line(&message, 72))), &result);
+    result?;
+    Ok(UndoneCommit { hash, message })
+}
+
+#[derive(Serialize, Debug, Default)]
+struct CommitResult {
+    hash: String,
+    short_hash: String,
+    /// First line of the message
+    subject: String,
+    /// Files the commit touches, counting both sides of a rename once
+    files_committed: usize,
+    /// Submodules whose recorded commit this commit moved, with old and new short hashes
+    submodule_bumps: Vec<submodules::SubmoduleBump>,
+}
+
+impl CommitResult {
+    /// Describes the commit HEAD now points at. The commit already exists at this point, so
+    /// a failing lookup leaves fields empty instead of turning the commit into an error.
+    fn for_head(path: &str) -> Self {
+        let mut result = CommitResult { submodule_bumps: submodules::bumps_in_head(path), ..Default::default() };
+        if let Ok(out) = run_git(path, &["log", "-1", "--format=%H%x00%h%x00%s", "HEAD"]) {
+            let mut fields = out.trim_end_matches('\n').splitn(3, '\0');
+            result.hash = fields.next().unwrap_or_default().to_string();
+            result.short_hash = fields.next().unwrap_or_default().to_string();
+            result.subject = fields.next().unwrap_or_default().to_string();
+        }
+        if let Ok(out) = run_git(path, &["diff-tree", "--root", "--no-commit-id", "-r", "-M", "--name-only", "-z", "HEAD"]) {
+            result.files_committed = out.split('\0').filter(|f| !f.is_empty()).count();
+        }
+        result
+    }
+}
+
+/// Restages exactly `files` and commits them. Files that are partially staged are committed
+/// with their staged content only; their unstaged edits stay in the work tree. Changes staged
+/// for other files stay staged after the commit unless `reset_index` is set, and during a
+/// merge nothing is unstaged since the merge commit records the whole index. Selected files
+/// that still have merge conflicts are refused up front, and so are ignored files unless
+/// `force_ignored` is set. If anything fails after the index has been
+/// touched (a rejecting hook, missing identity), the index is put back the way it was and
+/// the error says so with `index_restored`. With `expected_fingerprint` (from
+/// `generate_ai_commit`) the commit is refused if the changes moved on since the message
+/// was generated. With `amend` the files and message replace the last commit instead; that
+/// is refused when the last commit was already pushed unless `amend_pushed` is set. `sign`
+/// overrides `commit.gpgsign`; when signing fails the error is gpg's own output. `trailers`
+/// (name, value) pairs such as `Co-authored-by` are appended to the message. `no_verify`
+/// skips the commit hooks; when a hook rejects the commit the error carries its output.
+/// `allow_empty` with no files makes a commit without changes. Plain commits go through
+/// libgit2 when it's chosen in settings or git is missing; amending, signing, trailers and
+/// commit hooks need git and fail with `GitNotInstalled` without it.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn commit_changes(
+    path: &str,
+    message: &str,
+    files: Vec<String>,
+    acknowledge_warnings: Option<bool>,
+    expected_fingerprint: Option<String>,
+    force_ignored: Option<bool>,
+    amend: Option<bool>,
+    amend_pushed: Option<bool>,
+    sign: Option<SignMode>,
+    trailers: Option<Vec<(String, String)>>,
+    no_verify: Option<bool>,
+    allow_empty: Option<bool>,
+    reset_index: Option<bool>,
+    caps: State<'_, GitCapabilitiesState>,
+    repos: State<'_, ReadOnlyRepos>,
+    fingerprints: State<'_, DiffFingerprints>,
+    activity: State<'_, ActivityLog>,
+) -> Result<CommitResult, CommandError> {
+    if repos.is_read_only(path) {
+        return Err(CommandError::ReadOnlyRepository { path: repo_key(path) });
+    }
+    let amend = amend.unwrap_or(false);
+    let options = CommitOptions {
+        amend,
+        sign: sign.unwrap_or_default(),
+        no_verify: no_verify.unwrap_or(false),
+        allow_empty: allow_empty.unwrap_or(false),
+        reset_index: reset_index.unwrap_or(false),
+    };
+    let files: Vec<String> = files.iter().map(|file| decode_path(path, file)).collect();
+    // Falls back to git for what the chosen backend can't do, or fails with GitNotInstalled
+    let backend = backend::for_commit(path, &options, &files)?;
+    backend.ensure_identity(path)?;
+
+    if let Some(expected) = expected_fingerprint {
+        fingerprints.verify(path, &expected)?;
+    }
+
+    if amend {
+        require_commits(path)?;
+        let remotes = remotes_containing_head(path);
+        if !remotes.is_empty() && !amend_pushed.unwrap_or(false) {
+            return Err(CommandError::AmendingPushedCommit { remotes });
+        }
+    }
+
+    let conflicted: Vec<String> = backend
+        .status(path, false)?
+        .into_iter()
+        .filter(|f| f.conflicted && files.contains(&f.path))
+        .map(|f| f.path)
+        .collect();
+    if !conflicted.is_empty() {
+        return Err(CommandError::UnresolvedConflicts { files: conflicted });
+    }
+
+    let force_ignored = force_ignored.unwrap_or(false);
+    if !force_ignored {
+        let ignored = backend.ignored(path, &files);
+        if !ignored.is_empty() {
+            return Err(CommandError::IgnoredFilesSelected { files: ignored });
+        }
+    }
+
+    if !acknowledge_warnings.unwrap_or(false) {
+        let warnings = commit_checks::analyze_commit_selection(path, files.clone())?;
+        if !warnings.is_empty() {
+            return Err(CommandError::CommitWarnings { warnings });
+        }
+    }
+
+    let message = &trailers::append(path, message, &trailers.unwrap_or_default())?;
+    let verb = if amend { "Amend" } else { "Commit" };
+    let mut op = Operation::new(format!("{} \"{}\"", verb, first_line(message, 72)));
+    let request = CommitRequest { message, files, force_ignored, options, caps: caps.inner() };
+    let result = backend.commit(path, request, &mut op);
+    activity.record(path, op, &result);
+    result
+}
+
+/// A commit `commit_changes` has checked and is handing to a backend.
+struct CommitRequest<'a> {
+    message: &'a str,
+    files: Vec<String>,
+    force_ignored: bool,
+    options: CommitOptions,
+    /// Only git looks at these, and only once it commits
+    caps: &'a GitCapabilitiesState,
+}
+
+/// `GitBackend::commit` through the git executable: restages the selection and runs `git
+/// commit`. If anything fails after the index has been touched it's put back as it was.
+fn commit_with_git(path: &str, request: CommitRequest, op: &mut Operation) -> Result<CommitResult, CommandError> {
+    let caps = request.caps.get()?;
+    let snapshot = IndexSnapshot::take(path)?;
+    let CommitRequest { message, files, force_ignored, options, .. } = request;
+    stage_and_commit(path, message, files, force_ignored, options, &caps, op).map_err(|message| {
+        let index_restored = snapshot.restore();
+        let restored: Result<(), &str> = if index_restored { Ok(()) } else { Err("index could not be restored") };
+        op.step("Restore previous staging", &restored);
+        CommandError::CommitFailed { message, index_restored }
+    })?;
+    Ok(CommitResult::for_head(path))
+}
+
+#[derive(Serialize, Debug)]
+struct GeneratedCommit {
+    message: String,
+    /// Pass back to `commit_changes` as `expected_fingerprint` to catch edits made since
+    diff_fingerprint: String,
+}
+
 #[tauri::command]
-async fn generate_ai_commit(mut diff: String, config: AiConfig) -> Result<String, String> {
+async fn generate_ai_commit(
+    path: String,
+    scope: DiffScope,
+    config: AiConfig,
+    template: Option<String>,
+    fingerprints: State<'_, DiffFingerprints>,
+    settings: State<'_, settings::SettingsService>,
+) -> Result<GeneratedCommit, String> {
+    let saved = settings.get().await?;
+    let new_file_limit = saved
+        .get(diff::NEW_FILE_LIMIT_KEY)
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or(diff::DEFAULT_NEW_FILE_LIMIT_KB);
+    let ignore_whitespace = saved
+        .get(diff::IGNORE_WHITESPACE_KEY)
+        .and_then(serde_json::Value::as_bool)
+        .unwrap_or(false);
+    let (path, scope, diff, prompt_diff, vocabulary) = tauri::async_runtime::spawn_blocking(move || {
+        let diff = diff::collect_diff(&path, &scope)?;
+        // The fingerprint is always taken from the plain diff, which is what commit_changes
+        // checks; the prompt gets more context so the model sees the enclosing functions
+        let options = DiffOptions {
+            ignore_whitespace,
+            context_lines: Some(AI_CONTEXT_LINES),
+            ..Default::default()
+        };
+        let prompt_diff = backend::active().diff(&path, &scope, options)?;
+        let vocabulary = commit_vocabulary::load(&path, &saved)?;
+        Ok::<_, String>((path, scope, diff, prompt_diff, vocabulary))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    if diff.trim().is_empty() {
+        return Err("There are no changes to describe; type the commit message yourself".to_string());
+    }
+    let diff_fingerprint = fingerprints.record(&path, &scope, &diff);
+    let diff = diff::limit_new_files(&prompt_diff, new_file_limit as usize * 1024);
+    let mut diff = submodules::describe_submodule_bumps(&path, &diff);
+
     // Dynamically truncate diff based on provider's typical context limits
     let max_len = match config.provider.as_str() {
         "lmstudio" => 10_000, // ~2.5k tokens, safe for 4k context windows
@@ -188,225 +1223,38 @@ async fn generate_ai_commit(mut diff: String, config: AiConfig) -> Result<String
         _ => 40_000,          // Cloud models have massive windows
     };
 
-    if diff.len() > max_len {
-        diff.truncate(max_len);
-        diff.push_str("\n... [Diff truncated due to length limitations]");
-    }
+    truncate_diff(&mut diff, max_len);
 
+    // The repo's commit.template, when the message box started from one
+    let structure = match template.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        Some(template) => format!("\nFollow this structure for the message, filling in its sections:\n{}\n", template),
+        None => String::new(),
+    };
     let prompt = format!(
         "You are an expert developer inspecting a git diff. Generate a concise, conventional commit message summarizing the changes.
 Return ONLY the commit message (in the format '<type>: <subject>') without any markdown ticks, conversational text, extra explanations, or quotes.
-The <type> MUST be one of the following: feat, fix, docs, style, refactor, perf, test, build, ci, chore, revert.
+{}{}
 
 Diff to analyze:
 {}", 
+        commit_vocabulary::prompt_instructions(&vocabulary),
+        structure,
         diff
     );
 
-    let client = Client::builder()
-        .timeout(std::time::Duration::from_secs(45))
-        .build()
-        .map_err(|e| e.to_string())?;
-        
-    match config.provider.as_str() {
-        "ollama" => {
-            let req_body = OllamaRequest {
-                model: config.model,
-                prompt,
-                stream: false,
-            };
-
-            let res = client.post("http://localhost:11434/api/generate")
-                .json(&req_body)
-                .send()
-                .await
-                .map_err(|e| format!("Failed to connect to local Ollama (is it running on port 11434?): {}", e))?;
-
-            if !res.status().is_success() {
-                return Err(format!("Ollama API error: {}", res.status()));
-            }
-
-            let parsed: OllamaResponse = res.json()
-                .await
-                .map_err(|e| format!("Failed to parse Ollama response: {}", e))?;
-
-            Ok(parsed.response.trim().to_string())
-        }
-        "lmstudio" => {
-            let res = client.post("http://localhost:1234/v1/chat/completions")
-                .json(&serde_json::json!({
-                    "model": config.model,
-                    "messages": [{"role": "user", "content": prompt}]
-                }))
-                .send()
-                .await
-                .map_err(|e| format!("Failed to connect to local LM Studio (is it running on port 1234?): {}", e))?;
-                
-            if !res.status().is_success() {
-                let error_text = res.text().await.unwrap_or_default();
-                return Err(format!("LM Studio API error: {}", error_text));
-            }
-
-            let parsed: serde_json::Value = res.json()
-                .await
-                .map_err(|e| format!("Failed to parse LM Studio response: {}", e))?;
-                
-            if let Some(choices) = parsed.get("choices") {
-                if let Some(first_choice) = choices.get(0) {
-                    if let Some(message) = first_choice.get("message") {
-                        if let Some(content) = message.get("content") {
-                            return Ok(content.as_str().unwrap_or_default().trim().to_string());
-                        }
-                    }
-                }
-            }
-            Err("Unexpected response structure from LM Studio".to_string())
-        }
-        "openai" => {
-            let res = client.post("https://api.openai.com/v1/chat/completions")
-                .bearer_auth(config.api_key.unwrap_or_default())
-                .json(&serde_json::json!({
-                    "model": config.model,
-                    "messages": [{"role": "user", "content": prompt}]
-                }))
-                .send()
-                .await
-                .map_err(|e| format!("Failed to connect to OpenAI: {}", e))?;
-                
-            if !res.status().is_success() {
-                let error_text = res.text().await.unwrap_or_default();
-                return Err(format!("OpenAI API error: {} {}", error_text, "Check your API key."));
-            }
-
-            let parsed: serde_json::Value = res.json()
-                .await
-                .map_err(|e| format!("Failed to parse OpenAI response: {}", e))?;
-                
-            if let Some(choices) = parsed.get("choices") {
-                if let Some(first_choice) = choices.get(0) {
-                    if let Some(message) = first_choice.get("message") {
-                        if let Some(content) = message.get("content") {
-                            return Ok(content.as_str().unwrap_or_default().trim().to_string());
-                        }
-                    }
-                }
-            }
-            Err("Unexpected response structure from OpenAI".to_string())
-        }
-        "gemini" => {
-            let url = format!("https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}", 
-                config.model, config.api_key.unwrap_or_default());
-            
-            let res = client.post(&url)
-                .json(&serde_json::json!({
-                    "contents": [{"parts": [{"text": prompt}]}]
-                }))
-                .send()
-                .await
-                .map_err(|e| format!("Failed to connect to Gemini: {}", e))?;
-
-            if !res.status().is_success() {
-                let error_text = res.text().await.unwrap_or_default();
-                return Err(format!("Gemini API error: {} {}", error_text, "Check your API key."));
-            }
-
-            let parsed: serde_json::Value = res.json()
-                .await
-                .map_err(|e| format!("Failed to parse Gemini response: {}", e))?;
-                
-             if let Some(candidates) = parsed.get("candidates") {
-                if let Some(first_candidate) = candidates.get(0) {
-                    if let Some(content) = first_candidate.get("content") {
-                        if let Some(parts) = content.get("parts") {
-                            if let Some(first_part) = parts.get(0) {
-                                if let Some(text) = first_part.get("text") {
-                                    return Ok(text.as_str().unwrap_or_default().trim().to_string());
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            Err("Unexpected response structure from Gemini".to_string())
-        }
-        "anthropic" => {
-            let res = client.post("https://api.anthropic.com/v1/messages")
-                .header("x-api-key", config.api_key.unwrap_or_default())
-                .header("anthropic-version", "2023-06-01")
-                .header("content-type", "application/json")
-                .json(&serde_json::json!({
-                    "model": config.model,
-                    "max_tokens": 1024,
-                    "messages": [{"role": "user", "content": prompt}]
-                }))
-                .send()
-                .await
-                .map_err(|e| format!("Failed to connect to Anthropic: {}", e))?;
-                
-            if !res.status().is_success() {
-                let error_text = res.text().await.unwrap_or_default();
-                return Err(format!("Anthropic API error: {} {}", error_text, "Check your API key."));
-            }
-
-            let parsed: serde_json::Value = res.json()
-                .await
-                .map_err(|e| format!("Failed to parse Anthropic response: {}", e))?;
-                
-            if let Some(content_array) = parsed.get("content") {
-                if let Some(first_content) = content_array.get(0) {
-                    if let Some(text) = first_content.get("text") {
-                        return Ok(text.as_str().unwrap_or_default().trim().to_string());
-                    }
-                }
-            }
-            Err("Unexpected response structure from Anthropic".to_string())
-        }
-        "custom" => {
-            let base_url = config.custom_api_url.unwrap_or_else(|| "https://api.openai.com/v1".to_string());
-            let url = if base_url.ends_with("/chat/completions") {
-                base_url
-            } else if base_url.ends_with('/') {
-                format!("{}chat/completions", base_url)
-            } else {
-                format!("{}/chat/completions", base_url)
-            };
-
-            let res = client.post(&url)
-                .bearer_auth(config.api_key.unwrap_or_default())
-                .json(&serde_json::json!({
-                    "model": config.model,
-                    "messages": [{"role": "user", "content": prompt}]
-                }))
-                .send()
-                .await
-                .map_err(|e| format!("Failed to connect to Custom endpoint: {}", e))?;
-                
-            if !res.status().is_success() {
-                let error_text = res.text().await.unwrap_or_default();
-                return Err(format!("Custom API error: {} {}", error_text, "Check your URL and API key."));
-            }
+    let message = send_ai_prompt(config, prompt).await?;
+    Ok(GeneratedCommit { message, diff_fingerprint })
+}
 
-            let parsed: serde_json::Value = res.json()
-                .await
-                .map_err(|e| format!("Failed to parse Custom endpoint response: {}", e))?;
-                
-            if let Some(choices) = parsed.get("choices") {
-                if let Some(first_choice) = choices.get(0) {
-                    if let Some(message) = first_choice.get("message") {
-                        if let Some(content) = message.get("content") {
-                            return Ok(content.as_str().unwrap_or_default().trim().to_string());
-                        }
-                    }
-                }
-            }
-            Err("Unexpected response structure from Custom endpoint".to_string())
-        }
-        _ => Err("Unknown AI provider".to_string()),
-    }
+#[derive(Serialize)]
+struct LocalModel {
+    name: String,
+    /// Still downloading through `pull_ollama_model`, so not usable for generation yet
+    pulling: bool,
 }
 
 #[tauri::command]
-async fn get_ollama_models() -> Result<Vec<String>, String> {
+async fn get_ollama_models(pulls: State<'_, ollama_pull::OllamaPulls>) -> Result<Vec<LocalModel>, String> {
     let output = build_hidden_cmd("ollama")
         .arg("list")
         .output()
@@ -420,17 +1268,73 @@ async fn get_ollama_models() -> Result<Vec<String>, String> {
     let stdout = String::from_utf8_lossy(&output.stdout);
     let mut models = Vec::new();
 
+    let pulling = pulls.pulling();
+
     for line in stdout.lines().skip(1) {
         if let Some(model_name) = line.split_whitespace().next() {
             if !model_name.is_empty() {
-                models.push(model_name.to_string());
+                models.push(LocalModel {
+                    name: model_name.to_string(),
+                    pulling: pulling.iter().any(|p| ai::ollama::same_model(p, model_name)),
+                });
             }
         }
     }
+    // Models pulled for the first time don't show up in `ollama list` until they finish
+    for name in pulling {
+        if !models.iter().any(|m| ai::ollama::same_model(&m.name, &name)) {
+            models.push(LocalModel { name, pulling: true });
+        }
+    }
 
     Ok(models)
 }
 
+/// A fix `test_ai_connection` can offer for a failed check.
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Remediation {
+    /// The configured Ollama model isn't downloaded; `pull_ollama_model` fetches it
+    PullModel { model: String },
+}
+
+#[derive(Serialize)]
+struct ConnectionCheck {
+    ok: bool,
+    message: String,
+    remediation: Option<Remediation>,
+}
+
+/// Checks that the configured provider answers. For Ollama this also checks the model is
+/// installed, since generating with a missing model only gets back a bare 404.
+#[tauri::command]
+async fn test_ai_connection(config: AiConfig) -> Result<ConnectionCheck, String> {
+    if config.provider == "ollama" {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .map_err(|e| e.to_string())?;
+        let installed = match ai::ollama::installed_models(&client).await {
+            Ok(installed) => installed,
+            Err(e) => return Ok(ConnectionCheck { ok: false, message: e.to_string(), remediation: None }),
+        };
+        if !installed.iter().any(|m| ai::ollama::same_model(m, &config.model)) {
+            return Ok(ConnectionCheck {
+                ok: false,
+                message: format!("Ollama is running, but the model \"{}\" isn't downloaded yet", config.model),
+                remediation: Some(Remediation::PullModel { model: config.model }),
+            });
+        }
+        return Ok(ConnectionCheck { ok: true, message: format!("Connected to Ollama ({})", config.model), remediation: None });
+    }
+
+    let provider = config.provider.clone();
+    Ok(match send_ai_prompt(config, "Reply with the single word OK.".to_string()).await {
+        Ok(_) => ConnectionCheck { ok: true, message: format!("Connected to {}", provider), remediation: None },
+        Err(message) => ConnectionCheck { ok: false, message, remediation: None },
+    })
+}
+
 #[tauri::command]
 async fn get_openai_models(url: String, api_key: Option<String>) -> Result<Vec<String>, String> {
     let client = reqwest::Client::new();
@@ -520,44 +1424,16 @@ fn uninstall_context_menu() -> Result<(), String> {
 }
 
 #[tauri::command]
-fn init_repo(path: &str, remote_url: Option<String>) -> Result<(), String> {
-    // git init
-    let init_out = build_hidden_cmd("git")
-        .current_dir(path)
-        .args(["init"])
-        .output()
-        .map_err(|e| e.to_string())?;
-
-    if !init_out.status.success() {
-        return Err(format!("git init failed: {}", String::from_utf8_lossy(&init_out.stderr)));
-    }
-
-    // Optionally add remote
-    if let Some(url) = remote_url {
-        let trimmed = url.trim();
-        if !trimmed.is_empty() {
-            let remote_out = build_hidden_cmd("git")
-                .current_dir(path)
-                .args(["remote", "add", "origin", trimmed])
-                .output()
-                .map_err(|e| e.to_string())?;
-
-            if !remote_out.status.success() {
-                return Err(format!("git remote add failed: {}", String::from_utf8_lossy(&remote_out.stderr)));
-            }
-        }
-    }
-
-    Ok(())
+fn get_repo_root(path: &str) -> Result<String, String> {
+    backend::active().repo_root(path)
 }
 
-#[tauri::command]
-fn get_repo_root(path: &str) -> Result<String, String> {
+fn git_repo_root(path: &str) -> Result<String, String> {
     let output = build_hidden_cmd("git")
         .current_dir(path)
         .args(["rev-parse", "--show-toplevel"])
         .output()
-        .map_err(|e| e.to_string())?;
+        .map_err(spawn_error)?;
 
     if !output.status.success() {
         return Err(String::from_utf8_lossy(&output.stderr).to_string());
@@ -566,83 +1442,75 @@ fn get_repo_root(path: &str) -> Result<String, String> {
     Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
 pub struct SyncStatus {
+    /// Commits to push: ahead of the upstream branch, or every commit when there is none yet
     ahead: u32,
+    /// Upstream commits not in HEAD, as of the last fetch
+    behind: u32,
     has_upstream: bool,
+    /// Upstream branch such as `origin/main`; null when the branch tracks nothing
+    upstream: Option<String>,
     branch: String,
+    has_commits: bool,
+    /// Unix time of the last fetch by anything (the mtime of `FETCH_HEAD`); null if never fetched
+    last_fetch: Option<i64>,
+    has_remote: bool,
 }
 
+/// Where the current branch stands against its upstream. Only local refs are read, never the
+/// network, so it's cheap enough for every status refresh; a branch without an upstream and a
+/// repository without remotes are ordinary results rather than errors.
 #[tauri::command]
 fn get_sync_status(path: &str) -> Result<SyncStatus, String> {
-    // Get current branch name
-    let branch_out = build_hidden_cmd("git")
-        .current_dir(path)
-        .args(["rev-parse", "--abbrev-ref", "HEAD"])
-        .output()
-        .map_err(|e| e.to_string())?;
-
-    let branch = if branch_out.status.success() {
-        String::from_utf8_lossy(&branch_out.stdout).trim().to_string()
-    } else {
-        "main".to_string()
-    };
-
-    // Try to get ahead count — this fails if no upstream is set
-    let ahead_out = build_hidden_cmd("git")
-        .current_dir(path)
-        .args(["rev-list", "--count", "@{u}..HEAD"])
-        .output();
-
-    match ahead_out {
-        Ok(output) if output.status.success() => {
-            let count_str = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            let ahead = count_str.parse::<u32>().unwrap_or(0);
-            Ok(SyncStatus { ahead, has_upstream: true, branch })
-        }
-        _ => {
-            // No upstream configured (new branch or never pushed)
-            // Count all commits to know if there's something to push
-            let count_out = build_hidden_cmd("git")
-                .current_dir(path)
-                .args(["rev-list", "--count", "HEAD"])
-                .output();
-
-            let ahead = match count_out {
-                Ok(o) if o.status.success() => {
-                    String::from_utf8_lossy(&o.stdout).trim().parse::<u32>().unwrap_or(0)
-                }
-                _ => 0,
-            };
-
-            Ok(SyncStatus { ahead, has_upstream: false, branch })
-        }
-    }
+    backend::active().sync_status(path)
 }
 
-#[tauri::command]
-fn push_changes(path: &str, set_upstream: Option<bool>, branch: Option<String>) -> Result<(), String> {
-    let mut args = vec!["push".to_string()];
+fn git_sync_status(path: &str) -> Result<SyncStatus, String> {
+    let has_remote = run_git(path, &["remote"]).is_ok_and(|remotes| !remotes.trim().is_empty());
+    let last_fetch = branches::last_fetch_time(path).map(|time| time as i64);
 
-    if set_upstream.unwrap_or(false) {
-        args.push("-u".to_string());
-        args.push("origin".to_string());
-        if let Some(b) = branch {
-            args.push(b);
-        }
+    // An unborn branch has a name but nothing to count or push yet
+    if !has_commits(path) {
+        let branch = run_git(path, &["symbolic-ref", "--short", "-q", "HEAD"])
+            .map(|b| b.trim().to_string())
+            .unwrap_or_else(|_| "main".to_string());
+        return Ok(SyncStatus { branch, last_fetch, has_remote, ..Default::default() });
     }
 
-    let arg_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
-    let out = build_hidden_cmd("git")
-        .current_dir(path)
-        .args(&arg_refs)
-        .output()
-        .map_err(|e| e.to_string())?;
+    let branch = run_git(path, &["rev-parse", "--abbrev-ref", "HEAD"])
+        .map(|b| b.trim().to_string())
+        .unwrap_or_else(|_| "main".to_string());
+
+    // Fails when nothing is tracked (new branch, detached HEAD) or the upstream was deleted
+    let upstream = run_git(path, &["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{upstream}"])
+        .ok()
+        .map(|u| u.trim().to_string())
+        .filter(|u| !u.is_empty());
+
+    let (ahead, behind) = match upstream {
+        Some(_) => {
+            let counts = run_git(path, &["rev-list", "--left-right", "--count", "HEAD...@{upstream}"])?;
+            let mut counts = counts.split_whitespace().map(|n| n.parse::<u32>().unwrap_or(0));
+            (counts.next().unwrap_or(0), counts.next().unwrap_or(0))
+        }
+        // Never pushed, so every commit is still to push
+        None => {
+            let count = run_git(path, &["rev-list", "--count", "HEAD"]).ok();
+            (count.and_then(|c| c.trim().parse().ok()).unwrap_or(0), 0)
+        }
+    };
 
-    if !out.status.success() {
-        return Err(String::from_utf8_lossy(&out.stderr).to_string());
-    }
-    Ok(())
+    Ok(SyncStatus {
+        ahead,
+        behind,
+        has_upstream: upstream.is_some(),
+        upstream,
+        branch,
+        has_commits: true,
+        last_fetch,
+        has_remote,
+    })
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -652,21 +1520,531 @@ pub fn run() {
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_store::Builder::new().build())
         .plugin(tauri_plugin_opener::init())
+        .manage(GitCapabilitiesState::default())
+        .manage(commit_groups::CommitGroupsTask::default())
+        .manage(danger::ConfirmationTokens::default())
+        .manage(DiffFingerprints::default())
+        .manage(ollama_pull::OllamaPulls::default())
+        .manage(clone::CloneTasks::default())
+        .manage(auto_fetch::AutoFetch::default())
+        .setup(|app| {
+            // Same file the store plugin uses, so settings saved by older versions carry over
+            let data_dir = app.path().app_data_dir()?;
+            let settings_file = data_dir.join("settings.json");
+            let settings_service = settings::SettingsService::start(settings_file);
+            let saved = tauri::async_runtime::block_on(settings_service.get())?;
+            app.manage(ReadOnlyRepos::from_settings(&saved));
+            backend::load_preference(&saved);
+            app.manage(settings_service);
+            app.manage(ActivityLog::new(data_dir.join("activity.json")));
+            auto_fetch::AutoFetch::spawn(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             get_git_status,
+            get_file_diff,
+            get_git_diff_split,
+            is_working_tree_dirty,
             get_git_diff,
             commit_changes,
+            get_last_commit_message,
+            undo_last_commit,
+            reset::reset_to_commit,
+            revert::revert_commit,
+            revert::revert_continue,
+            revert::revert_abort,
+            cherry_pick::cherry_pick,
+            cherry_pick::cherry_pick_continue,
+            cherry_pick::cherry_pick_skip,
+            cherry_pick::cherry_pick_abort,
+            merge::merge_branch,
+            merge::merge_continue,
+            merge::merge_abort,
+            repo_state::get_repo_operation_state,
+            rebase::rebase_continue,
+            rebase::rebase_skip,
+            rebase::rebase_abort,
+            rebase::get_rebase_plan,
+            rebase::execute_rebase_plan,
+            gitignore::add_to_gitignore,
+            file_content::get_file_at_head,
+            file_content::get_file_at_revision,
+            search::search_working_tree,
+            stats::get_repo_stats,
+            commit_template::get_commit_template,
+            index_lock::remove_index_lock,
+            conflicts::resolve_conflict,
+            conflicts::mark_resolved,
+            conflicts::get_conflict_versions,
+            conflicts::save_resolved_file,
+            web_url::get_repo_web_url,
+            web_url::get_commit_web_url,
+            web_url::get_file_web_url,
+            tags::get_tags,
+            tags::create_tag,
+            tags::push_tag,
+            tags::delete_tag,
+            reflog::get_reflog,
+            reflog::checkout_reflog_entry,
+            clean::clean_preview,
+            clean::clean_execute,
+            worktrees::get_worktrees,
+            worktrees::add_worktree,
+            worktrees::remove_worktree,
+            repo_validation::validate_repo,
+            clone::clone_repo,
+            clone::cancel_clone,
+            signing::get_signing_config,
+            trailers::get_recent_coauthors,
+            commit_checks::analyze_commit_selection,
             generate_ai_commit,
             get_ollama_models,
+            test_ai_connection,
+            ollama_pull::pull_ollama_model,
+            ollama_pull::cancel_ollama_pull,
+            auto_fetch::watch_auto_fetch,
+            auto_fetch::unwatch_auto_fetch,
+            status_summary::get_status_summary,
+            commit_vocabulary::get_commit_vocabulary,
+            commit_vocabulary::lint_commit_message,
+            patch_commit::commit_patch,
+            submodules::get_submodule_status,
+            submodules::update_submodules,
             get_startup_dir,
             install_context_menu,
             uninstall_context_menu,
             get_repo_root,
-            push_changes,
+            push::push,
+            pull::fetch,
+            pull::pull,
             get_sync_status,
-            init_repo,
-            get_openai_models
+            release::get_release_info,
+            read_only::get_read_only_status,
+            read_only::set_read_only,
+            init::init_repo,
+            init::get_gitignore_templates,
+            get_openai_models,
+            branches::get_branches,
+            branches::get_current_branch,
+            branches::refresh_remote_branches,
+            branches::checkout_remote_branch,
+            branches::validate_branch_name,
+            branches::create_branch,
+            branches::checkout_branch,
+            hooks::get_hooks,
+            hunks::stage_hunks,
+            hunks::unstage_hunks,
+            staging::stage_file,
+            staging::unstage_file,
+            staging::stage_all,
+            staging::unstage_all,
+            stash::stash_list,
+            stash::stash_save,
+            stash::stash_apply,
+            stash::stash_pop,
+            stash::stash_drop,
+            health::check_repo_health,
+            capabilities::get_git_capabilities,
+            capabilities::get_git_version,
+            summary::summarize_commits,
+            settings::get_settings,
+            settings::update_settings,
+            snippets::list_snippets,
+            snippets::save_snippet,
+            snippets::render_snippet,
+            commit_groups::suggest_commit_groups,
+            commit_groups::cancel_commit_groups,
+            git_config::get_git_config,
+            git_config::set_git_config,
+            git_config::get_git_identity,
+            git_config::set_git_identity,
+            activity::get_activity_log,
+            activity::clear_activity_log,
+            discard::discard_changes,
+            danger::confirm_dangerous_operation,
+            structured_diff::get_structured_diff,
+            diff_stats::get_diff_stats,
+            word_diff::get_word_diff,
+            backend::get_repo_info,
+            backend::set_git_backend,
+            history::get_commit_history,
+            history::get_git_log,
+            history::get_commit_graph,
+            history::get_commit,
+            history::get_commit_diff,
+            history::search_history,
+            blame::get_blame
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{commit_selection, TestRepo};
+
+    fn plain_commit() -> CommitOptions {
+        CommitOptions { amend: false, sign: SignMode::Never, no_verify: false, allow_empty: false, reset_index: false }
+    }
+
+    fn commit_files(repo: &TestRepo, files: &[&str]) -> Result<CommitResult, CommandError> {
+        let caps = GitCapabilitiesState::default();
+        let request = CommitRequest {
+            message: "Commit the selection",
+            files: files.iter().map(|f| f.to_string()).collect(),
+            force_ignored: false,
+            options: plain_commit(),
+            caps: &caps,
+        };
+        commit_with_git(repo.path(), request, &mut Operation::new("Commit"))
+    }
+
+    /// `a.txt` changed but unstaged, `b.txt` partially staged and `c.txt` newly staged.
+    fn mixed_staging() -> TestRepo {
+        let repo = TestRepo::with_commit();
+        repo.write("b.txt", "b1\n");
+        repo.commit_all("Add b");
+        repo.write("a.txt", "two\n");
+        repo.write("b.txt", "b2\n");
+        repo.git(&["add", "b.txt"]);
+        repo.write("b.txt", "b3\n");
+        repo.write("c.txt", "c\n");
+        repo.git(&["add", "c.txt"]);
+        repo
+    }
+
+    const H1: &str = "257cc5642cb1a054f08cc83f2d943e56fd3ebe99";
+    const H2: &str = "5716ca5987cbf97d6bb54920bea6adde242d87e6";
+
+    #[test]
+    fn porcelain_v2_renames_conflicts_and_odd_paths() {
+        let out = [
+            format!("2 R. N... 100644 100644 100644 {H1} {H1} R100 new name.txt"),
+            "old name.txt".to_string(),
+            format!("u UU N... 100644 100644 100644 100644 {H1} {H2} {H1} both modified.txt"),
+            format!("1 .M N... 100644 100644 100644 {H1} {H1} line\nbreak.txt"),
+            format!("2 R. N... 100644 100644 100644 {H1} {H1} R100 say \"hi\".txt"),
+            "naïve \"draft\".txt".to_string(),
+            format!("1 A. N... 000000 100644 100644 {H1} {H2} Größe/日本語 🚀.txt"),
+            "? untracked dir/".to_string(),
+            "! target/".to_string(),
+        ]
+        .join("\0")
+            + "\0";
+
+        let files = parse_porcelain_v2(&out);
+        let summary: Vec<_> = files
+            .iter()
+            .map(|f| (f.path.as_str(), f.status.as_str(), f.staged, f.old_path.as_deref(), f.conflicted))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("new name.txt", "R", true, Some("old name.txt"), false),
+                ("both modified.txt", "X", false, None, true),
+                ("line\nbreak.txt", "M", false, None, false),
+                ("say \"hi\".txt", "R", true, Some("naïve \"draft\".txt"), false),
+                ("Größe/日本語 🚀.txt", "A", true, None, false),
+                ("untracked dir/", "U", false, None, false),
+                ("target/", "I", false, None, false),
+            ]
+        );
+        assert_eq!((files[1].index_status.as_deref(), files[1].worktree_status.as_deref()), (Some("U"), Some("U")));
+    }
+
+    #[test]
+    fn partially_staged_files_keep_both_columns() {
+        let zeros = "0".repeat(40);
+        let out = format!(
+            "1 MM N... 100644 100644 100644 {H1} {H2} b.txt\0\
+             1 AM N... 000000 100644 100644 {zeros} {H1} d.txt\0\
+             1 MD N... 100644 100644 000000 {H1} {H2} e.txt\0"
+        );
+        let columns: Vec<_> = parse_porcelain_v2(&out)
+            .iter()
+            .map(|f| (f.path.clone(), f.status.clone(), f.staged, f.index_status.clone(), f.worktree_status.clone()))
+            .collect();
+        let column = |path: &str, status: &str, index: &str, worktree: &str| {
+            (path.to_string(), status.to_string(), true, Some(index.to_string()), Some(worktree.to_string()))
+        };
+        assert_eq!(columns, [column("b.txt", "M", "M", "M"), column("d.txt", "A", "A", "M"), column("e.txt", "D", "M", "D")]);
+
+        let repo = mixed_staging();
+        // d.txt is added, then edited again; e.txt has a staged edit and is then deleted
+        repo.write("d.txt", "d1\n");
+        repo.git(&["add", "d.txt"]);
+        repo.write("d.txt", "d2\n");
+        repo.write("e.txt", "e1\n");
+        repo.git(&["add", "e.txt"]);
+        repo.git(&["commit", "-q", "-m", "Add e", "--", "e.txt"]);
+        repo.write("e.txt", "e2\n");
+        repo.git(&["add", "e.txt"]);
+        std::fs::remove_file(std::path::Path::new(repo.path()).join("e.txt")).unwrap();
+        let files = git_status(repo.path(), false).unwrap();
+        let columns: Vec<_> = files
+            .iter()
+            .map(|f| (f.path.as_str(), f.status.as_str(), f.staged, f.index_status.as_deref(), f.worktree_status.as_deref()))
+            .collect();
+        assert_eq!(
+            columns,
+            [
+                ("a.txt", "M", false, None, Some("M")),
+                ("b.txt", "M", true, Some("M"), Some("M")),
+                ("c.txt", "A", true, Some("A"), None),
+                ("d.txt", "A", true, Some("A"), Some("M")),
+                ("e.txt", "D", true, Some("M"), Some("D")),
+            ]
+        );
+    }
+
+    #[test]
+    fn non_ascii_paths_are_not_quoted() {
+        let repo = TestRepo::with_commit();
+        repo.git(&["config", "core.quotepath", "true"]);
+        repo.write("café.txt", "new\n");
+        repo.write("a.txt", "two\n");
+        repo.git(&["add", "a.txt", "café.txt"]);
+
+        let paths: Vec<String> = git_status(repo.path(), false).unwrap().into_iter().map(|f| f.path).collect();
+        assert_eq!(paths, ["a.txt", "café.txt"]);
+        let staged = diff::file_diff(repo.path(), None, true, DiffOptions::default()).unwrap();
+        assert!(staged.contains("+++ b/café.txt"), "{}", staged);
+
+        // Umlauts and emoji make it through status, diff and commit unescaped as well
+        let names = ["Größe.txt", "🚀 launch.txt"];
+        for name in names {
+            repo.write(name, "new\n");
+        }
+        repo.git(&["add", "--", names[0], names[1]]);
+        let status: Vec<_> = get_git_status(repo.path(), None).unwrap().into_iter().map(|f| (f.path, f.status)).collect();
+        for name in names {
+            assert!(status.contains(&(name.to_string(), "A".to_string())), "{:?}", status);
+        }
+        let diff = get_git_diff(repo.path(), names.map(String::from).to_vec(), None, None).unwrap();
+        for name in names {
+            assert!(diff.contains(&format!("diff --git a/{0} b/{0}\n", name)), "{}", diff);
+        }
+        let app = repo.app();
+        assert_eq!(commit_selection(&app, repo.path(), &names, false).unwrap().files_committed, 2);
+        let committed = repo.git(&["-c", "core.quotepath=true", "ls-tree", "-z", "--name-only", "HEAD"]);
+        assert_eq!(committed.split_terminator('\0').collect::<Vec<_>>(), ["Größe.txt", "a.txt", "🚀 launch.txt"]);
+        let paths: Vec<String> = git_status(repo.path(), false).unwrap().into_iter().map(|f| f.path).collect();
+        assert_eq!(paths, ["a.txt", "café.txt"]);
+
+        assert_eq!(decode_path(repo.path(), r#""caf\303\251.txt""#), "café.txt");
+        assert_eq!(decode_path(repo.path(), "café.txt"), "café.txt");
+        // A file literally named with quotes and escapes is taken at its word (Windows forbids
+        // quotes in file names)
+        #[cfg(unix)]
+        {
+            repo.write(r#""odd\303.txt""#, "odd\n");
+            assert_eq!(decode_path(repo.path(), r#""odd\303.txt""#), r#""odd\303.txt""#);
+        }
+    }
+
+    // Tabs, newlines and double quotes can't go in Windows file names
+    #[cfg(unix)]
+    #[test]
+    fn porcelain_v2_from_a_real_repository() {
+        let repo = TestRepo::with_commit();
+        repo.write("b c.txt", "b\n");
+        repo.write("naïve \"draft\".txt", "draft\n");
+        repo.commit_all("Add b c and the draft");
+        repo.git(&["mv", "b c.txt", "d\te.txt"]);
+        repo.git(&["mv", "naïve \"draft\".txt", "say \"hi\".txt"]);
+        repo.write("new\nline.txt", "new\n");
+        repo.write("Größe 🚀.txt", "new\n");
+
+        let files = git_status(repo.path(), false).unwrap();
+        let summary: Vec<_> = files.iter().map(|f| (f.path.as_str(), f.status.as_str(), f.old_path.as_deref())).collect();
+        assert_eq!(
+            summary,
+            [
+                ("d\te.txt", "R", Some("b c.txt")),
+                ("say \"hi\".txt", "R", Some("naïve \"draft\".txt")),
+                ("Größe 🚀.txt", "U", None),
+                ("new\nline.txt", "U", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn truncation_never_splits_a_character() {
+        // Headers only, so nothing can be trimmed and the cut lands wherever the limit falls
+        let diff = "diff --git a/日本語.txt b/日本語.txt\nBinary files differ 🎉\n".repeat(20);
+        for max_len in 1..120 {
+            let mut truncated = diff.clone();
+            truncate_diff(&mut truncated, max_len);
+            let (kept, note) = truncated.split_once("\n... [Diff truncated").unwrap();
+            assert!(kept.len() <= max_len && kept.len() + 3 >= max_len, "{}: {:?}", max_len, kept);
+            assert!(diff.starts_with(kept));
+            assert!(note.contains(&format!("{} bytes omitted", diff.len() - kept.len())), "{}", note);
+        }
+
+        let mut short = diff.clone();
+        truncate_diff(&mut short, diff.len());
+        assert_eq!(short, diff);
+    }
+
+    #[test]
+    fn optional_locks_are_skipped_for_reads_only() {
+        assert!(capabilities::supports_no_optional_locks());
+        assert_eq!(git_args(&["status", "--porcelain=v2"]), ["--no-optional-locks", "status", "--porcelain=v2"]);
+        assert_eq!(
+            git_args(&["-c", "core.quotepath=false", "diff", "--cached"]),
+            ["--no-optional-locks", "-c", "core.quotepath=false", "diff", "--cached"]
+        );
+        assert_eq!(git_args(&["-C", "sub", "--no-pager", "log", "-1"])[0], "--no-optional-locks");
+
+        for write in [
+            &["commit", "-m", "status"][..],
+            &["-c", "core.quotepath=false", "add", "--", "diff"],
+            &["reset", "-q", "--", "log"],
+            &["-c", "status", "checkout"],
+            &["stash", "show"],
+            &["--version"],
+        ] {
+            assert_eq!(git_args(write), write, "{:?}", write);
+        }
+    }
+
+    #[test]
+    fn working_tree_dirty_states() {
+        let repo = TestRepo::with_commit();
+        assert_eq!(is_working_tree_dirty(repo.path()).unwrap(), WorkingTreeState::Clean);
+
+        repo.write("new.txt", "untracked\n");
+        assert_eq!(is_working_tree_dirty(repo.path()).unwrap(), WorkingTreeState::DirtyWithUntracked);
+
+        std::fs::remove_file(std::path::Path::new(repo.path()).join("new.txt")).unwrap();
+        repo.write("a.txt", "changed\n");
+        assert_eq!(is_working_tree_dirty(repo.path()).unwrap(), WorkingTreeState::DirtyTracked);
+
+        repo.git(&["add", "a.txt"]);
+        assert_eq!(is_working_tree_dirty(repo.path()).unwrap(), WorkingTreeState::DirtyTracked);
+
+        // A merge stopped on conflicts, then one resolved to exactly what HEAD has: still in
+        // progress, so still dirty even with nothing left to diff
+        repo.commit_all("Change a on main");
+        repo.git(&["checkout", "-q", "-b", "other", "HEAD~1"]);
+        repo.write("a.txt", "other\n");
+        repo.commit_all("Change a on other");
+        repo.git(&["checkout", "-q", "main"]);
+        assert!(run_git(repo.path(), &["merge", "other"]).is_err());
+        assert_eq!(repo.short_status(), "UU a.txt\n");
+        assert_eq!(is_working_tree_dirty(repo.path()).unwrap(), WorkingTreeState::DirtyTracked);
+
+        repo.git(&["checkout", "-q", "--ours", "a.txt"]);
+        repo.git(&["add", "a.txt"]);
+        assert_eq!(repo.short_status(), "");
+        assert_eq!(is_working_tree_dirty(repo.path()).unwrap(), WorkingTreeState::DirtyTracked);
+    }
+
+    #[test]
+    fn first_commit_on_an_unborn_branch() {
+        let repo = TestRepo::init();
+        repo.write("a.txt", "one\n");
+        repo.git(&["add", "a.txt"]);
+
+        assert!(!has_commits(repo.path()));
+        assert!(matches!(get_last_commit_message(repo.path()), Err(CommandError::NoCommitsYet)));
+        let sync = git_sync_status(repo.path()).unwrap();
+        assert!(!sync.has_commits);
+        assert_eq!(sync.branch, "main");
+        assert_eq!((sync.ahead, sync.has_upstream), (0, false));
+
+        let status = git_status(repo.path(), false).unwrap();
+        assert_eq!(status.len(), 1);
+        assert_eq!((status[0].path.as_str(), status[0].status.as_str(), status[0].staged), ("a.txt", "A", true));
+        let staged = diff::file_diff(repo.path(), Some("a.txt"), true, DiffOptions::default()).unwrap();
+        assert!(staged.contains("+one"), "{}", staged);
+        let prompt = diff::collect_git_diff(repo.path(), &DiffScope::Staged, DiffOptions::default()).unwrap();
+        assert!(prompt.contains("+one"), "{}", prompt);
+
+        commit_files(&repo, &["a.txt"]).unwrap();
+
+        assert!(has_commits(repo.path()));
+        assert_eq!(repo.git(&["show", "HEAD:a.txt"]), "one\n");
+        assert_eq!(repo.short_status(), "");
+        let sync = git_sync_status(repo.path()).unwrap();
+        assert!(sync.has_commits);
+        assert_eq!(sync.ahead, 1);
+    }
+
+    #[test]
+    fn flagged_files_need_the_warnings_acknowledged() {
+        let repo = TestRepo::with_commit();
+        let head = repo.head();
+        repo.write(".env", "API_KEY=secret\n");
+        repo.write("a.txt", "two\n");
+
+        match commit_selection(&repo.app(), repo.path(), &["a.txt", ".env"], false) {
+            Err(CommandError::CommitWarnings { warnings }) => {
+                assert_eq!(warnings.len(), 1);
+                assert_eq!((warnings[0].path.as_str(), warnings[0].kind), (".env", commit_checks::WarningKind::Sensitive));
+            }
+            other => panic!("expected CommitWarnings, got {:?}", other.map(|r| r.hash)),
+        }
+        assert_eq!(repo.head(), head);
+        assert_eq!(repo.short_status(), " M a.txt\n?? .env\n");
+
+        commit_selection(&repo.app(), repo.path(), &["a.txt", ".env"], true).unwrap();
+        assert_eq!(repo.git(&["show", "--name-only", "--format=", "HEAD"]), ".env\na.txt\n");
+    }
+
+    #[cfg(feature = "libgit2")]
+    #[test]
+    fn flagged_files_commit_without_git_once_acknowledged() {
+        let repo = TestRepo::with_commit();
+        repo.write("logo.png", "\u{89}PNG\r\n\u{1a}\n\0\0\0\rIHDR");
+        backend::PRETEND_GIT_MISSING.with(|missing| missing.set(true));
+        assert_eq!(backend::current(), backend::Backend::Libgit2Only);
+
+        let warnings = commit_checks::analyze_commit_selection(repo.path(), vec!["logo.png".to_string()]).unwrap();
+        let refused = commit_selection(&repo.app(), repo.path(), &["logo.png"], false);
+        let result = commit_selection(&repo.app(), repo.path(), &["logo.png"], true);
+        backend::PRETEND_GIT_MISSING.with(|missing| missing.set(false));
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, commit_checks::WarningKind::Binary);
+        assert!(matches!(refused, Err(CommandError::CommitWarnings { .. })));
+        assert_eq!(result.unwrap().hash, repo.head());
+        assert_eq!(repo.git(&["show", "--name-only", "--format=", "HEAD"]), "logo.png\n");
+    }
+
+    #[test]
+    fn partial_commit_keeps_other_files_staged() {
+        let repo = mixed_staging();
+
+        commit_files(&repo, &["a.txt"]).unwrap();
+
+        assert_eq!(repo.git(&["show", "--name-only", "--format=", "HEAD"]), "a.txt\n");
+        assert_eq!(repo.git(&["show", "HEAD:a.txt"]), "two\n");
+        assert_eq!(repo.short_status(), "MM b.txt\nA  c.txt\n");
+        assert_eq!(repo.git(&["show", ":b.txt"]), "b2\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failing_pre_commit_hook_restores_the_index() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let repo = mixed_staging();
+        let hook = std::path::Path::new(repo.path()).join(".git/hooks/pre-commit");
+        std::fs::write(&hook, "#!/bin/sh\necho 'lint failed' >&2\nexit 1\n").unwrap();
+        std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let (head, status, staged) = (repo.head(), repo.short_status(), repo.git(&["diff", "--cached"]));
+
+        match commit_files(&repo, &["a.txt"]) {
+            Err(CommandError::CommitFailed { message, index_restored }) => {
+                assert!(index_restored);
+                assert!(message.contains("lint failed"), "{}", message);
+            }
+            other => panic!("expected CommitFailed, got {:?}", other.map(|r| r.hash)),
+        }
+        assert_eq!(repo.head(), head);
+        assert_eq!(repo.short_status(), status);
+        assert_eq!(repo.git(&["diff", "--cached"]), staged);
+    }
+}
@@ -0,0 +1,494 @@
+//! Status, diffs, history and plain commits through libgit2, for machines without a git
+//! executable or when it's chosen in settings to save starting a process on every refresh.
+//! Anything libgit2 can't do the way git would (hooks, signing, amending, filters such as
+//! LFS) is left to git by `backend::for_commit`.
+
+use git2::{DiffFindOptions, DiffFormat, ErrorCode, Repository, Status, StatusOptions};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::activity::Operation;
+use crate::backend::GitBackend;
+use crate::diff::{DiffOptions, MAX_CONTEXT_LINES};
+use crate::error::CommandError;
+use crate::history::CommitEntry;
+use crate::signing::SignMode;
+use crate::submodules::SubmoduleBump;
+use crate::{CommitOptions, CommitRequest, CommitResult, GitFileStatus, SyncStatus, GIT_NOT_INSTALLED};
+
+/// Hooks `git commit` runs; while any of them is installed commits need git, unless skipped.
+const COMMIT_HOOKS: &[&str] = &["pre-commit", "prepare-commit-msg", "commit-msg", "post-commit"];
+
+/// The libgit2 backend.
+pub(crate) struct Libgit2;
+
+fn open(path: &str) -> Result<Repository, String> {
+    Repository::discover(path).map_err(|e| e.message().to_string())
+}
+
+fn workdir(repo: &Repository) -> Result<PathBuf, String> {
+    repo.workdir()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| "Bare repositories can't be opened without git".to_string())
+}
+
+fn err(e: git2::Error) -> String {
+    e.message().to_string()
+}
+
+/// Git's two-letter code for an unmerged path, from which stages exist.
+fn conflict_codes(repo: &Repository) -> Result<BTreeMap<String, (char, char)>, String> {
+    let index = repo.index().map_err(err)?;
+    let mut codes = BTreeMap::new();
+    if !index.has_conflicts() {
+        return Ok(codes);
+    }
+    for conflict in index.conflicts().map_err(err)? {
+        let conflict = conflict.map_err(err)?;
+        let xy = match (conflict.ancestor.is_some(), conflict.our.is_some(), conflict.their.is_some()) {
+            (true, false, false) => ('D', 'D'),
+            (false, true, false) => ('A', 'U'),
+            (true, true, false) => ('U', 'D'),
+            (false, false, true) => ('U', 'A'),
+            (true, false, true) => ('D', 'U'),
+            (false, true, true) => ('A', 'A'),
+            _ => ('U', 'U'),
+        };
+        let entry = conflict.our.or(conflict.their).or(conflict.ancestor);
+        if let Some(entry) = entry {
+            codes.insert(String::from_utf8_lossy(&entry.path).to_string(), xy);
+        }
+    }
+    Ok(codes)
+}
+
+fn is_intent_to_add(entry: &git2::IndexEntry) -> bool {
+    git2::IndexEntryExtendedFlag::from_bits_truncate(entry.flags_extended).is_intent_to_add()
+}
+
+/// The index without `git add -N` placeholders, which git doesn't count as staged.
+fn staged_index(repo: &Repository) -> Result<git2::Index, String> {
+    let mut index = repo.index().map_err(err)?;
+    let placeholders: Vec<Vec<u8>> = index.iter().filter(is_intent_to_add).map(|e| e.path).collect();
+    for file in placeholders {
+        index.remove_path(Path::new(&*String::from_utf8_lossy(&file))).map_err(err)?;
+    }
+    Ok(index)
+}
+
+fn read_status(repo: &Repository, include_ignored: bool) -> Result<Vec<GitFileStatus>, String> {
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(false)
+        .include_ignored(include_ignored)
+        .recurse_ignored_dirs(false)
+        .renames_head_to_index(true)
+        .exclude_submodules(false);
+    let statuses = repo.statuses(Some(&mut options)).map_err(err)?;
+    let conflicts = conflict_codes(repo)?;
+    let repo_index = repo.index().map_err(err)?;
+
+    let (mut files, mut untracked, mut ignored) = (Vec::new(), Vec::new(), Vec::new());
+    for entry in statuses.iter() {
+        let Some(file) = entry.path().map(str::to_string) else {
+            continue;
+        };
+        let status = entry.status();
+        if status.contains(Status::CONFLICTED) {
+            let xy = conflicts.get(&file).copied().unwrap_or(('U', 'U'));
+            files.push(GitFileStatus::changed(file, xy, false, None, true));
+            continue;
+        }
+        if status.contains(Status::IGNORED) {
+            ignored.push(file);
+            continue;
+        }
+        if status.contains(Status::WT_NEW) && !status.intersects(Status::INDEX_NEW | Status::INDEX_MODIFIED | Status::INDEX_RENAMED) {
+            untracked.push(file);
+            continue;
+        }
+
+        if repo_index.get_path(Path::new(&file), 0).is_some_and(|e| is_intent_to_add(&e)) {
+            files.push(GitFileStatus::changed(file, ('.', 'A'), false, None, false));
+            continue;
+        }
+        let index = match status {
+            s if s.contains(Status::INDEX_RENAMED) => 'R',
+            s if s.contains(Status::INDEX_NEW) => 'A',
+            s if s.contains(Status::INDEX_DELETED) => 'D',
+            s if s.contains(Status::INDEX_TYPECHANGE) => 'T',
+            s if s.contains(Status::INDEX_MODIFIED) => 'M',
+            _ => '.',
+        };
+        let worktree = match status {
+            s if s.contains(Status::WT_DELETED) => 'D',
+            s if s.contains(Status::WT_TYPECHANGE) => 'T',
+            s if s.contains(Status::WT_MODIFIED) => 'M',
+            _ => '.',
+        };
+        if index == '.' && worktree == '.' {
+            continue;
+        }
+        let old_path = entry
+            .head_to_index()
+            .filter(|_| index == 'R')
+            .and_then(|delta| delta.old_file().path().map(|p| p.to_string_lossy().replace('\\', "/")));
+        let submodule = [entry.head_to_index(), entry.index_to_workdir()]
+            .iter()
+            .flatten()
+            .any(|delta| delta.new_file().mode() == git2::FileMode::Commit || delta.old_file().mode() == git2::FileMode::Commit);
+        // A renamed file is listed under its new name, like git does
+        let file = entry
+            .head_to_index()
+            .filter(|_| index == 'R')
+            .and_then(|delta| delta.new_file().path().map(|p| p.to_string_lossy().replace('\\', "/")))
+            .unwrap_or(file);
+        files.push(GitFileStatus::changed(file, (index, worktree), submodule, old_path, false));
+    }
+    // Same order as `git status --porcelain=v2`: changes, then untracked, then ignored files
+    files.extend(untracked.into_iter().map(GitFileStatus::untracked));
+    files.extend(ignored.into_iter().map(GitFileStatus::ignored));
+    Ok(files)
+}
+
+fn diff_options(options: DiffOptions) -> git2::DiffOptions {
+    let mut opts = git2::DiffOptions::new();
+    opts.context_lines(options.context_lines.unwrap_or(3).min(MAX_CONTEXT_LINES))
+        .ignore_whitespace(options.ignore_whitespace)
+        .ignore_blank_lines(options.ignore_whitespace);
+    opts
+}
+
+/// `diff` as `git diff` prints it.
+fn patch_text(diff: &git2::Diff) -> Result<String, String> {
+    let mut text = String::new();
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            text.push(line.origin());
+        }
+        text.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })
+    .map_err(err)?;
+    Ok(text)
+}
+
+fn head_tree(repo: &Repository) -> Result<Option<git2::Tree<'_>>, String> {
+    match repo.head() {
+        Ok(head) => head.peel_to_tree().map(Some).map_err(err),
+        Err(e) if matches!(e.code(), ErrorCode::UnbornBranch | ErrorCode::NotFound) => Ok(None),
+        Err(e) => Err(err(e)),
+    }
+}
+
+fn head_commit(repo: &Repository) -> Result<Option<git2::Commit<'_>>, String> {
+    match repo.head() {
+        Ok(head) => head.peel_to_commit().map(Some).map_err(err),
+        Err(e) if matches!(e.code(), ErrorCode::UnbornBranch | ErrorCode::NotFound) => Ok(None),
+        Err(e) => Err(err(e)),
+    }
+}
+
+/// Whether `file` has a `filter` attribute (LFS and the like), which only git can run.
+fn has_filter(repo: &Repository, file: &str) -> bool {
+    repo.get_attr(Path::new(file.trim_end_matches('/')), "filter", git2::AttrCheckFlags::FILE_THEN_INDEX)
+        .ok()
+        .flatten()
+        .is_some()
+}
+
+fn hooks_dir(repo: &Repository) -> PathBuf {
+    let configured = repo.config().and_then(|c| c.get_path("core.hooksPath")).ok();
+    match configured {
+        Some(dir) if dir.is_relative() => repo.workdir().unwrap_or(repo.path()).join(dir),
+        Some(dir) => dir,
+        None => repo.commondir().join("hooks"),
+    }
+}
+
+/// Untracked files under the untracked directory `dir`, one by one. `ignored` lists ignored
+/// ones too, for a forced commit.
+fn files_under(repo: &Repository, dir: &str, ignored: bool) -> Result<Vec<String>, String> {
+    let mut options = StatusOptions::new();
+    options
+        .pathspec(dir)
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(ignored)
+        .recurse_ignored_dirs(ignored);
+    let statuses = repo.statuses(Some(&mut options)).map_err(err)?;
+    Ok(statuses
+        .iter()
+        .filter(|e| e.status().intersects(Status::WT_NEW | Status::IGNORED))
+        .filter_map(|e| e.path().map(str::to_string))
+        .collect())
+}
+
+/// The message as `git commit -m` records it: trailing whitespace and surplus blank lines gone.
+fn clean_message(message: &str) -> Result<String, String> {
+    let cleaned = git2::message_prettify(message, None).map_err(err)?;
+    match cleaned.trim().is_empty() {
+        true => Err("Aborting commit due to empty commit message.".to_string()),
+        false => Ok(cleaned),
+    }
+}
+
+/// Builds the commit's tree from HEAD plus the selection and commits it. The real index is
+/// only written once the commit exists, so a failure before that leaves it untouched.
+fn make_commit(repo: &Repository, request: CommitRequest, op: &mut Operation) -> Result<CommitResult, String> {
+    let CommitRequest { message, files, force_ignored, options, .. } = request;
+    let message = clean_message(message)?;
+    let root = workdir(repo)?;
+    let statuses = read_status(repo, false)?;
+    let parent = head_commit(repo)?;
+    let base = head_tree(repo)?;
+
+    let mut index = repo.index().map_err(err)?;
+    let mut tree_index = git2::Index::new().map_err(err)?;
+    if let Some(tree) = &base {
+        tree_index.read_tree(tree).map_err(err)?;
+    }
+
+    // Files with staged changes are committed as staged, the rest as they are in the work tree
+    let mut from_worktree = Vec::new();
+    for file in &files {
+        let status = statuses.iter().find(|s| &s.path == file);
+        if let Some(old) = status.and_then(|s| s.old_path.as_deref()) {
+            let _ = tree_index.remove_path(Path::new(old));
+        }
+        if status.is_some_and(|s| s.index_status.is_some()) {
+            match index.get_path(Path::new(file), 0) {
+                Some(entry) => tree_index.add(&entry).map_err(err)?,
+                None => {
+                    let _ = tree_index.remove_path(Path::new(file));
+                }
+            }
+        } else if file.ends_with('/') {
+            from_worktree.extend(files_under(repo, file, force_ignored)?);
+        } else {
+            from_worktree.push(file.clone());
+        }
+    }
+    for file in &from_worktree {
+        let file_path = Path::new(file);
+        if std::fs::symlink_metadata(root.join(file)).is_ok() {
+            // Adding through the index applies line-ending conversion the way `git add` does
+            index.add_path(file_path).map_err(err)?;
+            let entry = index.get_path(file_path, 0).ok_or_else(|| format!("{} could not be added", file))?;
+            tree_index.add(&entry).map_err(err)?;
+        } else {
+            let _ = index.remove_path(file_path);
+            let _ = tree_index.remove_path(file_path);
+        }
+    }
+
+    let tree_id = tree_index.write_tree_to(repo).map_err(err)?;
+    if base.as_ref().map(|t| t.id()) == Some(tree_id) && !options.allow_empty {
+        return Err("Nothing to commit: the selected files match the last commit".to_string());
+    }
+    let tree = repo.find_tree(tree_id).map_err(err)?;
+    let signature = repo.signature().map_err(err)?;
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    let committed = repo
+        .commit(Some("HEAD"), &signature, &signature, &message, &tree, &parents)
+        .map_err(err);
+    op.step("Commit", &committed);
+    let id = committed?;
+
+    // The selection now matches HEAD in the index too; with `reset_index` so does the rest.
+    // The commit already exists, so a failure here is only logged
+    if options.reset_index {
+        let _ = index.read_tree(&tree);
+    }
+    let written = index.write().map_err(err);
+    op.step("Update the index", &written);
+
+    let mut diff = repo.diff_tree_to_tree(base.as_ref(), Some(&tree), None).map_err(err)?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true))).map_err(err)?;
+    let submodule_bumps = diff
+        .deltas()
+        .filter(|d| d.old_file().mode() == git2::FileMode::Commit || d.new_file().mode() == git2::FileMode::Commit)
+        .filter_map(|d| {
+            let file = d.new_file().path()?.to_string_lossy().replace('\\', "/");
+            Some(SubmoduleBump::new(file, &d.old_file().id().to_string(), &d.new_file().id().to_string()))
+        })
+        .collect();
+    let hash = id.to_string();
+    Ok(CommitResult {
+        short_hash: hash.chars().take(7).collect(),
+        hash,
+        subject: message.lines().next().unwrap_or_default().to_string(),
+        files_committed: diff.deltas().len(),
+        submodule_bumps,
+    })
+}
+
+impl GitBackend for Libgit2 {
+    fn repo_root(&self, path: &str) -> Result<String, String> {
+        let root = workdir(&open(path)?)?;
+        Ok(root.to_string_lossy().trim_end_matches(['/', '\\']).replace('\\', "/"))
+    }
+
+    fn status(&self, path: &str, include_ignored: bool) -> Result<Vec<GitFileStatus>, String> {
+        read_status(&open(path)?, include_ignored)
+    }
+
+    fn file_diff(&self, path: &str, file: Option<&str>, staged: bool, options: DiffOptions) -> Result<String, String> {
+        if options.word_diff {
+            return Err(GIT_NOT_INSTALLED.to_string());
+        }
+        let repo = open(path)?;
+        let mut opts = diff_options(options);
+        if staged {
+            // A renamed file needs its old path too to be shown as a rename
+            if let Some(file) = file {
+                opts.pathspec(file);
+                let old_path = read_status(&repo, false)?.into_iter().find(|s| s.path == file).and_then(|s| s.old_path);
+                if let Some(old) = old_path {
+                    opts.pathspec(old);
+                }
+            }
+            let tree = head_tree(&repo)?;
+            let index = staged_index(&repo)?;
+            let mut diff = repo.diff_tree_to_index(tree.as_ref(), Some(&index), Some(&mut opts)).map_err(err)?;
+            diff.find_similar(Some(DiffFindOptions::new().renames(true))).map_err(err)?;
+            return patch_text(&diff);
+        }
+        if let Some(file) = file {
+            opts.pathspec(file);
+        }
+        opts.include_untracked(true).recurse_untracked_dirs(true).show_untracked_content(true);
+        let diff = repo.diff_index_to_workdir(None, Some(&mut opts)).map_err(err)?;
+        patch_text(&diff)
+    }
+
+    fn blob_size(&self, path: &str, blob: &str, file: &str) -> Option<u64> {
+        let repo = open(path).ok()?;
+        if let Some(blob) = repo.revparse_single(blob).ok().and_then(|o| o.into_blob().ok()) {
+            return Some(blob.size() as u64);
+        }
+        std::fs::metadata(workdir(&repo).ok()?.join(file)).map(|m| m.len()).ok()
+    }
+
+    fn sync_status(&self, path: &str) -> Result<SyncStatus, String> {
+        let repo = open(path)?;
+        let has_remote = repo.remotes().is_ok_and(|remotes| !remotes.is_empty());
+        let last_fetch = std::fs::metadata(repo.commondir().join("FETCH_HEAD"))
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64);
+
+        // An unborn branch has a name but nothing to count or push yet
+        let Some(head) = head_commit(&repo)? else {
+            let branch = repo
+                .find_reference("HEAD")
+                .ok()
+                .and_then(|head| head.symbolic_target().map(|t| t.trim_start_matches("refs/heads/").to_string()))
+                .unwrap_or_else(|| "main".to_string());
+            return Ok(SyncStatus { branch, last_fetch, has_remote, ..Default::default() });
+        };
+        let head_ref = repo.head().map_err(err)?;
+        let branch = match head_ref.is_branch() {
+            true => head_ref.shorthand().unwrap_or("HEAD").to_string(),
+            false => "HEAD".to_string(),
+        };
+
+        let upstream = head_ref
+            .is_branch()
+            .then(|| git2::Branch::wrap(head_ref).upstream().ok())
+            .flatten();
+        let upstream_name = upstream.as_ref().and_then(|u| u.name().ok().flatten().map(str::to_string));
+        let (ahead, behind) = match upstream.as_ref().and_then(|u| u.get().target()) {
+            Some(target) => {
+                let (ahead, behind) = repo.graph_ahead_behind(head.id(), target).map_err(err)?;
+                (ahead as u32, behind as u32)
+            }
+            // Never pushed, so every commit is still to push
+            None => {
+                let mut walk = repo.revwalk().map_err(err)?;
+                walk.push(head.id()).map_err(err)?;
+                (walk.count() as u32, 0)
+            }
+        };
+
+        Ok(SyncStatus {
+            ahead,
+            behind,
+            has_upstream: upstream_name.is_some(),
+            upstream: upstream_name,
+            branch,
+            has_commits: true,
+            last_fetch,
+            has_remote,
+        })
+    }
+
+    fn history(&self, path: &str, limit: usize) -> Result<Vec<CommitEntry>, String> {
+        let repo = open(path)?;
+        let Some(head) = head_commit(&repo)? else {
+            return Ok(Vec::new());
+        };
+        let mut walk = repo.revwalk().map_err(err)?;
+        walk.push(head.id()).map_err(err)?;
+        walk.set_sorting(git2::Sort::TIME).map_err(err)?;
+
+        let mut commits = Vec::new();
+        for id in walk.take(limit) {
+            let commit = repo.find_commit(id.map_err(err)?).map_err(err)?;
+            commits.push(CommitEntry {
+                sha: commit.id().to_string(),
+                author: String::from_utf8_lossy(commit.author().name_bytes()).to_string(),
+                timestamp: commit.time().seconds(),
+                subject: commit.summary().unwrap_or_default().to_string(),
+            });
+        }
+        Ok(commits)
+    }
+
+    /// Plain commits only: amending, signing, commit hooks, a merge in progress and files with
+    /// a `filter` attribute all need git.
+    fn can_commit(&self, path: &str, options: &CommitOptions, files: &[String]) -> bool {
+        let Ok(repo) = open(path) else {
+            return false;
+        };
+        let signs = match options.sign {
+            SignMode::Always => true,
+            SignMode::Never => false,
+            SignMode::Auto => repo.config().and_then(|c| c.get_bool("commit.gpgsign")).unwrap_or(false),
+        };
+        let hooks = !options.no_verify && {
+            let dir = hooks_dir(&repo);
+            COMMIT_HOOKS.iter().any(|hook| dir.join(hook).is_file())
+        };
+        !options.amend
+            && !signs
+            && !hooks
+            && repo.state() == git2::RepositoryState::Clean
+            && !files.iter().any(|file| has_filter(&repo, file))
+    }
+
+    fn ensure_identity(&self, path: &str) -> Result<(), CommandError> {
+        let repo = open(path)?;
+        let Err(e) = repo.signature() else {
+            return Ok(());
+        };
+        let config = repo.config().map_err(err)?;
+        let missing: Vec<String> = ["user.name", "user.email"]
+            .into_iter()
+            .filter(|key| config.get_string(key).map_or(true, |v| v.trim().is_empty()))
+            .map(str::to_string)
+            .collect();
+        match missing.is_empty() {
+            true => Err(err(e).into()),
+            false => Err(CommandError::IdentityNotConfigured { missing }),
+        }
+    }
+
+    fn commit(&self, path: &str, request: CommitRequest, op: &mut Operation) -> Result<CommitResult, CommandError> {
+        let repo = open(path)?;
+        make_commit(&repo, request, op).map_err(|message| CommandError::CommitFailed { message, index_restored: true })
+    }
+}
@@ -0,0 +1,233 @@
+use serde::Serialize;
+use tauri::State;
+
+use crate::activity::{ActivityLog, Operation};
+use crate::error::CommandError;
+use crate::read_only::ReadOnlyRepos;
+use crate::{conflicted_files, git_path, refuse_during_operation, require_commits, run_git, run_git_no_editor, run_git_with_input};
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MergeResult {
+    /// The branch had nothing that isn't here already
+    UpToDate,
+    /// The current branch was moved forward to `commit` without a merge commit
+    FastForward { commit: String },
+    /// `commit` is the new merge commit
+    Merged { commit: String },
+    /// The branch's changes are staged to be committed as one regular commit; `message` is the
+    /// one git prepared for it
+    Squashed { message: String },
+}
+
+/// The message git prepared in `file` (`MERGE_MSG` or `SQUASH_MSG`), without its `#` comments.
+fn prepared_message(path: &str, file: &str) -> Option<String> {
+    let msg = std::fs::read_to_string(git_path(path, file).ok()?).ok()?;
+    Some(msg.lines().filter(|l| !l.starts_with('#')).collect::<Vec<_>>().join("\n").trim().to_string())
+}
+
+fn head(path: &str) -> Result<String, String> {
+    Ok(run_git(path, &["rev-parse", "HEAD"])?.trim().to_string())
+}
+
+/// How HEAD got from `old_head` to where it is after a successful merge.
+fn merge_outcome(path: &str, old_head: &str) -> Result<MergeResult, String> {
+    let parents = run_git(path, &["rev-list", "--parents", "-n1", "HEAD"])?;
+    let mut ids = parents.split_whitespace();
+    let commit = ids.next().unwrap_or_default().to_string();
+    Ok(match ids.count() {
+        _ if commit == old_head => MergeResult::UpToDate,
+        n if n > 1 => MergeResult::Merged { commit },
+        _ => MergeResult::FastForward { commit },
+    })
+}
+
+/// Merges `branch` into the current branch, fast-forwarding when possible unless `no_ff`.
+/// `squash` stages the branch's changes instead, to be committed as a single regular commit.
+/// On conflicts the merge stays in progress and `MergeConflict` lists the files and git's
+/// prepared message; finish with `merge_continue` or `merge_abort`.
+#[tauri::command]
+pub fn merge_branch(
+    path: &str,
+    branch: &str,
+    no_ff: Option<bool>,
+    squash: Option<bool>,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<MergeResult, CommandError> {
+    repos.ensure_writable(path)?;
+    require_commits(path)?;
+    refuse_during_operation(path)?;
+    let branch = branch.trim();
+    if branch.is_empty() || branch.starts_with('-') {
+        return Err(format!("Invalid branch: {}", branch).into());
+    }
+    let (no_ff, squash) = (no_ff.unwrap_or(false), squash.unwrap_or(false));
+    if no_ff && squash {
+        return Err("A squash merge never creates a merge commit, so it can't be combined with no-ff".to_string().into());
+    }
+
+    let mut args = vec!["merge", "--no-edit"];
+    if no_ff {
+        args.push("--no-ff");
+    }
+    if squash {
+        args.push("--squash");
+    }
+    args.push(branch);
+    let old_head = head(path)?;
+    let result = run_git_no_editor(path, &args);
+    let verb = if squash { "Squash merge" } else { "Merge" };
+    activity.record(path, Operation::new(format!("{} {}", verb, branch)), &result);
+    if let Err(stderr) = result {
+        let files = conflicted_files(path);
+        if files.is_empty() {
+            return Err(stderr.into());
+        }
+        let message = prepared_message(path, if squash { "SQUASH_MSG" } else { "MERGE_MSG" }).unwrap_or_default();
+        return Err(CommandError::MergeConflict { files, message });
+    }
+
+    if squash {
+        // Nothing is staged when the branch was already merged
+        if run_git(path, &["diff", "--cached", "--quiet"]).is_ok() {
+            return Ok(MergeResult::UpToDate);
+        }
+        return Ok(MergeResult::Squashed { message: prepared_message(path, "SQUASH_MSG").unwrap_or_default() });
+    }
+    Ok(merge_outcome(path, &old_head)?)
+}
+
+/// Creates the merge commit once the conflicts are resolved and staged, with `message`, or
+/// git's prepared one when it's empty.
+#[tauri::command]
+pub fn merge_continue(
+    path: &str,
+    message: Option<String>,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<MergeResult, CommandError> {
+    repos.ensure_writable(path)?;
+    if !git_path(path, "MERGE_HEAD")?.exists() {
+        return Err("There is no merge in progress".to_string().into());
+    }
+    let conflicted = conflicted_files(path);
+    if !conflicted.is_empty() {
+        return Err(CommandError::UnresolvedConflicts { files: conflicted });
+    }
+    let old_head = head(path)?;
+    let result = match message.as_deref().map(str::trim).filter(|m| !m.is_empty()) {
+        Some(message) => run_git_with_input(path, &["commit", "--cleanup=strip", "-F", "-"], message),
+        None => run_git_no_editor(path, &["commit", "--no-edit"]),
+    };
+    activity.record(path, Operation::new("Conclude merge"), &result);
+    result?;
+    Ok(merge_outcome(path, &old_head)?)
+}
+
+/// Gives up on the merge in progress and puts the branch, index and work tree back as they
+/// were before it.
+#[tauri::command]
+pub fn merge_abort(
+    path: &str,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<(), CommandError> {
+    repos.ensure_writable(path)?;
+    let result = run_git(path, &["merge", "--abort"]);
+    activity.record(path, Operation::new("Abort merge"), &result);
+    result?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestRepo;
+    use tauri::Manager;
+
+    /// `feature` and `main` both changed `a.txt` since they forked; `main` is checked out.
+    fn diverged() -> TestRepo {
+        let repo = TestRepo::with_commit();
+        repo.git(&["checkout", "-q", "-b", "feature"]);
+        repo.write("a.txt", "feature\n");
+        repo.write("f.txt", "f\n");
+        repo.commit_all("Feature change");
+        repo.git(&["checkout", "-q", "main"]);
+        repo.write("a.txt", "main\n");
+        repo.commit_all("Main change");
+        repo
+    }
+
+    fn merge(repo: &TestRepo, branch: &str) -> Result<MergeResult, CommandError> {
+        let app = repo.app();
+        merge_branch(repo.path(), branch, None, None, app.state(), app.state())
+    }
+
+    fn merge_head_exists(repo: &TestRepo) -> bool {
+        git_path(repo.path(), "MERGE_HEAD").unwrap().exists()
+    }
+
+    #[test]
+    fn conflicts_then_continue() {
+        let repo = diverged();
+        let app = repo.app();
+        let before = repo.head();
+
+        match merge(&repo, "feature") {
+            Err(CommandError::MergeConflict { files, message }) => {
+                assert_eq!(files, ["a.txt"]);
+                assert!(message.starts_with("Merge branch 'feature'"), "{}", message);
+                assert!(!message.contains('#'), "{}", message);
+            }
+            other => panic!("expected MergeConflict, got {:?}", other),
+        }
+        assert!(merge_head_exists(&repo));
+
+        match merge_continue(repo.path(), None, app.state(), app.state()) {
+            Err(CommandError::UnresolvedConflicts { files }) => assert_eq!(files, ["a.txt"]),
+            other => panic!("expected UnresolvedConflicts, got {:?}", other),
+        }
+
+        repo.write("a.txt", "both\n");
+        repo.git(&["add", "a.txt"]);
+        let commit = match merge_continue(repo.path(), Some("Merge feature\n".to_string()), app.state(), app.state()) {
+            Ok(MergeResult::Merged { commit }) => commit,
+            other => panic!("expected Merged, got {:?}", other),
+        };
+        assert_eq!(commit, repo.head());
+        assert_eq!(repo.git(&["log", "-1", "--format=%P%n%B", "HEAD"]).lines().nth(1), Some("Merge feature"));
+        assert!(repo.git(&["rev-list", "--parents", "-n1", "HEAD"]).contains(&before));
+        assert_eq!(repo.short_status(), "");
+        assert!(!merge_head_exists(&repo));
+    }
+
+    #[test]
+    fn conflicts_then_abort() {
+        let repo = diverged();
+        let app = repo.app();
+        let before = repo.head();
+
+        assert!(matches!(merge(&repo, "feature"), Err(CommandError::MergeConflict { .. })));
+        assert!(merge(&repo, "feature").unwrap_err().to_string().contains("Finish or abort the merge"));
+        merge_abort(repo.path(), app.state(), app.state()).unwrap();
+
+        assert_eq!(repo.head(), before);
+        assert_eq!(repo.short_status(), "");
+        assert_eq!(repo.read("a.txt"), "main\n");
+        assert!(!merge_head_exists(&repo));
+        assert!(merge_continue(repo.path(), None, app.state(), app.state()).is_err());
+    }
+
+    #[test]
+    fn fast_forward_and_up_to_date() {
+        let repo = TestRepo::with_commit();
+        repo.git(&["checkout", "-q", "-b", "feature"]);
+        let tip = repo.commit_all("Feature");
+        repo.git(&["checkout", "-q", "main"]);
+
+        assert!(matches!(merge(&repo, "feature"), Ok(MergeResult::FastForward { commit }) if commit == tip));
+        assert!(matches!(merge(&repo, "feature"), Ok(MergeResult::UpToDate)));
+        assert!(merge(&repo, "--abort").is_err());
+    }
+}
@@ -0,0 +1,73 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::oneshot;
+
+use crate::ai::ollama;
+use crate::commit_groups::cancellable;
+
+/// Event carrying `PullEvent`s while a model downloads.
+const PULL_EVENT: &str = "ollama-pull-progress";
+
+/// Models being pulled, each with the sender that cancels its download.
+#[derive(Default)]
+pub struct OllamaPulls(Mutex<HashMap<String, oneshot::Sender<()>>>);
+
+impl OllamaPulls {
+    pub fn pulling(&self) -> Vec<String> {
+        self.0.lock().unwrap().keys().cloned().collect()
+    }
+}
+
+#[derive(Serialize, Clone)]
+struct PullEvent {
+    model: String,
+    status: String,
+    completed: Option<u64>,
+    total: Option<u64>,
+}
+
+/// Downloads `model` through Ollama, forwarding progress as `ollama-pull-progress` events.
+/// Pulling a model that's already downloading cancels the earlier pull.
+#[tauri::command]
+pub async fn pull_ollama_model(app: AppHandle, model: String, pulls: State<'_, OllamaPulls>) -> Result<(), String> {
+    let (cancel_tx, mut cancel) = oneshot::channel();
+    if let Some(previous) = pulls.0.lock().unwrap().insert(model.clone(), cancel_tx) {
+        let _ = previous.send(());
+    }
+
+    // No overall timeout: large models take a long time, and the user can cancel
+    let client = reqwest::Client::new();
+    let pull = async {
+        ollama::pull(&client, &model, |progress| {
+            let _ = app.emit(
+                PULL_EVENT,
+                PullEvent {
+                    model: model.clone(),
+                    status: progress.status.clone(),
+                    completed: progress.completed,
+                    total: progress.total,
+                },
+            );
+        })
+        .await
+        .map_err(String::from)
+    };
+    let result = cancellable(pull, &mut cancel).await;
+    drop(cancel);
+
+    // Only our own sender is closed now; a newer pull of the same model keeps its entry
+    let mut active = pulls.0.lock().unwrap();
+    if active.get(&model).is_some_and(|sender| sender.is_closed()) {
+        active.remove(&model);
+    }
+    result
+}
+
+#[tauri::command]
+pub fn cancel_ollama_pull(model: String, pulls: State<'_, OllamaPulls>) {
+    if let Some(cancel) = pulls.0.lock().unwrap().remove(&model) {
+        let _ = cancel.send(());
+    }
+}
@@ -0,0 +1,188 @@
+use serde::Serialize;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+use crate::activity::{first_line, ActivityLog, Operation};
+use crate::error::CommandError;
+use crate::read_only::ReadOnlyRepos;
+use crate::{build_hidden_cmd, git_path, repo_key, run_git, spawn_error};
+
+/// One part of a patch that doesn't apply to HEAD, from `git apply --check --verbose`.
+#[derive(Serialize, Debug)]
+pub struct HunkFailure {
+    file: String,
+    /// Line in HEAD's version where the failing hunk starts, when git reports it
+    line: Option<u32>,
+    message: String,
+}
+
+/// A throwaway index next to the real one, deleted when dropped.
+struct TempIndex(PathBuf);
+
+impl TempIndex {
+    fn create(root: &str) -> Result<Self, String> {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default();
+        let name = format!("gitpop-patch-index-{}-{}", std::process::id(), nanos);
+        Ok(TempIndex(git_path(root, &name)?))
+    }
+
+    /// Runs git against this index instead of the real one, optionally feeding `input` on stdin.
+    fn git(&self, root: &str, args: &[&str], input: Option<&str>) -> Result<String, String> {
+        let mut child = build_hidden_cmd("git")
+            .current_dir(root)
+            .args(args)
+            .env("GIT_INDEX_FILE", &self.0)
+            .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(spawn_error)?;
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            stdin.write_all(input.as_bytes()).map_err(|e| e.to_string())?;
+        }
+        let output = child.wait_with_output().map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).to_string());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+impl Drop for TempIndex {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Turns `git apply --check --verbose` errors into one entry per failing hunk or file.
+fn parse_apply_errors(stderr: &str) -> Vec<HunkFailure> {
+    let mut failures: Vec<HunkFailure> = Vec::new();
+    for line in stderr.lines() {
+        let Some(error) = line.strip_prefix("error: ") else {
+            continue;
+        };
+        if let Some(location) = error.strip_prefix("patch failed: ") {
+            let (file, line) = match location.rsplit_once(':') {
+                Some((file, line)) => (file, line.parse().ok()),
+                None => (location, None),
+            };
+            failures.push(HunkFailure {
+                file: file.to_string(),
+                line,
+                message: "The hunk's context doesn't match HEAD".to_string(),
+            });
+        } else if let Some((file, message)) = error.split_once(": ") {
+            // "patch does not apply" just repeats the hunk failures above it
+            if message == "patch does not apply" && failures.iter().any(|f| f.file == file) {
+                continue;
+            }
+            failures.push(HunkFailure { file: file.to_string(), line: None, message: message.to_string() });
+        } else if error != "while searching for:" {
+            failures.push(HunkFailure { file: String::new(), line: None, message: error.to_string() });
+        }
+    }
+    failures
+}
+
+/// Files whose real index entry still matches the old HEAD get the committed content, so the
+/// new commit doesn't show up as a staged reversal. Entries the user staged differently are
+/// left alone.
+fn sync_unchanged_entries(root: &str, old: Option<&str>, new: &str) -> Result<(), String> {
+    let changed = match old {
+        Some(old) => run_git(root, &["diff-tree", "-r", "--no-renames", "--name-only", "-z", old, new])?,
+        None => run_git(root, &["ls-tree", "-r", "--name-only", "-z", new])?,
+    };
+    let mut in_sync = Vec::new();
+    for file in changed.split('\0').filter(|f| !f.is_empty()) {
+        let staged = run_git(root, &["ls-files", "-s", "--", file])?;
+        let staged_blob = staged.split_whitespace().nth(1);
+        let head_blob = old.and_then(|old| run_git(root, &["rev-parse", "--verify", "-q", &format!("{}:{}", old, file)]).ok());
+        if staged_blob == head_blob.as_deref().map(str::trim) {
+            in_sync.push(file);
+        }
+    }
+    if in_sync.is_empty() {
+        return Ok(());
+    }
+    let mut args = vec!["reset", "-q", new, "--"];
+    args.extend(in_sync);
+    run_git(root, &args).map(|_| ())
+}
+
+fn commit_patch_to_head(root: &str, message: &str, patch: &str, op: &mut Operation) -> Result<String, CommandError> {
+    let old = run_git(root, &["rev-parse", "--verify", "-q", "HEAD"]).ok().map(|h| h.trim().to_string());
+    let index = TempIndex::create(root)?;
+
+    let seeded = match &old {
+        Some(head) => index.git(root, &["read-tree", head], None),
+        None => index.git(root, &["read-tree", "--empty"], None),
+    };
+    op.step("Build temporary index from HEAD", &seeded);
+    seeded?;
+
+    if let Err(stderr) = index.git(root, &["apply", "--cached", "--check", "--verbose", "-"], Some(patch)) {
+        let failures = parse_apply_errors(&stderr);
+        op.step("Check patch against HEAD", &Err::<(), _>(stderr));
+        return Err(CommandError::PatchDoesNotApply { failures });
+    }
+    let applied = index.git(root, &["apply", "--cached", "-"], Some(patch));
+    op.step("Apply patch to temporary index", &applied);
+    applied?;
+
+    let tree = index.git(root, &["write-tree"], None)?.trim().to_string();
+    if let Some(head) = &old {
+        if run_git(root, &["rev-parse", &format!("{}^{{tree}}", head)])?.trim() == tree {
+            return Err(CommandError::from("The selected hunks don't change anything".to_string()));
+        }
+    }
+
+    let mut args = vec!["commit-tree", tree.as_str()];
+    if let Some(head) = &old {
+        args.extend(["-p", head.as_str()]);
+    }
+    args.extend(["-F", "-"]);
+    let created = index.git(root, &args, Some(message)).map(|sha| sha.trim().to_string());
+    op.step("Create commit", &created);
+    let new = created?;
+
+    // Compare-and-swap on HEAD: fails if anything else committed in the meantime
+    let zero = "0".repeat(new.len());
+    let reflog = format!("commit (patch): {}", first_line(message, 72));
+    let moved = run_git(root, &["update-ref", "-m", &reflog, "HEAD", &new, old.as_deref().unwrap_or(&zero)]);
+    op.step("Move HEAD to the new commit", &moved);
+    moved?;
+
+    let synced = sync_unchanged_entries(root, old.as_deref(), &new);
+    op.step("Update unchanged index entries", &synced);
+    Ok(new)
+}
+
+/// Commits exactly `patch` (assembled by the UI from the hunks the user ticked) on top of HEAD.
+/// The patch is applied to a temporary index built from HEAD and committed with
+/// `commit-tree` + `update-ref`, so whatever is staged or modified right now stays as it is.
+/// Returns the new commit's hash.
+#[tauri::command]
+pub fn commit_patch(
+    path: &str,
+    message: &str,
+    patch: &str,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<String, CommandError> {
+    repos.ensure_writable(path)?;
+    if message.trim().is_empty() {
+        return Err(CommandError::from("The commit message is empty".to_string()));
+    }
+    if patch.trim().is_empty() {
+        return Err(CommandError::from("No hunks selected".to_string()));
+    }
+
+    let root = repo_key(path);
+    let mut op = Operation::new(format!("Commit selected hunks \"{}\"", first_line(message, 72)));
+    let result = commit_patch_to_head(&root, message, patch, &mut op);
+    activity.record(path, op, &result);
+    result
+}
@@ -0,0 +1,149 @@
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::activity::{ActivityLog, Operation};
+use crate::error::CommandError;
+use crate::push::DEFAULT_REMOTE;
+use crate::read_only::ReadOnlyRepos;
+use crate::rebase::stopped_rebase;
+use crate::{conflicted_files, is_auth_failure, run_git, run_git_with_progress};
+
+#[derive(Serialize, Debug)]
+pub struct PullResult {
+    /// Commits that came in with the pull; 0 when the branch was already up to date
+    new_commits: usize,
+}
+
+fn head(path: &str) -> Option<String> {
+    run_git(path, &["rev-parse", "--verify", "-q", "HEAD"]).ok().map(|s| s.trim().to_string())
+}
+
+fn check_target(remote: &Option<String>, branch: &Option<String>) -> Result<(), CommandError> {
+    match remote.iter().chain(branch).find(|a| a.starts_with('-')) {
+        Some(arg) => Err(format!("Invalid remote or branch: {}", arg).into()),
+        None => Ok(()),
+    }
+}
+
+/// `AuthenticationFailed` when the remote turned away the credentials, else git's stderr as is.
+pub(crate) fn network_error(stderr: String, remote: Option<&str>) -> CommandError {
+    if is_auth_failure(&stderr) {
+        return CommandError::AuthenticationFailed { remote: remote.unwrap_or(DEFAULT_REMOTE).to_string() };
+    }
+    CommandError::from(stderr)
+}
+
+fn run_pull(
+    app: &AppHandle,
+    path: &str,
+    remote: Option<&str>,
+    branch: Option<&str>,
+    rebase: Option<bool>,
+) -> Result<PullResult, CommandError> {
+    let mut args = vec!["pull", "--progress"];
+    match rebase {
+        Some(true) => args.push("--rebase"),
+        Some(false) => args.push("--no-rebase"),
+        // Since git 2.33 a diverged pull fails unless told how to reconcile; merge unless the
+        // user configured `pull.rebase`
+        None if run_git(path, &["config", "--get", "pull.rebase"]).is_err() => args.push("--no-rebase"),
+        None => {}
+    }
+    let remote = remote.or(branch.is_some().then_some(DEFAULT_REMOTE));
+    args.extend(remote);
+    if remote.is_some() {
+        args.extend(branch);
+    }
+
+    let before = head(path);
+    let out = run_git_with_progress(app, path, &args, "pull-progress")?;
+    if !out.status.success() {
+        if let Some(stopped) = stopped_rebase(path) {
+            return Err(stopped);
+        }
+        let conflicted = conflicted_files(path);
+        if !conflicted.is_empty() {
+            return Err(CommandError::PullConflicts { files: conflicted });
+        }
+        return Err(network_error(String::from_utf8_lossy(&out.stderr).to_string(), remote));
+    }
+
+    let new_commits = match (before, head(path)) {
+        (Some(before), Some(after)) if before != after => {
+            run_git(path, &["rev-list", "--count", &format!("{}..{}", before, after)])?.trim().parse().unwrap_or(0)
+        }
+        (None, Some(after)) => run_git(path, &["rev-list", "--count", &after])?.trim().parse().unwrap_or(0),
+        _ => 0,
+    };
+    Ok(PullResult { new_commits })
+}
+
+/// Fetches `remote` (all remotes if not given), emitting `fetch-progress` events. With `prune`,
+/// remote-tracking branches deleted on the remote are removed too.
+#[tauri::command]
+pub async fn fetch(app: AppHandle, path: String, remote: Option<String>, prune: Option<bool>) -> Result<(), CommandError> {
+    // Fetching rewrites remote-tracking refs, so it counts as a write
+    app.state::<ReadOnlyRepos>().ensure_writable(&path)?;
+    let remote = remote.filter(|r| !r.trim().is_empty());
+    check_target(&remote, &None)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let prune = prune.unwrap_or(false);
+        let mut args = vec!["fetch", "--progress"];
+        if prune {
+            args.push("--prune");
+        }
+        args.push(remote.as_deref().unwrap_or("--all"));
+
+        let result = run_git_with_progress(&app, &path, &args, "fetch-progress")
+            .map_err(CommandError::from)
+            .and_then(|out| match out.status.success() {
+                true => Ok(()),
+                false => Err(network_error(String::from_utf8_lossy(&out.stderr).to_string(), remote.as_deref())),
+            });
+        let summary = format!(
+            "Fetch {}{}",
+            remote.as_deref().unwrap_or("all remotes"),
+            if prune { " (prune)" } else { "" }
+        );
+        app.state::<ActivityLog>().record(&path, Operation::new(summary), &result);
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Pulls `branch` from `remote` into the current branch, or its upstream when neither is
+/// given, emitting `pull-progress` events. `rebase` picks between rebasing and merging; left
+/// out, `pull.rebase` decides and merging is the default. When the pull stops on conflicts,
+/// the conflicted files are returned as `PullConflicts`, or as `RebaseConflict` along with the
+/// commit being replayed when a rebase is left in progress.
+#[tauri::command]
+pub async fn pull(
+    app: AppHandle,
+    path: String,
+    remote: Option<String>,
+    branch: Option<String>,
+    rebase: Option<bool>,
+) -> Result<PullResult, CommandError> {
+    app.state::<ReadOnlyRepos>().ensure_writable(&path)?;
+    let remote = remote.filter(|r| !r.trim().is_empty());
+    let branch = branch.filter(|b| !b.trim().is_empty());
+    check_target(&remote, &branch)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = run_pull(&app, &path, remote.as_deref(), branch.as_deref(), rebase);
+        let mut summary = match (&remote, &branch) {
+            (_, Some(branch)) => format!("Pull {} from {}", branch, remote.as_deref().unwrap_or(DEFAULT_REMOTE)),
+            (Some(remote), None) => format!("Pull from {}", remote),
+            (None, None) => "Pull".to_string(),
+        };
+        if rebase == Some(true) {
+            summary.push_str(" (rebase)");
+        }
+        app.state::<ActivityLog>().record(&path, Operation::new(summary), &result);
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
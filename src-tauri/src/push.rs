@@ -0,0 +1,274 @@
+use serde::Serialize;
+use std::process::Stdio;
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::activity::{ActivityLog, Operation};
+use crate::danger::{ConfirmationTokens, DangerLevel, DangerousOperation};
+use crate::error::CommandError;
+use crate::read_only::ReadOnlyRepos;
+use crate::{build_hidden_cmd, is_auth_failure, require_commits, run_git, run_git_with_progress};
+
+/// Remote pushed to when none is given and the branch has no upstream to follow yet.
+pub(crate) const DEFAULT_REMOTE: &str = "origin";
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PushRefStatus {
+    FastForward,
+    Forced,
+    New,
+    Deleted,
+    UpToDate,
+    Rejected,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PushedRef {
+    /// Local ref, empty for a deletion
+    source: String,
+    /// Ref on the remote, such as `refs/heads/main`
+    destination: String,
+    status: PushRefStatus,
+    /// Git's summary for the ref: `1a2b3c4..5d6e7f8`, `[new branch]`, `[rejected] (fetch first)`
+    summary: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PushSummary {
+    /// The remote's URL as git reported it
+    remote_url: String,
+    refs: Vec<PushedRef>,
+}
+
+/// Parses `git push --porcelain` output: a `To <url>` line, then one tab-separated
+/// `<flag>\t<src>:<dst>\t<summary>` line per ref and a final `Done`.
+fn parse_porcelain(out: &str) -> PushSummary {
+    let mut remote_url = String::new();
+    let mut refs = Vec::new();
+    for line in out.lines() {
+        if let Some(url) = line.strip_prefix("To ") {
+            remote_url = url.to_string();
+            continue;
+        }
+        let mut fields = line.splitn(3, '\t');
+        let (Some(flag), Some(refspec), Some(summary)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        let status = match flag {
+            " " => PushRefStatus::FastForward,
+            "+" => PushRefStatus::Forced,
+            "*" => PushRefStatus::New,
+            "-" => PushRefStatus::Deleted,
+            "=" => PushRefStatus::UpToDate,
+            "!" => PushRefStatus::Rejected,
+            _ => continue,
+        };
+        let (source, destination) = refspec.split_once(':').unwrap_or(("", refspec));
+        refs.push(PushedRef {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            status,
+            summary: summary.to_string(),
+        });
+    }
+    PushSummary { remote_url, refs }
+}
+
+/// How a push may replace commits on the remote.
+enum Force {
+    No,
+    /// `--force-with-lease`: only if the remote branch is still where our remote-tracking
+    /// branch says it is
+    Lease,
+    /// `--force-with-lease=<branch>:<sha>`: only if the remote branch is at exactly this commit
+    LeaseExpecting(String),
+}
+
+/// The remote branch a push without an explicit `branch` goes to: the upstream's, or the
+/// current branch's own name.
+fn destination_branch(path: &str, branch: Option<&str>) -> Result<String, String> {
+    if let Some(branch) = branch {
+        return Ok(branch.to_string());
+    }
+    let current = run_git(path, &["symbolic-ref", "--short", "-q", "HEAD"])
+        .map_err(|_| "HEAD is detached; pick the branch to push".to_string())?
+        .trim()
+        .to_string();
+    let merge = run_git(path, &["config", "--get", &format!("branch.{}.merge", current)]).unwrap_or_default();
+    let merge = merge.trim().trim_start_matches("refs/heads/");
+    Ok(if merge.is_empty() { current } else { merge.to_string() })
+}
+
+/// Where `branch` is on `remote` right now, asked from the remote itself.
+fn remote_tip(path: &str, remote: &str, branch: &str) -> Option<String> {
+    let out = build_hidden_cmd("git")
+        .current_dir(path)
+        .args(["ls-remote", "--", remote, &format!("refs/heads/{}", branch)])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    stdout.split_whitespace().next().map(str::to_string)
+}
+
+/// Sorts a failed push into a rejection the user can fix by pulling, a force push whose lease
+/// failed, missing or wrong credentials, or anything else (git's stderr as is).
+fn push_error(path: &str, summary: &PushSummary, stderr: String, remote: &str) -> CommandError {
+    let rejected = |reasons: &[&str]| -> Vec<String> {
+        summary
+            .refs
+            .iter()
+            .filter(|r| r.status == PushRefStatus::Rejected && reasons.iter().any(|reason| r.summary.contains(reason)))
+            .map(|r| r.destination.trim_start_matches("refs/heads/").to_string())
+            .collect()
+    };
+    if let Some(branch) = rejected(&["stale info"]).into_iter().next() {
+        let remote_sha = remote_tip(path, remote, &branch);
+        return CommandError::ForcePushLeaseFailed { branch, remote_sha };
+    }
+    let behind = rejected(&["non-fast-forward", "fetch first"]);
+    if !behind.is_empty() {
+        return CommandError::PushRejected { branches: behind };
+    }
+    if is_auth_failure(&stderr) {
+        return CommandError::AuthenticationFailed { remote: remote.to_string() };
+    }
+    CommandError::from(stderr)
+}
+
+fn run_push<R: Runtime>(
+    app: &AppHandle<R>,
+    path: &str,
+    remote: Option<&str>,
+    branch: Option<&str>,
+    set_upstream: bool,
+    no_verify: bool,
+    force: Force,
+) -> Result<PushSummary, CommandError> {
+    let mut args = vec!["push".to_string(), "--porcelain".to_string(), "--progress".to_string()];
+    // Skips the pre-push hook; commit hooks are controlled separately on commit
+    if no_verify {
+        args.push("--no-verify".to_string());
+    }
+    if set_upstream {
+        args.push("-u".to_string());
+    }
+    match force {
+        Force::No => {}
+        Force::Lease => args.push("--force-with-lease".to_string()),
+        Force::LeaseExpecting(sha) => {
+            args.push(format!("--force-with-lease={}:{}", destination_branch(path, branch)?, sha));
+        }
+    }
+    // A branch needs a remote in front of it; plain `git push` follows the upstream
+    let remote = remote.or((set_upstream || branch.is_some()).then_some(DEFAULT_REMOTE));
+    args.extend(remote.map(str::to_string));
+    if remote.is_some() {
+        args.extend(branch.map(str::to_string));
+    }
+
+    let refs: Vec<&str> = args.iter().map(String::as_str).collect();
+    let out = run_git_with_progress(app, path, &refs, "push-progress")?;
+    let summary = parse_porcelain(&String::from_utf8_lossy(&out.stdout));
+    if out.status.success() {
+        return Ok(summary);
+    }
+    let stderr = String::from_utf8_lossy(&out.stderr).to_string();
+    Err(push_error(path, &summary, stderr, remote.unwrap_or(DEFAULT_REMOTE)))
+}
+
+/// Pushes `branch` (the current one by default) to `remote`, following the upstream when
+/// neither is given, and with `set_upstream` records the remote branch as its upstream.
+/// git's progress is emitted as `push-progress` events. Returns the refs that were updated.
+///
+/// `force` replaces the remote branch with `--force-with-lease`, which refuses when someone
+/// else pushed since the last fetch (`ForcePushLeaseFailed`). Plain `--force` is never used:
+/// `force_unsafe` overwrites whatever is there only if it is exactly `expected_remote_sha`.
+/// Either needs a `confirmation` token for this remote and branch.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn push(
+    app: AppHandle,
+    path: String,
+    remote: Option<String>,
+    branch: Option<String>,
+    set_upstream: Option<bool>,
+    no_verify: Option<bool>,
+    force: Option<bool>,
+    force_unsafe: Option<bool>,
+    expected_remote_sha: Option<String>,
+    confirmation: Option<String>,
+) -> Result<PushSummary, CommandError> {
+    app.state::<ReadOnlyRepos>().ensure_writable(&path)?;
+    require_commits(&path)?;
+    let remote = remote.filter(|r| !r.trim().is_empty());
+    let branch = branch.filter(|b| !b.trim().is_empty());
+    if let Some(arg) = remote.iter().chain(&branch).find(|a| a.starts_with('-')) {
+        return Err(format!("Invalid push target: {}", arg).into());
+    }
+    let force = match (force_unsafe.unwrap_or(false), force.unwrap_or(false)) {
+        (true, _) => match expected_remote_sha.map(|s| s.trim().to_lowercase()) {
+            Some(sha) if (sha.len() == 40 || sha.len() == 64) && sha.chars().all(|c| c.is_ascii_hexdigit()) => {
+                Force::LeaseExpecting(sha)
+            }
+            _ => return Err("Force pushing needs the full commit hash the remote branch is expected at".to_string().into()),
+        },
+        (false, true) => Force::Lease,
+        (false, false) => Force::No,
+    };
+    let level = match force {
+        Force::No => DangerLevel::Recoverable,
+        _ => DangerLevel::Destructive,
+    };
+    let operation = DangerousOperation::ForcePush { path: path.clone(), remote: remote.clone(), branch: branch.clone() };
+    app.state::<ConfirmationTokens>().authorize(level, &operation, confirmation.as_deref())?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let (set_upstream, no_verify) = (set_upstream.unwrap_or(false), no_verify.unwrap_or(false));
+        let summary = match (&force, set_upstream, no_verify) {
+            (Force::Lease, ..) => "Force push (with lease)".to_string(),
+            (Force::LeaseExpecting(sha), ..) => format!("Force push over {}", &sha[..7]),
+            (Force::No, true, _) => "Push and set upstream".to_string(),
+            (Force::No, false, true) => "Push (hooks skipped)".to_string(),
+            (Force::No, false, false) => "Push".to_string(),
+        };
+        let result = run_push(&app, &path, remote.as_deref(), branch.as_deref(), set_upstream, no_verify, force);
+        app.state::<ActivityLog>().record(&path, Operation::new(summary), &result);
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{bare_remote, TestRepo};
+
+    #[test]
+    fn force_push_fails_when_someone_else_pushed_since_the_last_fetch() {
+        let app = tauri::test::mock_app();
+        let remote = bare_remote();
+        let first = TestRepo::clone_of(remote.path());
+        first.write("a.txt", "one\n");
+        first.commit_all("Initial commit");
+        first.git(&["push", "-q", "-u", "origin", "main"]);
+        let second = TestRepo::clone_of(remote.path());
+
+        first.write("a.txt", "two\n");
+        let pushed = first.commit_all("Pushed by someone else");
+        first.git(&["push", "-q"]);
+        second.git(&["commit", "-q", "--amend", "-m", "Amended without fetching"]);
+
+        let result = run_push(app.handle(), second.path(), None, None, false, false, Force::Lease);
+        match result {
+            Err(CommandError::ForcePushLeaseFailed { branch, remote_sha }) => {
+                assert_eq!(branch, "main");
+                assert_eq!(remote_sha, Some(pushed));
+            }
+            other => panic!("expected ForcePushLeaseFailed, got {:?}", other.map(|s| s.refs.len())),
+        }
+        assert_eq!(first.git(&["ls-remote", "origin", "refs/heads/main"]).split_whitespace().next(), Some(first.head().as_str()));
+    }
+}
@@ -0,0 +1,91 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+use tauri::State;
+
+use crate::error::CommandError;
+use crate::repo_key;
+use crate::settings::SettingsService;
+
+/// Settings key holding the list of repository roots opened in read-only mode.
+pub const READ_ONLY_KEY: &str = "readOnlyRepos";
+
+/// Repositories the user marked read-only, cached in memory so synchronous commands can
+/// check the flag without a round trip through the settings worker.
+pub struct ReadOnlyRepos(Mutex<BTreeSet<String>>);
+
+#[derive(Serialize, Debug)]
+pub struct ReadOnlyStatus {
+    read_only: bool,
+    /// The repo lives on a network share (UNC path), where read-only is the safer default
+    suggested: bool,
+}
+
+fn is_unc_path(path: &str) -> bool {
+    path.starts_with("\\\\") || path.starts_with("//")
+}
+
+impl ReadOnlyRepos {
+    pub fn from_settings(settings: &Map<String, Value>) -> Self {
+        let repos = settings
+            .get(READ_ONLY_KEY)
+            .and_then(Value::as_array)
+            .map(|list| list.iter().filter_map(Value::as_str).map(str::to_string).collect())
+            .unwrap_or_default();
+        ReadOnlyRepos(Mutex::new(repos))
+    }
+
+    pub fn is_read_only(&self, path: &str) -> bool {
+        let repos = self.0.lock().unwrap();
+        // Skip resolving the root when nothing is read-only, which is the common case
+        !repos.is_empty() && repos.contains(&repo_key(path))
+    }
+
+    /// Called first by every command that writes to the repository.
+    pub fn ensure_writable(&self, path: &str) -> Result<(), CommandError> {
+        if !crate::backend::git_found() {
+            return Err(CommandError::GitNotInstalled);
+        }
+        if self.is_read_only(path) {
+            return Err(CommandError::ReadOnlyRepository { path: repo_key(path) });
+        }
+        Ok(())
+    }
+}
+
+#[tauri::command]
+pub fn get_read_only_status(path: &str, repos: State<'_, ReadOnlyRepos>) -> ReadOnlyStatus {
+    ReadOnlyStatus {
+        read_only: repos.is_read_only(path),
+        suggested: is_unc_path(path),
+    }
+}
+
+#[tauri::command]
+pub async fn set_read_only(
+    path: String,
+    read_only: bool,
+    repos: State<'_, ReadOnlyRepos>,
+    settings: State<'_, SettingsService>,
+) -> Result<ReadOnlyStatus, String> {
+    let key = repo_key(&path);
+    let list: Vec<Value> = {
+        let mut repos = repos.0.lock().unwrap();
+        if read_only {
+            repos.insert(key);
+        } else {
+            repos.remove(&key);
+        }
+        repos.iter().cloned().map(Value::String).collect()
+    };
+
+    let mut changes = Map::new();
+    changes.insert(READ_ONLY_KEY.to_string(), Value::Array(list));
+    settings.update(changes).await?;
+
+    Ok(ReadOnlyStatus {
+        read_only,
+        suggested: is_unc_path(&path),
+    })
+}
@@ -0,0 +1,303 @@
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::Path;
+use tauri::State;
+
+use crate::activity::{ActivityLog, Operation};
+use crate::error::CommandError;
+use crate::history::{parse_entries, CommitEntry, ENTRY_FORMAT};
+use crate::read_only::ReadOnlyRepos;
+use crate::repo_state::{get_repo_operation_state, RepoOperation};
+use crate::{build_hidden_cmd, conflicted_files, git_path, run_git, run_git_no_editor, spawn_error};
+
+/// Scratch folder in the git directory for the todo list and reworded messages. It outlives a
+/// rebase that stops, since the remaining steps still read the messages.
+const PLAN_DIR: &str = "gitpop-rebase";
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RebaseCommand {
+    Pick,
+    Squash,
+    Fixup,
+    Reword,
+    Drop,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RebaseAction {
+    hash: String,
+    action: RebaseCommand,
+    /// Required for `reword`. On any other kept commit it replaces the message of the commit
+    /// being built, so on a `squash` or `fixup` it's the combined commit's message; without one
+    /// a squash keeps both messages, as git does
+    new_message: Option<String>,
+}
+
+/// A rebase that stopped partway, as `git pull --rebase` leaves it on a conflict: the
+/// conflicted files and the commit that was being replayed.
+pub(crate) fn stopped_rebase(path: &str) -> Option<CommandError> {
+    let in_progress = ["rebase-merge", "rebase-apply"]
+        .iter()
+        .any(|dir| git_path(path, dir).map(|p| p.is_dir()).unwrap_or(false));
+    if !in_progress {
+        return None;
+    }
+    let (commit, subject) = replaying(path);
+    Some(CommandError::RebaseConflict { files: conflicted_files(path), commit, subject })
+}
+
+/// The commit the rebase stopped on and its subject.
+fn replaying(path: &str) -> (Option<String>, Option<String>) {
+    let commit = run_git(path, &["log", "-1", "--format=%H%x00%s", "REBASE_HEAD"]).ok();
+    match commit.as_deref().and_then(|c| c.trim_end().split_once('\0')) {
+        Some((sha, subject)) => (Some(sha.to_string()), Some(subject.to_string())),
+        None => (None, None),
+    }
+}
+
+fn has_conflict_markers(path: &str, file: &str) -> bool {
+    let Ok(contents) = std::fs::read(std::path::Path::new(path).join(file)) else {
+        return false;
+    };
+    contents
+        .split(|&b| b == b'\n')
+        .any(|line| line.starts_with(b"<<<<<<< ") || line.starts_with(b">>>>>>> "))
+}
+
+fn require_rebase(path: &str) -> Result<(), CommandError> {
+    match get_repo_operation_state(path)? {
+        RepoOperation::Rebasing { .. } => Ok(()),
+        _ => Err("There is no rebase in progress".to_string().into()),
+    }
+}
+
+/// Sorts out where a failed rebase command stopped: on a conflict, on a commit with nothing
+/// left to commit, or somewhere git explains in `stderr`.
+fn rebase_error(path: &str, stderr: String) -> CommandError {
+    if !conflicted_files(path).is_empty() {
+        if let Some(stopped) = stopped_rebase(path) {
+            return stopped;
+        }
+    }
+    // Stopped with nothing staged: what's left of the commit is already on the new base
+    let still_rebasing = matches!(get_repo_operation_state(path), Ok(RepoOperation::Rebasing { .. }));
+    if still_rebasing && run_git(path, &["diff", "--cached", "--quiet"]).is_ok() {
+        let (commit, subject) = replaying(path);
+        return CommandError::RebaseNothingToCommit { commit, subject };
+    }
+    stderr.into()
+}
+
+/// Runs a rebase step (`--continue` or `--skip`) and sorts out where it stopped: another
+/// conflict, a commit with nothing left to commit, or the end of the rebase.
+fn run_step(path: &str, step: &str, activity: &ActivityLog) -> Result<RepoOperation, CommandError> {
+    let result = run_git_no_editor(path, &["rebase", step]);
+    let label = if step == "--skip" { "Skip rebased commit" } else { "Continue rebase" };
+    activity.record(path, Operation::new(label), &result);
+    if let Err(stderr) = result {
+        return Err(rebase_error(path, stderr));
+    }
+    Ok(get_repo_operation_state(path)?)
+}
+
+/// Carries on with a stopped rebase. Conflicted files that no longer contain conflict markers
+/// are staged first; any that still do are returned as `UnresolvedConflicts`. When the
+/// resolution left the commit empty, `RebaseNothingToCommit` suggests `rebase_skip`. Returns
+/// the state afterwards: `None` once the rebase is done.
+#[tauri::command]
+pub fn rebase_continue(
+    path: &str,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<RepoOperation, CommandError> {
+    repos.ensure_writable(path)?;
+    require_rebase(path)?;
+    let (unresolved, resolved): (Vec<String>, Vec<String>) =
+        conflicted_files(path).into_iter().partition(|f| has_conflict_markers(path, f));
+    if !unresolved.is_empty() {
+        return Err(CommandError::UnresolvedConflicts { files: unresolved });
+    }
+    if !resolved.is_empty() {
+        let mut args = vec!["add", "-A", "--"];
+        args.extend(resolved.iter().map(String::as_str));
+        run_git(path, &args)?;
+    }
+    run_step(path, "--continue", &activity)
+}
+
+/// Drops the commit the rebase stopped on and carries on with the rest.
+#[tauri::command]
+pub fn rebase_skip(
+    path: &str,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<RepoOperation, CommandError> {
+    repos.ensure_writable(path)?;
+    require_rebase(path)?;
+    run_step(path, "--skip", &activity)
+}
+
+/// Gives up on the rebase and puts the branch back where it was before it started.
+#[tauri::command]
+pub fn rebase_abort(
+    path: &str,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<(), CommandError> {
+    repos.ensure_writable(path)?;
+    let result = run_git(path, &["rebase", "--abort"]);
+    activity.record(path, Operation::new("Abort rebase"), &result);
+    result?;
+    Ok(())
+}
+
+
+/// Resolves `base` and lists the commits after it on the current branch, oldest first. Merges
+/// and commits already on the upstream branch are refused, since rebasing would flatten or
+/// rewrite them.
+fn rebase_range(path: &str, base: &str) -> Result<(String, Vec<CommitEntry>), CommandError> {
+    let base = run_git(path, &["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", base)])
+        .map(|b| b.trim().to_string())
+        .map_err(|_| format!("{} isn't a commit", base))?;
+    if run_git(path, &["merge-base", "--is-ancestor", &base, "HEAD"]).is_err() {
+        return Err(format!("{} isn't on the current branch", &base[..7]).into());
+    }
+    let range = format!("{}..HEAD", base);
+    let merges = run_git(path, &["rev-list", "--merges", "--count", &range])?;
+    if merges.trim() != "0" {
+        return Err("There are merge commits after that commit, which can't be rebased this way".to_string().into());
+    }
+    let commits = parse_entries(&run_git(path, &["log", "--reverse", ENTRY_FORMAT, &range])?);
+
+    // Commits reachable from the upstream but not from the base are the pushed ones
+    if let Ok(upstream) = run_git(path, &["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{upstream}"]) {
+        let on_upstream = run_git(path, &["rev-list", &format!("{}..@{{upstream}}", base)])?;
+        let on_upstream: HashSet<&str> = on_upstream.lines().collect();
+        let pushed: Vec<String> =
+            commits.iter().filter(|c| on_upstream.contains(c.sha.as_str())).map(|c| c.sha.clone()).collect();
+        if !pushed.is_empty() {
+            return Err(CommandError::RebasingPushedCommits { upstream: upstream.trim().to_string(), commits: pushed });
+        }
+    }
+    Ok((base, commits))
+}
+
+/// The commits after `base` that a rebase plan covers, oldest first (the order git replays
+/// them in).
+#[tauri::command]
+pub fn get_rebase_plan(path: &str, base: &str) -> Result<Vec<CommitEntry>, CommandError> {
+    Ok(rebase_range(path, base)?.1)
+}
+
+/// Quotes `value` for the shell git runs editors and `exec` lines in.
+fn shell_quote(value: &Path) -> String {
+    let value = value.to_string_lossy().replace('\\', "/");
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Writes the todo list for `plan` into `dir`, along with a message file for each commit
+/// whose message is replaced; an `exec` line amends that message in once the commit and
+/// anything squashed into it have been replayed.
+fn write_todo(dir: &Path, plan: &[(String, &RebaseAction)]) -> Result<std::path::PathBuf, String> {
+    let mut todo = String::new();
+    let mut message: Option<std::path::PathBuf> = None;
+    let amend = |todo: &mut String, message: &mut Option<std::path::PathBuf>| {
+        if let Some(file) = message.take() {
+            todo.push_str(&format!("exec git commit --amend --allow-empty --no-verify --quiet -F {}\n", shell_quote(&file)));
+        }
+    };
+    for (i, (sha, action)) in plan.iter().enumerate() {
+        let word = match action.action {
+            RebaseCommand::Pick | RebaseCommand::Reword => "pick",
+            RebaseCommand::Squash => "squash",
+            RebaseCommand::Fixup => "fixup",
+            RebaseCommand::Drop => "drop",
+        };
+        if word == "pick" {
+            amend(&mut todo, &mut message);
+        }
+        todo.push_str(&format!("{} {}\n", word, sha));
+        if let Some(text) = action.new_message.as_deref().filter(|_| word != "drop") {
+            let file = dir.join(format!("message-{}", i));
+            std::fs::write(&file, text).map_err(|e| e.to_string())?;
+            message = Some(file);
+        }
+    }
+    amend(&mut todo, &mut message);
+    let file = dir.join("todo");
+    std::fs::write(&file, todo).map_err(|e| e.to_string())?;
+    Ok(file)
+}
+
+/// Rewrites the commits after `base` as `plan` says: in the plan's order, each picked,
+/// squashed or fixed up into the one before, reworded or dropped. Every commit after `base`
+/// must appear exactly once. Runs `git rebase -i` with the todo list put in place of the
+/// editor, so nothing opens; a conflict stops it as `RebaseConflict`, to be finished with
+/// `rebase_continue` or undone with `rebase_abort`. Returns the state afterwards: `None` once
+/// the rebase is done.
+#[tauri::command]
+pub fn execute_rebase_plan(
+    path: &str,
+    base: &str,
+    plan: Vec<RebaseAction>,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<RepoOperation, CommandError> {
+    repos.ensure_writable(path)?;
+    if get_repo_operation_state(path)? != RepoOperation::None {
+        return Err("Finish or abort the operation in progress before rebasing".to_string().into());
+    }
+    let (base, commits) = rebase_range(path, base)?;
+
+    let mut seen = HashSet::new();
+    let mut resolved = Vec::new();
+    for action in &plan {
+        let commit = commits
+            .iter()
+            .find(|c| action.hash.len() >= 4 && c.sha.starts_with(&action.hash))
+            .ok_or_else(|| format!("{} isn't one of the commits after the base", action.hash))?;
+        if !seen.insert(commit.sha.as_str()) {
+            return Err(format!("{} appears more than once in the plan", action.hash).into());
+        }
+        if action.action == RebaseCommand::Reword && action.new_message.as_deref().is_none_or(|m| m.trim().is_empty()) {
+            return Err(format!("Rewording \"{}\" needs a new message", commit.subject).into());
+        }
+        resolved.push((commit.sha.clone(), action));
+    }
+    if let Some(missing) = commits.iter().find(|c| !seen.contains(c.sha.as_str())) {
+        return Err(format!("The plan leaves out \"{}\"; drop it explicitly to remove it", missing.subject).into());
+    }
+    match resolved.iter().map(|(_, a)| a.action).find(|a| *a != RebaseCommand::Drop) {
+        None => return Err("The plan drops every commit".to_string().into()),
+        Some(RebaseCommand::Squash | RebaseCommand::Fixup) => {
+            return Err("The first commit kept can't be squashed or fixed up: there's nothing before it to fold into".to_string().into())
+        }
+        _ => {}
+    }
+
+    let dir = git_path(path, PLAN_DIR)?;
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let dir = dir.canonicalize().map_err(|e| e.to_string())?;
+    let todo = write_todo(&dir, &resolved)?;
+
+    let output = build_hidden_cmd("git")
+        .current_dir(path)
+        .args(["rebase", "--interactive", "--no-autosquash", &base])
+        .env("GIT_SEQUENCE_EDITOR", format!("cp {}", shell_quote(&todo)))
+        .env("GIT_EDITOR", "true")
+        .output()
+        .map_err(spawn_error)?;
+    let result = match output.status.success() {
+        true => Ok(()),
+        false => Err(String::from_utf8_lossy(&output.stderr).to_string()),
+    };
+    let op = Operation::new(format!("Rebase {} commits onto {}", commits.len(), &base[..7]));
+    activity.record(path, op, &result);
+    if let Err(stderr) = result {
+        return Err(rebase_error(path, stderr));
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+    Ok(get_repo_operation_state(path)?)
+}
@@ -0,0 +1,146 @@
+use serde::Serialize;
+use tauri::State;
+
+use crate::activity::{ActivityLog, Operation};
+use crate::branches::{checkout_error, ref_name_problem};
+use crate::error::CommandError;
+use crate::history::validate_hash;
+use crate::read_only::ReadOnlyRepos;
+use crate::{git_path, has_commits, run_git};
+
+const DEFAULT_LIMIT: usize = 100;
+
+/// What moved HEAD, from the start of the reflog subject (`commit (amend): ...`,
+/// `reset: moving to HEAD~1`, `rebase (finish): ...`).
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReflogAction {
+    Commit,
+    Amend,
+    Reset,
+    Rebase,
+    Checkout,
+    Merge,
+    Pull,
+    CherryPick,
+    Revert,
+    Other,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReflogEntry {
+    /// Where HEAD pointed after this step
+    hash: String,
+    short_hash: String,
+    /// `HEAD@{n}`, usable anywhere git takes a revision
+    selector: String,
+    action: ReflogAction,
+    /// The reflog subject after the action, such as the commit subject or `moving to HEAD~1`
+    message: String,
+    /// When HEAD moved, as a unix timestamp
+    date: i64,
+}
+
+fn classify(subject: &str) -> (ReflogAction, String) {
+    let (kind, message) = subject.split_once(": ").unwrap_or((subject, ""));
+    let action = match kind {
+        "commit (amend)" => ReflogAction::Amend,
+        _ if kind.starts_with("commit") => ReflogAction::Commit,
+        "reset" => ReflogAction::Reset,
+        _ if kind.starts_with("rebase") => ReflogAction::Rebase,
+        "checkout" => ReflogAction::Checkout,
+        _ if kind.starts_with("merge") => ReflogAction::Merge,
+        _ if kind.starts_with("pull") => ReflogAction::Pull,
+        "cherry-pick" => ReflogAction::CherryPick,
+        "revert" => ReflogAction::Revert,
+        _ => return (ReflogAction::Other, subject.to_string()),
+    };
+    (action, message.to_string())
+}
+
+/// Parses `git reflog --date=unix --format=%H%x00%h%x00%gd%x00%gs`, one entry per line; with
+/// a date format `%gd` comes out as `HEAD@{<timestamp>}`, so the selector is rebuilt from
+/// the entry's position.
+fn parse_reflog(out: &str) -> Vec<ReflogEntry> {
+    out.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, '\0');
+            let (Some(hash), Some(short_hash), Some(stamp), Some(subject)) =
+                (fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                return None;
+            };
+            let date = stamp.split_once("@{").and_then(|(_, t)| t.trim_end_matches('}').parse().ok()).unwrap_or(0);
+            let (action, message) = classify(subject);
+            Some((hash.to_string(), short_hash.to_string(), action, message, date))
+        })
+        .enumerate()
+        .map(|(n, (hash, short_hash, action, message, date))| ReflogEntry {
+            hash,
+            short_hash,
+            selector: format!("HEAD@{{{}}}", n),
+            action,
+            message,
+            date,
+        })
+        .collect()
+}
+
+/// Where HEAD has been, most recent first, for finding commits lost to a reset, rebase or
+/// deleted branch. Empty when there's no history yet or reflogs are turned off.
+#[tauri::command]
+pub fn get_reflog(path: &str, limit: Option<usize>) -> Result<Vec<ReflogEntry>, String> {
+    let has_log = git_path(path, "logs/HEAD").map(|p| p.exists()).unwrap_or(false);
+    if !has_commits(path) || !has_log {
+        return Ok(Vec::new());
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).to_string();
+    let out = run_git(path, &[
+        "reflog",
+        "show",
+        "--date=unix",
+        "--format=%H%x00%h%x00%gd%x00%gs",
+        "-n",
+        &limit,
+        "HEAD",
+        "--",
+    ])?;
+    Ok(parse_reflog(&out))
+}
+
+/// Gets back to a reflog entry's commit: with `as_branch`, a new branch of that name is
+/// created there (nothing is checked out); otherwise HEAD is detached onto it. Returns the
+/// branch name, or the commit HEAD is now detached at.
+#[tauri::command]
+pub fn checkout_reflog_entry(
+    path: &str,
+    hash: &str,
+    as_branch: Option<String>,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<String, CommandError> {
+    repos.ensure_writable(path)?;
+    validate_hash(hash)?;
+    let commit = run_git(path, &["rev-parse", "-q", "--verify", &format!("{}^{{commit}}", hash.trim())])
+        .map_err(|_| format!("'{}' is not a commit in this repository", hash))?;
+    let commit = commit.trim();
+    let short = &commit[..7];
+
+    match as_branch.as_deref().map(str::trim).filter(|b| !b.is_empty()) {
+        Some(branch) => {
+            if let Some(problem) = ref_name_problem(branch) {
+                return Err(format!("Branch names {}", problem).into());
+            }
+            let result = run_git(path, &["branch", "--", branch, commit]);
+            activity.record(path, Operation::new(format!("Rescue {} as branch {}", short, branch)), &result);
+            result?;
+            Ok(branch.to_string())
+        }
+        None => {
+            let result = run_git(path, &["checkout", "--detach", commit]);
+            activity.record(path, Operation::new(format!("Check out {} (detached)", short)), &result);
+            result.map_err(checkout_error)?;
+            Ok(commit.to_string())
+        }
+    }
+}
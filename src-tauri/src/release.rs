@@ -0,0 +1,80 @@
+use serde::Serialize;
+
+use crate::git_cmd;
+
+#[derive(Serialize, Debug)]
+pub struct LastCommit {
+    sha: String,
+    age_seconds: u64,
+    subject: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DescribeInfo {
+    nearest_tag: String,
+    commits_since_tag: u32,
+    dirty: bool,
+}
+
+/// `last_commit` is null on an unborn branch and `describe` is null when no tag is reachable.
+#[derive(Serialize, Debug)]
+pub struct ReleaseInfo {
+    last_commit: Option<LastCommit>,
+    describe: Option<DescribeInfo>,
+}
+
+/// Runs git and returns trimmed stdout, or None when git fails (no commits, no tags).
+fn git_optional(path: &str, args: &[&str]) -> Option<String> {
+    let output = git_cmd(path, args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn parse_last_commit(out: &str) -> Option<LastCommit> {
+    let mut fields = out.splitn(3, '\0');
+    let sha = fields.next()?.to_string();
+    let timestamp: u64 = fields.next()?.parse().ok()?;
+    let subject = fields.next().unwrap_or_default().to_string();
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Some(LastCommit {
+        sha,
+        // Committer clocks can be ahead of ours
+        age_seconds: now.saturating_sub(timestamp),
+        subject,
+    })
+}
+
+/// Parses `<tag>-<count>-g<sha>[-dirty]` from the right, since tags may contain dashes.
+fn parse_describe(out: &str) -> Option<DescribeInfo> {
+    let (rest, dirty) = match out.strip_suffix("-dirty") {
+        Some(rest) => (rest, true),
+        None => (out, false),
+    };
+    let mut parts = rest.rsplitn(3, '-');
+    let _sha = parts.next()?.strip_prefix('g')?;
+    let commits_since_tag = parts.next()?.parse().ok()?;
+    let nearest_tag = parts.next()?.to_string();
+    Some(DescribeInfo { nearest_tag, commits_since_tag, dirty })
+}
+
+/// Time since the last commit and distance from the nearest tag, for the status bar.
+#[tauri::command]
+pub fn get_release_info(path: &str) -> Result<ReleaseInfo, String> {
+    let last_commit = git_optional(path, &["log", "-1", "--format=%H%x00%ct%x00%s"])
+        .as_deref()
+        .and_then(parse_last_commit);
+    let describe = match last_commit {
+        Some(_) => git_optional(path, &["describe", "--tags", "--long", "--dirty"])
+            .as_deref()
+            .and_then(parse_describe),
+        None => None,
+    };
+
+    Ok(ReleaseInfo { last_commit, describe })
+}
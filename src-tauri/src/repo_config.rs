@@ -0,0 +1,64 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use crate::commit_vocabulary::CommitType;
+
+pub const REPO_CONFIG_FILE: &str = ".gitpop.toml";
+
+/// Per-repository settings read from `.gitpop.toml` at the work tree root. Every section is
+/// optional; anything left out falls back to the built-in defaults.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct RepoConfig {
+    pub commit_checks: CommitChecksConfig,
+    /// `[snippets]`: commit message templates shared with everyone working on the repo
+    pub snippets: BTreeMap<String, String>,
+    /// `[[commit_types]]` with `name` and `description`: replaces the conventional-commit types
+    pub commit_types: Option<Vec<CommitType>>,
+    /// Scopes allowed in `type(scope): ...`; any scope is accepted when unset
+    pub allowed_scopes: Option<Vec<String>>,
+}
+
+/// `[commit_checks]`: a list given here replaces the default list rather than extending it.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct CommitChecksConfig {
+    pub sensitive_patterns: Vec<String>,
+    pub ignored_patterns: Vec<String>,
+    /// Paths matching these are never flagged, e.g. `.env.example`.
+    pub allow_patterns: Vec<String>,
+    pub max_file_size_mb: f64,
+}
+
+impl Default for CommitChecksConfig {
+    fn default() -> Self {
+        let list = |items: &[&str]| items.iter().map(|s| s.to_string()).collect();
+        CommitChecksConfig {
+            sensitive_patterns: list(&[
+                ".env", ".env.*", "*.pem", "*.key", "*.p12", "*.pfx", "*.jks", "*.keystore",
+                "id_rsa", "id_dsa", "id_ecdsa", "id_ed25519", "credentials", "credentials.*",
+                "*.tfvars", ".npmrc", ".pypirc", ".netrc", "secrets.*",
+            ]),
+            ignored_patterns: list(&[
+                "node_modules", "dist", "build", "target", ".DS_Store", "Thumbs.db",
+                "__pycache__", "*.pyc", ".idea", "*.log",
+            ]),
+            allow_patterns: list(&["*.example", "*.sample", "*.template"]),
+            max_file_size_mb: 5.0,
+        }
+    }
+}
+
+/// Loads `.gitpop.toml` from the root of the repository containing `path`. A missing file
+/// yields the defaults; a malformed one is an error so typos don't silently disable checks.
+pub fn load(path: &str) -> Result<RepoConfig, String> {
+    let root = crate::backend::active().repo_root(path)?;
+    let file = std::path::Path::new(&root).join(REPO_CONFIG_FILE);
+
+    let contents = match std::fs::read_to_string(&file) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(RepoConfig::default()),
+        Err(e) => return Err(format!("Failed to read {}: {}", REPO_CONFIG_FILE, e)),
+    };
+    toml::from_str(&contents).map_err(|e| format!("Invalid {}: {}", REPO_CONFIG_FILE, e))
+}
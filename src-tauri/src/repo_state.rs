@@ -0,0 +1,85 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::run_git;
+
+/// An operation git stopped in the middle of, waiting for the user to finish or abort it.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RepoOperation {
+    None,
+    Merging,
+    /// Replaying commit `current` of `total` onto commit `onto`
+    Rebasing { current: Option<u32>, total: Option<u32>, onto: Option<String> },
+    CherryPicking,
+    Reverting,
+    Bisecting,
+}
+
+/// The git directory for the work tree containing `path`, found by walking up to the `.git`
+/// directory, or following a `.git` file (`gitdir: ...`) for linked worktrees and submodules.
+fn find_git_dir(path: &str) -> Option<PathBuf> {
+    for dir in Path::new(path).ancestors() {
+        let dot_git = dir.join(".git");
+        if dot_git.is_dir() {
+            return Some(dot_git);
+        }
+        if dot_git.is_file() {
+            let contents = std::fs::read_to_string(&dot_git).ok()?;
+            let target = contents.trim().strip_prefix("gitdir:")?.trim();
+            return Some(dir.join(target));
+        }
+    }
+    None
+}
+
+fn read_number(file: PathBuf) -> Option<u32> {
+    std::fs::read_to_string(file).ok()?.trim().parse().ok()
+}
+
+/// Reads a rebase's progress from its state directory: `msgnum`/`end` for the merge backend
+/// (`rebase-merge`), `next`/`last` for the apply backend (`rebase-apply`).
+fn rebase_progress(dir: &Path, current: &str, total: &str) -> RepoOperation {
+    RepoOperation::Rebasing {
+        current: read_number(dir.join(current)),
+        total: read_number(dir.join(total)),
+        onto: std::fs::read_to_string(dir.join("onto")).ok().map(|s| s.trim().to_string()),
+    }
+}
+
+fn operation_in(git_dir: &Path) -> RepoOperation {
+    let rebase_merge = git_dir.join("rebase-merge");
+    if rebase_merge.is_dir() {
+        return rebase_progress(&rebase_merge, "msgnum", "end");
+    }
+    let rebase_apply = git_dir.join("rebase-apply");
+    // `git am` keeps its state in the same directory and marks it with `applying`
+    if rebase_apply.is_dir() && !rebase_apply.join("applying").exists() {
+        return rebase_progress(&rebase_apply, "next", "last");
+    }
+    if git_dir.join("MERGE_HEAD").exists() {
+        return RepoOperation::Merging;
+    }
+    if git_dir.join("CHERRY_PICK_HEAD").exists() {
+        return RepoOperation::CherryPicking;
+    }
+    if git_dir.join("REVERT_HEAD").exists() {
+        return RepoOperation::Reverting;
+    }
+    if git_dir.join("BISECT_LOG").exists() {
+        return RepoOperation::Bisecting;
+    }
+    RepoOperation::None
+}
+
+/// The merge, rebase, cherry-pick, revert or bisect in progress, if any. Only reads the git
+/// directory (no git process unless it can't be found on disk), since the frontend polls it
+/// on every refresh.
+#[tauri::command]
+pub fn get_repo_operation_state(path: &str) -> Result<RepoOperation, String> {
+    let git_dir = match find_git_dir(path) {
+        Some(dir) => dir,
+        None => PathBuf::from(run_git(path, &["rev-parse", "--absolute-git-dir"])?.trim()),
+    };
+    Ok(operation_in(&git_dir))
+}
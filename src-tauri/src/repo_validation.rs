@@ -0,0 +1,81 @@
+use serde::Serialize;
+use std::path::Path;
+
+use crate::backend::git_found;
+use crate::error::CommandError;
+use crate::{build_hidden_cmd, has_commits, run_git, spawn_error};
+
+#[derive(Serialize, Debug, Default)]
+pub struct RepoValidation {
+    pub(crate) is_repo: bool,
+    /// Work tree root; null outside a repository and for bare repositories
+    pub(crate) root: Option<String>,
+    is_bare: bool,
+    /// A linked worktree (`git worktree add`) rather than the main one
+    is_worktree: bool,
+    /// Absolute path of the git directory; empty when `is_repo` is false
+    pub(crate) git_dir: String,
+    /// False on a freshly initialized repository whose branch is still unborn
+    has_commits: bool,
+    /// The branch `origin/HEAD` points at, or else the branch checked out (which, with no
+    /// commits yet, is the one `init.defaultBranch` picked)
+    default_branch: Option<String>,
+}
+
+fn default_branch(path: &str) -> Option<String> {
+    let remote = run_git(path, &["symbolic-ref", "--quiet", "--short", "refs/remotes/origin/HEAD"]).ok();
+    if let Some(branch) = remote.as_deref().and_then(|r| r.trim().strip_prefix("origin/")) {
+        return Some(branch.to_string());
+    }
+    run_git(path, &["symbolic-ref", "--quiet", "--short", "HEAD"]).ok().map(|b| b.trim().to_string())
+}
+
+/// What kind of folder `path` is, checked before a folder is opened: a repository (bare, a
+/// linked worktree, without commits) or a plain folder that could be initialized. A missing
+/// path or a file is an error of its own.
+#[tauri::command]
+pub fn validate_repo(path: &str) -> Result<RepoValidation, CommandError> {
+    let dir = Path::new(path);
+    if !dir.exists() {
+        return Err(CommandError::PathNotFound { path: path.to_string() });
+    }
+    if !dir.is_dir() {
+        return Err(CommandError::NotADirectory { path: path.to_string() });
+    }
+    if !git_found() {
+        return Err(CommandError::GitNotInstalled);
+    }
+
+    // `--show-toplevel` fails in a bare repository, so it's asked for separately
+    let output = build_hidden_cmd("git")
+        .current_dir(path)
+        .args(["rev-parse", "--is-bare-repository", "--absolute-git-dir", "--git-common-dir"])
+        .output()
+        .map_err(spawn_error)?;
+    if !output.status.success() {
+        return Ok(RepoValidation::default());
+    }
+    let out = String::from_utf8_lossy(&output.stdout);
+    let mut lines = out.lines();
+    let (Some(bare), Some(git_dir), Some(common_dir)) = (lines.next(), lines.next(), lines.next()) else {
+        return Err(format!("Unexpected output from git rev-parse: {}", out).into());
+    };
+    let is_bare = bare == "true";
+    // The common directory is relative to `path` unless it's elsewhere
+    let common_dir = dir.join(common_dir).canonicalize().ok();
+    let is_worktree = common_dir.is_some_and(|common| Path::new(git_dir).canonicalize().ok() != Some(common));
+    let root = match is_bare {
+        true => None,
+        false => run_git(path, &["rev-parse", "--show-toplevel"]).ok().map(|r| r.trim().to_string()),
+    };
+
+    Ok(RepoValidation {
+        is_repo: true,
+        root,
+        is_bare,
+        is_worktree,
+        git_dir: git_dir.to_string(),
+        has_commits: has_commits(path),
+        default_branch: default_branch(path),
+    })
+}
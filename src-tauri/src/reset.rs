@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::activity::{ActivityLog, Operation};
+use crate::error::CommandError;
+use crate::read_only::ReadOnlyRepos;
+use crate::{refuse_during_operation, require_commits, run_git};
+
+/// Message of the stash entry a hard reset saves the work tree in.
+const SNAPSHOT_LABEL: &str = "GitPop reset snapshot";
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ResetMode {
+    /// Moves the branch only; the undone commits' changes end up staged
+    Soft,
+    /// Also resets the index; the changes stay in the work tree, unstaged
+    Mixed,
+    /// Also resets the work tree, after saving it in a snapshot
+    Hard,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ResetResult {
+    old_head: String,
+    new_head: String,
+    /// Stash commit holding the work tree, index and untracked files from before a hard
+    /// reset; null when there were no changes to save. `git reset --hard <old_head>` followed
+    /// by `git stash apply --index <snapshot>` brings back the exact previous state.
+    snapshot: Option<String>,
+}
+
+/// Stashes every local change, untracked files included, as a "GitPop reset snapshot" entry.
+fn take_snapshot(path: &str) -> Result<Option<String>, String> {
+    let out = run_git(path, &["stash", "push", "--include-untracked", "-m", SNAPSHOT_LABEL])?;
+    if out.contains("No local changes to save") {
+        return Ok(None);
+    }
+    Ok(Some(run_git(path, &["rev-parse", "stash@{0}"])?.trim().to_string()))
+}
+
+/// Moves the current branch to `hash`. A hard reset first saves all local changes in a
+/// snapshot stash entry, so nothing is lost for good; see `ResetResult::snapshot`.
+#[tauri::command]
+pub fn reset_to_commit(
+    path: &str,
+    hash: &str,
+    mode: ResetMode,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<ResetResult, CommandError> {
+    repos.ensure_writable(path)?;
+    require_commits(path)?;
+    refuse_during_operation(path)?;
+    if hash.starts_with('-') {
+        return Err(format!("Invalid revision: {}", hash).into());
+    }
+    let target = run_git(path, &["rev-parse", "--verify", "-q", &format!("{}^{{commit}}", hash)])
+        .map_err(|_| format!("'{}' is not a commit in this repository", hash))?
+        .trim()
+        .to_string();
+    let old_head = run_git(path, &["rev-parse", "HEAD"])?.trim().to_string();
+
+    let (flag, verb) = match mode {
+        ResetMode::Soft => ("--soft", "Soft reset"),
+        ResetMode::Mixed => ("--mixed", "Reset"),
+        ResetMode::Hard => ("--hard", "Hard reset"),
+    };
+    let mut op = Operation::new(format!("{} to {}", verb, &target[..7]));
+    let result = (|| {
+        let snapshot = match mode {
+            ResetMode::Hard => {
+                let snapshot = take_snapshot(path);
+                op.step("Save local changes", &snapshot);
+                snapshot?
+            }
+            _ => None,
+        };
+        let reset = run_git(path, &["reset", "-q", flag, &target]);
+        op.step(format!("git reset {}", flag), &reset);
+        if let Err(e) = reset {
+            // Put the work tree back the way it was rather than leave it stashed
+            if let Some(snapshot) = &snapshot {
+                let restored = run_git(path, &["stash", "pop", "--index", "-q"]);
+                op.step(format!("Restore snapshot {}", &snapshot[..7]), &restored);
+            }
+            return Err(e);
+        }
+        Ok(ResetResult { old_head: old_head.clone(), new_head: target.clone(), snapshot })
+    })();
+    activity.record(path, op, &result);
+    Ok(result?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestRepo;
+    use tauri::Manager;
+
+    /// Two commits: `a.txt` changed and `b.txt` added in the second. Returns the first.
+    fn two_commits() -> (TestRepo, String) {
+        let repo = TestRepo::with_commit();
+        let first = repo.head();
+        repo.write("a.txt", "two\n");
+        repo.write("b.txt", "b\n");
+        repo.commit_all("Second");
+        (repo, first)
+    }
+
+    fn reset(repo: &TestRepo, hash: &str, mode: ResetMode) -> Result<ResetResult, CommandError> {
+        let app = repo.app();
+        reset_to_commit(repo.path(), hash, mode, app.state(), app.state())
+    }
+
+    #[test]
+    fn soft_reset_keeps_the_changes_staged() {
+        let (repo, first) = two_commits();
+        let second = repo.head();
+
+        let result = reset(&repo, &first[..7], ResetMode::Soft).unwrap();
+        assert_eq!((result.old_head, result.new_head.as_str(), result.snapshot), (second, first.as_str(), None));
+        assert_eq!(repo.head(), first);
+        assert_eq!(repo.short_status(), "M  a.txt\nA  b.txt\n");
+        assert_eq!(repo.git(&["show", ":a.txt"]), "two\n");
+    }
+
+    #[test]
+    fn mixed_reset_unstages_the_changes() {
+        let (repo, first) = two_commits();
+
+        reset(&repo, &first, ResetMode::Mixed).unwrap();
+        assert_eq!(repo.head(), first);
+        assert_eq!(repo.short_status(), " M a.txt\n?? b.txt\n");
+        assert_eq!(repo.git(&["show", ":a.txt"]), "one\n");
+        assert_eq!(repo.read("a.txt"), "two\n");
+    }
+
+    #[test]
+    fn hard_reset_saves_a_snapshot_first() {
+        let (repo, first) = two_commits();
+        let second = repo.head();
+        repo.write("a.txt", "staged\n");
+        repo.git(&["add", "a.txt"]);
+        repo.write("a.txt", "unstaged\n");
+        repo.write("untracked.txt", "u\n");
+
+        let snapshot = reset(&repo, &first, ResetMode::Hard).unwrap().snapshot.unwrap();
+        assert_eq!(repo.head(), first);
+        assert_eq!(repo.short_status(), "");
+        assert_eq!(repo.read("a.txt"), "one\n");
+        assert!(repo.git(&["stash", "list", "--format=%gs"]).contains(SNAPSHOT_LABEL));
+
+        // The documented way back gives the exact previous state
+        repo.git(&["reset", "-q", "--hard", &second]);
+        repo.git(&["stash", "apply", "-q", "--index", &snapshot]);
+        assert_eq!(repo.short_status(), "MM a.txt\n?? untracked.txt\n");
+        assert_eq!(repo.git(&["show", ":a.txt"]), "staged\n");
+        assert_eq!(repo.read("a.txt"), "unstaged\n");
+    }
+
+    #[test]
+    fn hard_reset_of_a_clean_tree_has_no_snapshot() {
+        let (repo, first) = two_commits();
+
+        assert!(reset(&repo, &first, ResetMode::Hard).unwrap().snapshot.is_none());
+        assert_eq!(repo.git(&["stash", "list"]), "");
+        assert!(reset(&repo, "--hard", ResetMode::Soft).is_err());
+        assert!(reset(&repo, "no-such-commit", ResetMode::Soft).is_err());
+        assert_eq!(repo.head(), first);
+    }
+}
@@ -0,0 +1,124 @@
+use serde::Serialize;
+use tauri::State;
+
+use crate::activity::{ActivityLog, Operation};
+use crate::error::CommandError;
+use crate::read_only::ReadOnlyRepos;
+use crate::{conflicted_files, git_path, refuse_during_operation, require_commits, run_git, run_git_no_editor};
+
+#[derive(Serialize, Debug)]
+pub struct RevertResult {
+    /// The revert commit, null with `no_commit` (the changes are staged instead)
+    commit: Option<String>,
+    /// Git's "Revert ..." message, to prefill the message box when committing by hand
+    message: String,
+}
+
+/// The message git prepared for the revert it stopped in (conflicts or `--no-commit`).
+fn prepared_message(path: &str) -> String {
+    git_path(path, "MERGE_MSG")
+        .and_then(|p| std::fs::read_to_string(p).map_err(|e| e.to_string()))
+        .map(|msg| {
+            // Drop git's `#` comment lines, such as the list of conflicts
+            msg.lines().filter(|l| !l.starts_with('#')).collect::<Vec<_>>().join("\n").trim_end().to_string()
+        })
+        .unwrap_or_default()
+}
+
+fn head_commit(path: &str) -> Result<RevertResult, String> {
+    let out = run_git(path, &["log", "-1", "--format=%H%x00%B", "HEAD"])?;
+    let (commit, message) = out.split_once('\0').unwrap_or((&out, ""));
+    Ok(RevertResult { commit: Some(commit.to_string()), message: message.trim_end().to_string() })
+}
+
+/// Creates a commit undoing `hash`, or with `no_commit` only stages the reverse changes so they
+/// can be edited and committed normally. A merge is reverted relative to its `mainline` parent,
+/// the first (the branch it was merged into) unless given. When the revert conflicts, the
+/// files are returned as `RevertConflicts`; finish with `revert_continue` or `revert_abort`.
+#[tauri::command]
+pub fn revert_commit(
+    path: &str,
+    hash: &str,
+    no_commit: Option<bool>,
+    mainline: Option<u32>,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<RevertResult, CommandError> {
+    repos.ensure_writable(path)?;
+    require_commits(path)?;
+    refuse_during_operation(path)?;
+    if hash.starts_with('-') {
+        return Err(format!("Invalid revision: {}", hash).into());
+    }
+    let parents = run_git(path, &["rev-list", "--parents", "-n1", &format!("{}^{{commit}}", hash), "--"])
+        .map_err(|_| format!("'{}' is not a commit in this repository", hash))?;
+    let mut ids = parents.split_whitespace();
+    let commit = ids.next().unwrap_or_default().to_string();
+    let parent_count = ids.count() as u32;
+    if parent_count <= 1 && mainline.is_some() {
+        return Err(format!("{} is not a merge, so there is no parent to pick", &commit[..7]).into());
+    }
+
+    let mut args = vec!["revert".to_string(), "--no-edit".to_string()];
+    let no_commit = no_commit.unwrap_or(false);
+    if no_commit {
+        args.push("--no-commit".to_string());
+    }
+    if parent_count > 1 {
+        let mainline = mainline.unwrap_or(1);
+        if mainline == 0 || mainline > parent_count {
+            return Err(format!("The merge has {} parents; pick one of 1 to {}", parent_count, parent_count).into());
+        }
+        args.extend(["-m".to_string(), mainline.to_string()]);
+    }
+    args.push(commit.clone());
+
+    let refs: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = run_git_no_editor(path, &refs);
+    activity.record(path, Operation::new(format!("Revert {}", &commit[..7.min(commit.len())])), &result);
+    if let Err(stderr) = result {
+        let conflicted = conflicted_files(path);
+        if conflicted.is_empty() {
+            return Err(stderr.into());
+        }
+        return Err(CommandError::RevertConflicts { files: conflicted, message: prepared_message(path) });
+    }
+
+    if no_commit {
+        return Ok(RevertResult { commit: None, message: prepared_message(path) });
+    }
+    Ok(head_commit(path)?)
+}
+
+/// Commits a revert that stopped on conflicts, once they are resolved and staged, with git's
+/// prepared message.
+#[tauri::command]
+pub fn revert_continue(
+    path: &str,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<RevertResult, CommandError> {
+    repos.ensure_writable(path)?;
+    let conflicted = conflicted_files(path);
+    if !conflicted.is_empty() {
+        return Err(CommandError::UnresolvedConflicts { files: conflicted });
+    }
+    let result = run_git_no_editor(path, &["revert", "--continue"]);
+    activity.record(path, Operation::new("Continue revert"), &result);
+    result?;
+    Ok(head_commit(path)?)
+}
+
+/// Gives up on a revert in progress and puts HEAD, index and work tree back as they were.
+#[tauri::command]
+pub fn revert_abort(
+    path: &str,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<(), CommandError> {
+    repos.ensure_writable(path)?;
+    let result = run_git(path, &["revert", "--abort"]);
+    activity.record(path, Operation::new("Abort revert"), &result);
+    result?;
+    Ok(())
+}
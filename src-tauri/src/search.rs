@@ -0,0 +1,123 @@
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Read};
+use std::process::Stdio;
+use tauri::State;
+
+use crate::capabilities::{GitCapabilitiesState, GitFeature};
+use crate::error::CommandError;
+use crate::{git_cmd, spawn_error};
+
+/// Matched lines longer than this many characters are cut down around the match.
+const MAX_LINE_LEN: usize = 300;
+
+/// Characters kept before the match when a long line is cut.
+const LEAD_LEN: usize = 40;
+
+/// Used when the caller asks for no limit, or for more than this.
+const MAX_RESULTS: u32 = 2000;
+
+#[derive(Serialize, Debug)]
+pub struct GrepMatch {
+    /// Relative to the work tree root
+    file: String,
+    line: u32,
+    /// 1-based byte offset of the match in the full line, as git reports it
+    column: u32,
+    /// The line, cut down around the match with `…` when it's longer than `MAX_LINE_LEN`
+    text: String,
+}
+
+/// Shortens `text` to `MAX_LINE_LEN` characters, starting a little before the match at byte
+/// `column` so the match stays in view.
+fn clip(text: &str, column: usize) -> String {
+    if text.chars().count() <= MAX_LINE_LEN {
+        return text.to_string();
+    }
+    let before = text.get(..column.saturating_sub(1)).map_or(0, |b| b.chars().count());
+    let start = before.saturating_sub(LEAD_LEN);
+    let mut clipped: String = text.chars().skip(start).take(MAX_LINE_LEN).collect();
+    if start > 0 {
+        clipped.insert(0, '…');
+    }
+    if start + MAX_LINE_LEN < text.chars().count() {
+        clipped.push('…');
+    }
+    clipped
+}
+
+/// Parses one `git grep -z -n --column` line: `<file>\0<line>\0<column>\0<text>`.
+fn parse_match(line: &str) -> Option<GrepMatch> {
+    let mut fields = line.splitn(4, '\0');
+    let file = fields.next()?.to_string();
+    let line = fields.next()?.parse().ok()?;
+    let column: u32 = fields.next()?.parse().ok()?;
+    let text = clip(fields.next()?, column as usize);
+    Some(GrepMatch { file, line, column, text })
+}
+
+fn grep(path: &str, query: &str, regex: bool, case_sensitive: bool, max_results: usize) -> Result<Vec<GrepMatch>, String> {
+    // Untracked files are searched too; ignored ones and binaries aren't
+    let mut args = vec!["grep", "-z", "-n", "--column", "-I", "--untracked", "--full-name"];
+    args.push(if regex { "-E" } else { "-F" });
+    if !case_sensitive {
+        args.push("-i");
+    }
+    args.extend(["-e", query]);
+
+    let mut child = git_cmd(path, &args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(spawn_error)?;
+    let mut matches = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).split(b'\n') {
+            let line = line.map_err(|e| e.to_string())?;
+            if let Some(found) = parse_match(&String::from_utf8_lossy(&line)) {
+                matches.push(found);
+            }
+            if matches.len() >= max_results {
+                // Enough: no need to let git walk the rest of the tree
+                let _ = child.kill();
+                let _ = child.wait();
+                return Ok(matches);
+            }
+        }
+    }
+    let mut stderr = String::new();
+    if let Some(mut err) = child.stderr.take() {
+        let _ = err.read_to_string(&mut stderr);
+    }
+    let status = child.wait().map_err(|e| e.to_string())?;
+    // 1 is git grep's "nothing matched"
+    match status.code() {
+        Some(0) | Some(1) => Ok(matches),
+        _ => Err(stderr.trim().to_string()),
+    }
+}
+
+/// Finds `query` in the work tree's tracked and untracked files, leaving out ignored files and
+/// binaries: as a literal string, or with `regex` as an extended regular expression. Returns
+/// at most `max_results` matching lines, in file order.
+#[tauri::command]
+pub async fn search_working_tree(
+    path: String,
+    query: String,
+    regex: bool,
+    case_sensitive: bool,
+    max_results: u32,
+    caps: State<'_, GitCapabilitiesState>,
+) -> Result<Vec<GrepMatch>, CommandError> {
+    caps.get()?.require(GitFeature::GrepColumn)?;
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let max_results = match max_results {
+        0 => MAX_RESULTS,
+        n => n.min(MAX_RESULTS),
+    } as usize;
+    tauri::async_runtime::spawn_blocking(move || grep(&path, &query, regex, case_sensitive, max_results))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(CommandError::from)
+}
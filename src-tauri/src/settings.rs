@@ -0,0 +1,191 @@
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime};
+use tauri::State;
+use tokio::sync::oneshot;
+
+/// Key holding the checksum of the other keys, so truncated or half-written files are detected.
+const CHECKSUM_KEY: &str = "__checksum";
+/// A lock file older than this is assumed to be left over from a crashed instance.
+const STALE_LOCK_AGE: Duration = Duration::from_secs(10);
+const LOCK_TIMEOUT: Duration = Duration::from_secs(3);
+
+type Settings = Map<String, Value>;
+
+enum SettingsRequest {
+    Get(oneshot::Sender<Result<Settings, String>>),
+    /// Key-level changes; a `null` value removes the key
+    Update(Settings, oneshot::Sender<Result<Settings, String>>),
+}
+
+/// Owns `settings.json`. All reads and writes go through a single worker thread, and each
+/// write re-reads the file under a cross-process lock and merges only the changed keys, so
+/// two GitPop windows never overwrite each other's settings.
+pub struct SettingsService {
+    requests: mpsc::Sender<SettingsRequest>,
+}
+
+impl SettingsService {
+    pub fn start(file: PathBuf) -> Self {
+        let (tx, rx) = mpsc::channel::<SettingsRequest>();
+        std::thread::spawn(move || {
+            for request in rx {
+                match request {
+                    SettingsRequest::Get(reply) => {
+                        let _ = reply.send(Ok(load_verified(&file)));
+                    }
+                    SettingsRequest::Update(changes, reply) => {
+                        let _ = reply.send(apply_changes(&file, changes));
+                    }
+                }
+            }
+        });
+        SettingsService { requests: tx }
+    }
+
+    pub(crate) async fn get(&self) -> Result<Settings, String> {
+        let (reply, rx) = oneshot::channel();
+        self.requests.send(SettingsRequest::Get(reply)).map_err(|e| e.to_string())?;
+        rx.await.map_err(|e| e.to_string())?
+    }
+
+    pub(crate) async fn update(&self, changes: Settings) -> Result<Settings, String> {
+        let (reply, rx) = oneshot::channel();
+        self.requests.send(SettingsRequest::Update(changes, reply)).map_err(|e| e.to_string())?;
+        rx.await.map_err(|e| e.to_string())?
+    }
+}
+
+fn with_suffix(file: &Path, suffix: &str) -> PathBuf {
+    let mut name = file.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn checksum(settings: &Settings) -> String {
+    // Sort the keys so the checksum doesn't depend on the map's iteration order
+    let sorted: BTreeMap<&String, &Value> = settings.iter().collect();
+    let body = serde_json::to_string(&sorted).unwrap_or_default();
+    let digest = Sha256::digest(body.as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Parses a settings file and verifies its checksum. Files written before checksums were
+/// added (by the store plugin) have no checksum key and are accepted as long as they parse.
+fn read_settings(file: &Path) -> Option<Settings> {
+    let text = fs::read_to_string(file).ok()?;
+    let mut settings: Settings = serde_json::from_str(&text).ok()?;
+    match settings.remove(CHECKSUM_KEY) {
+        Some(Value::String(expected)) if expected != checksum(&settings) => None,
+        _ => Some(settings),
+    }
+}
+
+/// Loads the settings, falling back to the `.bak` copy of the last good save (and
+/// restoring the main file from it) when the main file is corrupt.
+fn load_verified(file: &Path) -> Settings {
+    if !file.exists() {
+        return Settings::new();
+    }
+    if let Some(settings) = read_settings(file) {
+        return settings;
+    }
+
+    let backup = with_suffix(file, ".bak");
+    match read_settings(&backup) {
+        Some(settings) => {
+            let _ = write_atomic(file, &settings);
+            settings
+        }
+        None => Settings::new(),
+    }
+}
+
+fn write_atomic(file: &Path, settings: &Settings) -> Result<(), String> {
+    let mut stored = settings.clone();
+    stored.insert(CHECKSUM_KEY.to_string(), Value::String(checksum(settings)));
+    let text = serde_json::to_string_pretty(&stored).map_err(|e| e.to_string())?;
+
+    let tmp = with_suffix(file, ".tmp");
+    fs::write(&tmp, text.as_bytes()).map_err(|e| e.to_string())?;
+    if let Ok(handle) = OpenOptions::new().write(true).open(&tmp) {
+        let _ = handle.sync_all();
+    }
+    fs::rename(&tmp, file).map_err(|e| e.to_string())
+}
+
+/// Cross-process lock, released when dropped.
+struct LockFile(PathBuf);
+
+impl LockFile {
+    fn acquire(file: &Path) -> Result<LockFile, String> {
+        let lock = with_suffix(file, ".lock");
+        let started = SystemTime::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&lock) {
+                Ok(_) => return Ok(LockFile(lock)),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let age = fs::metadata(&lock)
+                        .and_then(|m| m.modified())
+                        .ok()
+                        .and_then(|m| m.elapsed().ok())
+                        .unwrap_or_default();
+                    if age > STALE_LOCK_AGE {
+                        let _ = fs::remove_file(&lock);
+                        continue;
+                    }
+                    if started.elapsed().unwrap_or_default() > LOCK_TIMEOUT {
+                        return Err("Settings are locked by another GitPop window, try again".to_string());
+                    }
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+    }
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+fn apply_changes(file: &Path, changes: Settings) -> Result<Settings, String> {
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let _lock = LockFile::acquire(file)?;
+
+    // Re-read under the lock so keys written by other instances since our last read survive
+    let mut settings = load_verified(file);
+    for (key, value) in changes {
+        if key == CHECKSUM_KEY {
+            continue;
+        }
+        if value.is_null() {
+            settings.remove(&key);
+        } else {
+            settings.insert(key, value);
+        }
+    }
+
+    write_atomic(file, &settings)?;
+    let _ = write_atomic(&with_suffix(file, ".bak"), &settings);
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn get_settings(service: State<'_, SettingsService>) -> Result<Settings, String> {
+    service.get().await
+}
+
+/// Merges `changes` into the stored settings key by key and returns the merged result.
+#[tauri::command]
+pub async fn update_settings(changes: Settings, service: State<'_, SettingsService>) -> Result<Settings, String> {
+    service.update(changes).await
+}
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use std::process::{Command, Stdio};
+
+use crate::run_git;
+
+/// Whether `commit_changes` signs the commit. `Auto` leaves it to `commit.gpgsign`.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignMode {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl SignMode {
+    /// Flag for `git commit`, if any.
+    pub fn flag(self) -> Option<&'static str> {
+        match self {
+            SignMode::Auto => None,
+            SignMode::Always => Some("-S"),
+            SignMode::Never => Some("--no-gpg-sign"),
+        }
+    }
+
+    /// True when a commit made in `path` with this mode will be signed.
+    pub fn signs(self, path: &str) -> bool {
+        match self {
+            SignMode::Always => true,
+            SignMode::Never => false,
+            SignMode::Auto => config(path, &["--bool", "--get", "commit.gpgsign"]).as_deref() == Some("true"),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct SigningConfig {
+    /// `commit.gpgsign`: commits are signed unless `commit_changes` is told not to
+    enabled: bool,
+    /// `user.signingkey`; without it gpg picks a key from the committer email
+    signing_key: Option<String>,
+    /// `gpg.format`: `openpgp` (the default), `ssh` or `x509`
+    format: String,
+}
+
+fn config(path: &str, args: &[&str]) -> Option<String> {
+    run_git(path, &[&["config"], args].concat())
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Lets gpg ask for a passphrase. A console pinentry reads from the terminal named by
+/// `GPG_TTY`, so when GitPop was started from a terminal that one is passed on along with
+/// stdin; graphical pinentries (pinentry-mac, gpg4win) don't need either.
+pub fn allow_pinentry(cmd: &mut Command) {
+    cmd.stdin(Stdio::inherit());
+    #[cfg(unix)]
+    if std::env::var_os("GPG_TTY").is_none() {
+        use std::io::IsTerminal;
+        if std::io::stdin().is_terminal() {
+            let tty = Command::new("tty").stdin(Stdio::inherit()).output();
+            if let Some(out) = tty.ok().filter(|o| o.status.success()) {
+                cmd.env("GPG_TTY", String::from_utf8_lossy(&out.stdout).trim());
+            }
+        }
+    }
+}
+
+/// The signing settings in effect for `path`, so the UI can show whether commits get signed.
+#[tauri::command]
+pub fn get_signing_config(path: &str) -> Result<SigningConfig, String> {
+    run_git(path, &["rev-parse", "--git-dir"])?;
+    Ok(SigningConfig {
+        enabled: SignMode::Auto.signs(path),
+        signing_key: config(path, &["--get", "user.signingkey"]),
+        format: config(path, &["--get", "gpg.format"]).unwrap_or_else(|| "openpgp".to_string()),
+    })
+}
@@ -0,0 +1,196 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tauri::State;
+
+use crate::repo_config;
+use crate::run_git;
+use crate::settings::SettingsService;
+
+/// Settings key holding the user's own snippets as a `{ name: template }` object.
+const SNIPPETS_KEY: &str = "snippets";
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SnippetSource {
+    /// Saved in the app settings, available in every repository
+    User,
+    /// Shared through the `[snippets]` table of the repo's `.gitpop.toml`
+    Repo,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Snippet {
+    name: String,
+    template: String,
+    source: SnippetSource,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RenderedSnippet {
+    text: String,
+    /// One entry per placeholder that couldn't be resolved and was left in the text
+    warnings: Vec<String>,
+}
+
+fn user_snippets(settings: &Map<String, Value>) -> BTreeMap<String, String> {
+    settings
+        .get(SNIPPETS_KEY)
+        .and_then(Value::as_object)
+        .map(|map| {
+            map.iter()
+                .filter_map(|(name, template)| Some((name.clone(), template.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Repo snippets first, then the user's; a user snippet with the same name replaces the
+/// shared one so people can override a team template locally.
+async fn all_snippets(path: Option<&str>, settings: &SettingsService) -> Result<Vec<Snippet>, String> {
+    let mut snippets: BTreeMap<String, Snippet> = BTreeMap::new();
+    if let Some(path) = path {
+        for (name, template) in repo_config::load(path)?.snippets {
+            snippets.insert(name.clone(), Snippet { name, template, source: SnippetSource::Repo });
+        }
+    }
+    for (name, template) in user_snippets(&settings.get().await?) {
+        snippets.insert(name.clone(), Snippet { name, template, source: SnippetSource::User });
+    }
+    Ok(snippets.into_values().collect())
+}
+
+/// Pulls a ticket reference out of a branch name: `feature/ABC-123-login` gives `ABC-123`,
+/// `fix/482-crash` gives `#482`.
+fn ticket_from_branch(branch: &str) -> Option<String> {
+    for segment in branch.split(['/', '_']) {
+        let parts: Vec<&str> = segment.split('-').collect();
+        for pair in parts.windows(2) {
+            let (key, num) = (pair[0], pair[1]);
+            if !key.is_empty()
+                && key.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+                && key.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+                && !num.is_empty()
+                && num.chars().all(|c| c.is_ascii_digit())
+            {
+                return Some(format!("{}-{}", key, num));
+            }
+        }
+    }
+    branch
+        .split('/')
+        .filter_map(|segment| segment.split(['-', '_']).next())
+        .find(|lead| !lead.is_empty() && lead.chars().all(|c| c.is_ascii_digit()))
+        .map(|num| format!("#{}", num))
+}
+
+/// Version from `Cargo.toml` (`[package]` or `[workspace.package]`), falling back to
+/// `package.json`, both at the repository root.
+fn project_version(root: &Path) -> Option<String> {
+    if let Ok(text) = std::fs::read_to_string(root.join("Cargo.toml")) {
+        if let Ok(manifest) = text.parse::<toml::Table>() {
+            let version = manifest
+                .get("package")
+                .and_then(|p| p.get("version"))
+                .or_else(|| manifest.get("workspace")?.get("package")?.get("version"))
+                .and_then(|v| v.as_str());
+            if let Some(version) = version {
+                return Some(version.to_string());
+            }
+        }
+    }
+    let text = std::fs::read_to_string(root.join("package.json")).ok()?;
+    let manifest: Value = serde_json::from_str(&text).ok()?;
+    manifest.get("version")?.as_str().map(str::to_string)
+}
+
+fn resolve_variable(name: &str, path: &str) -> Option<String> {
+    let branch = || {
+        run_git(path, &["rev-parse", "--abbrev-ref", "HEAD"])
+            .ok()
+            .map(|b| b.trim().to_string())
+            .filter(|b| !b.is_empty() && b != "HEAD")
+    };
+    match name {
+        "branch" => branch(),
+        "ticket" => ticket_from_branch(&branch()?),
+        "date" => Some(chrono::Local::now().format("%Y-%m-%d").to_string()),
+        "version_from_cargo_toml" => {
+            let root = run_git(path, &["rev-parse", "--show-toplevel"]).ok()?;
+            project_version(Path::new(root.trim()))
+        }
+        _ => None,
+    }
+}
+
+/// Replaces `{{variable}}` placeholders. Anything that can't be resolved stays in the text
+/// verbatim, so it's obvious in the message box, and is reported as a warning.
+fn render(template: &str, path: &str) -> RenderedSnippet {
+    let mut text = String::with_capacity(template.len());
+    let mut warnings = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        text.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+        let name = after[..end].trim();
+        match resolve_variable(name, path) {
+            Some(value) => text.push_str(&value),
+            None => {
+                text.push_str(&rest[start..start + 2 + end + 2]);
+                warnings.push(format!("Could not resolve {{{{{}}}}}", name));
+            }
+        }
+        rest = &after[end + 2..];
+    }
+    text.push_str(rest);
+
+    RenderedSnippet { text, warnings }
+}
+
+#[tauri::command]
+pub async fn list_snippets(path: Option<String>, settings: State<'_, SettingsService>) -> Result<Vec<Snippet>, String> {
+    all_snippets(path.as_deref(), &settings).await
+}
+
+/// Saves (or with an empty template, deletes) one of the user's own snippets.
+#[tauri::command]
+pub async fn save_snippet(name: String, template: String, settings: State<'_, SettingsService>) -> Result<(), String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Snippet name cannot be empty".to_string());
+    }
+
+    let mut snippets = user_snippets(&settings.get().await?);
+    if template.is_empty() {
+        snippets.remove(&name);
+    } else {
+        snippets.insert(name, template);
+    }
+
+    let mut changes = Map::new();
+    changes.insert(
+        SNIPPETS_KEY.to_string(),
+        Value::Object(snippets.into_iter().map(|(k, v)| (k, Value::String(v))).collect()),
+    );
+    settings.update(changes).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn render_snippet(name: String, path: String, settings: State<'_, SettingsService>) -> Result<RenderedSnippet, String> {
+    let snippet = all_snippets(Some(&path), &settings)
+        .await?
+        .into_iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| format!("Snippet '{}' not found", name))?;
+
+    tauri::async_runtime::spawn_blocking(move || render(&snippet.template, &path))
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,138 @@
+use tauri::State;
+
+use crate::activity::{ActivityLog, Operation};
+use crate::capabilities::{GitCapabilitiesState, GitFeature};
+use crate::error::CommandError;
+use crate::read_only::ReadOnlyRepos;
+use crate::{decode_path, get_git_status, has_commits, run_git, stage_files, GitFileStatus};
+
+/// The status rows for `paths` after a change: the file itself, or for a directory
+/// (`dir/`) everything in it. Empty when nothing there has changes anymore.
+fn statuses_of(path: &str, paths: &[&str]) -> Result<Vec<GitFileStatus>, String> {
+    Ok(get_git_status(path, None)?
+        .into_iter()
+        .filter(|s| paths.iter().any(|p| s.path == *p || (p.ends_with('/') && s.path.starts_with(p))))
+        .collect())
+}
+
+/// Takes `pathspec` out of the index. Without a HEAD to restore from (no commits yet), the
+/// entries are removed from the index instead, which leaves the files untracked; gits older
+/// than 2.23 use `reset`.
+fn unstage_paths(path: &str, pathspec: &[&str], caps: &GitCapabilitiesState) -> Result<(), String> {
+    let mut args = if !has_commits(path) {
+        vec!["rm", "--cached", "-r", "-q", "--ignore-unmatch", "--"]
+    } else if caps.get()?.supports(GitFeature::Restore) {
+        vec!["restore", "--staged", "--"]
+    } else {
+        vec!["reset", "-q", "--"]
+    };
+    args.extend_from_slice(pathspec);
+    run_git(path, &args).map(|_| ())
+}
+
+/// Stages `file` as it is in the work tree, including a deletion or a whole untracked
+/// directory. Returns the refreshed status rows of what was staged.
+#[tauri::command]
+pub fn stage_file(
+    path: &str,
+    file: &str,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<Vec<GitFileStatus>, CommandError> {
+    repos.ensure_writable(path)?;
+    let file = decode_path(path, file);
+    let result = stage_files(path, std::slice::from_ref(&file), false);
+    activity.record(path, Operation::new(format!("Stage {}", file)), &result);
+    result?;
+    Ok(statuses_of(path, &[&file])?)
+}
+
+/// Unstages `file`, keeping its work tree content. For a staged rename the old path is
+/// unstaged too, so the pair turns back into a deletion and an untracked file; both rows
+/// are returned.
+#[tauri::command]
+pub fn unstage_file(
+    path: &str,
+    file: &str,
+    caps: State<'_, GitCapabilitiesState>,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<Vec<GitFileStatus>, CommandError> {
+    repos.ensure_writable(path)?;
+    let file = decode_path(path, file);
+    let old_path = statuses_of(path, &[&file])?.into_iter().find_map(|s| s.old_path);
+    let mut pathspec = vec![file.as_str()];
+    pathspec.extend(old_path.as_deref());
+
+    let result = unstage_paths(path, &pathspec, &caps);
+    activity.record(path, Operation::new(format!("Unstage {}", file)), &result);
+    result?;
+    Ok(statuses_of(path, &pathspec)?)
+}
+
+/// Stages every change, untracked files included. Returns the refreshed status.
+#[tauri::command]
+pub fn stage_all(
+    path: &str,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<Vec<GitFileStatus>, CommandError> {
+    repos.ensure_writable(path)?;
+    let result = run_git(path, &["add", "-A", "--", "."]);
+    activity.record(path, Operation::new("Stage all changes"), &result);
+    result?;
+    Ok(get_git_status(path, None)?)
+}
+
+/// Unstages everything, keeping the work tree as it is. Returns the refreshed status.
+#[tauri::command]
+pub fn unstage_all(
+    path: &str,
+    caps: State<'_, GitCapabilitiesState>,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<Vec<GitFileStatus>, CommandError> {
+    repos.ensure_writable(path)?;
+    let result = unstage_paths(path, &["."], &caps);
+    activity.record(path, Operation::new("Unstage all changes"), &result);
+    result?;
+    Ok(get_git_status(path, None)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{commit_selection, TestRepo};
+    use tauri::Manager;
+
+    #[test]
+    fn paths_that_look_like_options() {
+        for repo in [TestRepo::with_commit(), TestRepo::init()] {
+            let app = repo.app();
+            repo.write("-n.txt", "n\n");
+            repo.write("--force.txt", "f\n");
+            repo.write("keep.txt", "k\n");
+            repo.git(&["add", "keep.txt"]);
+
+            for file in ["-n.txt", "--force.txt"] {
+                let rows = stage_file(repo.path(), file, app.state(), app.state()).unwrap();
+                assert_eq!(rows.len(), 1);
+                assert_eq!((rows[0].path.as_str(), rows[0].status.as_str(), rows[0].staged), (file, "A", true));
+            }
+            assert_eq!(repo.short_status(), "A  --force.txt\nA  -n.txt\nA  keep.txt\n");
+
+            for file in ["-n.txt", "--force.txt"] {
+                let rows = unstage_file(repo.path(), file, app.state(), app.state(), app.state()).unwrap();
+                assert_eq!((rows[0].status.as_str(), rows[0].staged), ("U", false));
+            }
+            assert_eq!(repo.short_status(), "A  keep.txt\n?? --force.txt\n?? -n.txt\n");
+
+            repo.write("--version", "not git's version\n");
+            let result = commit_selection(&app, repo.path(), &["--version"], false).unwrap();
+            assert_eq!(result.files_committed, 1);
+            assert_eq!(repo.git(&["show", "--format=", "--name-only", "HEAD"]), "--version\n");
+            assert_eq!(repo.git(&["show", "HEAD:--version"]), "not git's version\n");
+            assert_eq!(repo.short_status(), "A  keep.txt\n?? --force.txt\n?? -n.txt\n");
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use serde::Serialize;
+use tauri::State;
+
+use crate::activity::{ActivityLog, Operation};
+use crate::capabilities::{GitCapabilitiesState, GitFeature};
+use crate::error::CommandError;
+use crate::read_only::ReadOnlyRepos;
+use crate::{conflicted_files, require_commits, run_git};
+
+#[derive(Serialize, Debug)]
+pub struct StashEntry {
+    /// N in `stash@{N}`, 0 being the newest
+    index: usize,
+    message: String,
+    /// Branch the stash was made on, null when git recorded it on a detached HEAD
+    branch: Option<String>,
+    /// When the stash was made, as a unix timestamp
+    date: i64,
+}
+
+/// Splits a stash subject, `WIP on main: 1a2b3c4 Fix typo` or `On main: my message`, into
+/// branch and message.
+fn parse_subject(subject: &str) -> (Option<String>, String) {
+    let rest = subject.strip_prefix("WIP on ").or_else(|| subject.strip_prefix("On "));
+    match rest.and_then(|rest| rest.split_once(": ")) {
+        Some((branch, message)) => {
+            let branch = (branch != "(no branch)").then(|| branch.to_string());
+            (branch, message.to_string())
+        }
+        None => (None, subject.to_string()),
+    }
+}
+
+fn parse_stash_list(out: &str) -> Vec<StashEntry> {
+    out.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\0');
+            let index = fields.next()?.strip_prefix("stash@{")?.strip_suffix('}')?.parse().ok()?;
+            let date = fields.next()?.parse().ok()?;
+            let (branch, message) = parse_subject(fields.next().unwrap_or_default());
+            Some(StashEntry { index, message, branch, date })
+        })
+        .collect()
+}
+
+/// The stash entries, newest first.
+#[tauri::command]
+pub fn stash_list(path: &str) -> Result<Vec<StashEntry>, String> {
+    let out = run_git(path, &["stash", "list", "--format=%gd%x00%ct%x00%gs"])?;
+    Ok(parse_stash_list(&out))
+}
+
+/// Stashes the tracked changes, and untracked files too with `include_untracked`, leaving a
+/// clean work tree. Gits older than 2.13 use `stash save`.
+#[tauri::command]
+pub fn stash_save(
+    path: &str,
+    message: Option<String>,
+    include_untracked: Option<bool>,
+    caps: State<'_, GitCapabilitiesState>,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<(), CommandError> {
+    repos.ensure_writable(path)?;
+    require_commits(path)?;
+    let message = message.map(|m| m.trim().to_string()).filter(|m| !m.is_empty());
+
+    let mut args = vec!["stash"];
+    let push = caps.get()?.supports(GitFeature::StashPush);
+    args.push(if push { "push" } else { "save" });
+    if include_untracked.unwrap_or(false) {
+        args.push("--include-untracked");
+    }
+    if let Some(message) = &message {
+        if push {
+            args.push("-m");
+        }
+        args.push(message);
+    }
+
+    let summary = match &message {
+        Some(message) => format!("Stash changes \"{}\"", message),
+        None => "Stash changes".to_string(),
+    };
+    let result = run_git(path, &args).and_then(|out| {
+        // Exits 0 with only this message when there was nothing to stash
+        if out.contains("No local changes to save") {
+            Err("There are no changes to stash".to_string())
+        } else {
+            Ok(())
+        }
+    });
+    activity.record(path, Operation::new(summary), &result);
+    Ok(result?)
+}
+
+/// Runs `stash apply` or `stash pop`; when that stops on conflicts, the conflicted files are
+/// reported instead of git's output. Git keeps the entry in that case, even for `pop`.
+fn unstash(path: &str, subcommand: &str, index: usize, activity: &ActivityLog) -> Result<(), CommandError> {
+    let reference = format!("stash@{{{}}}", index);
+    let verb = if subcommand == "pop" { "Pop" } else { "Apply" };
+    let result = run_git(path, &["stash", subcommand, &reference]);
+    activity.record(path, Operation::new(format!("{} {}", verb, reference)), &result);
+
+    let Err(stderr) = result else {
+        return Ok(());
+    };
+    let conflicted = conflicted_files(path);
+    if conflicted.is_empty() {
+        return Err(stderr.into());
+    }
+    Err(CommandError::StashConflicts { files: conflicted })
+}
+
+/// Applies `stash@{index}` to the work tree and keeps the entry.
+#[tauri::command]
+pub fn stash_apply(
+    path: &str,
+    index: usize,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<(), CommandError> {
+    repos.ensure_writable(path)?;
+    unstash(path, "apply", index, &activity)
+}
+
+/// Applies `stash@{index}` and removes it, unless applying it conflicted.
+#[tauri::command]
+pub fn stash_pop(
+    path: &str,
+    index: usize,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<(), CommandError> {
+    repos.ensure_writable(path)?;
+    unstash(path, "pop", index, &activity)
+}
+
+/// Deletes `stash@{index}`. Returns the dropped stash commit, which `git stash apply <commit>`
+/// can still bring back until git garbage-collects it.
+#[tauri::command]
+pub fn stash_drop(
+    path: &str,
+    index: usize,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<String, CommandError> {
+    repos.ensure_writable(path)?;
+    let reference = format!("stash@{{{}}}", index);
+    let commit = run_git(path, &["rev-parse", "--verify", "-q", &reference])
+        .map_err(|_| format!("There is no {}", reference))?
+        .trim()
+        .to_string();
+    let result = run_git(path, &["stash", "drop", "-q", &reference]);
+    activity.record(path, Operation::new(format!("Drop {} ({})", reference, &commit[..7.min(commit.len())])), &result);
+    result?;
+    Ok(commit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestRepo;
+    use tauri::Manager;
+
+    #[test]
+    fn subjects() {
+        assert_eq!(parse_subject("WIP on main: 1a2b3c4 Fix typo"), (Some("main".to_string()), "1a2b3c4 Fix typo".to_string()));
+        assert_eq!(parse_subject("On feature/login: fix: keep the colon"), (Some("feature/login".to_string()), "fix: keep the colon".to_string()));
+        assert_eq!(parse_subject("WIP on (no branch): 1a2b3c4 Detached"), (None, "1a2b3c4 Detached".to_string()));
+        assert_eq!(parse_subject("autostash"), (None, "autostash".to_string()));
+    }
+
+    #[test]
+    fn stash_list_fields() {
+        let entries = parse_stash_list(
+            "stash@{0}\u{0}1700000100\u{0}On main: second\nstash@{1}\u{0}1700000000\u{0}WIP on (no branch): 1a2b3c4 first\nnot a stash line\n",
+        );
+        let fields: Vec<_> = entries.iter().map(|e| (e.index, e.date, e.branch.as_deref(), e.message.as_str())).collect();
+        assert_eq!(fields, [(0, 1700000100, Some("main"), "second"), (1, 1700000000, None, "1a2b3c4 first")]);
+    }
+
+    #[test]
+    fn stashes_from_a_real_repository() {
+        let repo = TestRepo::with_commit();
+        repo.write("a.txt", "two\n");
+        repo.git(&["stash", "push", "-q", "-m", "named: with colon"]);
+        repo.git(&["checkout", "-q", "--detach"]);
+        repo.write("a.txt", "three\n");
+        repo.git(&["stash", "push", "-q"]);
+
+        let entries = stash_list(repo.path()).unwrap();
+        let fields: Vec<_> = entries.iter().map(|e| (e.index, e.branch.as_deref(), e.message.as_str())).collect();
+        let short = repo.git(&["rev-parse", "--short", "HEAD"]);
+        assert_eq!(
+            fields,
+            [(0, None, format!("{} Initial commit", short.trim()).as_str()), (1, Some("main"), "named: with colon")]
+        );
+    }
+
+    #[test]
+    fn save_list_and_pop() {
+        let repo = TestRepo::with_commit();
+        let app = repo.app();
+        repo.write("a.txt", "two\n");
+        repo.write("new.txt", "untracked\n");
+
+        stash_save(repo.path(), Some(" Half done ".to_string()), Some(true), app.state(), app.state(), app.state()).unwrap();
+        assert_eq!(repo.short_status(), "");
+        assert!(!repo.exists("new.txt"));
+        let entries = stash_list(repo.path()).unwrap();
+        let fields: Vec<_> = entries.iter().map(|e| (e.index, e.branch.as_deref(), e.message.as_str())).collect();
+        assert_eq!(fields, [(0, Some("main"), "Half done")]);
+
+        stash_pop(repo.path(), 0, app.state(), app.state()).unwrap();
+        assert_eq!(repo.short_status(), " M a.txt\n?? new.txt\n");
+        assert_eq!(repo.read("new.txt"), "untracked\n");
+        assert!(stash_list(repo.path()).unwrap().is_empty());
+
+        repo.commit_all("Everything");
+        let nothing = stash_save(repo.path(), None, None, app.state(), app.state(), app.state());
+        assert!(nothing.unwrap_err().to_string().contains("no changes to stash"));
+    }
+
+    #[test]
+    fn popping_onto_conflicting_changes_keeps_the_entry() {
+        let repo = TestRepo::with_commit();
+        let app = repo.app();
+        repo.write("a.txt", "stashed\n");
+        stash_save(repo.path(), None, None, app.state(), app.state(), app.state()).unwrap();
+        repo.write("a.txt", "committed meanwhile\n");
+        repo.commit_all("Change a");
+
+        match stash_pop(repo.path(), 0, app.state(), app.state()) {
+            Err(CommandError::StashConflicts { files }) => assert_eq!(files, ["a.txt"]),
+            other => panic!("expected StashConflicts, got {:?}", other),
+        }
+        assert_eq!(stash_list(repo.path()).unwrap().len(), 1);
+        assert!(repo.read("a.txt").contains("<<<<<<<"));
+    }
+}
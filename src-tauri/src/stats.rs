@@ -0,0 +1,80 @@
+use serde::Serialize;
+
+use crate::{has_commits, run_git};
+
+#[derive(Serialize, Debug)]
+pub struct Contributor {
+    name: String,
+    email: String,
+    commits: u32,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct RepoStats {
+    /// Commits reachable from HEAD
+    total_commits: u32,
+    /// By commit count, most first, with `.mailmap` applied
+    contributors: Vec<Contributor>,
+    /// Commit time of the oldest root commit, as a unix timestamp; `None` without commits
+    first_commit_date: Option<i64>,
+    /// Commit time of HEAD, as a unix timestamp; `None` without commits
+    last_commit_date: Option<i64>,
+    /// Local branches
+    branch_count: u32,
+    tag_count: u32,
+    tracked_file_count: u32,
+}
+
+/// Parses `git shortlog -sne` lines: `<count>\t<name> <<email>>`.
+fn parse_shortlog(out: &str) -> Vec<Contributor> {
+    out.lines()
+        .filter_map(|line| {
+            let (count, author) = line.trim_start().split_once('\t')?;
+            let (name, email) = match author.rsplit_once(" <") {
+                Some((name, email)) => (name, email.trim_end_matches('>')),
+                None => (author, ""),
+            };
+            Some(Contributor {
+                name: name.to_string(),
+                email: email.to_string(),
+                commits: count.trim().parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+fn count_refs(path: &str, prefix: &str) -> Result<u32, String> {
+    Ok(run_git(path, &["for-each-ref", "--format=x", prefix])?.lines().count() as u32)
+}
+
+fn repo_stats(path: &str) -> Result<RepoStats, String> {
+    let mut stats = RepoStats {
+        branch_count: count_refs(path, "refs/heads")?,
+        tag_count: count_refs(path, "refs/tags")?,
+        tracked_file_count: run_git(path, &["ls-files", "-z"])?.split('\0').filter(|f| !f.is_empty()).count() as u32,
+        ..Default::default()
+    };
+    if !has_commits(path) {
+        return Ok(stats);
+    }
+
+    stats.total_commits = run_git(path, &["rev-list", "--count", "HEAD"])?.trim().parse().unwrap_or(0);
+    // Without a revision shortlog reads a log from stdin instead of walking HEAD
+    stats.contributors = parse_shortlog(&run_git(path, &["shortlog", "-sne", "HEAD", "--"])?);
+    stats.last_commit_date = run_git(path, &["log", "-1", "--format=%ct", "HEAD", "--"])?.trim().parse().ok();
+    // A history can have several roots, e.g. after merging in an unrelated one
+    stats.first_commit_date = run_git(path, &["log", "--max-parents=0", "--format=%ct", "HEAD", "--"])?
+        .lines()
+        .filter_map(|t| t.trim().parse().ok())
+        .min();
+    Ok(stats)
+}
+
+/// Commit, contributor, ref and file counts for an overview of the repository. All zero (and
+/// no dates) on a repository without commits.
+#[tauri::command]
+pub async fn get_repo_stats(path: String) -> Result<RepoStats, String> {
+    tauri::async_runtime::spawn_blocking(move || repo_stats(&path))
+        .await
+        .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,279 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use tauri::State;
+
+use crate::settings::SettingsService;
+use crate::{get_git_status, get_sync_status, GitFileStatus, SyncStatus};
+
+const LOCALE_KEY: &str = "locale";
+/// Change sets with more files than this also get a per-directory breakdown.
+const ROLLUP_THRESHOLD: usize = 10;
+/// Directories listed individually in the breakdown; the rest are summed up as "other".
+const MAX_ROLLUPS: usize = 8;
+
+/// Everything the summary is built from, gathered up front so the wording is a pure function.
+pub struct StatusSnapshot<'a> {
+    pub files: &'a [GitFileStatus],
+    pub sync: &'a SyncStatus,
+}
+
+#[derive(Serialize, Debug)]
+pub struct StatusSummary {
+    /// One sentence covering the whole working tree
+    summary: String,
+    /// One sentence per top-level directory, only for large change sets
+    directories: Vec<String>,
+}
+
+/// Thousands separator for `locale` (a BCP 47 tag such as `de-DE`); English style by default.
+fn group_separator(locale: &str) -> &'static str {
+    let lower = locale.to_lowercase();
+    if lower == "de-ch" || lower.starts_with("rm") {
+        return "'";
+    }
+    match lower.split(['-', '_']).next().unwrap_or_default() {
+        "de" | "it" | "es" | "nl" | "pt" | "da" | "id" | "tr" => ".",
+        "fr" | "sv" | "nb" | "no" | "fi" | "pl" | "cs" | "ru" | "uk" => "\u{202f}",
+        _ => ",",
+    }
+}
+
+fn format_number(n: usize, locale: &str) -> String {
+    let digits = n.to_string();
+    let separator = group_separator(locale);
+    let mut out = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push_str(separator);
+        }
+        out.push(digit);
+    }
+    out
+}
+
+/// "1 file", "2 files", "1,204 files".
+fn count(n: usize, singular: &str, plural: &str, locale: &str) -> String {
+    format!("{} {}", format_number(n, locale), if n == 1 { singular } else { plural })
+}
+
+#[derive(Default)]
+struct Counts {
+    modified: usize,
+    added: usize,
+    deleted: usize,
+    renamed: usize,
+    copied: usize,
+    /// Submodules whose checked-out commit or content changed
+    submodules: usize,
+    untracked: usize,
+    conflicted: usize,
+}
+
+impl Counts {
+    fn add(&mut self, file: &GitFileStatus) {
+        match file.status.as_str() {
+            "M" => self.modified += 1,
+            "A" => self.added += 1,
+            "D" => self.deleted += 1,
+            "R" => self.renamed += 1,
+            "C" => self.copied += 1,
+            "S" => self.submodules += 1,
+            "X" => self.conflicted += 1,
+            _ => self.untracked += 1,
+        }
+    }
+
+    /// "3 modified, 2 added, 1 submodule", skipping kinds with no files.
+    fn describe(&self, locale: &str) -> String {
+        [
+            (self.modified, "modified", "modified"),
+            (self.added, "added", "added"),
+            (self.deleted, "deleted", "deleted"),
+            (self.renamed, "renamed", "renamed"),
+            (self.copied, "copied", "copied"),
+            (self.submodules, "submodule", "submodules"),
+            (self.untracked, "untracked", "untracked"),
+            (self.conflicted, "conflicted", "conflicted"),
+        ]
+        .iter()
+        .filter(|(n, _, _)| *n > 0)
+        .map(|(n, singular, plural)| count(*n, singular, plural, locale))
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+}
+
+fn top_directory(path: &str) -> &str {
+    match path.trim_end_matches('/').split_once('/') {
+        Some((dir, _)) => dir,
+        None => "",
+    }
+}
+
+fn sync_phrase(snapshot: &StatusSnapshot, locale: &str) -> String {
+    if !snapshot.sync.has_commits {
+        return "no commits yet".to_string();
+    }
+    match &snapshot.sync.upstream {
+        None => "no upstream branch".to_string(),
+        Some(upstream) if snapshot.sync.ahead == 0 => format!("up to date with {}", upstream),
+        Some(upstream) => format!(
+            "{} ahead of {}",
+            count(snapshot.sync.ahead as usize, "commit", "commits", locale),
+            upstream
+        ),
+    }
+}
+
+/// Builds the spoken-style summary, e.g. "7 files changed on branch feature/login:
+/// 3 modified, 2 added, 1 deleted, 1 conflicted; 2 commits ahead of origin/feature/login".
+pub fn summarize(snapshot: &StatusSnapshot, locale: &str) -> StatusSummary {
+    let branch = &snapshot.sync.branch;
+    let sync = sync_phrase(snapshot, locale);
+    if snapshot.files.is_empty() {
+        return StatusSummary {
+            summary: format!("No changes on branch {}; {}", branch, sync),
+            directories: Vec::new(),
+        };
+    }
+
+    let mut totals = Counts::default();
+    let mut by_directory: BTreeMap<&str, (usize, Counts)> = BTreeMap::new();
+    for file in snapshot.files {
+        totals.add(file);
+        let (files, counts) = by_directory.entry(top_directory(&file.path)).or_default();
+        *files += 1;
+        counts.add(file);
+    }
+
+    let summary = format!(
+        "{} changed on branch {}: {}; {}",
+        count(snapshot.files.len(), "file", "files", locale),
+        branch,
+        totals.describe(locale),
+        sync
+    );
+
+    let mut directories = Vec::new();
+    if snapshot.files.len() > ROLLUP_THRESHOLD {
+        let mut ranked: Vec<_> = by_directory.into_iter().collect();
+        ranked.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then(a.0.cmp(b.0)));
+        let rest: usize = ranked.iter().skip(MAX_ROLLUPS).map(|(_, (files, _))| files).sum();
+        for (dir, (files, counts)) in ranked.iter().take(MAX_ROLLUPS) {
+            let name = if dir.is_empty() { "Top level".to_string() } else { format!("{}/", dir) };
+            directories.push(format!(
+                "{}: {} ({})",
+                name,
+                count(*files, "file", "files", locale),
+                counts.describe(locale)
+            ));
+        }
+        if rest > 0 {
+            directories.push(format!("Other directories: {}", count(rest, "file", "files", locale)));
+        }
+    }
+
+    StatusSummary { summary, directories }
+}
+
+/// A plain-text description of the working tree for screen readers, computed locally.
+#[tauri::command]
+pub async fn get_status_summary(path: String, settings: State<'_, SettingsService>) -> Result<StatusSummary, String> {
+    let locale = settings
+        .get()
+        .await?
+        .get(LOCALE_KEY)
+        .and_then(Value::as_str)
+        .unwrap_or("en")
+        .to_string();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let files = get_git_status(&path, None)?;
+        let sync = get_sync_status(&path)?;
+        let snapshot = StatusSnapshot { files: &files, sync: &sync };
+        Ok(summarize(&snapshot, &locale))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, status: &str) -> GitFileStatus {
+        GitFileStatus {
+            path: path.to_string(),
+            status: status.to_string(),
+            staged: false,
+            old_path: None,
+            index_status: None,
+            worktree_status: None,
+            conflicted: false,
+        }
+    }
+
+    fn tracking(ahead: u32) -> SyncStatus {
+        SyncStatus {
+            ahead,
+            has_upstream: true,
+            upstream: Some("origin/main".to_string()),
+            branch: "main".to_string(),
+            has_commits: true,
+            ..Default::default()
+        }
+    }
+
+    fn summary(files: &[GitFileStatus], sync: &SyncStatus, locale: &str) -> StatusSummary {
+        summarize(&StatusSnapshot { files, sync }, locale)
+    }
+
+    #[test]
+    fn clean_tree() {
+        assert_eq!(summary(&[], &tracking(0), "en").summary, "No changes on branch main; up to date with origin/main");
+
+        let unborn = SyncStatus { branch: "main".to_string(), ..Default::default() };
+        assert_eq!(summary(&[], &unborn, "en").summary, "No changes on branch main; no commits yet");
+        let untracked = SyncStatus { upstream: None, has_upstream: false, ..tracking(2) };
+        assert_eq!(summary(&[], &untracked, "en").summary, "No changes on branch main; no upstream branch");
+    }
+
+    #[test]
+    fn singular_and_plural() {
+        let one = summary(&[file("a.txt", "M")], &tracking(1), "en");
+        assert_eq!(one.summary, "1 file changed on branch main: 1 modified; 1 commit ahead of origin/main");
+        assert!(one.directories.is_empty());
+
+        let files = [file("a.txt", "M"), file("b.txt", "M"), file("c.txt", "A"), file("d.txt", "U"), file("e.txt", "X")];
+        assert_eq!(
+            summary(&files, &tracking(3), "en").summary,
+            "5 files changed on branch main: 2 modified, 1 added, 1 untracked, 1 conflicted; 3 commits ahead of origin/main"
+        );
+    }
+
+    #[test]
+    fn large_change_sets_are_broken_down_by_directory() {
+        let mut files: Vec<GitFileStatus> = (0..1204).map(|i| file(&format!("src/f{}.rs", i), "M")).collect();
+        files.push(file("README.md", "A"));
+        files.push(file("docs/", "U"));
+
+        let result = summary(&files, &tracking(0), "de-DE");
+        assert_eq!(
+            result.summary,
+            "1.206 files changed on branch main: 1.204 modified, 1 added, 1 untracked; up to date with origin/main"
+        );
+        assert_eq!(result.directories, ["src/: 1.204 files (1.204 modified)", "Top level: 2 files (1 added, 1 untracked)"]);
+    }
+
+    #[test]
+    fn submodules_are_not_counted_as_untracked() {
+        let files = [file("a.txt", "M"), file("vendor/lib", "S"), file("vendor/other", "S"), file("new.txt", "U")];
+        let result = summary(&files, &tracking(0), "en");
+        assert_eq!(
+            result.summary,
+            "4 files changed on branch main: 1 modified, 2 submodules, 1 untracked; up to date with origin/main"
+        );
+        assert_eq!(summary(&files[1..2], &tracking(0), "en").summary, "1 file changed on branch main: 1 submodule; up to date with origin/main");
+    }
+}
@@ -0,0 +1,712 @@
+use serde::Serialize;
+use std::io::Read;
+use std::path::Path;
+
+use crate::backend;
+use crate::diff::DiffOptions;
+
+/// Removed/added lines longer than this (in bytes) get no intra-line ranges.
+const MAX_INTRA_LINE_LEN: usize = 1000;
+/// Hunks pairing more removed/added lines than this get no intra-line ranges at all.
+const MAX_INTRA_LINE_PAIRS: usize = 200;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+/// Half-open range of a line's content, in UTF-16 code units so it can be passed straight
+/// to `String.prototype.slice` in the webview.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    start: usize,
+    end: usize,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DiffLine {
+    kind: LineKind,
+    content: String,
+    old_line: Option<u32>,
+    new_line: Option<u32>,
+    /// Words that changed relative to the paired removed/added line; empty when the line
+    /// has no counterpart, changed entirely, or was over the size caps
+    intra_line_ranges: Vec<Range>,
+    /// Followed by git's `\ No newline at end of file`: the line is the last in its side of
+    /// the file and has no trailing newline
+    no_newline: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DiffHunk {
+    header: String,
+    old_start: u32,
+    old_lines: u32,
+    new_start: u32,
+    new_lines: u32,
+    lines: Vec<DiffLine>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DiffFile {
+    /// Path before the change, null for an added file
+    old_path: Option<String>,
+    /// Path after the change, null for a deleted file
+    new_path: Option<String>,
+    /// Highlighter language id such as `rust` or `typescript`, null when unknown
+    language: Option<&'static str>,
+    /// Git reported `Binary files ... differ`, so there are no hunks
+    is_binary: bool,
+    /// `old_path` and `new_path` differ because git detected a rename
+    is_renamed: bool,
+    /// File modes such as `100644` and `100755`, set only when the mode changed; a diff
+    /// with a mode change and no hunks is a pure `chmod`
+    old_mode: Option<String>,
+    new_mode: Option<String>,
+    hunks: Vec<DiffHunk>,
+}
+
+impl DiffFile {
+    /// The path to show: the new one, or the old one for a deleted file.
+    fn path(&self) -> &str {
+        self.new_path.as_deref().or(self.old_path.as_deref()).unwrap_or_default()
+    }
+}
+
+/// Undoes git's C-style quoting of paths with unusual characters (`"caf\303\251.txt"`).
+pub(crate) fn unquote(path: &str) -> String {
+    let Some(inner) = path.strip_prefix('"').and_then(|p| p.strip_suffix('"')) else {
+        return path.to_string();
+    };
+    let mut bytes = Vec::with_capacity(inner.len());
+    let mut chars = inner.bytes().peekable();
+    while let Some(b) = chars.next() {
+        if b != b'\\' {
+            bytes.push(b);
+            continue;
+        }
+        match chars.next() {
+            Some(b'n') => bytes.push(b'\n'),
+            Some(b't') => bytes.push(b'\t'),
+            Some(d @ b'0'..=b'7') => {
+                let mut value = u32::from(d - b'0');
+                for _ in 0..2 {
+                    if let Some(&next @ b'0'..=b'7') = chars.peek() {
+                        value = value * 8 + u32::from(next - b'0');
+                        chars.next();
+                    }
+                }
+                bytes.push(value as u8);
+            }
+            Some(other) => bytes.push(other),
+            None => {}
+        }
+    }
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+/// Strips the `a/` or `b/` prefix from a `---`/`+++` path; `/dev/null` gives `None`.
+fn side_path(raw: &str) -> Option<String> {
+    let path = unquote(raw.trim_end());
+    if path == "/dev/null" {
+        return None;
+    }
+    Some(path.get(2..).unwrap_or_default().to_string())
+}
+
+fn language_for_name(name: &str) -> Option<&'static str> {
+    let lower = name.to_ascii_lowercase();
+    let by_name = match lower.as_str() {
+        "dockerfile" | "containerfile" => Some("dockerfile"),
+        "makefile" | "gnumakefile" => Some("makefile"),
+        "cmakelists.txt" => Some("cmake"),
+        "cargo.lock" => Some("toml"),
+        ".gitignore" | ".gitattributes" | ".dockerignore" => Some("ignore"),
+        ".bashrc" | ".zshrc" | ".profile" => Some("bash"),
+        _ => None,
+    };
+    if by_name.is_some() {
+        return by_name;
+    }
+
+    let (_, ext) = lower.rsplit_once('.')?;
+    Some(match ext {
+        "rs" => "rust",
+        "ts" | "mts" | "cts" => "typescript",
+        "tsx" => "tsx",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "jsx",
+        "py" | "pyw" => "python",
+        "go" => "go",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" | "hxx" => "cpp",
+        "cs" => "csharp",
+        "rb" => "ruby",
+        "php" => "php",
+        "swift" => "swift",
+        "sh" | "bash" => "bash",
+        "zsh" => "zsh",
+        "ps1" | "psm1" => "powershell",
+        "bat" | "cmd" => "batch",
+        "json" | "jsonc" => "json",
+        "toml" => "toml",
+        "yaml" | "yml" => "yaml",
+        "md" | "markdown" => "markdown",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "scss" => "scss",
+        "less" => "less",
+        "sql" => "sql",
+        "xml" | "svg" | "xaml" | "csproj" => "xml",
+        "vue" => "vue",
+        "svelte" => "svelte",
+        "lua" => "lua",
+        "dart" => "dart",
+        "r" => "r",
+        "scala" => "scala",
+        "ex" | "exs" => "elixir",
+        "hs" => "haskell",
+        "clj" | "cljs" => "clojure",
+        "pl" | "pm" => "perl",
+        "ini" | "cfg" => "ini",
+        "gradle" => "groovy",
+        "tf" => "hcl",
+        _ => return None,
+    })
+}
+
+/// Guesses the language of an extensionless file from its shebang or opening tag.
+fn language_from_content(first_line: &str) -> Option<&'static str> {
+    let line = first_line.trim_start_matches('\u{feff}').trim();
+    if let Some(shebang) = line.strip_prefix("#!") {
+        let mut words = shebang.split_whitespace();
+        let mut program = words.next()?.rsplit('/').next()?;
+        if program == "env" {
+            program = words.find(|w| !w.starts_with('-'))?;
+        }
+        let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+        return match program {
+            "sh" | "bash" | "dash" | "ksh" => Some("bash"),
+            "zsh" => Some("zsh"),
+            "python" => Some("python"),
+            "node" | "nodejs" => Some("javascript"),
+            "deno" | "bun" | "ts-node" => Some("typescript"),
+            "ruby" => Some("ruby"),
+            "perl" => Some("perl"),
+            "php" => Some("php"),
+            "lua" => Some("lua"),
+            "pwsh" => Some("powershell"),
+            _ => None,
+        };
+    }
+    let lower = line.to_ascii_lowercase();
+    if lower.starts_with("<?php") {
+        Some("php")
+    } else if lower.starts_with("<?xml") {
+        Some("xml")
+    } else if lower.starts_with("<!doctype html") || lower.starts_with("<html") {
+        Some("html")
+    } else {
+        None
+    }
+}
+
+/// First line of the file on disk, or failing that (deleted files) of the diff, when the
+/// hunk starts at the top of the file.
+fn first_line(root: &Path, file: &DiffFile) -> Option<String> {
+    let mut head = [0u8; 256];
+    if let Ok(mut f) = std::fs::File::open(root.join(file.path())) {
+        let read = f.read(&mut head).unwrap_or(0);
+        let text = String::from_utf8_lossy(&head[..read]);
+        return text.lines().next().map(str::to_string);
+    }
+    let hunk = file.hunks.first()?;
+    let line = hunk.lines.first()?;
+    (line.old_line == Some(1) || line.new_line == Some(1)).then(|| line.content.clone())
+}
+
+fn detect_language(root: &Path, file: &DiffFile) -> Option<&'static str> {
+    let name = file.path().rsplit('/').next().unwrap_or_default();
+    language_for_name(name).or_else(|| {
+        if name.trim_start_matches('.').contains('.') {
+            return None;
+        }
+        language_from_content(&first_line(root, file)?)
+    })
+}
+
+/// `@@ -12,7 +12,8 @@ fn main()` gives ((12, 7), (12, 8)); a missing count means 1.
+pub(crate) fn hunk_ranges(header: &str) -> ((u32, u32), (u32, u32)) {
+    let mut parts = header.split_whitespace().skip(1);
+    let mut range = |prefix: char| {
+        let Some(spec) = parts.next().and_then(|p| p.strip_prefix(prefix)) else {
+            return (0, 0);
+        };
+        let (start, count) = spec.split_once(',').unwrap_or((spec, "1"));
+        (start.parse().unwrap_or(0), count.parse().unwrap_or(0))
+    };
+    let old = range('-');
+    (old, range('+'))
+}
+
+fn parse(diff_text: &str) -> Vec<DiffFile> {
+    let mut files: Vec<DiffFile> = Vec::new();
+    let (mut old_no, mut new_no) = (0u32, 0u32);
+
+    for line in diff_text.lines() {
+        if let Some(header) = line.strip_prefix("diff --git ") {
+            // Refined by the lines below; this covers binary and mode-only changes, where both
+            // sides have the same path
+            let path = unquote(header.rsplit_once(" b/").map(|(_, p)| p).unwrap_or(header));
+            files.push(DiffFile {
+                old_path: Some(path.clone()),
+                new_path: Some(path),
+                language: None,
+                is_binary: false,
+                is_renamed: false,
+                old_mode: None,
+                new_mode: None,
+                hunks: Vec::new(),
+            });
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+
+        if let (false, Some(hunk)) = (line.starts_with("@@"), file.hunks.last_mut()) {
+            let (kind, content) = match line.as_bytes().first() {
+                Some(b' ') => (LineKind::Context, &line[1..]),
+                Some(b'+') => (LineKind::Added, &line[1..]),
+                Some(b'-') => (LineKind::Removed, &line[1..]),
+                None => (LineKind::Context, ""),
+                Some(b'\\') => {
+                    // "\ No newline at end of file" applies to the line before it
+                    if let Some(last) = hunk.lines.last_mut() {
+                        last.no_newline = true;
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            let (old_line, new_line) = match kind {
+                LineKind::Context => (Some(old_no), Some(new_no)),
+                LineKind::Added => (None, Some(new_no)),
+                LineKind::Removed => (Some(old_no), None),
+            };
+            if kind != LineKind::Added {
+                old_no += 1;
+            }
+            if kind != LineKind::Removed {
+                new_no += 1;
+            }
+            hunk.lines.push(DiffLine {
+                kind,
+                content: content.to_string(),
+                old_line,
+                new_line,
+                intra_line_ranges: Vec::new(),
+                no_newline: false,
+            });
+            continue;
+        }
+
+        if line.starts_with("@@") {
+            let ((old_start, old_lines), (new_start, new_lines)) = hunk_ranges(line);
+            old_no = old_start;
+            new_no = new_start;
+            file.hunks.push(DiffHunk {
+                header: line.to_string(),
+                old_start,
+                old_lines,
+                new_start,
+                new_lines,
+                lines: Vec::new(),
+            });
+        } else if let Some(old) = line.strip_prefix("rename from ") {
+            file.old_path = Some(unquote(old));
+            file.is_renamed = true;
+        } else if let Some(new) = line.strip_prefix("rename to ") {
+            file.new_path = Some(unquote(new));
+            file.is_renamed = true;
+        } else if let Some(old) = line.strip_prefix("--- ") {
+            file.old_path = side_path(old);
+        } else if let Some(new) = line.strip_prefix("+++ ") {
+            file.new_path = side_path(new);
+        } else if line.starts_with("new file mode ") {
+            file.old_path = None;
+        } else if line.starts_with("deleted file mode ") {
+            file.new_path = None;
+        } else if let Some(mode) = line.strip_prefix("old mode ") {
+            file.old_mode = Some(mode.to_string());
+        } else if let Some(mode) = line.strip_prefix("new mode ") {
+            file.new_mode = Some(mode.to_string());
+        } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+            file.is_binary = true;
+        }
+    }
+    files
+}
+
+/// Splits a line into words, whitespace runs and single punctuation characters.
+fn tokenize(line: &str) -> Vec<&str> {
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            0
+        } else if c.is_whitespace() {
+            1
+        } else {
+            2
+        }
+    };
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut prev: Option<u8> = None;
+    for (i, c) in line.char_indices() {
+        let current = class(c);
+        if let Some(p) = prev {
+            if p != current || current == 2 {
+                tokens.push(&line[start..i]);
+                start = i;
+            }
+        }
+        prev = Some(current);
+    }
+    if start < line.len() {
+        tokens.push(&line[start..]);
+    }
+    tokens
+}
+
+/// Marks which tokens of `a` and `b` are outside their longest common subsequence.
+fn lcs_changes(a: &[&str], b: &[&str]) -> (Vec<bool>, Vec<bool>) {
+    let (n, m) = (a.len(), b.len());
+    let mut table = vec![0u32; (n + 1) * (m + 1)];
+    let at = |i: usize, j: usize| i * (m + 1) + j;
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[at(i, j)] = if a[i] == b[j] {
+                table[at(i + 1, j + 1)] + 1
+            } else {
+                table[at(i + 1, j)].max(table[at(i, j + 1)])
+            };
+        }
+    }
+
+    let (mut changed_a, mut changed_b) = (vec![true; n], vec![true; m]);
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a[i] == b[j] {
+            changed_a[i] = false;
+            changed_b[j] = false;
+            i += 1;
+            j += 1;
+        } else if table[at(i + 1, j)] >= table[at(i, j + 1)] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    (changed_a, changed_b)
+}
+
+/// Turns per-token change flags into merged UTF-16 ranges.
+fn to_ranges(tokens: &[&str], changed: &[bool]) -> Vec<Range> {
+    let mut ranges: Vec<Range> = Vec::new();
+    let mut offset = 0;
+    for (token, &is_changed) in tokens.iter().zip(changed) {
+        let len = token.encode_utf16().count();
+        if is_changed {
+            match ranges.last_mut() {
+                Some(last) if last.end == offset => last.end += len,
+                _ => ranges.push(Range { start: offset, end: offset + len }),
+            }
+        }
+        offset += len;
+    }
+    ranges
+}
+
+fn intra_line(removed: &str, added: &str) -> Option<(Vec<Range>, Vec<Range>)> {
+    if removed.len() > MAX_INTRA_LINE_LEN || added.len() > MAX_INTRA_LINE_LEN {
+        return None;
+    }
+    let (a, b) = (tokenize(removed), tokenize(added));
+    let (changed_a, changed_b) = lcs_changes(&a, &b);
+    // Nothing but whitespace in common: the line was rewritten, so highlight nothing extra
+    let shared_words = a.iter().zip(&changed_a).any(|(t, &c)| !c && !t.trim().is_empty());
+    if !shared_words {
+        return None;
+    }
+    Some((to_ranges(&a, &changed_a), to_ranges(&b, &changed_b)))
+}
+
+/// Pairs each run of removed lines with the added lines right after it, first to first, and
+/// fills in their intra-line ranges.
+fn add_intra_line_ranges(hunk: &mut DiffHunk) {
+    let mut pairs = Vec::new();
+    let mut i = 0;
+    while i < hunk.lines.len() {
+        let removed_start = i;
+        while i < hunk.lines.len() && hunk.lines[i].kind == LineKind::Removed {
+            i += 1;
+        }
+        let added_start = i;
+        while i < hunk.lines.len() && hunk.lines[i].kind == LineKind::Added {
+            i += 1;
+        }
+        let count = (added_start - removed_start).min(i - added_start);
+        pairs.extend((0..count).map(|k| (removed_start + k, added_start + k)));
+        if i == removed_start {
+            i += 1;
+        }
+    }
+    if pairs.len() > MAX_INTRA_LINE_PAIRS {
+        return;
+    }
+
+    for (r, a) in pairs {
+        if let Some((removed, added)) = intra_line(&hunk.lines[r].content, &hunk.lines[a].content) {
+            hunk.lines[r].intra_line_ranges = removed;
+            hunk.lines[a].intra_line_ranges = added;
+        }
+    }
+}
+
+fn structured_diff(path: &str, staged: bool, file: Option<&str>, options: DiffOptions) -> Result<Vec<DiffFile>, String> {
+    let backend = backend::active();
+    let root = backend.repo_root(path)?;
+    let root = Path::new(&root);
+
+    let mut files = parse(&backend.file_diff(path, file, staged, options)?);
+    for file in &mut files {
+        file.language = detect_language(root, file);
+        file.hunks.iter_mut().for_each(add_intra_line_ranges);
+    }
+    Ok(files)
+}
+
+/// The staged or unstaged changes to `file` (every changed file when null) split into files,
+/// hunks and lines, with a highlighting language per file and word-level change ranges for
+/// modified lines. Untracked files count as unstaged. `context_lines` works as in
+/// `get_git_diff`; with 0 hunks hold only the changed lines.
+#[tauri::command]
+pub async fn get_structured_diff(
+    path: String,
+    staged: bool,
+    file: Option<String>,
+    context_lines: Option<u32>,
+) -> Result<Vec<DiffFile>, String> {
+    let options = DiffOptions { context_lines, ..Default::default() };
+    tauri::async_runtime::spawn_blocking(move || structured_diff(&path, staged, file.as_deref(), options))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestRepo;
+
+    fn kinds(hunk: &DiffHunk) -> Vec<LineKind> {
+        hunk.lines.iter().map(|l| l.kind).collect()
+    }
+
+    #[test]
+    fn unquotes_c_style_paths() {
+        assert_eq!(unquote(r#""caf\303\251.txt""#), "café.txt");
+        assert_eq!(unquote(r#""\346\227\245\346\234\254.md""#), "日本.md");
+        assert_eq!(unquote(r#""tab\there \"quoted\" back\\slash\nnewline""#), "tab\there \"quoted\" back\\slash\nnewline");
+        // Not quoted: left exactly as is, escapes and all
+        assert_eq!(unquote(r"plain\303.txt"), r"plain\303.txt");
+        assert_eq!(unquote("café.txt"), "café.txt");
+        assert_eq!(unquote(r#""unterminated"#), r#""unterminated"#);
+    }
+
+    #[test]
+    fn parses_a_rename_with_changes() {
+        let diff = "\
+diff --git a/src/old name.rs b/src/new name.rs
+similarity index 80%
+rename from src/old name.rs
+rename to src/new name.rs
+index 1111111..2222222 100644
+--- a/src/old name.rs
++++ b/src/new name.rs
+@@ -1,3 +1,3 @@
+ fn main() {
+-    old();
++    new();
+ }
+";
+        let files = parse(diff);
+        assert_eq!(files.len(), 1);
+        let file = &files[0];
+        assert_eq!(file.old_path.as_deref(), Some("src/old name.rs"));
+        assert_eq!(file.new_path.as_deref(), Some("src/new name.rs"));
+        assert!(file.is_renamed);
+        assert!(!file.is_binary);
+        assert_eq!(kinds(&file.hunks[0]), [LineKind::Context, LineKind::Removed, LineKind::Added, LineKind::Context]);
+    }
+
+    #[test]
+    fn parses_a_pure_rename_without_hunks() {
+        let diff = "\
+diff --git a/a.txt b/b.txt
+similarity index 100%
+rename from a.txt
+rename to b.txt
+";
+        let file = &parse(diff)[0];
+        assert_eq!((file.old_path.as_deref(), file.new_path.as_deref()), (Some("a.txt"), Some("b.txt")));
+        assert!(file.is_renamed);
+        assert!(file.hunks.is_empty());
+    }
+
+    #[test]
+    fn numbers_lines_across_multiple_hunks_and_files() {
+        let diff = "\
+diff --git a/a.txt b/a.txt
+index 1111111..2222222 100644
+--- a/a.txt
++++ b/a.txt
+@@ -2,3 +2,4 @@ header context
+ two
++inserted
+ three
+ four
+@@ -20,2 +21 @@
+ twenty
+-gone
+diff --git a/b.txt b/b.txt
+new file mode 100644
+index 0000000..3333333
+--- /dev/null
++++ b/b.txt
+@@ -0,0 +1,2 @@
++first
++second
+";
+        let files = parse(diff);
+        assert_eq!(files.len(), 2);
+
+        let a = &files[0];
+        assert!(!a.is_renamed);
+        assert_eq!(a.hunks.len(), 2);
+        let first = &a.hunks[0];
+        assert_eq!((first.old_start, first.old_lines, first.new_start, first.new_lines), (2, 3, 2, 4));
+        assert_eq!(first.lines[1].old_line, None);
+        assert_eq!(first.lines[1].new_line, Some(3));
+        assert_eq!((first.lines[2].old_line, first.lines[2].new_line), (Some(3), Some(4)));
+        let second = &a.hunks[1];
+        assert_eq!((second.old_start, second.old_lines, second.new_start, second.new_lines), (20, 2, 21, 1));
+        assert_eq!((second.lines[1].old_line, second.lines[1].new_line), (Some(21), None));
+
+        let b = &files[1];
+        assert_eq!(b.old_path, None);
+        assert_eq!(b.new_path.as_deref(), Some("b.txt"));
+        assert_eq!(kinds(&b.hunks[0]), [LineKind::Added, LineKind::Added]);
+        assert_eq!(b.hunks[0].lines[0].new_line, Some(1));
+    }
+
+    #[test]
+    fn marks_lines_without_a_trailing_newline() {
+        let diff = "\
+diff --git a/a.txt b/a.txt
+index 1111111..2222222 100644
+--- a/a.txt
++++ b/a.txt
+@@ -1 +1 @@
+-old
+\\ No newline at end of file
++new
+\\ No newline at end of file
+";
+        let lines = &parse(diff)[0].hunks[0].lines;
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|l| l.no_newline));
+        assert_eq!(lines[1].content, "new");
+    }
+
+    #[test]
+    fn parses_a_mode_only_change() {
+        let diff = "\
+diff --git a/run.sh b/run.sh
+old mode 100644
+new mode 100755
+";
+        let file = &parse(diff)[0];
+        assert_eq!((file.old_path.as_deref(), file.new_path.as_deref()), (Some("run.sh"), Some("run.sh")));
+        assert_eq!((file.old_mode.as_deref(), file.new_mode.as_deref()), (Some("100644"), Some("100755")));
+        assert!(file.hunks.is_empty());
+        assert!(!file.is_renamed);
+    }
+
+    #[test]
+    fn parses_binary_files() {
+        let diff = "\
+diff --git a/logo.png b/logo.png
+new file mode 100644
+index 0000000..4444444
+Binary files /dev/null and b/logo.png differ
+diff --git a/old.bin b/old.bin
+deleted file mode 100644
+index 5555555..0000000
+Binary files a/old.bin and /dev/null differ
+";
+        let files = parse(diff);
+        assert!(files.iter().all(|f| f.is_binary && f.hunks.is_empty()));
+        assert_eq!((files[0].old_path.as_deref(), files[0].new_path.as_deref()), (None, Some("logo.png")));
+        assert_eq!((files[1].old_path.as_deref(), files[1].new_path.as_deref()), (Some("old.bin"), None));
+    }
+
+    #[test]
+    fn hunk_headers_without_counts_or_with_zero_counts() {
+        let diff = "\
+diff --git a/a.txt b/a.txt
+index 1111111..2222222 100644
+--- a/a.txt
++++ b/a.txt
+@@ -3 +3 @@
+-three
++THREE
+@@ -5,0 +6,2 @@ after five
++six
++seven
+";
+        let hunks = &parse(diff)[0].hunks;
+        assert_eq!(hunks.len(), 2);
+        let single = &hunks[0];
+        assert_eq!((single.old_start, single.old_lines, single.new_start, single.new_lines), (3, 1, 3, 1));
+        assert_eq!((single.lines[0].old_line, single.lines[1].new_line), (Some(3), Some(3)));
+        let inserted = &hunks[1];
+        assert_eq!((inserted.old_start, inserted.old_lines, inserted.new_start, inserted.new_lines), (5, 0, 6, 2));
+        assert_eq!(kinds(inserted), [LineKind::Added, LineKind::Added]);
+        let numbers: Vec<_> = inserted.lines.iter().map(|l| (l.old_line, l.new_line)).collect();
+        assert_eq!(numbers, [(None, Some(6)), (None, Some(7))]);
+    }
+
+    #[tokio::test]
+    async fn zero_context_lines_from_a_real_repository() {
+        let repo = TestRepo::init();
+        repo.write("a.txt", "one\ntwo\nthree\nfour\nfive\nsix\n");
+        repo.commit_all("Six lines");
+        repo.write("a.txt", "one\ntwo\nTHREE\nfour\nfive\nsix\nseven\n");
+
+        let files = get_structured_diff(repo.path().to_string(), false, None, Some(0)).await.unwrap();
+        let hunks = &files[0].hunks;
+        let ranges: Vec<_> = hunks.iter().map(|h| (h.old_start, h.old_lines, h.new_start, h.new_lines)).collect();
+        assert_eq!(ranges, [(3, 1, 3, 1), (6, 0, 7, 1)]);
+        assert!(hunks.iter().flat_map(|h| &h.lines).all(|l| l.kind != LineKind::Context));
+        assert_eq!((hunks[1].lines[0].content.as_str(), hunks[1].lines[0].new_line), ("seven", Some(7)));
+
+        let files = get_structured_diff(repo.path().to_string(), false, None, None).await.unwrap();
+        assert_eq!(files[0].hunks.len(), 1);
+        assert!(files[0].hunks[0].lines.iter().any(|l| l.kind == LineKind::Context));
+    }
+}
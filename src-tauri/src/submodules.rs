@@ -0,0 +1,245 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+use crate::activity::{ActivityLog, Operation};
+use crate::error::CommandError;
+use crate::read_only::ReadOnlyRepos;
+use crate::{run_git, run_git_with_progress};
+
+/// Commits of a bumped submodule listed in the AI prompt; the rest are counted.
+const MAX_BUMP_COMMITS: usize = 10;
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmoduleState {
+    /// Checked out at the recorded commit with a clean work tree
+    UpToDate,
+    /// Listed in `.gitmodules` but never cloned; `update_submodules` with `init` fixes it
+    NotInitialized,
+    /// The submodule's HEAD isn't the commit the superproject records, e.g. after a pull
+    CommitDiffers,
+    /// At the recorded commit, but with uncommitted changes inside
+    DirtyWorkTree,
+    /// The recorded commit is conflicted in a merge of the superproject
+    Conflicted,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SubmoduleStatus {
+    path: String,
+    /// Where the submodule is cloned from, per `.gitmodules`
+    url: Option<String>,
+    state: SubmoduleState,
+    /// Commit the superproject records for this submodule
+    recorded: Option<String>,
+    /// Commit checked out inside the submodule, null when it isn't initialized
+    checked_out: Option<String>,
+    /// Uncommitted changes inside the submodule; can be true together with `CommitDiffers`
+    dirty: bool,
+}
+
+/// A submodule whose recorded commit changed in a commit.
+#[derive(Serialize, Debug)]
+pub struct SubmoduleBump {
+    path: String,
+    old: String,
+    new: String,
+}
+
+fn short(sha: &str) -> String {
+    sha.chars().take(7).collect()
+}
+
+impl SubmoduleBump {
+    pub(crate) fn new(path: String, old: &str, new: &str) -> Self {
+        SubmoduleBump { path, old: short(old), new: short(new) }
+    }
+}
+
+fn has_local_changes(root: &str, submodule: &str) -> bool {
+    let dir = Path::new(root).join(submodule);
+    run_git(&dir.to_string_lossy(), &["status", "--porcelain"]).is_ok_and(|out| !out.trim().is_empty())
+}
+
+fn recorded_commit(root: &str, submodule: &str) -> Option<String> {
+    // `ls-files -s` shows the gitlink as `160000 <sha> <stage>\t<path>`
+    let out = run_git(root, &["ls-files", "-s", "--", submodule]).ok()?;
+    out.lines().next()?.split_whitespace().nth(1).map(str::to_string)
+}
+
+/// Submodule URLs from the superproject's `.gitmodules`, by path. Nested submodules are
+/// listed in their parent's `.gitmodules` instead, which is read when they're reached.
+fn urls_by_path(root: &str) -> HashMap<String, String> {
+    let out = run_git(root, &["config", "-f", ".gitmodules", "--get-regexp", r"^submodule\..*\.(path|url)$"])
+        .unwrap_or_default();
+    // Lines are `submodule.<name>.<key> <value>`, and names may contain dots themselves
+    let mut paths = HashMap::new();
+    let mut urls = HashMap::new();
+    for line in out.lines() {
+        let Some((key, value)) = line.split_once(' ') else {
+            continue;
+        };
+        let Some((name, field)) = key.strip_prefix("submodule.").and_then(|k| k.rsplit_once('.')) else {
+            continue;
+        };
+        match field {
+            "path" => paths.insert(name.to_string(), value.to_string()),
+            _ => urls.insert(name.to_string(), value.to_string()),
+        };
+    }
+    paths.into_iter().filter_map(|(name, path)| Some((path, urls.remove(&name)?))).collect()
+}
+
+/// The URL of `submodule` (a path from the repository root), looked up in the `.gitmodules`
+/// of the repository directly containing it.
+fn submodule_url(root: &str, submodule: &str, listed: &mut HashMap<String, HashMap<String, String>>) -> Option<String> {
+    let mut parent = String::new();
+    let mut rest = submodule;
+    // Walk down through the submodules that contain this one
+    loop {
+        let dir = Path::new(root).join(&parent).to_string_lossy().to_string();
+        let urls = listed.entry(parent.clone()).or_insert_with(|| urls_by_path(&dir));
+        if let Some(url) = urls.get(rest) {
+            return Some(url.clone());
+        }
+        let inner = urls.keys().find(|p| rest.starts_with(&format!("{}/", p)))?.clone();
+        rest = &rest[inner.len() + 1..];
+        parent = if parent.is_empty() { inner } else { format!("{}/{}", parent, inner) };
+    }
+}
+
+/// Parses `git submodule status --recursive`, where the first character of each line is
+/// ` ` (in sync), `-` (not initialized), `+` (different commit) or `U` (conflict).
+fn parse_status(root: &str, out: &str) -> Vec<SubmoduleStatus> {
+    let mut submodules = Vec::new();
+    let mut listed = HashMap::new();
+    for line in out.lines() {
+        let Some(flag) = line.chars().next() else {
+            continue;
+        };
+        let mut fields = line[flag.len_utf8()..].split_whitespace();
+        let (Some(sha), Some(path)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let initialized = flag != '-';
+        let dirty = initialized && flag != 'U' && has_local_changes(root, path);
+        let state = match flag {
+            '-' => SubmoduleState::NotInitialized,
+            '+' => SubmoduleState::CommitDiffers,
+            'U' => SubmoduleState::Conflicted,
+            _ if dirty => SubmoduleState::DirtyWorkTree,
+            _ => SubmoduleState::UpToDate,
+        };
+        submodules.push(SubmoduleStatus {
+            path: path.to_string(),
+            url: submodule_url(root, path, &mut listed),
+            state,
+            recorded: recorded_commit(root, path),
+            checked_out: initialized.then(|| sha.to_string()),
+            dirty,
+        });
+    }
+    submodules
+}
+
+#[tauri::command]
+pub fn get_submodule_status(path: &str) -> Result<Vec<SubmoduleStatus>, String> {
+    let root = run_git(path, &["rev-parse", "--show-toplevel"])?.trim().to_string();
+    let out = run_git(&root, &["submodule", "status", "--recursive"])?;
+    Ok(parse_status(&root, &out))
+}
+
+/// Checks submodules out at their recorded commits, cloning them first with `init`.
+/// Progress lines are forwarded as `submodule-progress` events.
+#[tauri::command]
+pub async fn update_submodules(
+    app: AppHandle,
+    path: String,
+    init: bool,
+    recursive: bool,
+) -> Result<Vec<SubmoduleStatus>, CommandError> {
+    app.state::<ReadOnlyRepos>().ensure_writable(&path)?;
+
+    let statuses = tauri::async_runtime::spawn_blocking(move || {
+        let mut args = vec!["submodule", "update", "--progress"];
+        if init {
+            args.push("--init");
+        }
+        if recursive {
+            args.push("--recursive");
+        }
+        let updated = run_git_with_progress(&app, &path, &args, "submodule-progress").and_then(|out| {
+            match out.status.success() {
+                true => Ok(()),
+                false => Err(String::from_utf8_lossy(&out.stderr).to_string()),
+            }
+        });
+        let summary = if init { "Initialize and update submodules" } else { "Update submodules" };
+        app.state::<ActivityLog>().record(&path, Operation::new(summary), &updated);
+        updated?;
+        get_submodule_status(&path)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    Ok(statuses)
+}
+
+/// Submodule pointers that changed in HEAD, for the commit result.
+pub fn bumps_in_head(path: &str) -> Vec<SubmoduleBump> {
+    let Ok(out) = run_git(path, &["diff-tree", "--root", "--no-commit-id", "-r", "--raw", "HEAD"]) else {
+        return Vec::new();
+    };
+    out.lines()
+        .filter_map(|line| {
+            // `:160000 160000 <old> <new> M\t<path>`
+            let (meta, file) = line.strip_prefix(':')?.split_once('\t')?;
+            let fields: Vec<&str> = meta.split_whitespace().collect();
+            if fields.len() < 4 || (fields[0] != "160000" && fields[1] != "160000") {
+                return None;
+            }
+            Some(SubmoduleBump::new(file.to_string(), fields[2], fields[3]))
+        })
+        .collect()
+}
+
+/// Rewrites the `Subproject commit` sections of a diff into a sentence per submodule with the
+/// subjects of the commits it moved over, which models describe far better than two hashes.
+pub fn describe_submodule_bumps(path: &str, diff: &str) -> String {
+    let mut out = String::new();
+    for (i, section) in diff.split("\ndiff --git ").enumerate() {
+        if i > 0 {
+            out.push_str("\ndiff --git ");
+        }
+        let old = section.lines().find_map(|l| l.strip_prefix("-Subproject commit "));
+        let new = section.lines().find_map(|l| l.strip_prefix("+Subproject commit "));
+        let file = section.lines().next().and_then(|h| h.rsplit_once(" b/")).map(|(_, f)| f);
+        let (Some(file), Some(new)) = (file, new) else {
+            out.push_str(section);
+            continue;
+        };
+        let new = new.trim_end_matches("-dirty");
+        let Some(old) = old.map(|o| o.trim_end_matches("-dirty")) else {
+            out.push_str(&format!("a/{} b/{}\nSubmodule {} added at {}\n", file, file, file, short(new)));
+            continue;
+        };
+
+        let dir = Path::new(path).join(file);
+        let log = run_git(&dir.to_string_lossy(), &["log", "--format=%s", &format!("{}..{}", old, new)]).unwrap_or_default();
+        let subjects: Vec<&str> = log.lines().collect();
+        out.push_str(&format!("a/{} b/{}\nSubmodule {} updated from {} to {}", file, file, file, short(old), short(new)));
+        if subjects.is_empty() {
+            out.push('\n');
+            continue;
+        }
+        out.push_str(&format!(" ({} commits):\n", subjects.len()));
+        for subject in subjects.iter().take(MAX_BUMP_COMMITS) {
+            out.push_str(&format!("  - {}\n", subject));
+        }
+        if subjects.len() > MAX_BUMP_COMMITS {
+            out.push_str(&format!("  - ... and {} more\n", subjects.len() - MAX_BUMP_COMMITS));
+        }
+    }
+    out
+}
@@ -0,0 +1,177 @@
+use crate::ai::send_ai_prompt;
+use crate::{has_commits, run_git, AiConfig};
+
+/// Commits beyond this are left out of the prompt (and the offline list) with a note.
+const MAX_SUMMARY_COMMITS: usize = 80;
+
+struct CommitSummary {
+    short_hash: String,
+    date: String,
+    subject: String,
+    body: String,
+    stat: String,
+}
+
+impl CommitSummary {
+    /// Conventional-commit type of the subject (`feat(ui): ...` -> `feat`), if any
+    fn commit_type(&self) -> Option<&str> {
+        let (prefix, _) = self.subject.split_once(':')?;
+        let prefix = prefix.split('(').next()?.trim_end_matches('!');
+        if !prefix.is_empty() && prefix.chars().all(|c| c.is_ascii_alphanumeric()) {
+            Some(prefix)
+        } else {
+            None
+        }
+    }
+}
+
+fn collect_commits(path: &str, since: &str, until: Option<&str>, author: Option<&str>, all_branches: bool) -> Result<Vec<CommitSummary>, String> {
+    let since_arg = format!("--since={}", since);
+    let mut args = vec![
+        "log".to_string(),
+        "--no-merges".to_string(),
+        "--date=short".to_string(),
+        "--shortstat".to_string(),
+        "--format=%x1e%h%x1f%s%x1f%b%x1f%ad".to_string(),
+        since_arg,
+    ];
+    if let Some(until) = until {
+        args.push(format!("--until={}", until));
+    }
+    if let Some(author) = author {
+        args.push("--fixed-strings".to_string());
+        args.push(format!("--author={}", author));
+    }
+    if all_branches {
+        args.push("--all".to_string());
+    }
+
+    let arg_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    let stdout = run_git(path, &arg_refs)?;
+
+    let mut commits = Vec::new();
+    for record in stdout.split('\x1e').filter(|r| !r.trim().is_empty()) {
+        let fields: Vec<&str> = record.splitn(4, '\x1f').collect();
+        if fields.len() < 4 {
+            continue;
+        }
+        // --shortstat output follows the formatted message after a blank line
+        let (date, stat) = fields[3].split_once('\n').unwrap_or((fields[3], ""));
+        commits.push(CommitSummary {
+            short_hash: fields[0].trim().to_string(),
+            subject: fields[1].trim().to_string(),
+            body: fields[2].trim().to_string(),
+            date: date.trim().to_string(),
+            stat: stat.trim().to_string(),
+        });
+    }
+    Ok(commits)
+}
+
+fn theme_title(commit_type: Option<&str>) -> &'static str {
+    match commit_type {
+        Some("feat") => "Features",
+        Some("fix") => "Fixes",
+        Some("docs") => "Documentation",
+        Some("refactor") | Some("style") | Some("perf") => "Refactoring & performance",
+        Some("test") => "Tests",
+        Some("build") | Some("ci") | Some("chore") => "Build & maintenance",
+        _ => "Other",
+    }
+}
+
+/// Offline summary: commits grouped by conventional-commit type as a markdown list.
+fn group_commits_markdown(commits: &[CommitSummary]) -> String {
+    let mut groups: Vec<(&str, Vec<&CommitSummary>)> = Vec::new();
+    for commit in commits {
+        let title = theme_title(commit.commit_type());
+        match groups.iter_mut().find(|(t, _)| *t == title) {
+            Some((_, members)) => members.push(commit),
+            None => groups.push((title, vec![commit])),
+        }
+    }
+
+    let mut markdown = String::new();
+    for (title, members) in groups {
+        markdown.push_str(&format!("### {}\n", title));
+        for commit in members {
+            markdown.push_str(&format!("- {} ({}, {})", commit.subject, commit.short_hash, commit.date));
+            if !commit.stat.is_empty() {
+                markdown.push_str(&format!(" — {}", commit.stat));
+            }
+            markdown.push('\n');
+        }
+        markdown.push('\n');
+    }
+    markdown.trim_end().to_string()
+}
+
+/// Summarizes commits in a date range (e.g. `since: "yesterday"`) for a standup update.
+/// The author defaults to the repo's configured `user.email`; with `no_ai` the commits are
+/// only grouped locally so the command also works offline.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn summarize_commits(
+    path: String,
+    since: String,
+    until: Option<String>,
+    author: Option<String>,
+    all_branches: Option<bool>,
+    no_ai: Option<bool>,
+    config: AiConfig,
+) -> Result<String, String> {
+    let author = match author {
+        Some(a) if !a.trim().is_empty() => Some(a.trim().to_string()),
+        _ => run_git(&path, &["config", "--get", "user.email"])
+            .ok()
+            .map(|e| e.trim().to_string())
+            .filter(|e| !e.is_empty()),
+    };
+
+    if !has_commits(&path) {
+        return Ok("No commits yet.".to_string());
+    }
+
+    let mut commits = collect_commits(&path, &since, until.as_deref(), author.as_deref(), all_branches.unwrap_or(false))?;
+    if commits.is_empty() {
+        return Ok("No commits found in this period.".to_string());
+    }
+
+    let omitted = commits.len().saturating_sub(MAX_SUMMARY_COMMITS);
+    commits.truncate(MAX_SUMMARY_COMMITS);
+    let omitted_note = if omitted > 0 {
+        format!("\n\n_{} older commits were not included._", omitted)
+    } else {
+        String::new()
+    };
+
+    if no_ai.unwrap_or(false) {
+        return Ok(format!("{}{}", group_commits_markdown(&commits), omitted_note));
+    }
+
+    let mut commit_list = String::new();
+    for commit in &commits {
+        commit_list.push_str(&format!("- {} ({})\n", commit.subject, commit.date));
+        for line in commit.body.lines().filter(|l| !l.trim().is_empty()).take(5) {
+            commit_list.push_str(&format!("  {}\n", line.trim()));
+        }
+        if !commit.stat.is_empty() {
+            commit_list.push_str(&format!("  [{}]\n", commit.stat));
+        }
+    }
+    if omitted > 0 {
+        commit_list.push_str(&format!("(and {} older commits not shown)\n", omitted));
+    }
+
+    let prompt = format!(
+        "You are helping a developer write their daily standup update. Summarize the commits below as short markdown bullet points grouped under a few theme headings (### Heading).
+Describe what was accomplished rather than listing every commit, and do not invent work that isn't in the list. Return ONLY the markdown, without an introduction or closing remarks.
+
+Commits:
+{}",
+        commit_list
+    );
+
+    let summary = send_ai_prompt(config, prompt).await?;
+    Ok(format!("{}{}", summary, omitted_note))
+}
@@ -0,0 +1,185 @@
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use crate::activity::{ActivityLog, Operation};
+use crate::branches::ref_name_problem;
+use crate::danger::{ConfirmationTokens, DangerLevel, DangerousOperation};
+use crate::error::CommandError;
+use crate::pull::network_error;
+use crate::push::DEFAULT_REMOTE;
+use crate::read_only::ReadOnlyRepos;
+use crate::{run_git, run_git_with_input, run_git_with_progress};
+
+/// Fields `parse_tags` expects, NUL-separated; `%1e` ends each record since annotated tag
+/// messages span several lines.
+const TAG_FORMAT: &str =
+    "--format=%(refname:short)%00%(objecttype)%00%(objectname)%00%(*objectname)%00%(taggerdate:unix)%00%(contents)%1e";
+
+#[derive(Serialize, Debug)]
+pub struct TagInfo {
+    name: String,
+    /// The commit the tag points to (for an annotated tag, the commit behind the tag object)
+    target_hash: String,
+    is_annotated: bool,
+    /// The annotated tag's message; null for lightweight tags
+    message: Option<String>,
+    /// When an annotated tag was made, as a unix timestamp
+    tagger_date: Option<i64>,
+}
+
+fn parse_tags(out: &str) -> Vec<TagInfo> {
+    out.split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').splitn(6, '\0');
+            let (Some(name), Some(kind), Some(object), Some(peeled), Some(date), Some(contents)) = (
+                fields.next(),
+                fields.next(),
+                fields.next(),
+                fields.next(),
+                fields.next(),
+                fields.next(),
+            ) else {
+                return None;
+            };
+            let is_annotated = kind == "tag";
+            Some(TagInfo {
+                name: name.to_string(),
+                target_hash: if peeled.is_empty() { object } else { peeled }.to_string(),
+                is_annotated,
+                message: is_annotated.then(|| contents.trim_end().to_string()),
+                tagger_date: date.parse().ok(),
+            })
+        })
+        .collect()
+}
+
+/// The repository's tags, newest version first: `--sort=-v:refname` orders the numbers in
+/// them as numbers, so `v1.10.0` comes before `v1.9.0`.
+#[tauri::command]
+pub fn get_tags(path: &str) -> Result<Vec<TagInfo>, String> {
+    Ok(parse_tags(&run_git(path, &["for-each-ref", "--sort=-v:refname", TAG_FORMAT, "refs/tags"])?))
+}
+
+fn check_tag_name(name: &str) -> Result<(), String> {
+    match ref_name_problem(name) {
+        Some(problem) => Err(format!("Tag names {}", problem)),
+        None => Ok(()),
+    }
+}
+
+/// Tags `target` (HEAD by default). With a `message` the tag is annotated, recording the
+/// tagger and date; otherwise it's a lightweight tag. Returns the new tag.
+#[tauri::command]
+pub fn create_tag(
+    path: &str,
+    name: &str,
+    message: Option<String>,
+    target: Option<String>,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<TagInfo, CommandError> {
+    repos.ensure_writable(path)?;
+    let name = name.trim();
+    check_tag_name(name)?;
+    let target = target.filter(|t| !t.trim().is_empty()).unwrap_or_else(|| "HEAD".to_string());
+    if target.starts_with('-') {
+        return Err(format!("Invalid revision: {}", target).into());
+    }
+    let commit = run_git(path, &["rev-parse", "-q", "--verify", &format!("{}^{{commit}}", target)])
+        .map_err(|_| format!("'{}' is not a commit in this repository", target))?;
+    let commit = commit.trim();
+
+    let message = message.filter(|m| !m.trim().is_empty());
+    let result = match &message {
+        Some(message) => run_git_with_input(path, &["tag", "-a", name, "-F", "-", commit], message),
+        None => run_git(path, &["tag", name, commit]),
+    };
+    activity.record(path, Operation::new(format!("Tag {} as {}", &commit[..7], name)), &result);
+    result?;
+    let created = run_git(path, &["for-each-ref", TAG_FORMAT, &format!("refs/tags/{}", name)])?;
+    parse_tags(&created)
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("Tag {} was not created", name).into())
+}
+
+/// Runs a network `git push` for tags, mapping refused credentials to `AuthenticationFailed`.
+fn push_refs(app: &AppHandle, path: &str, remote: &str, args: &[&str]) -> Result<(), CommandError> {
+    let mut push = vec!["push", "--progress", remote];
+    push.extend_from_slice(args);
+    let out = run_git_with_progress(app, path, &push, "push-progress")?;
+    if !out.status.success() {
+        return Err(network_error(String::from_utf8_lossy(&out.stderr).to_string(), Some(remote)));
+    }
+    Ok(())
+}
+
+fn check_remote(remote: Option<String>) -> Result<String, CommandError> {
+    let remote = remote.filter(|r| !r.trim().is_empty()).unwrap_or_else(|| DEFAULT_REMOTE.to_string());
+    if remote.starts_with('-') {
+        return Err(format!("Invalid remote: {}", remote).into());
+    }
+    Ok(remote)
+}
+
+/// Pushes tag `name` to `remote` (`origin` by default), emitting `push-progress` events.
+#[tauri::command]
+pub async fn push_tag(app: AppHandle, path: String, remote: Option<String>, name: String) -> Result<(), CommandError> {
+    app.state::<ReadOnlyRepos>().ensure_writable(&path)?;
+    let remote = check_remote(remote)?;
+    check_tag_name(&name)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = push_refs(&app, &path, &remote, &[&format!("refs/tags/{}", name)]);
+        app.state::<ActivityLog>().record(&path, Operation::new(format!("Push tag {} to {}", name, remote)), &result);
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn delete_tag_refs(
+    app: &AppHandle,
+    path: &str,
+    name: &str,
+    remote: Option<&str>,
+    op: &mut Operation,
+) -> Result<(), CommandError> {
+    if let Some(remote) = remote {
+        let pushed = push_refs(app, path, remote, &["--delete", &format!("refs/tags/{}", name)]);
+        op.step(format!("Delete from {}", remote), &pushed);
+        pushed?;
+    }
+    run_git(path, &["tag", "-d", name])?;
+    Ok(())
+}
+
+/// Deletes tag `name`, and with `also_remote` from `remote` (`origin` by default) as well,
+/// which needs a `confirmation` token. The remote tag goes first, so when that fails the local
+/// one is still there to retry with.
+#[tauri::command]
+pub async fn delete_tag(
+    app: AppHandle,
+    path: String,
+    name: String,
+    also_remote: Option<bool>,
+    remote: Option<String>,
+    confirmation: Option<String>,
+) -> Result<(), CommandError> {
+    app.state::<ReadOnlyRepos>().ensure_writable(&path)?;
+    let remote = check_remote(remote)?;
+    check_tag_name(&name)?;
+    let also_remote = also_remote.unwrap_or(false);
+    let level = if also_remote { DangerLevel::Destructive } else { DangerLevel::Recoverable };
+    let operation = DangerousOperation::DeleteRemoteTag { path: path.clone(), remote: remote.clone(), name: name.clone() };
+    app.state::<ConfirmationTokens>().authorize(level, &operation, confirmation.as_deref())?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut op = Operation::new(format!("Delete tag {}", name));
+        let result = delete_tag_refs(&app, &path, &name, also_remote.then_some(remote.as_str()), &mut op);
+        app.state::<ActivityLog>().record(&path, op, &result);
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
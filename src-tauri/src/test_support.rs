@@ -0,0 +1,149 @@
+//! Scratch repositories for the unit tests, created in a temporary directory that's removed
+//! again when the `TestRepo` is dropped.
+
+use serde_json::Map;
+use std::path::Path;
+use std::process::Command;
+use tauri::test::{mock_app, MockRuntime};
+use tauri::{App, Manager};
+use tempfile::TempDir;
+
+use crate::activity::ActivityLog;
+use crate::capabilities::GitCapabilitiesState;
+use crate::danger::ConfirmationTokens;
+use crate::error::CommandError;
+use crate::fingerprint::DiffFingerprints;
+use crate::read_only::ReadOnlyRepos;
+use crate::{commit_changes, CommitResult};
+
+/// Runs git in `dir` and returns its stdout, failing the test when git does.
+pub(crate) fn git(dir: &Path, args: &[&str]) -> String {
+    let out = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .output()
+        .expect("git is installed");
+    assert!(out.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&out.stderr));
+    String::from_utf8_lossy(&out.stdout).to_string()
+}
+
+pub(crate) struct TestRepo {
+    dir: TempDir,
+}
+
+impl TestRepo {
+    /// An empty repository on `main`, with an identity and signing turned off so the user's
+    /// global config doesn't get in the way.
+    pub fn init() -> Self {
+        let repo = TestRepo { dir: TempDir::new().unwrap() };
+        repo.git(&["init", "-q", "-b", "main"]);
+        repo.configure();
+        repo
+    }
+
+    /// `init` plus a first commit adding `a.txt`.
+    pub fn with_commit() -> Self {
+        let repo = Self::init();
+        repo.write("a.txt", "one\n");
+        repo.commit_all("Initial commit");
+        repo
+    }
+
+    /// A clone of `origin` (usually a bare repository), set up like `init`.
+    pub fn clone_of(origin: &Path) -> Self {
+        let repo = TestRepo { dir: TempDir::new().unwrap() };
+        git(repo.dir.path(), &["clone", "-q", &origin.to_string_lossy(), "."]);
+        repo.configure();
+        repo
+    }
+
+    fn configure(&self) {
+        self.git(&["config", "user.name", "Test"]);
+        self.git(&["config", "user.email", "test@example.com"]);
+        self.git(&["config", "commit.gpgsign", "false"]);
+        self.git(&["config", "core.autocrlf", "false"]);
+    }
+
+    /// A mock app managing the state write commands take, with nothing read-only and the
+    /// activity log kept inside `.git`.
+    pub fn app(&self) -> App<MockRuntime> {
+        let app = mock_app();
+        app.manage(GitCapabilitiesState::default());
+        app.manage(ConfirmationTokens::default());
+        app.manage(DiffFingerprints::default());
+        app.manage(ReadOnlyRepos::from_settings(&Map::new()));
+        app.manage(ActivityLog::new(self.dir.path().join(".git/activity.json")));
+        app
+    }
+
+    pub fn path(&self) -> &str {
+        self.dir.path().to_str().unwrap()
+    }
+
+    pub fn git(&self, args: &[&str]) -> String {
+        git(self.dir.path(), args)
+    }
+
+    pub fn write(&self, file: &str, contents: &str) {
+        let file = self.dir.path().join(file);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(file, contents).unwrap();
+    }
+
+    pub fn read(&self, file: &str) -> String {
+        std::fs::read_to_string(self.dir.path().join(file)).unwrap()
+    }
+
+    pub fn exists(&self, file: &str) -> bool {
+        self.dir.path().join(file).exists()
+    }
+
+    /// Stages everything and commits it, returning the new commit's hash.
+    pub fn commit_all(&self, message: &str) -> String {
+        self.git(&["add", "-A"]);
+        self.git(&["commit", "-q", "--allow-empty", "-m", message]);
+        self.head()
+    }
+
+    pub fn head(&self) -> String {
+        self.git(&["rev-parse", "HEAD"]).trim().to_string()
+    }
+
+    /// `git status --porcelain` as is, to compare the index and work tree against.
+    pub fn short_status(&self) -> String {
+        self.git(&["status", "--porcelain"])
+    }
+}
+
+/// A bare repository to push to and fetch from.
+pub(crate) fn bare_remote() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q", "--bare", "-b", "main"]);
+    dir
+}
+
+/// `commit_changes` in `path` as the commit button calls it, with the warnings acknowledged
+/// or not; `app` comes from `TestRepo::app`.
+pub(crate) fn commit_selection(app: &App<MockRuntime>, path: &str, files: &[&str], acknowledge_warnings: bool) -> Result<CommitResult, CommandError> {
+    let files = files.iter().map(|f| f.to_string()).collect();
+    commit_changes(
+        path,
+        "Commit the selection",
+        files,
+        Some(acknowledge_warnings),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        app.state(),
+        app.state(),
+        app.state(),
+        app.state(),
+    )
+}
@@ -0,0 +1,70 @@
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::{has_commits, run_git, run_git_with_input};
+
+/// How far back `get_recent_coauthors` looks for people to suggest.
+const COAUTHOR_SCAN_DEPTH: usize = 200;
+
+#[derive(Serialize, Debug)]
+pub struct Coauthor {
+    name: String,
+    email: String,
+}
+
+fn validate(key: &str, value: &str) -> Result<(), String> {
+    let key_ok = !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    if !key_ok {
+        return Err(format!("'{}' is not a valid trailer name", key));
+    }
+    if value.trim().is_empty() || value.contains(['\n', '\r']) {
+        return Err(format!("The {} trailer needs a single-line value", key));
+    }
+    Ok(())
+}
+
+/// Adds `trailers` (such as `Co-authored-by`) to the end of `message` with
+/// `git interpret-trailers`, which takes care of the blank line before the trailer block.
+/// A trailer already in the message with the same value, typed by hand or listed twice, is
+/// only kept once.
+pub fn append(path: &str, message: &str, trailers: &[(String, String)]) -> Result<String, String> {
+    if trailers.is_empty() {
+        return Ok(message.to_string());
+    }
+    let mut args = vec!["interpret-trailers".to_string(), "--if-exists".to_string(), "addIfDifferent".to_string()];
+    for (key, value) in trailers {
+        let (key, value) = (key.trim(), value.trim());
+        validate(key, value)?;
+        args.push("--trailer".to_string());
+        args.push(format!("{}: {}", key, value));
+    }
+
+    let refs: Vec<&str> = args.iter().map(String::as_str).collect();
+    // Without the final newline a one-line message gets the trailer right below it
+    let output = run_git_with_input(path, &refs, &format!("{}\n", message.trim_end()))?;
+    Ok(output.trim_end().to_string())
+}
+
+/// Distinct authors of the recent commits, most recent first and without the current user,
+/// for `Co-authored-by` suggestions.
+#[tauri::command]
+pub fn get_recent_coauthors(path: &str) -> Result<Vec<Coauthor>, String> {
+    if !has_commits(path) {
+        return Ok(Vec::new());
+    }
+    let me = run_git(path, &["config", "user.email"]).unwrap_or_default().trim().to_lowercase();
+    let out = run_git(path, &["log", &format!("-n{}", COAUTHOR_SCAN_DEPTH), "--format=%aN%x00%aE"])?;
+
+    let mut seen = HashSet::new();
+    Ok(out
+        .lines()
+        .filter_map(|line| {
+            let (name, email) = line.split_once('\0')?;
+            let key = email.to_lowercase();
+            if email.is_empty() || key == me || !seen.insert(key) {
+                return None;
+            }
+            Some(Coauthor { name: name.to_string(), email: email.to_string() })
+        })
+        .collect())
+}
@@ -0,0 +1,189 @@
+use serde::Serialize;
+
+use crate::diff::{self, DiffOptions};
+use crate::structured_diff::hunk_ranges;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SegmentKind {
+    Unchanged,
+    Added,
+    Removed,
+}
+
+#[derive(Serialize, Debug)]
+pub struct WordSegment {
+    kind: SegmentKind,
+    text: String,
+}
+
+/// One line of the merged old/new text. A line with only added segments exists only in the
+/// new file, one with only removed segments only in the old file.
+#[derive(Serialize, Debug, Default)]
+pub struct WordDiffLine {
+    old_line: Option<u32>,
+    new_line: Option<u32>,
+    segments: Vec<WordSegment>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct WordDiffHunk {
+    header: String,
+    lines: Vec<WordDiffLine>,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct WordDiff {
+    binary: bool,
+    hunks: Vec<WordDiffHunk>,
+}
+
+/// Parses `git diff --word-diff=porcelain`. After each hunk header every line is one segment,
+/// prefixed with ` ` (unchanged), `-` (removed) or `+` (added), and a line holding just `~`
+/// ends a line of the file. Line numbers count a line as old when it has unchanged or removed
+/// words and as new when it has unchanged or added words; an empty line counts as both.
+pub fn parse(text: &str) -> WordDiff {
+    let mut result = WordDiff::default();
+    let (mut old_no, mut new_no) = (0u32, 0u32);
+    let mut current = WordDiffLine::default();
+
+    for line in text.lines() {
+        if line.starts_with("Binary files ") {
+            result.binary = true;
+            continue;
+        }
+        if line.starts_with("@@") {
+            let ((old_start, _), (new_start, _)) = hunk_ranges(line);
+            old_no = old_start;
+            new_no = new_start;
+            current = WordDiffLine::default();
+            result.hunks.push(WordDiffHunk { header: line.to_string(), lines: Vec::new() });
+            continue;
+        }
+        let Some(hunk) = result.hunks.last_mut() else {
+            continue;
+        };
+
+        let kind = match line.as_bytes().first() {
+            Some(b' ') => SegmentKind::Unchanged,
+            Some(b'+') => SegmentKind::Added,
+            Some(b'-') => SegmentKind::Removed,
+            Some(b'~') => {
+                let mut finished = std::mem::take(&mut current);
+                let has = |kind: SegmentKind| finished.segments.iter().any(|s| s.kind == kind);
+                let unchanged = has(SegmentKind::Unchanged) || finished.segments.is_empty();
+                let (old, new) = (unchanged || has(SegmentKind::Removed), unchanged || has(SegmentKind::Added));
+                if old {
+                    finished.old_line = Some(old_no);
+                    old_no += 1;
+                }
+                if new {
+                    finished.new_line = Some(new_no);
+                    new_no += 1;
+                }
+                hunk.lines.push(finished);
+                continue;
+            }
+            // "\ No newline at end of file"
+            _ => continue,
+        };
+        current.segments.push(WordSegment { kind, text: line[1..].to_string() });
+    }
+    result
+}
+
+/// Word-by-word diff of one file from the status list, for prose where line diffs are hard
+/// to read. `staged` picks the side the same way as `get_file_diff`.
+#[tauri::command]
+pub fn get_word_diff(path: &str, file: &str, staged: bool, ignore_whitespace: Option<bool>) -> Result<WordDiff, String> {
+    let options = DiffOptions {
+        ignore_whitespace: ignore_whitespace.unwrap_or(false),
+        word_diff: true,
+        ..Default::default()
+    };
+    Ok(parse(&diff::file_diff(path, Some(file), staged, options)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestRepo;
+
+    /// Old and new line number and segments of one line.
+    type Line<'a> = (Option<u32>, Option<u32>, Vec<(SegmentKind, &'a str)>);
+
+    fn lines(diff: &WordDiff) -> Vec<Line<'_>> {
+        diff.hunks[0]
+            .lines
+            .iter()
+            .map(|l| (l.old_line, l.new_line, l.segments.iter().map(|s| (s.kind, s.text.as_str())).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_porcelain_word_diff() {
+        use SegmentKind::{Added, Removed, Unchanged};
+
+        let text = "\
+diff --git a/t.txt b/t.txt
+index 218e925..d471389 100644
+--- a/t.txt
++++ b/t.txt
+@@ -1,4 +1,5 @@
+ The quick 
+-brown
++red
+  fox
+~
+ 
+~
+ jumps over
+~
+ the
+-lazy
+  dog
+~
++and runs
+~
+";
+        let diff = parse(text);
+        assert!(!diff.binary);
+        assert_eq!(diff.hunks.len(), 1);
+        assert_eq!(diff.hunks[0].header, "@@ -1,4 +1,5 @@");
+        assert_eq!(
+            lines(&diff),
+            [
+                (Some(1), Some(1), vec![(Unchanged, "The quick "), (Removed, "brown"), (Added, "red"), (Unchanged, " fox")]),
+                (Some(2), Some(2), vec![(Unchanged, "")]),
+                (Some(3), Some(3), vec![(Unchanged, "jumps over")]),
+                (Some(4), Some(4), vec![(Unchanged, "the"), (Removed, "lazy"), (Unchanged, " dog")]),
+                (None, Some(5), vec![(Added, "and runs")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn removed_lines_and_binary_files() {
+        let diff = parse("@@ -10,2 +10 @@\n-gone\n~\n keep\n~\n\\ No newline at end of file\n");
+        assert_eq!(
+            lines(&diff),
+            [
+                (Some(10), None, vec![(SegmentKind::Removed, "gone")]),
+                (Some(11), Some(10), vec![(SegmentKind::Unchanged, "keep")]),
+            ]
+        );
+
+        let binary = parse("diff --git a/x.png b/x.png\nindex 1111111..2222222 100644\nBinary files a/x.png and b/x.png differ\n");
+        assert!(binary.binary && binary.hunks.is_empty());
+    }
+
+    #[test]
+    fn word_diff_of_a_file_in_the_work_tree() {
+        let repo = TestRepo::with_commit();
+        repo.write("a.txt", "one two\n");
+
+        let diff = get_word_diff(repo.path(), "a.txt", false, None).unwrap();
+        assert_eq!(lines(&diff), [(Some(1), Some(1), vec![(SegmentKind::Unchanged, "one "), (SegmentKind::Added, "two")])]);
+        assert!(get_word_diff(repo.path(), "a.txt", true, None).unwrap().hunks.is_empty());
+    }
+}
@@ -0,0 +1,211 @@
+use serde::Serialize;
+use tauri::State;
+
+use crate::activity::{ActivityLog, Operation};
+use crate::branches::validate_branch_name;
+use crate::danger::{ConfirmationTokens, DangerLevel, DangerousOperation};
+use crate::error::CommandError;
+use crate::read_only::ReadOnlyRepos;
+use crate::run_git;
+
+#[derive(Serialize, Debug, Default)]
+pub struct WorktreeInfo {
+    path: String,
+    /// Commit checked out there; null for a bare repository
+    head: Option<String>,
+    /// Branch checked out there, without `refs/heads/`; null when detached
+    branch: Option<String>,
+    /// The main worktree, the one the repository was cloned into
+    is_main: bool,
+    is_bare: bool,
+    is_locked: bool,
+    /// Its directory is gone, so `git worktree prune` would forget it
+    is_prunable: bool,
+}
+
+/// Parses `git worktree list --porcelain`: one block of `<key> [<value>]` lines per worktree,
+/// separated by blank lines, the main worktree first.
+fn parse_worktrees(out: &str) -> Vec<WorktreeInfo> {
+    let mut worktrees: Vec<WorktreeInfo> = Vec::new();
+    for line in out.lines() {
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        if key == "worktree" {
+            let is_main = worktrees.is_empty();
+            worktrees.push(WorktreeInfo { path: value.to_string(), is_main, ..Default::default() });
+            continue;
+        }
+        let Some(worktree) = worktrees.last_mut() else {
+            continue;
+        };
+        match key {
+            "HEAD" => worktree.head = Some(value.to_string()),
+            "branch" => worktree.branch = Some(value.strip_prefix("refs/heads/").unwrap_or(value).to_string()),
+            "bare" => worktree.is_bare = true,
+            "locked" => worktree.is_locked = true,
+            "prunable" => worktree.is_prunable = true,
+            _ => {}
+        }
+    }
+    worktrees
+}
+
+/// The main worktree and every linked one, wherever `path` is among them.
+#[tauri::command]
+pub fn get_worktrees(path: &str) -> Result<Vec<WorktreeInfo>, String> {
+    let out = run_git(path, &["worktree", "list", "--porcelain"])?;
+    Ok(parse_worktrees(&out))
+}
+
+/// Checks `branch` out in a new linked worktree at `new_path`, creating the branch from the
+/// current commit first with `create_branch`. Returns the worktrees afterwards.
+#[tauri::command]
+pub fn add_worktree(
+    path: &str,
+    new_path: &str,
+    branch: &str,
+    create_branch: Option<bool>,
+    repos: State<'_, ReadOnlyRepos>,
+    activity: State<'_, ActivityLog>,
+) -> Result<Vec<WorktreeInfo>, CommandError> {
+    repos.ensure_writable(path)?;
+    validate_branch_name(branch)?;
+    let result = match create_branch.unwrap_or(false) {
+        true => run_git(path, &["worktree", "add", "-b", branch, "--", new_path]),
+        false => run_git(path, &["worktree", "add", "--", new_path, branch]),
+    };
+    activity.record(path, Operation::new(format!("Add worktree for {} at {}", branch, new_path)), &result);
+    result?;
+    Ok(get_worktrees(path)?)
+}
+
+/// Removes the linked worktree at `worktree_path` and its directory. Git refuses while it has
+/// uncommitted changes or untracked files; `force` removes it anyway, losing them, and needs a
+/// `confirmation` token.
+#[tauri::command]
+pub fn remove_worktree(
+    path: &str,
+    worktree_path: &str,
+    force: Option<bool>,
+    confirmation: Option<String>,
+    repos: State<'_, ReadOnlyRepos>,
+    tokens: State<'_, ConfirmationTokens>,
+    activity: State<'_, ActivityLog>,
+) -> Result<Vec<WorktreeInfo>, CommandError> {
+    repos.ensure_writable(path)?;
+    let force = force.unwrap_or(false);
+    let level = if force { DangerLevel::Destructive } else { DangerLevel::Recoverable };
+    let operation = DangerousOperation::RemoveWorktree { path: path.to_string(), worktree: worktree_path.to_string() };
+    tokens.authorize(level, &operation, confirmation.as_deref())?;
+
+    let mut args = vec!["worktree", "remove"];
+    if force {
+        args.push("--force");
+    }
+    args.extend(["--", worktree_path]);
+    let result = run_git(path, &args);
+    activity.record(path, Operation::new(format!("Remove worktree {}", worktree_path)), &result);
+    result?;
+    Ok(get_worktrees(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::danger::confirm_dangerous_operation;
+    use crate::test_support::{commit_selection, git, TestRepo};
+    use crate::{get_git_diff, get_git_status};
+    use tauri::Manager;
+
+    #[test]
+    fn parses_porcelain_list() {
+        let out = "\
+worktree /repos/app
+HEAD 1111111111111111111111111111111111111111
+branch refs/heads/main
+
+worktree /repos/app-hotfix
+HEAD 2222222222222222222222222222222222222222
+detached
+locked moving to another disk
+
+worktree /tmp/app-gone
+HEAD 3333333333333333333333333333333333333333
+branch refs/heads/feature/old
+prunable gitdir file points to non-existent location
+";
+        let worktrees = parse_worktrees(out);
+        let fields: Vec<_> = worktrees
+            .iter()
+            .map(|w| (w.path.as_str(), w.branch.as_deref(), w.is_main, w.is_locked, w.is_prunable))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("/repos/app", Some("main"), true, false, false),
+                ("/repos/app-hotfix", None, false, true, false),
+                ("/tmp/app-gone", Some("feature/old"), false, false, true),
+            ]
+        );
+        assert_eq!(worktrees[1].head.as_deref(), Some("2222222222222222222222222222222222222222"));
+
+        let bare = parse_worktrees("worktree /repos/app.git\nbare\n");
+        assert!(bare[0].is_bare && bare[0].head.is_none());
+    }
+
+    #[test]
+    fn add_and_remove_a_worktree() {
+        let repo = TestRepo::with_commit();
+        let app = repo.app();
+        let dir = tempfile::TempDir::new().unwrap();
+        let linked = dir.path().join("hotfix");
+        let linked = linked.to_str().unwrap();
+
+        let worktrees = add_worktree(repo.path(), linked, "hotfix", Some(true), app.state(), app.state()).unwrap();
+        let fields: Vec<_> = worktrees.iter().map(|w| (w.branch.as_deref(), w.is_main, w.head.clone())).collect();
+        assert_eq!(fields, [(Some("main"), true, Some(repo.head())), (Some("hotfix"), false, Some(repo.head()))]);
+        assert!(worktrees[1].path.ends_with("/hotfix"), "{}", worktrees[1].path);
+        assert!(add_worktree(repo.path(), linked, "-b", None, app.state(), app.state()).is_err());
+
+        // Untracked work in the worktree makes git refuse, and forcing needs a confirmation
+        std::fs::write(dir.path().join("hotfix/wip.txt"), "wip\n").unwrap();
+        assert!(remove_worktree(repo.path(), linked, None, None, app.state(), app.state(), app.state()).is_err());
+        let forced = remove_worktree(repo.path(), linked, Some(true), None, app.state(), app.state(), app.state());
+        assert!(matches!(forced, Err(CommandError::ConfirmationRequired { .. })));
+        assert_eq!(get_worktrees(repo.path()).unwrap().len(), 2);
+
+        let operation = DangerousOperation::RemoveWorktree { path: repo.path().to_string(), worktree: linked.to_string() };
+        let token = confirm_dangerous_operation(operation, app.state());
+        let worktrees = remove_worktree(repo.path(), linked, Some(true), Some(token), app.state(), app.state(), app.state()).unwrap();
+        assert_eq!(worktrees.len(), 1);
+        assert!(!dir.path().join("hotfix").exists());
+        assert_eq!(repo.git(&["branch", "--list", "hotfix"]), "  hotfix\n");
+    }
+
+    #[test]
+    fn status_diff_and_commit_inside_a_linked_worktree() {
+        let repo = TestRepo::with_commit();
+        let app = repo.app();
+        let main = repo.head();
+        let dir = tempfile::TempDir::new().unwrap();
+        let linked = dir.path().join("hotfix");
+        let linked = linked.to_str().unwrap();
+        add_worktree(repo.path(), linked, "hotfix", Some(true), app.state(), app.state()).unwrap();
+        std::fs::write(dir.path().join("hotfix/a.txt"), "fixed\n").unwrap();
+        std::fs::write(dir.path().join("hotfix/b.txt"), "new\n").unwrap();
+
+        let status: Vec<_> = get_git_status(linked, None).unwrap().into_iter().map(|f| (f.path, f.status)).collect();
+        assert_eq!(status, [("a.txt".to_string(), "M".to_string()), ("b.txt".to_string(), "U".to_string())]);
+        let diff = get_git_diff(linked, vec!["a.txt".to_string()], None, None).unwrap();
+        assert!(diff.contains("-one\n+fixed\n"), "{}", diff);
+        assert_eq!(repo.short_status(), "");
+
+        let commit = commit_selection(&app, linked, &["a.txt", "b.txt"], false).unwrap();
+        let worktree = dir.path().join("hotfix");
+        assert_eq!(git(&worktree, &["rev-parse", "HEAD"]).trim(), commit.hash);
+        assert_eq!(repo.git(&["rev-parse", "hotfix"]).trim(), commit.hash);
+        assert_eq!(repo.git(&["rev-parse", "main"]).trim(), main);
+        assert_eq!(repo.head(), main);
+        assert_eq!(git(&worktree, &["status", "--porcelain"]), "");
+        assert_eq!(repo.short_status(), "");
+    }
+}
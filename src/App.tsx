@@ -1,26 +1,389 @@
 import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { getVersion } from "@tauri-apps/api/app";
 import { getCurrentWindow } from '@tauri-apps/api/window';
+import { openUrl } from "@tauri-apps/plugin-opener";
 import { exit, relaunch } from "@tauri-apps/plugin-process";
 import { check } from "@tauri-apps/plugin-updater";
-import { load } from '@tauri-apps/plugin-store';
 import "./App.css";
 
+type WordDiff = {
+  binary: boolean;
+  hunks: { header: string; lines: { segments: { kind: 'unchanged' | 'added' | 'removed'; text: string }[] }[] }[];
+};
+
 type FileStatus = {
   path: string;
-  status: 'M' | 'A' | 'D' | 'U';
+  status: 'M' | 'A' | 'D' | 'U' | 'R' | 'C' | 'X' | 'I';
   staged: boolean;
+  old_path: string | null;
+  index_status: string | null;
+  worktree_status: string | null;
+  conflicted: boolean;
+};
+
+type CommitWarning = {
+  path: string;
+  kind: 'sensitive' | 'large' | 'binary' | 'usually_ignored';
+  message: string;
+};
+
+type ReleaseInfo = {
+  last_commit: { sha: string; age_seconds: number; subject: string } | null;
+  describe: { nearest_tag: string; commits_since_tag: number; dirty: boolean } | null;
+};
+
+const formatAge = (seconds: number): string => {
+  if (seconds < 60) return "just now";
+  if (seconds < 3600) return `${Math.floor(seconds / 60)}m ago`;
+  if (seconds < 86400) return `${Math.floor(seconds / 3600)}h ago`;
+  return `${Math.floor(seconds / 86400)}d ago`;
+};
+
+type GrepMatch = {
+  file: string;
+  line: number;
+  column: number;
+  text: string;
+};
+
+// Enough to scan by eye; narrow the query for more
+const SEARCH_RESULT_LIMIT = 200;
+
+type ReadOnlyStatus = {
+  read_only: boolean;
+  suggested: boolean;
+};
+
+type RepoInfo = {
+  backend: 'git' | 'libgit2' | 'libgit2-only' | 'embedded-readonly' | 'unavailable';
+  root: string | null;
+  notice: string | null;
+};
+
+type RepoValidation = {
+  is_repo: boolean;
+  root: string | null;
+  is_bare: boolean;
+  is_worktree: boolean;
+  git_dir: string;
+  has_commits: boolean;
+  default_branch: string | null;
+};
+
+type InitResult = {
+  repo: RepoValidation;
+  status: FileStatus[];
+};
+
+type LocalModel = {
+  name: string;
+  pulling: boolean;
+};
+
+type ConnectionCheck = {
+  ok: boolean;
+  message: string;
+  remediation: { kind: 'pull_model'; model: string } | null;
+};
+
+type PullProgress = {
+  model: string;
+  status: string;
+  completed: number | null;
+  total: number | null;
+};
+
+type GitProgress = {
+  path: string;
+  phase: string;
+  percent: number | null;
+  detail: string;
+};
+
+type CommitVocabulary = {
+  types: { name: string; description: string }[];
+  allowed_scopes: string[] | null;
+  custom_types: boolean;
+};
+
+type LintIssue = {
+  severity: 'error' | 'warning';
+  message: string;
+};
+
+type SubmoduleStatus = {
+  path: string;
+  url: string | null;
+  state: 'up_to_date' | 'not_initialized' | 'commit_differs' | 'dirty_work_tree' | 'conflicted';
+  recorded: string | null;
+  checked_out: string | null;
+  dirty: boolean;
+};
+
+type StashEntry = {
+  index: number;
+  message: string;
+  branch: string | null;
+  date: number;
+};
+
+type CleanEntry = {
+  path: string;
+  is_dir: boolean;
+  file_count: number | null;
+};
+
+type Coauthor = {
+  name: string;
+  email: string;
+};
+
+type SigningConfig = {
+  enabled: boolean;
+  signing_key: string | null;
+  format: string;
+};
+
+type CommitResult = {
+  hash: string;
+  short_hash: string;
+  subject: string;
+  files_committed: number;
+  submodule_bumps: { path: string; old: string; new: string }[];
+};
+
+type Snippet = {
+  name: string;
+  template: string;
+  source: 'user' | 'repo';
+};
+
+type CommitGroup = {
+  message: string;
+  files: string[];
+};
+
+type DiscardResult = {
+  files: {
+    path: string;
+    destination: { kind: 'trash' | 'deleted' | 'deleted_after_trash_failed' | 'restored_from_head' | 'restored_from_index' | 'skipped'; reason?: string };
+  }[];
+  backup: string | null;
+};
+
+// Where a discarded file ended up, as told to the user
+const describeDiscard = (result: DiscardResult): string => {
+  const lines = result.files.map(({ path, destination }) => {
+    switch (destination.kind) {
+      case 'trash': return `${path}: moved to the trash`;
+      case 'deleted': return `${path}: deleted permanently`;
+      case 'deleted_after_trash_failed': return `${path}: deleted permanently (trash unavailable: ${destination.reason})`;
+      case 'restored_from_head': return `${path}: restored to the last commit`;
+      case 'restored_from_index': return `${path}: unstaged changes discarded, staged changes kept`;
+      case 'skipped': return `${path}: skipped (${destination.reason})`;
+    }
+  });
+  if (result.backup) lines.push(`Previous content saved as a "GitPop discard backup" stash (${result.backup.slice(0, 7)})`);
+  return lines.join("\n");
+};
+
+type GeneratedCommit = {
+  message: string;
+  diff_fingerprint: string;
+};
+
+type DiffChangeSummary = {
+  added_files: string[];
+  removed_files: string[];
+  modified_files: string[];
+  lines_changed: number;
+};
+
+type CurrentBranch = {
+  name: string | null;
+  detached: boolean;
+  commit: string;
+  short_commit: string;
+  operation:
+    | { kind: "rebase" | "bisect"; branch: string | null }
+    | { kind: "cherry_pick"; commit: string | null }
+    | null;
+};
+
+const describeHead = (head: CurrentBranch): string => {
+  if (!head.detached && head.operation?.kind !== "cherry_pick") return head.name ?? "";
+  if (head.operation?.kind === "rebase") return `rebasing ${head.operation.branch ?? head.short_commit}`;
+  if (head.operation?.kind === "bisect") return `bisecting at ${head.short_commit}`;
+  if (head.operation?.kind === "cherry_pick") {
+    const picking = head.operation.commit ? ` ${head.operation.commit.slice(0, 7)}` : "";
+    return `${head.name ?? head.short_commit} · cherry-picking${picking}`;
+  }
+  return `detached at ${head.short_commit}`;
+};
+
+type RepoOperation =
+  | { kind: "none" | "merging" | "cherry_picking" | "reverting" | "bisecting" }
+  | { kind: "rebasing"; current: number | null; total: number | null; onto: string | null };
+
+type OperationStep = { label: string; command: string; title: string; done: string };
+
+const OPERATION_STEPS: Record<RepoOperation["kind"], OperationStep[]> = {
+  none: [],
+  merging: [
+    { label: "Continue", command: "merge_continue", title: "Commit the resolved merge", done: "Merge committed" },
+    { label: "Abort", command: "merge_abort", title: "Put the branch back as it was before the merge", done: "Merge aborted" },
+  ],
+  rebasing: [
+    { label: "Continue", command: "rebase_continue", title: "Stage the resolved files and replay the rest", done: "Rebase continued" },
+    { label: "Skip", command: "rebase_skip", title: "Drop this commit and replay the rest", done: "Commit skipped" },
+    { label: "Abort", command: "rebase_abort", title: "Put the branch back as it was before the rebase", done: "Rebase aborted" },
+  ],
+  cherry_picking: [
+    { label: "Continue", command: "cherry_pick_continue", title: "Commit the resolved pick and apply the rest", done: "Cherry-pick continued" },
+    { label: "Skip", command: "cherry_pick_skip", title: "Drop this commit and apply the rest", done: "Commit skipped" },
+    { label: "Abort", command: "cherry_pick_abort", title: "Put the branch back as it was before the cherry-pick", done: "Cherry-pick aborted" },
+  ],
+  reverting: [
+    { label: "Continue", command: "revert_continue", title: "Commit the resolved revert", done: "Revert committed" },
+    { label: "Abort", command: "revert_abort", title: "Put the branch back as it was before the revert", done: "Revert aborted" },
+  ],
+  bisecting: [],
+};
+
+const describeOperation = (op: RepoOperation): string => {
+  if (op.kind === "rebasing") return op.current && op.total ? `Rebasing ${op.current}/${op.total}` : "Rebasing";
+  return { none: "", merging: "Merging", cherry_picking: "Cherry-picking", reverting: "Reverting", bisecting: "Bisecting" }[op.kind];
+};
+
+type RepoWebUrl = {
+  provider: "github" | "gitlab" | "bitbucket" | "azure_devops" | "unknown";
+  base_url: string;
+};
+
+const PROVIDER_NAMES: Record<RepoWebUrl["provider"], string> = {
+  github: "GitHub",
+  gitlab: "GitLab",
+  bitbucket: "Bitbucket",
+  azure_devops: "Azure DevOps",
+  unknown: "the web",
+};
+
+type GitIdentity = {
+  name: string | null;
+  email: string | null;
+  name_scope: "local" | "global" | null;
+  email_scope: "local" | "global" | null;
 };
 
 type SyncStatus = {
   ahead: number;
+  behind: number;
   has_upstream: boolean;
+  upstream: string | null;
   branch: string;
+  has_commits: boolean;
+  last_fetch: number | null;
+  has_remote: boolean;
+};
+
+// Commands with typed errors reject with `{ kind, message, ... }` instead of a plain string
+const errorMessage = (err: unknown): string => {
+  if (typeof err !== "object" || err === null) return String(err);
+  const e = err as { kind?: string; message?: string };
+  if (e.kind === "read_only_repository") return "This repository is open in read-only mode.";
+  if (e.kind === "no_commits_yet") return "This repository has no commits yet.";
+  if (e.kind === "patch_does_not_apply") {
+    const failures = (err as { failures: { file: string; line: number | null; message: string }[] }).failures;
+    return `The selected changes no longer apply: ${failures.map(f => f.line ? `${f.file}:${f.line}` : f.file || f.message).join(", ")}`;
+  }
+  if (e.kind === "unresolved_conflicts") {
+    const files = (err as { files: string[] }).files;
+    return `Resolve the merge conflicts in ${files.join(", ")} before committing.`;
+  }
+  if (e.kind === "stash_conflicts") {
+    const files = (err as { files: string[] }).files;
+    return `Applying the stash left conflicts in ${files.join(", ")}; the stash entry was kept.`;
+  }
+  if (e.kind === "checkout_would_overwrite") {
+    const files = (err as { files: string[] }).files;
+    return `Switching branches would overwrite local changes to ${files.join(", ")}; commit or stash them first.`;
+  }
+  if (e.kind === "push_rejected") {
+    const branches = (err as { branches: string[] }).branches;
+    return `The remote has new commits on ${branches.join(", ")}. Pull them first, then push again.`;
+  }
+  if (e.kind === "host_key_verification_failed") {
+    return `The SSH host key of ${(err as { host: string }).host} isn't trusted yet or has changed. Connect once from a terminal to verify it.`;
+  }
+  if (e.kind === "authentication_failed") {
+    return `Could not sign in to ${(err as { remote: string }).remote}. Check your git credentials (credential helper or SSH key).`;
+  }
+  if (e.kind === "pull_conflicts") {
+    const files = (err as { files: string[] }).files;
+    return `The pull stopped on conflicts in ${files.join(", ")}. Resolve them to finish.`;
+  }
+  if (e.kind === "force_push_lease_failed") {
+    const { branch, remote_sha } = err as { branch: string; remote_sha: string | null };
+    return `Someone else pushed to ${branch}${remote_sha ? ` (now at ${remote_sha.slice(0, 7)})` : ""}. Fetch first, then force push again.`;
+  }
+  if (e.kind === "rebase_conflict") {
+    const { files, subject } = err as { files: string[]; subject: string | null };
+    return `The rebase stopped${subject ? ` at "${subject}"` : ""} with conflicts in ${files.join(", ") || "the work tree"}. Resolve them, then continue or abort the rebase.`;
+  }
+  if (e.kind === "merge_conflict") {
+    const files = (err as { files: string[] }).files;
+    return `Merging left conflicts in ${files.join(", ")}. Resolve them, then continue or abort the merge.`;
+  }
+  if (e.kind === "rebase_nothing_to_commit") {
+    const subject = (err as { subject: string | null }).subject;
+    return `Nothing is left to commit for ${subject ? `"${subject}"` : "this commit"} after resolving. Skip it to carry on with the rebase.`;
+  }
+  if (e.kind === "file_too_large") {
+    const { file, size, limit } = err as { file: string; size: number; limit: number };
+    return `${file} is ${Math.ceil(size / 1048576)} MB, too large to show here (the limit is ${Math.floor(limit / 1048576)} MB).`;
+  }
+  if (e.kind === "rebasing_pushed_commits") {
+    const { upstream, commits } = err as { upstream: string; commits: string[] };
+    return `${commits.length} of these commits are already on ${upstream}. Only commits that haven't been pushed can be rebased.`;
+  }
+  if (e.kind === "index_locked") {
+    const { age_seconds, probably_stale } = err as { age_seconds: number; probably_stale: boolean };
+    return probably_stale
+      ? `The repository is locked by an index.lock left over from a git that crashed (${Math.round(age_seconds / 60)} min old).`
+      : "Another git command is using the repository. Try again once it's done.";
+  }
+  if (e.kind === "identity_not_configured") {
+    const missing = (err as { missing: string[] }).missing;
+    return `Git doesn't know who you are yet. Set ${missing.join(" and ")} to commit.`;
+  }
+  if (e.kind === "cherry_pick_conflicts") {
+    const { files, subject } = err as { files: string[]; subject: string | null };
+    return `Cherry-picking${subject ? ` "${subject}"` : ""} left conflicts in ${files.join(", ")}. Resolve them, then continue, skip or abort the cherry-pick.`;
+  }
+  if (e.kind === "revert_conflicts") {
+    const files = (err as { files: string[] }).files;
+    return `Reverting left conflicts in ${files.join(", ")}. Resolve them, then continue or abort the revert.`;
+  }
+  if (e.kind === "git_not_installed") return "Git isn't installed, so changes can't be made.";
+  if (e.kind === "path_not_found") return `${(err as { path: string }).path} doesn't exist.`;
+  if (e.kind === "inside_existing_repo") return `This folder is already inside the repository at ${(err as { root: string }).root}.`;
+  if (e.kind === "not_a_directory") return `${(err as { path: string }).path} is a file, not a folder.`;
+  if (e.kind === "confirmation_required") return `${e.message} needs to be confirmed first.`;
+  return e.message ?? JSON.stringify(err);
 };
 
 function App() {
   const [commitMessage, setCommitMessage] = useState("");
+  // commit.template with its comments stripped; new messages start from it
+  const [commitTemplate, setCommitTemplate] = useState<string | null>(null);
+  const [amend, setAmend] = useState(false);
+  const [skipHooks, setSkipHooks] = useState(false);
+  const [allowEmpty, setAllowEmpty] = useState(false);
+  const [signingConfig, setSigningConfig] = useState<SigningConfig | null>(null);
+  const [recentCoauthors, setRecentCoauthors] = useState<Coauthor[]>([]);
+  const [coauthors, setCoauthors] = useState<Coauthor[]>([]);
   const [isSparkling, setIsSparkling] = useState(false);
   const [isSetupMode, setIsSetupMode] = useState(false);
   const [isNotRepo, setIsNotRepo] = useState(false);
@@ -28,13 +391,44 @@ function App() {
   const [appVersion, setAppVersion] = useState('');
   const [isCommitting, setIsCommitting] = useState(false);
   const [isPushing, setIsPushing] = useState(false);
+  const [pushProgress, setPushProgress] = useState<GitProgress | null>(null);
+  const [isPulling, setIsPulling] = useState(false);
   const [files, setFiles] = useState<FileStatus[]>([]);
   const [repoPath, setRepoPath] = useState<string>(".");
   const [error, setError] = useState<string | null>(null);
   const [setupMessage, setSetupMessage] = useState<{ text: string, isError: boolean } | null>(null);
   const [toast, setToast] = useState<{ message: string, type: 'error' | 'info' } | null>(null);
   const [syncStatus, setSyncStatus] = useState<SyncStatus | null>(null);
+  const [repoOperation, setRepoOperation] = useState<RepoOperation>({ kind: "none" });
+  const [releaseInfo, setReleaseInfo] = useState<ReleaseInfo | null>(null);
+  const [repoWebUrl, setRepoWebUrl] = useState<RepoWebUrl | null>(null);
+  const [isReadOnly, setIsReadOnly] = useState(false);
+  // Git is missing, but libgit2 can still make plain commits
+  const [canCommitWithoutGit, setCanCommitWithoutGit] = useState(false);
+  const [statusSummary, setStatusSummary] = useState("");
+  const [showIgnored, setShowIgnored] = useState(false);
+  const [stashes, setStashes] = useState<StashEntry[]>([]);
+  const [currentBranch, setCurrentBranch] = useState<CurrentBranch | null>(null);
+  const [diffStats, setDiffStats] = useState<Record<string, { additions: number; deletions: number; binary: boolean }>>({});
+  const [fileDiff, setFileDiff] = useState<{ file: FileStatus; staged: boolean; text: string; words: WordDiff | null } | null>(null);
+  const [vocabulary, setVocabulary] = useState<CommitVocabulary | null>(null);
+  const [lintIssues, setLintIssues] = useState<LintIssue[]>([]);
+  const [staleSubmodules, setStaleSubmodules] = useState<SubmoduleStatus[]>([]);
+  const [isUpdatingSubmodules, setIsUpdatingSubmodules] = useState(false);
+  const [backendNotice, setBackendNotice] = useState<string | null>(null);
+  const [snippets, setSnippets] = useState<Snippet[]>([]);
+  // Fingerprint of the diff the current message was generated from, checked again at commit
+  const [messageFingerprint, setMessageFingerprint] = useState<string | null>(null);
+  const [commitGroups, setCommitGroups] = useState<CommitGroup[] | null>(null);
+  const [isGrouping, setIsGrouping] = useState(false);
   const [remoteUrl, setRemoteUrl] = useState("");
+  const [initBranch, setInitBranch] = useState("main");
+  const [cloneUrl, setCloneUrl] = useState("");
+  const [cloneDest, setCloneDest] = useState("");
+  const [cloneShallow, setCloneShallow] = useState(false);
+  const [cloneProgress, setCloneProgress] = useState<GitProgress | null>(null);
+  const [gitignoreTemplate, setGitignoreTemplate] = useState("");
+  const [gitignoreTemplates, setGitignoreTemplates] = useState<string[]>([]);
   const [isInitializing, setIsInitializing] = useState(false);
 
   const showToast = (message: string, type: 'error' | 'info' = 'error') => {
@@ -42,12 +436,42 @@ function App() {
     setTimeout(() => setToast(null), 4000);
   };
 
+  // Shows a failure; a stale index.lock blocks everything after it, so offer to remove it
+  const reportError = async (prefix: string, err: unknown) => {
+    showToast(`${prefix}${errorMessage(err)}`);
+    const e = err as { kind?: string; probably_stale?: boolean } | null;
+    if (e?.kind !== "index_locked" || !e.probably_stale) return;
+    if (!window.confirm("A git that crashed seems to have left .git/index.lock behind, which blocks changes to this repository.\n\nRemove the lock? Only do this if no other git program is still working in this repository.")) return;
+    try {
+      await invoke("remove_index_lock", { path: repoPath });
+      showToast("Removed the stale index.lock. Try again.", "info");
+    } catch (removeErr) {
+      showToast(errorMessage(removeErr));
+    }
+  };
+
+  const [isSearchOpen, setIsSearchOpen] = useState(false);
+  const [searchQuery, setSearchQuery] = useState("");
+  const [searchRegex, setSearchRegex] = useState(false);
+  const [searchCaseSensitive, setSearchCaseSensitive] = useState(false);
+  const [searchResults, setSearchResults] = useState<GrepMatch[] | null>(null);
+  const [isSearching, setIsSearching] = useState(false);
+
   // Settings State
   const [aiProvider, setAiProvider] = useState("ollama");
   const [aiModel, setAiModel] = useState("llama3.2");
   const [apiKey, setApiKey] = useState("");
   const [customApiUrl, setCustomApiUrl] = useState("");
+  const [discardBackupDays, setDiscardBackupDays] = useState("7");
+  const [autoFetch, setAutoFetch] = useState(false);
+  const [autoFetchMinutes, setAutoFetchMinutes] = useState("15");
+  const [newFileDiffLimitKb, setNewFileDiffLimitKb] = useState("16");
+  const [ignoreWhitespace, setIgnoreWhitespace] = useState(false);
+  const [gitBackend, setGitBackend] = useState<'cli' | 'libgit2'>('cli');
   const [localModels, setLocalModels] = useState<string[]>([]);
+  const [pullingModels, setPullingModels] = useState<string[]>([]);
+  const [connectionCheck, setConnectionCheck] = useState<ConnectionCheck | null>(null);
+  const [pullProgress, setPullProgress] = useState<PullProgress | null>(null);
   const [isFetchingModels, setIsFetchingModels] = useState(false);
 
   // Auto-Updater status tracking
@@ -58,39 +482,113 @@ function App() {
     getVersion().then(v => setAppVersion(v));
   }, []);
 
+  // Background fetches only report back when remote refs moved, or once when they start failing
+  useEffect(() => {
+    const updated = listen<{ path: string }>("repo://remote-updated", (event) => {
+      refreshSyncStatus(event.payload.path);
+    });
+    const failed = listen<{ path: string; message: string }>("repo://auto-fetch-failed", (event) => {
+      showToast(`Background fetch failed: ${event.payload.message}`, "info");
+    });
+    return () => {
+      updated.then(unlisten => unlisten());
+      failed.then(unlisten => unlisten());
+    };
+  }, []);
+
+  // Lint as the user types, after a short pause
+  useEffect(() => {
+    if (!repoPath || !commitMessage.trim()) {
+      setLintIssues([]);
+      return;
+    }
+    const timer = setTimeout(() => {
+      invoke<LintIssue[]>("lint_commit_message", { path: repoPath, message: commitMessage })
+        .then(setLintIssues)
+        .catch(() => setLintIssues([]));
+    }, 400);
+    return () => clearTimeout(timer);
+  }, [commitMessage, repoPath, vocabulary]);
+
   useEffect(() => {
     async function init() {
       try {
-        const store = await load('settings.json', { autoSave: false, defaults: {} });
-        const savedProvider = await store.get<{ value: string }>('aiProvider');
-        const savedModel = await store.get<{ value: string }>('aiModel');
-        const savedApiKey = await store.get<{ value: string }>('apiKey');
-        const savedCustomApiUrl = await store.get<{ value: string }>('customApiUrl');
+        const settings: Record<string, string | undefined> = await invoke("get_settings");
+        const savedProvider = settings.aiProvider;
+        const savedModel = settings.aiModel;
+        const savedApiKey = settings.apiKey;
+        const savedCustomApiUrl = settings.customApiUrl;
 
-        if (savedProvider) setAiProvider(savedProvider as unknown as string);
-        if (savedModel) setAiModel(savedModel as unknown as string);
-        if (savedApiKey) setApiKey(savedApiKey as unknown as string);
-        if (savedCustomApiUrl) setCustomApiUrl(savedCustomApiUrl as unknown as string);
+        if (savedProvider) setAiProvider(savedProvider);
+        if (savedModel) setAiModel(savedModel);
+        if (savedApiKey) setApiKey(savedApiKey);
+        if (savedCustomApiUrl) setCustomApiUrl(savedCustomApiUrl);
+        if (settings.discardBackupDays !== undefined) setDiscardBackupDays(String(settings.discardBackupDays));
+        setAutoFetch(Boolean(settings.autoFetch));
+        if (settings.autoFetchMinutes !== undefined) setAutoFetchMinutes(String(settings.autoFetchMinutes));
+        if (settings.newFileDiffLimitKb !== undefined) setNewFileDiffLimitKb(String(settings.newFileDiffLimitKb));
+        if (settings.ignoreWhitespace !== undefined) setIgnoreWhitespace(settings.ignoreWhitespace);
+        if (settings.gitBackend === "libgit2") setGitBackend("libgit2");
 
         const dir: string = await invoke("get_startup_dir");
+        const repoInfo: RepoInfo = await invoke("get_repo_info", { path: dir });
+        setBackendNotice(repoInfo.notice);
+        const hasGit = repoInfo.backend === "git" || repoInfo.backend === "libgit2";
+        invoke<{ installed: boolean; version: string | null; path: string; outdated: boolean }>("get_git_version")
+          .then(git => {
+            if (git.outdated) showToast(`Git ${git.version} is quite old; update to 2.23 or newer for the best experience.`, "info");
+          })
+          .catch(() => {});
 
         // Resolve to the root of the Git repo so subdirectories work
         try {
-          const rootDir: string = await invoke("get_repo_root", { path: dir });
+          let rootDir: string;
+          if (hasGit) {
+            const validation = await invoke<RepoValidation>("validate_repo", { path: dir }).catch(err => {
+              showToast(errorMessage(err));
+              throw err;
+            });
+            if (validation.is_bare) {
+              showToast("This is a bare repository: it has no working files to commit from.");
+            }
+            if (!validation.root) throw new Error("not a git repository");
+            rootDir = validation.root;
+          } else {
+            rootDir = await invoke("get_repo_root", { path: dir });
+          }
           setRepoPath(rootDir);
           await fetchStatus(rootDir);
           await refreshSyncStatus(rootDir);
+
+          await refreshSnippets(rootDir);
+          await refreshStashes(rootDir);
+          setSigningConfig(await invoke<SigningConfig>("get_signing_config", { path: rootDir }).catch(() => null));
+          setRecentCoauthors(await invoke<Coauthor[]>("get_recent_coauthors", { path: rootDir }).catch(() => []));
+          setCoauthors([]);
+          const template = await invoke<string | null>("get_commit_template", { path: rootDir }).catch(() => null);
+          setCommitTemplate(template);
+          if (template) setCommitMessage(template);
+          invoke("watch_auto_fetch", { path: rootDir });
+
+          const access: ReadOnlyStatus = await invoke("get_read_only_status", { path: rootDir });
+          // Without git every write but a plain commit is refused, so present the repo as read-only up front
+          setIsReadOnly(access.read_only || !hasGit);
+          setCanCommitWithoutGit(repoInfo.backend === "libgit2-only" && !access.read_only);
+          if (access.suggested && !access.read_only) {
+            showToast("This repository is on a network share. Use the lock in the title bar to browse it read-only.", "info");
+          }
         } catch {
           // Not in a git repo - save the dir path anyway for potential init
           setRepoPath(dir);
           setIsNotRepo(true);
           setIsSetupMode(true);
+          invoke<string[]>("get_gitignore_templates").then(setGitignoreTemplates).catch(() => {});
         }
 
         fetchModels(
-          (savedProvider as { value: string })?.value || "ollama",
-          (savedCustomApiUrl as { value: string })?.value || "",
-          (savedApiKey as { value: string })?.value || ""
+          savedProvider || "ollama",
+          savedCustomApiUrl || "",
+          savedApiKey || ""
         );
       } catch (err) {
         console.error(err);
@@ -102,13 +600,33 @@ function App() {
     init();
   }, []);
 
-  const fetchStatus = async (path: string = repoPath) => {
+  const fetchStatus = async (path: string = repoPath, includeIgnored: boolean = showIgnored) => {
     try {
-      const result: FileStatus[] = await invoke("get_git_status", { path });
+      const result: FileStatus[] = await invoke("get_git_status", { path, includeIgnored });
       setFiles(result);
       setError(null);
       setIsSetupMode(false);
       setIsNotRepo(false);
+      invoke<{ summary: string; directories: string[] }>("get_status_summary", { path })
+        .then(s => setStatusSummary([s.summary, ...s.directories].join(". ")))
+        .catch(() => setStatusSummary(""));
+      invoke<{ files: { path: string; additions: number; deletions: number; binary: boolean }[] }>("get_diff_stats", { path })
+        .then(stats => {
+          // Staged and unstaged sides of the same file are added up
+          const byPath: Record<string, { additions: number; deletions: number; binary: boolean }> = {};
+          for (const f of stats.files) {
+            const entry = byPath[f.path] ?? { additions: 0, deletions: 0, binary: false };
+            byPath[f.path] = { additions: entry.additions + f.additions, deletions: entry.deletions + f.deletions, binary: entry.binary || f.binary };
+          }
+          setDiffStats(byPath);
+        })
+        .catch(() => setDiffStats({}));
+      invoke<RepoOperation>("get_repo_operation_state", { path })
+        .then(setRepoOperation)
+        .catch(() => setRepoOperation({ kind: "none" }));
+      invoke<SubmoduleStatus[]>("get_submodule_status", { path })
+        .then(subs => setStaleSubmodules(subs.filter(s => s.state === 'not_initialized' || s.state === 'commit_differs')))
+        .catch(() => setStaleSubmodules([]));
     } catch (err) {
       setError(String(err));
     }
@@ -119,7 +637,9 @@ function App() {
     try {
       let models: string[] = [];
       if (provider === 'ollama') {
-        models = await invoke("get_ollama_models");
+        const listed: LocalModel[] = await invoke("get_ollama_models");
+        models = listed.filter(m => !m.pulling).map(m => m.name);
+        setPullingModels(listed.filter(m => m.pulling).map(m => m.name));
       } else if (provider === 'lmstudio') {
         models = await invoke("get_openai_models", { url: "http://localhost:1234/v1", api_key: null });
       } else if (provider === "custom" && url) {
@@ -133,7 +653,43 @@ function App() {
     }
   };
 
+  const testConnection = async () => {
+    setConnectionCheck(null);
+    try {
+      const config = { provider: aiProvider, api_key: apiKey, model: aiModel, custom_api_url: customApiUrl };
+      setConnectionCheck(await invoke("test_ai_connection", { config }));
+    } catch (err) {
+      setConnectionCheck({ ok: false, message: String(err), remediation: null });
+    }
+  };
+
+  const pullModel = async (model: string) => {
+    setPullProgress({ model, status: "starting", completed: null, total: null });
+    const unlisten = await listen<PullProgress>("ollama-pull-progress", (event) => {
+      if (event.payload.model === model) setPullProgress(event.payload);
+    });
+    try {
+      await invoke("pull_ollama_model", { model });
+      setConnectionCheck({ ok: true, message: `Downloaded ${model}`, remediation: null });
+    } catch (err) {
+      if (String(err) !== "Cancelled") setConnectionCheck({ ok: false, message: String(err), remediation: null });
+    } finally {
+      unlisten();
+      setPullProgress(null);
+      fetchModels('ollama');
+    }
+  };
+
   const refreshSyncStatus = async (path: string = repoPath) => {
+    try {
+      const head: CurrentBranch = await invoke("get_current_branch", { path });
+      setCurrentBranch(head);
+      const repoName = path.split(/[\\/]/).pop() || "repo";
+      await getCurrentWindow().setTitle(`GitPop - ${repoName} (${describeHead(head)})`);
+    } catch (err) {
+      console.warn("Could not fetch current branch:", err);
+      setCurrentBranch(null);
+    }
     try {
       const status: SyncStatus = await invoke("get_sync_status", { path });
       setSyncStatus(status);
@@ -141,6 +697,120 @@ function App() {
       console.warn("Could not fetch sync status:", err);
       setSyncStatus(null);
     }
+    try {
+      setReleaseInfo(await invoke("get_release_info", { path }));
+    } catch (err) {
+      console.warn("Could not fetch release info:", err);
+      setReleaseInfo(null);
+    }
+    // Local or missing remotes simply have no page to open
+    invoke<RepoWebUrl>("get_repo_web_url", { path })
+      .then(setRepoWebUrl)
+      .catch(() => setRepoWebUrl(null));
+  };
+
+  const refreshStashes = async (path: string = repoPath) => {
+    try {
+      setStashes(await invoke("stash_list", { path }));
+    } catch (err) {
+      console.warn("Could not list stashes:", err);
+      setStashes([]);
+    }
+  };
+
+  const saveStash = async () => {
+    const message = window.prompt("Stash message (optional):");
+    if (message === null) return;
+    try {
+      await invoke("stash_save", { path: repoPath, message, includeUntracked: files.some(f => f.status === 'U') });
+      await fetchStatus();
+      await refreshStashes();
+    } catch (err) {
+      reportError("Stash failed: ", err);
+    }
+  };
+
+  const cleanUntracked = async () => {
+    try {
+      const entries: CleanEntry[] = await invoke("clean_preview", { path: repoPath, includeDirectories: true });
+      if (entries.length === 0) {
+        showToast("There are no untracked files to clean", 'info');
+        return;
+      }
+      const listing = entries
+        .map(e => e.is_dir ? `${e.path} (${e.file_count ?? 0} file(s))` : e.path)
+        .join("\n");
+      if (!window.confirm(`Move these untracked paths to the trash?\n\n${listing}`)) return;
+      const paths = entries.map(e => e.path);
+      await invoke("clean_execute", { path: repoPath, paths, includeDirectories: true });
+    } catch (err) {
+      reportError("Clean failed: ", err);
+    }
+    await fetchStatus();
+  };
+
+  const runStashAction = async (action: "apply" | "pop" | "drop", stash: StashEntry) => {
+    if (action === "drop" && !window.confirm(`Drop the stash "${stash.message}"?`)) return;
+    try {
+      await invoke(`stash_${action}`, { path: repoPath, index: stash.index });
+    } catch (err) {
+      reportError(`Stash ${action} failed: `, err);
+    }
+    await fetchStatus();
+    await refreshStashes();
+  };
+
+  const refreshSnippets = async (path: string = repoPath) => {
+    try {
+      setSnippets(await invoke("list_snippets", { path }));
+    } catch (err) {
+      console.warn("Could not load snippets:", err);
+    }
+    try {
+      setVocabulary(await invoke("get_commit_vocabulary", { path }));
+    } catch (err) {
+      console.warn("Could not load commit types:", err);
+    }
+  };
+
+  // Replaces the type (and scope) at the start of the message, keeping the subject
+  const applyCommitType = (prefix: string) => {
+    setCommitMessage(prev => {
+      const rest = prev.replace(/^[\w-]+(\([^)]*\))?!?:\s*/, "");
+      return `${prefix}: ${rest}`;
+    });
+  };
+
+  const insertSnippet = async (name: string) => {
+    try {
+      const rendered: { text: string; warnings: string[] } = await invoke("render_snippet", { name, path: repoPath });
+      setCommitMessage(prev => prev.trim() ? `${prev.trimEnd()} ${rendered.text}` : rendered.text);
+      if (rendered.warnings.length > 0) {
+        showToast(rendered.warnings.join("; "), "info");
+      }
+    } catch (err) {
+      showToast(`Snippet failed: ${err}`);
+    }
+  };
+
+  const saveMessageAsSnippet = async () => {
+    const name = window.prompt("Snippet name (use {{branch}}, {{ticket}}, {{date}}, {{version_from_cargo_toml}} in the message):");
+    if (!name) return;
+    try {
+      await invoke("save_snippet", { name, template: commitMessage });
+      await refreshSnippets();
+    } catch (err) {
+      showToast(`Could not save snippet: ${err}`);
+    }
+  };
+
+  const toggleReadOnly = async () => {
+    try {
+      const access: ReadOnlyStatus = await invoke("set_read_only", { path: repoPath, readOnly: !isReadOnly });
+      setIsReadOnly(access.read_only);
+    } catch (err) {
+      showToast(`Could not change read-only mode: ${errorMessage(err)}`);
+    }
   };
 
   const handleInstallMenu = async () => {
@@ -165,6 +835,34 @@ function App() {
     }
   };
 
+  const handleSuggestGroups = async () => {
+    setIsGrouping(true);
+    try {
+      const config = { provider: aiProvider, api_key: apiKey, model: aiModel, custom_api_url: customApiUrl };
+      const groups: CommitGroup[] = await invoke("suggest_commit_groups", { path: repoPath, config });
+      setCommitGroups(groups);
+    } catch (err) {
+      if (String(err) !== "Cancelled") showToast(String(err));
+    } finally {
+      setIsGrouping(false);
+    }
+  };
+
+  const cancelSuggestGroups = () => {
+    invoke("cancel_commit_groups");
+  };
+
+  // Selects the group's files (untracked folders show up as a single "dir/" entry) and
+  // fills in its message, so it goes through the normal commit flow
+  const applyGroup = (group: CommitGroup) => {
+    const inGroup = (path: string) =>
+      group.files.some(f => f === path || (path.endsWith("/") && f.startsWith(path)));
+    setFiles(files.map(f => ({ ...f, staged: inGroup(f.path) })));
+    setCommitMessage(group.message);
+    setMessageFingerprint(null);
+    setCommitGroups(commitGroups && commitGroups.filter(g => g !== group));
+  };
+
   const handleSparkle = async () => {
     const stagedFiles = files.filter(f => f.staged).map(f => f.path);
     if (stagedFiles.length === 0) {
@@ -174,10 +872,11 @@ function App() {
 
     setIsSparkling(true);
     try {
-      const diff: string = await invoke("get_git_diff", { path: repoPath, files: stagedFiles });
       const config = { provider: aiProvider, api_key: apiKey, model: aiModel, custom_api_url: customApiUrl };
-      const aiResponse: string = await invoke("generate_ai_commit", { diff, config });
-      setCommitMessage(aiResponse);
+      const scope = { kind: "selected_files", files: stagedFiles };
+      const generated: GeneratedCommit = await invoke("generate_ai_commit", { path: repoPath, scope, config, template: commitTemplate });
+      setCommitMessage(generated.message);
+      setMessageFingerprint(generated.diff_fingerprint);
     } catch (err) {
       console.error("AI Generation failed:", err);
       showToast(String(err));
@@ -186,21 +885,95 @@ function App() {
     }
   };
 
+  const toggleAmend = async (enabled: boolean) => {
+    setAmend(enabled);
+    if (!enabled) return;
+    try {
+      setCommitMessage(await invoke<string>("get_last_commit_message", { path: repoPath }));
+      setMessageFingerprint(null);
+    } catch (err) {
+      setAmend(false);
+      showToast(`Can't amend: ${errorMessage(err)}`);
+    }
+  };
+
+  const undoLastCommit = async () => {
+    if (!window.confirm("Undo the last commit? Its changes stay staged.")) return;
+    let undone: { hash: string; message: string };
+    try {
+      try {
+        undone = await invoke("undo_last_commit", { path: repoPath });
+      } catch (err) {
+        if ((err as { kind?: string })?.kind !== "undoing_pushed_commit" || !window.confirm(`${errorMessage(err)}\n\nUndo it anyway?`)) throw err;
+        undone = await invoke("undo_last_commit", { path: repoPath, allowPushed: true });
+      }
+    } catch (err) {
+      reportError("Undo failed: ", err);
+      return;
+    }
+    setAmend(false);
+    setCommitMessage(undone.message);
+    setMessageFingerprint(null);
+    showToast(`Undid commit ${undone.hash.slice(0, 7)}`, "info");
+    await fetchStatus();
+    await refreshSyncStatus();
+  };
+
+  const runOperationStep = async (step: OperationStep) => {
+    try {
+      await invoke(step.command, { path: repoPath });
+      showToast(step.done, "info");
+    } catch (err) {
+      showToast(errorMessage(err));
+    }
+    await fetchStatus();
+    await refreshSyncStatus();
+  };
+
+  // Asks for the name and email git is missing; true once they're saved
+  const setupIdentity = async (): Promise<boolean> => {
+    const current = await invoke<GitIdentity>("get_git_identity", { path: repoPath }).catch(() => null);
+    const name = window.prompt("Git needs your name for commits:", current?.name ?? "")?.trim();
+    if (!name) return false;
+    const email = window.prompt("And your email address:", current?.email ?? "")?.trim();
+    if (!email) return false;
+    const global = window.confirm("Use this name and email for all your repositories?\n\nCancel to set them for this repository only.");
+    try {
+      await invoke("set_git_identity", { path: repoPath, name, email, global });
+      return true;
+    } catch (err) {
+      showToast(errorMessage(err));
+      return false;
+    }
+  };
+
   // Reusable commit logic for both Commit and Commit & Push
   const performCommit = async (): Promise<boolean> => {
     const stagedFiles = files.filter(f => f.staged).map(f => f.path);
-    if (stagedFiles.length === 0) return false;
+    if (stagedFiles.length === 0 && !amend && !allowEmpty) return false;
 
-    let finalMessage = commitMessage.trim();
+    // An untouched template counts as no message, as it does for git
+    let finalMessage = commitMessage.trim() === commitTemplate?.trim() ? "" : commitMessage.trim();
+    let fingerprint = finalMessage ? messageFingerprint : null;
+
+    // There is no diff to generate from, so ask for the message instead
+    if (!finalMessage && stagedFiles.length === 0) {
+      finalMessage = window.prompt("Commit message:")?.trim() ?? "";
+      if (!finalMessage) return false;
+      setCommitMessage(finalMessage);
+    }
 
     // If empty input, auto-generate first
     if (!finalMessage) {
       setIsCommitting(true);
       try {
-        const diff: string = await invoke("get_git_diff", { path: repoPath, files: stagedFiles });
         const config = { provider: aiProvider, api_key: apiKey, model: aiModel, custom_api_url: customApiUrl };
-        finalMessage = await invoke("generate_ai_commit", { diff, config });
+        const scope = { kind: "selected_files", files: stagedFiles };
+        const generated: GeneratedCommit = await invoke("generate_ai_commit", { path: repoPath, scope, config, template: commitTemplate });
+        finalMessage = generated.message;
+        fingerprint = generated.diff_fingerprint;
         setCommitMessage(finalMessage);
+        setMessageFingerprint(fingerprint);
       } catch (err) {
         showToast(`Error auto-generating commit: ${err}`);
         setIsCommitting(false);
@@ -208,49 +981,186 @@ function App() {
       }
     }
 
+    let warnings: CommitWarning[] = [];
+    try {
+      warnings = await invoke("analyze_commit_selection", { path: repoPath, files: stagedFiles });
+    } catch (err) {
+      showToast(`Pre-commit checks failed: ${err}`);
+      setIsCommitting(false);
+      return false;
+    }
+    if (warnings.length > 0) {
+      const details = warnings.map(w => `• ${w.path}: ${w.message}`).join("\n");
+      if (!window.confirm(`Some selected files look like they shouldn't be committed:\n\n${details}\n\nCommit anyway?`)) {
+        setIsCommitting(false);
+        return false;
+      }
+    }
+
+    const ignoredFiles = files.filter(f => f.staged && f.status === 'I').map(f => f.path);
+    if (ignoredFiles.length > 0 && !window.confirm(`These files are ignored by .gitignore:\n\n${ignoredFiles.join("\n")}\n\nCommit them anyway?`)) {
+      setIsCommitting(false);
+      return false;
+    }
+
     setIsCommitting(true);
     try {
-      await invoke("commit_changes", {
+      const commit = (expectedFingerprint: string | null, amendPushed = false, acknowledged = warnings.length > 0) => invoke<CommitResult>("commit_changes", {
         path: repoPath,
         message: finalMessage,
-        files: stagedFiles
+        files: stagedFiles,
+        acknowledgeWarnings: acknowledged,
+        expectedFingerprint,
+        forceIgnored: ignoredFiles.length > 0,
+        amend,
+        amendPushed,
+        trailers: coauthors.map(c => ["Co-authored-by", `${c.name} <${c.email}>`]),
+        noVerify: skipHooks,
+        allowEmpty: allowEmpty && stagedFiles.length === 0,
       });
+      let result: CommitResult;
+      try {
+        result = await commit(fingerprint);
+      } catch (err) {
+        const e = err as { kind?: string; changes?: DiffChangeSummary; remotes?: string[]; warnings?: CommitWarning[] };
+        if (e?.kind === "commit_warnings" && e.warnings) {
+          // Files changed since the checks above ran and now get flagged
+          const details = e.warnings.map(w => `• ${w.path}: ${w.message}`).join("\n");
+          if (!window.confirm(`Some selected files look like they shouldn't be committed:\n\n${details}\n\nCommit anyway?`)) return false;
+          result = await commit(fingerprint, false, true);
+        } else if (e?.kind === "amending_pushed_commit" && e.remotes) {
+          if (!window.confirm(`The last commit is already on ${e.remotes.join(", ")}. Amending it rewrites published history and needs a force push.\n\nAmend anyway?`)) return false;
+          result = await commit(fingerprint, true);
+        } else if (e?.kind === "identity_not_configured") {
+          if (!(await setupIdentity())) return false;
+          result = await commit(fingerprint);
+        } else {
+          if (e?.kind !== "diff_changed_since_generation" || !e.changes) throw err;
+          const { added_files, removed_files, modified_files, lines_changed } = e.changes;
+          const details = [
+            ...added_files.map(f => `+ ${f}`),
+            ...removed_files.map(f => `- ${f}`),
+            ...modified_files.map(f => `~ ${f}`),
+          ].join("\n");
+          const question = `The changes were edited after this message was generated${lines_changed ? ` (${lines_changed} lines)` : ""}:\n\n${details || "(details unavailable)"}\n\nCommit anyway? Cancel to keep the message and regenerate it with Sparkle.`;
+          if (!window.confirm(question)) return false;
+          result = await commit(null);
+        }
+      }
+      setMessageFingerprint(null);
+      setAmend(false);
+      setAllowEmpty(false);
+      setCoauthors([]);
+      showToast(`${amend ? 'Amended' : 'Committed'} ${result.short_hash} (${result.files_committed} ${result.files_committed === 1 ? 'file' : 'files'}): ${result.subject}`, "info");
+      if (result.submodule_bumps.length > 0) {
+        showToast(`Submodules updated: ${result.submodule_bumps.map(b => `${b.path} ${b.old} → ${b.new}`).join(", ")}`, "info");
+      }
       return true;
     } catch (err) {
-      showToast(`Commit failed: ${err}`);
+      reportError("Commit failed: ", err);
       return false;
     } finally {
       setIsCommitting(false);
     }
   };
 
+  const updateSubmodules = async () => {
+    setIsUpdatingSubmodules(true);
+    try {
+      const init = staleSubmodules.some(s => s.state === 'not_initialized');
+      await invoke("update_submodules", { path: repoPath, init, recursive: true });
+      await fetchStatus();
+    } catch (err) {
+      showToast(`Submodule update failed: ${errorMessage(err)}`);
+    } finally {
+      setIsUpdatingSubmodules(false);
+    }
+  };
+
   const handleCommit = async () => {
     const success = await performCommit();
     if (success) {
-      setCommitMessage("");
+      setCommitMessage(commitTemplate ?? "");
       await fetchStatus();
       await refreshSyncStatus();
     }
   };
 
+  const cloneRepository = async () => {
+    const dest = cloneDest.trim();
+    setCloneProgress({ path: dest, phase: "Starting", percent: null, detail: "" });
+    const unlisten = await listen<GitProgress>("clone-progress", (event) => {
+      if (event.payload.path === dest) setCloneProgress(event.payload);
+    });
+    try {
+      const repo: RepoValidation = await invoke("clone_repo", { url: cloneUrl.trim(), destPath: dest, depth: cloneShallow ? 1 : null });
+      const root = repo.root ?? dest;
+      setRepoPath(root);
+      await fetchStatus(root);
+      await refreshSyncStatus(root);
+      setSetupMessage(null);
+    } catch (err) {
+      setSetupMessage({ text: "Clone failed: " + errorMessage(err), isError: true });
+      setTimeout(() => setSetupMessage(null), 5000);
+    } finally {
+      unlisten();
+      setCloneProgress(null);
+    }
+  };
+
   const performPush = async () => {
     setIsPushing(true);
+    const unlisten = await listen<GitProgress>("push-progress", (event) => {
+      if (event.payload.path === repoPath) setPushProgress(event.payload);
+    });
     try {
       const needsUpstream = syncStatus && !syncStatus.has_upstream;
-      await invoke("push_changes", {
+      const args = {
         path: repoPath,
-        set_upstream: needsUpstream || false,
-        branch: syncStatus?.branch || undefined
-      });
+        setUpstream: needsUpstream || false,
+        branch: needsUpstream ? syncStatus?.branch : undefined
+      };
+      try {
+        await invoke("push", args);
+      } catch (err) {
+        // Typically after amending a pushed commit; the lease still refuses if someone else pushed
+        const rejected = (err as { kind?: string })?.kind === "push_rejected";
+        if (!rejected || !window.confirm(`${errorMessage(err)}\n\nIf you rewrote these commits on purpose (amend, rebase), force push them? This replaces the remote branch.`)) throw err;
+        const confirmation: string = await invoke("confirm_dangerous_operation", {
+          operation: { kind: "force_push", path: repoPath, remote: null, branch: args.branch ?? null },
+        });
+        await invoke("push", { ...args, force: true, confirmation });
+      }
       return true;
     } catch (err) {
-      showToast(`Push failed: ${err}`);
+      showToast(`Push failed: ${errorMessage(err)}`);
       return false;
     } finally {
+      unlisten();
+      setPushProgress(null);
       setIsPushing(false);
     }
   };
 
+  const runRemoteUpdate = async (command: "fetch" | "pull", rebase?: boolean) => {
+    setIsPulling(true);
+    try {
+      if (command === "fetch") {
+        await invoke("fetch", { path: repoPath, prune: true });
+        showToast("Fetched all remotes", "info");
+      } else {
+        const result: { new_commits: number } = await invoke("pull", { path: repoPath, rebase });
+        showToast(result.new_commits > 0 ? `Pulled ${result.new_commits} commit(s)` : "Already up to date", "info");
+      }
+    } catch (err) {
+      reportError(`${command === "fetch" ? "Fetch" : "Pull"} failed: `, err);
+    } finally {
+      setIsPulling(false);
+      await fetchStatus();
+      await refreshSyncStatus();
+    }
+  };
+
   // Adaptive: if files are staged → commit & push; if clean tree → just push
   const handlePushAction = async () => {
     const hasStagedFiles = files.some(f => f.staged);
@@ -268,15 +1178,128 @@ function App() {
       } else {
         showToast("Pushed successfully!", "info");
       }
-      setCommitMessage("");
+      setCommitMessage(commitTemplate ?? "");
       await fetchStatus();
       await refreshSyncStatus();
     }
   };
 
+  const searchProject = async () => {
+    if (!searchQuery) return;
+    setIsSearching(true);
+    try {
+      setSearchResults(await invoke<GrepMatch[]>("search_working_tree", {
+        path: repoPath,
+        query: searchQuery,
+        regex: searchRegex,
+        caseSensitive: searchCaseSensitive,
+        maxResults: SEARCH_RESULT_LIMIT,
+      }));
+    } catch (err) {
+      showToast(errorMessage(err));
+    } finally {
+      setIsSearching(false);
+    }
+  };
+
+  const ignoreFile = async (path: string, localOnly: boolean) => {
+    try {
+      const result = await invoke<{ added: Record<string, string[]>; still_tracked: string[] }>("add_to_gitignore", {
+        path: repoPath,
+        patterns: [path],
+        scope: localOnly ? "exclude" : "root",
+        fromPaths: true,
+      });
+      const files = Object.keys(result.added);
+      let message = files.length > 0 ? `Added ${path} to ${files.join(", ")}.` : `${path} is already ignored.`;
+      if (result.still_tracked.length > 0) {
+        message += ` ${result.still_tracked.join(", ")} ${result.still_tracked.length === 1 ? "is" : "are"} still tracked; git rm --cached stops tracking them.`;
+      }
+      showToast(message, "info");
+      await fetchStatus();
+    } catch (err) {
+      showToast(errorMessage(err));
+    }
+  };
+
+  const discardFile = async (path: string, permanent: boolean, staged: boolean = true) => {
+    const question = permanent
+      ? `Permanently delete the changes to ${path}? This cannot be undone.`
+      : staged
+        ? `Discard the changes to ${path}? Untracked files go to the trash.`
+        : `Discard the unstaged changes to ${path}? Staged changes are kept.`;
+    if (!window.confirm(question)) return;
+    try {
+      // Only honored for this exact discard, once, within 30 seconds
+      const confirmation: string = await invoke("confirm_dangerous_operation", {
+        operation: { kind: "discard_changes", path: repoPath, files: [path], permanent },
+      });
+      const result: DiscardResult = await invoke("discard_changes", { path: repoPath, files: [path], staged, permanent, confirmation });
+      const dropped = result.files.some(f => f.destination.kind === 'deleted_after_trash_failed');
+      showToast(describeDiscard(result), dropped ? 'error' : 'info');
+      await fetchStatus();
+    } catch (err) {
+      reportError("Discard failed: ", err);
+    }
+  };
+
+  // Shows the staged side of fully staged files and the unstaged side of everything else
+  const showFileDiff = async (file: FileStatus) => {
+    if (fileDiff?.file.path === file.path) {
+      setFileDiff(null);
+      return;
+    }
+    await loadFileDiff(file, !!file.index_status && !file.worktree_status);
+  };
+
+  const loadFileDiff = async (file: FileStatus, staged: boolean) => {
+    try {
+      const text: string = await invoke("get_file_diff", { path: repoPath, file: file.path, staged, ignoreWhitespace });
+      setFileDiff({ file, staged, text: text || "No textual changes.", words: null });
+    } catch (err) {
+      showToast(`Could not load the diff: ${errorMessage(err)}`);
+    }
+  };
+
+  // Hunk indices count the `@@` sections of the diff on show, which is what the backend uses
+  const toggleHunk = async (index: number) => {
+    if (!fileDiff) return;
+    try {
+      await invoke(fileDiff.staged ? "unstage_hunks" : "stage_hunks", { path: repoPath, file: fileDiff.file.path, hunkIndices: [index] });
+      await fetchStatus();
+      await loadFileDiff(fileDiff.file, fileDiff.staged);
+    } catch (err) {
+      reportError(`Could not ${fileDiff.staged ? 'unstage' : 'stage'} the hunk: `, err);
+    }
+  };
+
+  const toggleWordDiff = async () => {
+    if (!fileDiff) return;
+    if (fileDiff.words) {
+      setFileDiff({ ...fileDiff, words: null });
+      return;
+    }
+    try {
+      const words: WordDiff = await invoke("get_word_diff", { path: repoPath, file: fileDiff.file.path, staged: fileDiff.staged, ignoreWhitespace });
+      setFileDiff({ ...fileDiff, words });
+    } catch (err) {
+      showToast(`Could not load the word diff: ${errorMessage(err)}`);
+    }
+  };
+
+  // Ignored files are only ever selected one by one
   const toggleAll = () => {
-    const allStaged = files.every(f => f.staged);
-    setFiles(files.map(f => ({ ...f, staged: !allStaged })));
+    const allStaged = files.filter(f => f.status !== 'I').every(f => f.staged);
+    setFiles(files.map(f => ({ ...f, staged: f.status !== 'I' && !allStaged })));
+  };
+
+  const resolveConflict = async (path: string, side: "ours" | "theirs") => {
+    try {
+      await invoke("resolve_conflict", { path: repoPath, file: path, side });
+    } catch (err) {
+      showToast(`Couldn't resolve ${path}: ${errorMessage(err)}`);
+    }
+    await fetchStatus();
   };
 
   const toggleFile = (path: string) => {
@@ -298,12 +1321,19 @@ function App() {
 
   const saveSettings = async () => {
     try {
-      const store = await load('settings.json', { autoSave: false, defaults: {} });
-      await store.set('aiProvider', aiProvider);
-      await store.set('aiModel', aiModel);
-      await store.set('apiKey', apiKey);
-      await store.set('customApiUrl', customApiUrl);
-      await store.save();
+      // Merged key by key on the Rust side so other open windows don't lose their changes
+      await invoke("update_settings", {
+        changes: {
+          aiProvider, aiModel, apiKey, customApiUrl,
+          discardBackupDays: Number(discardBackupDays) || 0,
+          autoFetch,
+          autoFetchMinutes: Math.max(5, Number(autoFetchMinutes) || 15),
+          newFileDiffLimitKb: Math.max(1, Number(newFileDiffLimitKb) || 16),
+          ignoreWhitespace,
+        },
+      });
+      await invoke("set_git_backend", { preference: gitBackend });
+      if (repoPath && !isNotRepo) await fetchStatus();
       setIsSettingsMode(false);
     } catch (err) {
       showToast("Failed to save settings: " + err);
@@ -403,23 +1433,56 @@ function App() {
                   className="settings-input"
                 />
               </div>
+              <div className="settings-group" style={{ textAlign: 'left' }}>
+                <label>First branch</label>
+                <input
+                  type="text"
+                  value={initBranch}
+                  onChange={(e) => setInitBranch(e.target.value)}
+                  placeholder="main"
+                  className="settings-input"
+                />
+              </div>
+              <div className="settings-group" style={{ textAlign: 'left' }}>
+                <label>Starter .gitignore</label>
+                <select
+                  value={gitignoreTemplate}
+                  onChange={(e) => setGitignoreTemplate(e.target.value)}
+                  className="settings-input"
+                >
+                  <option value="">None</option>
+                  {gitignoreTemplates.map(name => <option key={name} value={name}>{name}</option>)}
+                </select>
+              </div>
               <button
                 className="btn-primary"
                 onClick={async () => {
                   setIsInitializing(true);
+                  const init = (allowNested: boolean) => invoke<InitResult>("init_repo", {
+                    path: repoPath,
+                    remoteUrl: remoteUrl.trim() || null,
+                    defaultBranch: initBranch.trim() || null,
+                    gitignoreTemplate: gitignoreTemplate || null,
+                    allowNested,
+                  });
                   try {
-                    await invoke("init_repo", {
-                      path: repoPath,
-                      remote_url: remoteUrl.trim() || null
-                    });
-                    // After init, reload as a normal repo
+                    let result: InitResult;
+                    try {
+                      result = await init(false);
+                    } catch (err) {
+                      const e = err as { kind?: string; root?: string };
+                      if (e.kind !== "inside_existing_repo") throw err;
+                      if (!window.confirm(`This folder is inside the repository at ${e.root}. Create a separate repository nested in it anyway?`)) return;
+                      result = await init(true);
+                    }
+                    // After init, show it as a normal repo straight from the returned status
+                    setFiles(result.status);
                     setIsNotRepo(false);
                     setIsSetupMode(false);
-                    await fetchStatus(repoPath);
                     await refreshSyncStatus(repoPath);
                     setSetupMessage(null);
                   } catch (err) {
-                    setSetupMessage({ text: "Init failed: " + err, isError: true });
+                    setSetupMessage({ text: "Init failed: " + errorMessage(err), isError: true });
                     setTimeout(() => setSetupMessage(null), 5000);
                   } finally {
                     setIsInitializing(false);
@@ -430,6 +1493,48 @@ function App() {
               >
                 {isInitializing ? 'Initializing...' : 'Initialize Repository'}
               </button>
+
+              <div className="settings-group" style={{ textAlign: 'left' }}>
+                <label>Or clone a repository</label>
+                <input
+                  type="text"
+                  value={cloneUrl}
+                  onChange={(e) => setCloneUrl(e.target.value)}
+                  placeholder="https://github.com/user/repo.git"
+                  className="settings-input"
+                />
+                <input
+                  type="text"
+                  value={cloneDest}
+                  onChange={(e) => setCloneDest(e.target.value)}
+                  placeholder={`${repoPath}/repo`}
+                  className="settings-input"
+                />
+                <label className="show-ignored">
+                  <input type="checkbox" checked={cloneShallow} onChange={(e) => setCloneShallow(e.target.checked)} />
+                  Latest commit only
+                </label>
+              </div>
+              {cloneProgress ? (
+                <div className="pull-progress">
+                  <span>
+                    {cloneProgress.phase || "Cloning"}
+                    {cloneProgress.percent !== null ? ` · ${cloneProgress.percent}%` : ''}
+                  </span>
+                  <button className="btn-secondary" onClick={() => invoke("cancel_clone", { destPath: cloneProgress.path })}>
+                    Cancel
+                  </button>
+                </div>
+              ) : (
+                <button
+                  className="btn-secondary"
+                  onClick={cloneRepository}
+                  disabled={!cloneUrl.trim() || !cloneDest.trim()}
+                  style={{ marginBottom: '16px' }}
+                >
+                  Clone
+                </button>
+              )}
             </div>
           )}
 
@@ -494,8 +1599,60 @@ function App() {
             </p>
           </div>
 
+          <div className="settings-group">
+            <label>Keep discard backups (days, 0 = off)</label>
+            <input
+              type="number"
+              min="0"
+              value={discardBackupDays}
+              onChange={(e) => setDiscardBackupDays(e.target.value)}
+              className="settings-input"
+            />
+          </div>
+
+          <div className="settings-group">
+            <label>Read status, diffs and history and make plain commits with</label>
+            <select value={gitBackend} onChange={(e) => setGitBackend(e.target.value as 'cli' | 'libgit2')} className="settings-input">
+              <option value="cli">Git (default)</option>
+              <option value="libgit2">libgit2 (faster refreshes)</option>
+            </select>
+          </div>
+
+          <div className="settings-group">
+            <label>
+              <input type="checkbox" checked={autoFetch} onChange={(e) => setAutoFetch(e.target.checked)} />
+              {' '}Fetch in the background every (minutes, at least 5)
+            </label>
+            <input
+              type="number"
+              min="5"
+              value={autoFetchMinutes}
+              onChange={(e) => setAutoFetchMinutes(e.target.value)}
+              disabled={!autoFetch}
+              className="settings-input"
+            />
+          </div>
+
           <h2>AI Provider Settings</h2>
 
+          <div className="settings-group">
+            <label>
+              <input type="checkbox" checked={ignoreWhitespace} onChange={(e) => setIgnoreWhitespace(e.target.checked)} />
+              {' '}Ignore whitespace-only changes in diffs and AI prompts
+            </label>
+          </div>
+
+          <div className="settings-group">
+            <label>Content sent per new file (KB)</label>
+            <input
+              type="number"
+              min="1"
+              value={newFileDiffLimitKb}
+              onChange={(e) => setNewFileDiffLimitKb(e.target.value)}
+              className="settings-input"
+            />
+          </div>
+
           <div className="settings-group">
             <label>Provider</label>
             <select value={aiProvider} onChange={(e) => {
@@ -577,6 +1734,9 @@ function App() {
                         <option value="deepseek-coder">deepseek-coder</option>
                       </>
                     )}
+                    {pullingModels.map(model => (
+                      <option key={model} disabled>{model} (downloading...)</option>
+                    ))}
                   </>
                 )}
                 {aiProvider === 'lmstudio' && (
@@ -645,6 +1805,38 @@ function App() {
             </div>
           )}
 
+          <div className="settings-group">
+            <button
+              className="btn-secondary"
+              style={{ width: '100%', fontSize: '11px', padding: '6px' }}
+              onClick={testConnection}
+              disabled={!!pullProgress}
+            >
+              Test Connection
+            </button>
+            {connectionCheck && (
+              <div className={`connection-check ${connectionCheck.ok ? 'connection-ok' : 'connection-failed'}`}>
+                <span>{connectionCheck.message}</span>
+                {connectionCheck.remediation?.kind === 'pull_model' && !pullProgress && (
+                  <button className="btn-secondary" onClick={() => pullModel(connectionCheck.remediation!.model)}>
+                    Download {connectionCheck.remediation.model}
+                  </button>
+                )}
+              </div>
+            )}
+            {pullProgress && (
+              <div className="pull-progress">
+                <span>
+                  {pullProgress.status}
+                  {pullProgress.total ? ` · ${Math.floor(((pullProgress.completed ?? 0) / pullProgress.total) * 100)}%` : ''}
+                </span>
+                <button className="btn-secondary" onClick={() => invoke("cancel_ollama_pull", { model: pullProgress.model })}>
+                  Cancel
+                </button>
+              </div>
+            )}
+          </div>
+
           <div className="setup-actions" style={{ marginTop: 'auto', marginBottom: 0 }}>
             <button className="btn-primary" onClick={saveSettings}>
               Save Settings
@@ -662,11 +1854,55 @@ function App() {
         <div className="titlebar-left">
           <span>GitPop</span>
           <span className="repo-name">{repoPath.split(/[\\/]/).pop() || "repo"}</span>
-          {syncStatus && syncStatus.ahead > 0 && (
+          {currentBranch && (
+            <span className={`branch-name${currentBranch.detached ? " detached" : ""}`} title={currentBranch.commit}>
+              {describeHead(currentBranch)}
+            </span>
+          )}
+          {repoOperation.kind !== "none" && (
+            <span className="operation-controls">
+              <span>{describeOperation(repoOperation)}</span>
+              {OPERATION_STEPS[repoOperation.kind].map(step => (
+                <button key={step.command} className="link-button" onClick={() => runOperationStep(step)} title={step.title}>{step.label}</button>
+              ))}
+            </span>
+          )}
+          {syncStatus?.upstream ? (
+            <span className="sync-info" title="Compared with the upstream branch as of the last fetch">
+              {syncStatus.ahead > 0 && <span className="ahead-badge">↑{syncStatus.ahead}</span>}
+              {syncStatus.behind > 0 && <span className="behind-badge">↓{syncStatus.behind}</span>}
+              {syncStatus.upstream}
+              {syncStatus.last_fetch !== null && ` · fetched ${formatAge(Math.floor(Date.now() / 1000) - syncStatus.last_fetch)}`}
+            </span>
+          ) : syncStatus && syncStatus.ahead > 0 && (
             <span className="ahead-badge">↑ {syncStatus.ahead}</span>
           )}
+          {releaseInfo?.last_commit && (
+            <span className="release-info" title={releaseInfo.last_commit.subject}>
+              {formatAge(releaseInfo.last_commit.age_seconds)}
+              {releaseInfo.describe && ` · ${releaseInfo.describe.commits_since_tag} since ${releaseInfo.describe.nearest_tag}`}
+            </span>
+          )}
         </div>
         <div style={{ display: 'flex', alignItems: 'center', gap: '8px' }}>
+          {repoWebUrl && (
+            <button
+              className="titlebar-close"
+              style={{ opacity: 0.7 }}
+              onClick={() => openUrl(repoWebUrl.base_url)}
+              title={`Open on ${PROVIDER_NAMES[repoWebUrl.provider]}`}
+            >
+              ↗
+            </button>
+          )}
+          {!backendNotice && <button
+            className="titlebar-close"
+            style={{ opacity: isReadOnly ? 1 : 0.5 }}
+            onClick={toggleReadOnly}
+            title={isReadOnly ? "Read-only: click to allow changes" : "Browse read-only"}
+          >
+            {isReadOnly ? '🔒' : '🔓'}
+          </button>}
           <button className="titlebar-close" style={{ opacity: 0.7 }} onClick={() => setIsSettingsMode(true)} title="Settings">
             <svg width="14" height="14" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2" strokeLinecap="round" strokeLinejoin="round">
               <circle cx="12" cy="12" r="3"></circle>
@@ -691,8 +1927,37 @@ function App() {
           </div>
         )}
 
+        {backendNotice && <div className="backend-notice">{backendNotice}</div>}
+
+        {staleSubmodules.length > 0 && (
+          <div className="backend-notice submodule-notice">
+            <span>
+              {staleSubmodules.length === 1 ? `Submodule ${staleSubmodules[0].path} is` : `${staleSubmodules.length} submodules are`}
+              {' '}not at the recorded commit
+            </span>
+            <button className="btn-secondary" onClick={updateSubmodules} disabled={isUpdatingSubmodules || isReadOnly}>
+              {isUpdatingSubmodules ? 'Updating...' : 'Update'}
+            </button>
+          </div>
+        )}
+
+        {/* Announced by screen readers whenever the status refreshes */}
+        <div className="sr-only" role="status" aria-live="polite">{statusSummary}</div>
+
         {error && <div style={{ color: 'var(--color-deleted)', fontSize: '12px', padding: '8px', background: 'rgba(255,0,0,0.1)', borderRadius: '4px', wordBreak: 'break-word', overflow: 'hidden' }}>{error}</div>}
 
+        {commitGroups && commitGroups.length > 0 && (
+          <div className="commit-groups">
+            {commitGroups.map((group, i) => (
+              <button key={i} className="commit-group" onClick={() => applyGroup(group)} title={group.files.join("\n")}>
+                <span>{group.message}</span>
+                <span className="commit-group-count">{group.files.length} files</span>
+              </button>
+            ))}
+            <button className="btn-refresh" onClick={() => setCommitGroups(null)} title="Dismiss">✕</button>
+          </div>
+        )}
+
         <textarea
           className="commit-msg"
           placeholder="Message (Cmd+Enter to commit)"
@@ -700,11 +1965,113 @@ function App() {
           onChange={(e) => setCommitMessage(e.target.value)}
         />
 
+        {lintIssues.length > 0 && (
+          <ul className="lint-issues">
+            {lintIssues.map((issue, i) => (
+              <li key={i} className={`lint-${issue.severity}`}>{issue.message}</li>
+            ))}
+          </ul>
+        )}
+
         <div className="ai-actions">
           <button className="btn-sparkle" onClick={handleSparkle} disabled={isSparkling}>
             <span className="sparkle-icon">✨</span>
             <span>{isSparkling ? 'Generating...' : 'Sparkle'}</span>
           </button>
+          <button
+            className="btn-sparkle"
+            onClick={isGrouping ? cancelSuggestGroups : handleSuggestGroups}
+            disabled={files.length < 2 && !isGrouping}
+            title="Split changes into logical commits"
+          >
+            <span>{isGrouping ? 'Cancel split' : 'Split'}</span>
+          </button>
+          {vocabulary && (
+            <select
+              className="snippet-select"
+              value=""
+              onChange={(e) => e.target.value && applyCommitType(e.target.value)}
+              title="Commit type"
+            >
+              <option value="">Type…</option>
+              {vocabulary.types.map(t => (
+                <option key={t.name} value={t.name} title={t.description}>{t.name}</option>
+              ))}
+              {vocabulary.allowed_scopes && vocabulary.types.flatMap(t =>
+                vocabulary.allowed_scopes!.map(scope => (
+                  <option key={`${t.name}(${scope})`} value={`${t.name}(${scope})`}>{t.name}({scope})</option>
+                ))
+              )}
+            </select>
+          )}
+          {snippets.length > 0 && (
+            <select
+              className="snippet-select"
+              value=""
+              onChange={(e) => e.target.value && insertSnippet(e.target.value)}
+              title="Insert snippet"
+            >
+              <option value="">Snippets…</option>
+              {snippets.map(s => (
+                <option key={s.name} value={s.name}>{s.source === 'repo' ? `${s.name} (repo)` : s.name}</option>
+              ))}
+            </select>
+          )}
+          {commitMessage.trim() && (
+            <button className="btn-snippet-save" onClick={saveMessageAsSnippet} title="Save message as snippet">
+              ＋
+            </button>
+          )}
+          {recentCoauthors.length > 0 && (
+            <select
+              className="snippet-select"
+              value=""
+              onChange={(e) => {
+                const picked = recentCoauthors.find(c => c.email === e.target.value);
+                if (picked && !coauthors.includes(picked)) setCoauthors([...coauthors, picked]);
+              }}
+              title="Add a Co-authored-by trailer"
+            >
+              <option value="">Co-author…</option>
+              {recentCoauthors.map(c => (
+                <option key={c.email} value={c.email}>{c.name} &lt;{c.email}&gt;</option>
+              ))}
+            </select>
+          )}
+          {coauthors.map(c => (
+            <button key={c.email} className="coauthor-chip" onClick={() => setCoauthors(coauthors.filter(x => x !== c))} title="Remove co-author">
+              {c.name} ×
+            </button>
+          ))}
+          {syncStatus?.has_commits && (
+            <label className="amend-toggle" title="Fold the selected files and this message into the last commit">
+              <input type="checkbox" checked={amend} onChange={(e) => toggleAmend(e.target.checked)} />
+              Amend
+            </label>
+          )}
+          {syncStatus?.has_commits && !isReadOnly && (
+            <button className="link-button" onClick={undoLastCommit} title="Undo the last commit and keep its changes staged">
+              Undo commit
+            </button>
+          )}
+          <label className="amend-toggle" title="Commit without running the pre-commit and commit-msg hooks (--no-verify)">
+            <input type="checkbox" checked={skipHooks} onChange={(e) => setSkipHooks(e.target.checked)} />
+            Skip hooks
+          </label>
+          {!files.some(f => f.staged) && syncStatus?.has_commits && (
+            <label className="amend-toggle" title="Commit without any changes, e.g. to trigger CI">
+              <input type="checkbox" checked={allowEmpty} onChange={(e) => setAllowEmpty(e.target.checked)} />
+              Empty
+            </label>
+          )}
+          {signingConfig?.enabled && (
+            <span
+              className="signing-badge"
+              title={`Commits are signed with ${signingConfig.format === 'openpgp' ? 'GPG' : signingConfig.format.toUpperCase()}${signingConfig.signing_key ? ` (${signingConfig.signing_key})` : ''}`}
+            >
+              🔏
+            </span>
+          )}
         </div>
 
         <div className="files-section">
@@ -719,8 +2086,29 @@ function App() {
                 </svg>
               </button>
             </div>
+            {!isReadOnly && files.length > 0 && syncStatus?.has_commits && (
+              <button className="btn-refresh" onClick={saveStash} title="Stash all changes">
+                Stash
+              </button>
+            )}
+            {!isReadOnly && files.some(f => f.status === 'U') && (
+              <button className="btn-refresh" onClick={cleanUntracked} title="Remove untracked files">
+                Clean…
+              </button>
+            )}
+            <button className="btn-refresh" onClick={() => setIsSearchOpen(!isSearchOpen)} title="Find in project (skips ignored files)">
+              Find
+            </button>
+            <label className="show-ignored" title="Also list files excluded by .gitignore">
+              <input
+                type="checkbox"
+                checked={showIgnored}
+                onChange={(e) => { setShowIgnored(e.target.checked); fetchStatus(repoPath, e.target.checked); }}
+              />
+              Ignored
+            </label>
             <span style={{ cursor: 'pointer', opacity: 0.8 }} onClick={toggleAll}>
-              {files.length > 0 && files.every(f => f.staged) ? 'Unstage All' : 'Stage All'}
+              {files.length > 0 && files.filter(f => f.status !== 'I').every(f => f.staged) ? 'Unstage All' : 'Stage All'}
             </span>
           </div>
 
@@ -738,44 +2126,213 @@ function App() {
                   checked={file.staged}
                   onChange={() => { }} // Handled by parent click
                 />
-                <span className="file-icon">📄</span>
-                <span className="file-path" title={file.path}>
+                <span className="file-icon" title={diffStats[file.path]?.binary ? 'Binary file' : undefined}>
+                  {diffStats[file.path]?.binary ? '🖼️' : '📄'}
+                </span>
+                <span className="file-path" title={file.old_path ? `${file.old_path} → ${file.path}` : file.path}>
                   {file.path.split('/').pop()}
                   <span style={{ opacity: 0.4, fontSize: '11px', marginLeft: '6px' }}>
-                    {file.path.split('/').slice(0, -1).join('/')}
+                    {file.old_path ? `from ${file.old_path}` : file.path.split('/').slice(0, -1).join('/')}
+                  </span>
+                </span>
+                {diffStats[file.path] && (
+                  <span className="file-stats">
+                    {diffStats[file.path].binary ? 'bin' : (
+                      <>
+                        <span className="stat-add">+{diffStats[file.path].additions}</span>
+                        <span className="stat-del">−{diffStats[file.path].deletions}</span>
+                      </>
+                    )}
+                  </span>
+                )}
+                {!file.conflicted && file.index_status && file.worktree_status && (
+                  <span className="file-partial" title="Partially staged: only the staged part is committed">
+                    ±
                   </span>
+                )}
+                <span
+                  className={`file-status status-${file.status}`}
+                  title={file.conflicted
+                    ? `Merge conflict (${file.index_status}${file.worktree_status})`
+                    : file.status === 'S' ? "Submodule: committing records the commit checked out inside it" : undefined}
+                >
+                  {file.status}
                 </span>
-                <span className={`file-status status-${file.status}`}>{file.status}</span>
+                {file.conflicted && !isReadOnly && (
+                  <>
+                    <button
+                      className="btn-discard"
+                      onClick={(e) => { e.stopPropagation(); resolveConflict(file.path, "ours"); }}
+                      title="Keep our side of this file"
+                    >
+                      ours
+                    </button>
+                    <button
+                      className="btn-discard"
+                      onClick={(e) => { e.stopPropagation(); resolveConflict(file.path, "theirs"); }}
+                      title="Keep their side of this file"
+                    >
+                      theirs
+                    </button>
+                  </>
+                )}
+                {file.status !== 'I' && (
+                  <button
+                    className="btn-discard"
+                    onClick={(e) => { e.stopPropagation(); showFileDiff(file); }}
+                    title="Show diff"
+                  >
+                    ±
+                  </button>
+                )}
+                {!isReadOnly && file.status === 'U' && (
+                  <button
+                    className="btn-discard"
+                    onClick={(e) => { e.stopPropagation(); ignoreFile(file.path, e.altKey); }}
+                    title="Add to .gitignore (Alt+click: ignore only in this clone)"
+                  >
+                    ⊘
+                  </button>
+                )}
+                {!isReadOnly && (
+                  <button
+                    className="btn-discard"
+                    onClick={(e) => { e.stopPropagation(); discardFile(file.path, e.shiftKey, !e.altKey); }}
+                    title="Discard changes (Shift+click to delete permanently, Alt+click to keep staged changes)"
+                  >
+                    ↺
+                  </button>
+                )}
               </div>
             ))}
           </div>
+          {isSearchOpen && (
+            <div className="search-panel">
+              <div className="search-bar">
+                <input
+                  className="settings-input"
+                  value={searchQuery}
+                  placeholder="Find in project"
+                  onChange={(e) => setSearchQuery(e.target.value)}
+                  onKeyDown={(e) => { if (e.key === "Enter") searchProject(); }}
+                  autoFocus
+                />
+                <label title="Regular expression">
+                  <input type="checkbox" checked={searchRegex} onChange={(e) => setSearchRegex(e.target.checked)} />.*
+                </label>
+                <label title="Match case">
+                  <input type="checkbox" checked={searchCaseSensitive} onChange={(e) => setSearchCaseSensitive(e.target.checked)} />Aa
+                </label>
+              </div>
+              {isSearching && <div className="search-status">Searching…</div>}
+              {!isSearching && searchResults && (
+                <div className="search-status">
+                  {searchResults.length === 0 ? "No matches" : `${searchResults.length}${searchResults.length >= SEARCH_RESULT_LIMIT ? "+" : ""} matches`}
+                </div>
+              )}
+              {!isSearching && searchResults?.map(match => (
+                <div key={`${match.file}:${match.line}:${match.column}`} className="search-result" title={`${match.file}:${match.line}:${match.column}`}>
+                  <span className="search-location">{match.file}:{match.line}</span>
+                  <span className="search-text">{match.text}</span>
+                </div>
+              ))}
+            </div>
+          )}
+          {stashes.length > 0 && (
+            <div className="stash-list">
+              <div className="section-header">
+                <span>Stashes ({stashes.length})</span>
+              </div>
+              {stashes.map(stash => (
+                <div key={stash.index} className="stash-item" title={new Date(stash.date * 1000).toLocaleString()}>
+                  <span className="stash-message">{stash.message}</span>
+                  {stash.branch && <span className="stash-branch">{stash.branch}</span>}
+                  {!isReadOnly && (
+                    <>
+                      <button className="btn-refresh" onClick={() => runStashAction("apply", stash)} title="Apply and keep the stash">Apply</button>
+                      <button className="btn-refresh" onClick={() => runStashAction("pop", stash)} title="Apply and remove the stash">Pop</button>
+                      <button className="btn-refresh" onClick={() => runStashAction("drop", stash)} title="Delete the stash">Drop</button>
+                    </>
+                  )}
+                </div>
+              ))}
+            </div>
+          )}
+          {fileDiff && (
+            <div className="file-diff">
+              <div className="file-diff-header">
+                <span>{fileDiff.file.path}</span>
+                <span>
+                  <button className="btn-secondary" onClick={toggleWordDiff}>{fileDiff.words ? 'Lines' : 'Words'}</button>
+                  <button className="toast-close" onClick={() => setFileDiff(null)} title="Close">×</button>
+                </span>
+              </div>
+              {fileDiff.words ? (
+                <pre>
+                  {fileDiff.words.hunks.flatMap((hunk, h) => [
+                    <div key={`h${h}`} className="word-hunk">{hunk.header}</div>,
+                    ...hunk.lines.map((line, l) => (
+                      <div key={`${h}-${l}`}>
+                        {line.segments.map((seg, i) => <span key={i} className={`word-${seg.kind}`}>{seg.text}</span>)}
+                      </div>
+                    )),
+                  ])}
+                </pre>
+              ) : ignoreWhitespace || !fileDiff.text.includes("\n@@") ? (
+                <pre>{fileDiff.text}</pre>
+              ) : (
+                fileDiff.text.split(/\n(?=@@)/).map((section, i) => (
+                  <div key={i} className="diff-hunk">
+                    {i > 0 && (
+                      <button className="btn-secondary hunk-action" onClick={() => toggleHunk(i - 1)}>
+                        {fileDiff.staged ? 'Unstage hunk' : 'Stage hunk'}
+                      </button>
+                    )}
+                    <pre>{section}</pre>
+                  </div>
+                ))
+              )}
+            </div>
+          )}
         </div>
       </div>
 
       {/* Action Bar */}
       <div className="action-bar">
-        {files.some(f => f.staged) ? (
-          <button className="btn-primary" onClick={handleCommit} disabled={isCommitting || isPushing}>
-            {isCommitting && !isPushing ? 'Committing...' : 'Commit'}
+        {files.some(f => f.staged) || amend || allowEmpty ? (
+          <button className="btn-primary" onClick={handleCommit} disabled={(isReadOnly && !canCommitWithoutGit) || isCommitting || isPushing}>
+            {isReadOnly && !canCommitWithoutGit ? 'Read-only' : isCommitting && !isPushing ? 'Committing...' : amend ? 'Amend' : 'Commit'}
           </button>
         ) : (
           <button className="btn-primary" disabled style={{ opacity: 0.5 }}>
             Commit
           </button>
         )}
+        <button
+          className="btn-icon"
+          onClick={(e) => runRemoteUpdate(e.altKey ? "fetch" : "pull", e.shiftKey || undefined)}
+          disabled={isReadOnly || isPulling || isPushing || !syncStatus?.has_upstream}
+          title={isPulling ? 'Updating...' : 'Pull (Shift+click to rebase, Alt+click to only fetch)'}
+        >
+          <svg width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2" strokeLinecap="round" strokeLinejoin="round">
+            <path d="M12 5v14M5 12l7 7 7-7" />
+          </svg>
+        </button>
         <button
           className={`btn-icon${!files.some(f => f.staged) && syncStatus && syncStatus.ahead > 0 ? ' btn-push-ready' : ''}`}
           onClick={handlePushAction}
-          disabled={isCommitting || isPushing || (!files.some(f => f.staged) && (!syncStatus || syncStatus.ahead === 0))}
+          disabled={isReadOnly || isCommitting || isPushing || (!files.some(f => f.staged) && (!syncStatus || syncStatus.ahead === 0))}
           title={
-            isPushing ? 'Pushing...' :
+            isPushing ? (pushProgress?.detail || 'Pushing...') :
               files.some(f => f.staged) ? 'Commit & Push' :
                 syncStatus && syncStatus.ahead > 0 ? (syncStatus.has_upstream ? `Push ↑${syncStatus.ahead}` : 'Initial Push') :
                   'Nothing to push'
           }
         >
           {isPushing ? (
-            <span style={{ fontSize: '12px', fontWeight: 500 }}>Pushing...</span>
+            <span style={{ fontSize: '12px', fontWeight: 500 }}>
+              {pushProgress?.percent != null ? `${pushProgress.phase} ${pushProgress.percent}%` : 'Pushing...'}
+            </span>
           ) : !files.some(f => f.staged) && syncStatus && syncStatus.ahead > 0 ? (
             <span style={{ fontSize: '12px', fontWeight: 500 }}>
               {syncStatus.has_upstream ? `Push ↑${syncStatus.ahead}` : 'Initial Push'}